@@ -0,0 +1,32 @@
+// A minimal bot plugin that plays the first empty tile.
+//
+// Build it with `cargo build --example plugin_bot`, copy the resulting shared
+// library from target/debug/examples into a `plugins` directory (or point
+// TTT_PLUGIN_DIR at it) and it shows up next to the built-in difficulties.
+
+use std::ffi::c_char;
+
+#[no_mangle]
+pub extern "C" fn ttt_bot_abi_version() -> u32 {
+    1
+}
+
+#[no_mangle]
+pub extern "C" fn ttt_bot_name() -> *const c_char {
+    c"First empty tile".as_ptr()
+}
+
+/// # Safety
+///
+/// `tiles` must point to `size * size` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_bot_choose_move(tiles: *const u8, size: usize, _symbol: u8) -> i32 {
+    // SAFETY: the caller guarantees tiles points to size * size bytes.
+    let tiles = unsafe { std::slice::from_raw_parts(tiles, size * size) };
+
+    tiles
+        .iter()
+        .position(|tile| *tile == 0)
+        .and_then(|index| i32::try_from(index).ok())
+        .unwrap_or(-1)
+}
@@ -0,0 +1,31 @@
+;; A WASM bot that takes the center when it can and otherwise the first empty
+;; tile. WAT text is accepted as is, so try it with
+;; `cargo run --features wasm-bots -- --p2 wasm:examples/wasm_bot.wat`.
+(module
+  (memory (export "memory") 1)
+  (data (i32.const 0) "Center first\00")
+
+  (func (export "ttt_bot_name") (result i32)
+    i32.const 0)
+
+  ;; The host writes size * size tiles here: 0 empty, 1 Plus, 2 Circle.
+  (func (export "ttt_board_buffer") (result i32)
+    i32.const 64)
+
+  ;; Returns row * size + column, or -1 to pass.
+  (func (export "ttt_choose_move") (param $size i32) (param $symbol i32) (result i32)
+    (local $center i32)
+    (local $i i32)
+    (local.set $center
+      (i32.div_u (i32.mul (local.get $size) (local.get $size)) (i32.const 2)))
+    (if (i32.eqz (i32.load8_u offset=64 (local.get $center)))
+      (then (return (local.get $center))))
+    (block $done
+      (loop $next
+        (br_if $done
+          (i32.ge_u (local.get $i) (i32.mul (local.get $size) (local.get $size))))
+        (if (i32.eqz (i32.load8_u offset=64 (local.get $i)))
+          (then (return (local.get $i))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    i32.const -1))
@@ -0,0 +1,390 @@
+// Achievements for games against the computer, kept across sessions.
+//
+// The file has an "unlocked <achievement>" line for every achievement
+// earned and a "hard_draws <games>" line for the current run of draws
+// against the hard bot.
+
+use std::collections::BTreeSet;
+use std::fmt::{Display, Write as _};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::ai::Difficulty;
+use crate::board::Symbol;
+use crate::bot::BotSpec;
+use crate::browser::GameRecord;
+use crate::game::{Outcome, Player};
+use crate::profile;
+
+// Where achievements are kept unless TTT_ACHIEVEMENTS says otherwise.
+pub const DEFAULT_ACHIEVEMENTS: &str = "tic-tac-toe.achievements";
+
+// Draws in a row against the hard bot for Unbreakable.
+const HARD_DRAWS: u32 = 10;
+
+pub fn path() -> PathBuf {
+    std::env::var_os("TTT_ACHIEVEMENTS")
+        .map_or_else(|| profile::file(DEFAULT_ACHIEVEMENTS), PathBuf::from)
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Achievement {
+    FirstWin,
+    WinWithoutCenter,
+    Unbreakable,
+    // On the biggest board, see src/sized.rs.
+    FiveByFiveWin,
+}
+
+const ACHIEVEMENTS: [Achievement; 4] = [
+    Achievement::FirstWin,
+    Achievement::WinWithoutCenter,
+    Achievement::Unbreakable,
+    Achievement::FiveByFiveWin,
+];
+
+impl Achievement {
+    // The name used in the file.
+    fn id(self) -> &'static str {
+        match self {
+            Achievement::FirstWin => "first_win",
+            Achievement::WinWithoutCenter => "win_without_center",
+            Achievement::Unbreakable => "unbreakable",
+            Achievement::FiveByFiveWin => "five_by_five_win",
+        }
+    }
+
+    fn description(self) -> String {
+        match self {
+            Achievement::FirstWin => String::from("Win a game against the computer."),
+            Achievement::WinWithoutCenter => {
+                String::from("Win a game without ever taking the center.")
+            }
+            Achievement::Unbreakable => {
+                format!("Draw {HARD_DRAWS} games in a row against the hard bot.")
+            }
+            Achievement::FiveByFiveWin => String::from("Win a 5x5 game against the computer."),
+        }
+    }
+}
+
+impl Display for Achievement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let title = match self {
+            Achievement::FirstWin => "First win",
+            Achievement::WinWithoutCenter => "Off center",
+            Achievement::Unbreakable => "Unbreakable",
+            Achievement::FiveByFiveWin => "Wide open",
+        };
+
+        write!(f, "{title}")
+    }
+}
+
+impl FromStr for Achievement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ACHIEVEMENTS
+            .into_iter()
+            .find(|achievement| achievement.id() == s)
+            .ok_or_else(|| format!("'{s}' is not an achievement."))
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Achievements {
+    unlocked: BTreeSet<Achievement>,
+    // Draws in a row against the hard bot, towards Unbreakable.
+    hard_draws: u32,
+}
+
+impl Achievements {
+    // Counts a game Player 1 played against opponent, returning the
+    // achievements it unlocked.
+    pub fn update(
+        &mut self,
+        opponent: &BotSpec,
+        record: &GameRecord,
+        outcome: Outcome,
+    ) -> Vec<Achievement> {
+        let won = outcome.winner() == Some(Player::One);
+        let mine = Symbol::from(Player::One);
+        // The center may have been Player 1's from the start.
+        let took_center = record.start().tiles[1][1] == mine
+            || record
+                .moves()
+                .iter()
+                .any(|(symbol, position)| *symbol == mine && *position == [1, 1]);
+
+        if *opponent == BotSpec::Builtin(Difficulty::Hard) {
+            self.hard_draws = match outcome.winner() {
+                None => self.hard_draws + 1,
+                Some(_) => 0,
+            };
+        }
+
+        let earned = [
+            (Achievement::FirstWin, won),
+            (Achievement::WinWithoutCenter, won && !took_center),
+            (Achievement::Unbreakable, self.hard_draws >= HARD_DRAWS),
+        ];
+        self.unlock(earned)
+    }
+
+    // Counts a game Player 1 played against the computer on a bigger board,
+    // which only wins count for.
+    pub fn update_sized(&mut self, size: usize, outcome: Outcome) -> Vec<Achievement> {
+        let won = outcome.winner() == Some(Player::One);
+        self.unlock([
+            (Achievement::FirstWin, won),
+            (Achievement::FiveByFiveWin, won && size == 5),
+        ])
+    }
+
+    // The earned achievements that were not unlocked before.
+    fn unlock(
+        &mut self,
+        earned: impl IntoIterator<Item = (Achievement, bool)>,
+    ) -> Vec<Achievement> {
+        earned
+            .into_iter()
+            .filter(|(achievement, earned)| *earned && self.unlocked.insert(*achievement))
+            .map(|(achievement, _)| achievement)
+            .collect()
+    }
+
+    // Keeps the achievements unlocked in either, returning how many are new.
+    // The draw streak is the longer one, since games cannot be interleaved.
+    pub fn merge(&mut self, other: Achievements) -> usize {
+        let before = self.unlocked.len();
+        self.unlocked.extend(other.unlocked);
+        self.hard_draws = self.hard_draws.max(other.hard_draws);
+        self.unlocked.len() - before
+    }
+
+    // Every achievement, unlocked or not, with the progress towards streaks.
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        for achievement in ACHIEVEMENTS {
+            let mark = if self.unlocked.contains(&achievement) {
+                "x"
+            } else {
+                " "
+            };
+            let _ = write!(
+                report,
+                "[{mark}] {achievement}: {}",
+                achievement.description()
+            );
+            if achievement == Achievement::Unbreakable && !self.unlocked.contains(&achievement) {
+                let _ = write!(report, " ({}/{HARD_DRAWS})", self.hard_draws);
+            }
+            report.push('\n');
+        }
+
+        let _ = write!(
+            report,
+            "\n{} of {} unlocked.",
+            self.unlocked.len(),
+            ACHIEVEMENTS.len()
+        );
+        report
+    }
+}
+
+impl Display for Achievements {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for achievement in &self.unlocked {
+            writeln!(f, "unlocked {}", achievement.id())?;
+        }
+        writeln!(f, "hard_draws {}", self.hard_draws)
+    }
+}
+
+impl FromStr for Achievements {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut achievements = Achievements::default();
+
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            match line.split_once(' ') {
+                Some(("unlocked", id)) => {
+                    achievements.unlocked.insert(id.parse()?);
+                }
+                Some(("hard_draws", games)) => {
+                    achievements.hard_draws = games
+                        .parse()
+                        .map_err(|_| format!("'{games}' is not a number of games."))?;
+                }
+                _ => return Err(format!("'{line}' is not part of the achievements.")),
+            }
+        }
+
+        Ok(achievements)
+    }
+}
+
+// Nothing earned yet is not an error.
+pub fn load(path: &Path) -> Result<Achievements, String> {
+    if !path.exists() {
+        return Ok(Achievements::default());
+    }
+    fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {error}", path.display()))?
+        .parse()
+        .map_err(|error| format!("{}: {error}", path.display()))
+}
+
+// Where a game's achievements are kept and who it is against.
+#[derive(Clone)]
+pub struct AchievementTracker {
+    path: PathBuf,
+    opponent: BotSpec,
+}
+
+impl AchievementTracker {
+    pub fn new(path: PathBuf, opponent: BotSpec) -> AchievementTracker {
+        AchievementTracker { path, opponent }
+    }
+
+    // Returns the achievements the game unlocked.
+    pub fn track(&self, record: &GameRecord, outcome: Outcome) -> Result<Vec<Achievement>, String> {
+        self.save_with(|achievements| achievements.update(&self.opponent, record, outcome))
+    }
+
+    // The same for a game on a bigger board.
+    pub fn track_sized(&self, size: usize, outcome: Outcome) -> Result<Vec<Achievement>, String> {
+        self.save_with(|achievements| achievements.update_sized(size, outcome))
+    }
+
+    fn save_with(
+        &self,
+        update: impl FnOnce(&mut Achievements) -> Vec<Achievement>,
+    ) -> Result<Vec<Achievement>, String> {
+        let mut achievements = load(&self.path)?;
+        let unlocked = update(&mut achievements);
+        fs::write(&self.path, achievements.to_string()).map_err(|error| {
+            format!(
+                "Could not save the achievements to {}: {error}",
+                self.path.display()
+            )
+        })?;
+        Ok(unlocked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{DrawReason, WinReason};
+
+    const HARD: BotSpec = BotSpec::Builtin(Difficulty::Hard);
+
+    #[test]
+    fn wins_unlock_achievements_once() {
+        let mut achievements = Achievements::default();
+        let won = Outcome::Winner(Player::One, WinReason::Line);
+        let corners: GameRecord = "---/---/--- +0,0 o1,1 +0,2 o0,1 +2,0 o1,0 +2,2"
+            .parse()
+            .unwrap();
+        let center: GameRecord = "---/---/--- +1,1 o0,1 +0,0 o0,2 +2,2".parse().unwrap();
+
+        let medium = BotSpec::Builtin(Difficulty::Medium);
+        assert_eq!(
+            achievements.update(&medium, &center, won),
+            [Achievement::FirstWin]
+        );
+        assert_eq!(
+            achievements.update(&medium, &corners, won),
+            [Achievement::WinWithoutCenter]
+        );
+        assert!(achievements.update(&medium, &corners, won).is_empty());
+
+        let mut achievements = Achievements::default();
+        let started_in_the_center: GameRecord = "---/-+-/--- o0,1 +0,0 o0,2 +2,2".parse().unwrap();
+        assert_eq!(
+            achievements.update(&medium, &started_in_the_center, won),
+            [Achievement::FirstWin]
+        );
+    }
+
+    #[test]
+    fn draws_against_the_hard_bot_must_be_in_a_row() {
+        let mut achievements = Achievements::default();
+        let record: GameRecord = "---/---/---".parse().unwrap();
+        let drawn = Outcome::Draw(DrawReason::FullBoard);
+
+        for _ in 0..HARD_DRAWS - 1 {
+            assert!(achievements.update(&HARD, &record, drawn).is_empty());
+        }
+        achievements.update(
+            &HARD,
+            &record,
+            Outcome::Winner(Player::Two, WinReason::Line),
+        );
+        assert_eq!(achievements.hard_draws, 0);
+
+        for _ in 0..HARD_DRAWS - 1 {
+            achievements.update(&HARD, &record, drawn);
+        }
+        assert!(achievements.report().contains("(9/10)"));
+        assert_eq!(
+            achievements.update(&HARD, &record, drawn),
+            [Achievement::Unbreakable]
+        );
+    }
+
+    #[test]
+    fn only_wins_on_five_by_five_boards_count_as_such() {
+        let mut achievements = Achievements::default();
+        let won = Outcome::Winner(Player::One, WinReason::Line);
+
+        assert!(achievements
+            .update_sized(5, Outcome::Draw(DrawReason::FullBoard))
+            .is_empty());
+        assert_eq!(achievements.update_sized(4, won), [Achievement::FirstWin]);
+        assert_eq!(
+            achievements.update_sized(5, won),
+            [Achievement::FiveByFiveWin]
+        );
+        assert!(achievements
+            .report()
+            .contains("[x] Wide open: Win a 5x5 game"));
+        assert_eq!(
+            "unlocked five_by_five_win".parse::<Achievements>(),
+            Ok(Achievements {
+                unlocked: BTreeSet::from([Achievement::FiveByFiveWin]),
+                hard_draws: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn achievements_round_trip() {
+        let mut achievements = Achievements::default();
+        achievements.unlocked.insert(Achievement::FirstWin);
+        achievements.hard_draws = 4;
+
+        let text = achievements.to_string();
+        assert_eq!(text, "unlocked first_win\nhard_draws 4\n");
+        assert_eq!(text.parse(), Ok(achievements));
+        assert!("unlocked everything".parse::<Achievements>().is_err());
+    }
+
+    #[test]
+    fn merging_keeps_achievements_from_both() {
+        let mut ours: Achievements = "unlocked first_win\nhard_draws 2".parse().unwrap();
+        let theirs: Achievements = "unlocked first_win\nunlocked unbreakable\nhard_draws 0"
+            .parse()
+            .unwrap();
+
+        assert_eq!(ours.merge(theirs), 1);
+        assert_eq!(
+            ours.to_string(),
+            "unlocked first_win\nunlocked unbreakable\nhard_draws 2\n"
+        );
+    }
+}
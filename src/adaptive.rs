@@ -0,0 +1,206 @@
+// A computer opponent that adjusts to the player. It plays the best move
+// apart from a chance of a random mistake, which drops after every game the
+// player wins and rises once the player has lost two in a row, and is kept
+// across sessions.
+//
+// The file has a "mistakes <percent>" and a "losing_streak <games>" line.
+
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::ai::{self, Difficulty};
+use crate::board::{Board, Symbol};
+use crate::bot::Bot;
+use crate::profile;
+use crate::rng::Rng;
+use crate::tournament::Record;
+
+// Where the strength is kept unless TTT_ADAPTIVE says otherwise.
+pub const DEFAULT_ADAPTIVE: &str = "tic-tac-toe.adaptive";
+
+// A new player starts against a bot that blunders fairly often.
+const START_MISTAKES: u32 = 40;
+const MAX_MISTAKES: u32 = 90;
+// How much the chance of a mistake drops after the player wins a game.
+const STRONGER: u32 = 10;
+// How much it rises for each loss once the player has lost this many in a row.
+const EASIER: u32 = 10;
+const EASE_OFF_AFTER: u32 = 2;
+
+pub fn path() -> PathBuf {
+    std::env::var_os("TTT_ADAPTIVE").map_or_else(|| profile::file(DEFAULT_ADAPTIVE), PathBuf::from)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Strength {
+    // The chance in percent of playing a random move instead of the best one.
+    pub mistakes: u32,
+    // Games the player has lost since they last won or drew.
+    pub losing_streak: u32,
+}
+
+impl Default for Strength {
+    fn default() -> Self {
+        Strength {
+            mistakes: START_MISTAKES,
+            losing_streak: 0,
+        }
+    }
+}
+
+impl Strength {
+    // Adjusts to a game's result, counted from the bot's side.
+    pub fn adjust(&mut self, result: Record) {
+        if result.losses > 0 {
+            self.mistakes = self.mistakes.saturating_sub(STRONGER);
+            self.losing_streak = 0;
+        } else if result.wins > 0 {
+            self.losing_streak += 1;
+            if self.losing_streak >= EASE_OFF_AFTER {
+                self.mistakes = (self.mistakes + EASIER).min(MAX_MISTAKES);
+            }
+        } else {
+            self.losing_streak = 0;
+        }
+    }
+}
+
+impl Display for Strength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "mistakes {}", self.mistakes)?;
+        writeln!(f, "losing_streak {}", self.losing_streak)
+    }
+}
+
+impl FromStr for Strength {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut strength = Strength::default();
+
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            let number = |value: &str| {
+                value
+                    .parse::<u32>()
+                    .map_err(|_| format!("'{line}' does not hold a number."))
+            };
+            match line.split_once(' ') {
+                Some(("mistakes", value)) => strength.mistakes = number(value)?.min(MAX_MISTAKES),
+                Some(("losing_streak", value)) => strength.losing_streak = number(value)?,
+                _ => {
+                    return Err(format!(
+                        "'{line}' is not part of the adaptive bot's strength."
+                    ))
+                }
+            }
+        }
+
+        Ok(strength)
+    }
+}
+
+pub struct AdaptiveBot {
+    path: PathBuf,
+    strength: Strength,
+    rng: Rng,
+}
+
+impl AdaptiveBot {
+    // A player without a saved strength starts from the default.
+    pub fn load(path: &Path) -> Result<AdaptiveBot, String> {
+        let strength = if path.exists() {
+            fs::read_to_string(path)
+                .map_err(|error| format!("Could not read {}: {error}", path.display()))?
+                .parse()
+                .map_err(|error| format!("{}: {error}", path.display()))?
+        } else {
+            Strength::default()
+        };
+
+        Ok(AdaptiveBot {
+            path: path.to_path_buf(),
+            strength,
+            rng: Rng::from_time(),
+        })
+    }
+}
+
+impl Bot for AdaptiveBot {
+    fn name(&self) -> String {
+        format!("Adaptive computer ({}% mistakes)", self.strength.mistakes)
+    }
+
+    fn choose_move(&mut self, board: &Board, symbol: Symbol) -> Option<[usize; 2]> {
+        // An easy move is a random one.
+        let difficulty = if self.rng.below(100) < self.strength.mistakes as usize {
+            Difficulty::Easy
+        } else {
+            Difficulty::Hard
+        };
+        ai::choose_move(board, symbol, difficulty, &mut self.rng)
+    }
+
+    fn game_over(&mut self, result: Record) -> Result<(), String> {
+        self.strength.adjust(result);
+        fs::write(&self.path, self.strength.to_string()).map_err(|error| {
+            format!(
+                "Could not save the adaptive bot to {}: {error}",
+                self.path.display()
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(wins: u32, draws: u32, losses: u32) -> Record {
+        Record {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    #[test]
+    fn strength_follows_the_players_results() {
+        let mut strength = Strength::default();
+        let player_won = result(0, 0, 1);
+        let player_lost = result(1, 0, 0);
+
+        strength.adjust(player_won);
+        assert_eq!(strength.mistakes, START_MISTAKES - STRONGER);
+
+        // A single loss is not enough to ease off.
+        strength.adjust(player_lost);
+        assert_eq!(strength.mistakes, START_MISTAKES - STRONGER);
+        strength.adjust(player_lost);
+        strength.adjust(player_lost);
+        assert_eq!(strength.mistakes, START_MISTAKES + EASIER);
+        assert_eq!(strength.losing_streak, 3);
+
+        strength.adjust(result(0, 1, 0));
+        assert_eq!(strength.losing_streak, 0);
+
+        for _ in 0..20 {
+            strength.adjust(player_won);
+        }
+        assert_eq!(strength.mistakes, 0);
+    }
+
+    #[test]
+    fn strength_round_trips() {
+        let strength = Strength {
+            mistakes: 70,
+            losing_streak: 2,
+        };
+
+        assert_eq!(strength.to_string().parse(), Ok(strength));
+        assert_eq!("".parse(), Ok(Strength::default()));
+        assert!("mistakes many".parse::<Strength>().is_err());
+        assert!("skill 3".parse::<Strength>().is_err());
+    }
+}
@@ -0,0 +1,363 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::board::{Board, Symbol};
+use crate::rng::Rng;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard]
+            .into_iter()
+            .find(|difficulty| difficulty.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("'{s}' is not a difficulty, use easy, medium or hard."))
+    }
+}
+
+pub fn choose_move(
+    board: &Board,
+    symbol: Symbol,
+    difficulty: Difficulty,
+    rng: &mut Rng,
+) -> Option<[usize; 2]> {
+    let empty_tiles = board.empty_tiles().count();
+
+    if empty_tiles == 0 || board.winner().is_some() {
+        return None;
+    }
+
+    let mut random_move = || board.empty_tiles().nth(rng.below(empty_tiles));
+    match difficulty {
+        Difficulty::Easy => random_move(),
+        Difficulty::Medium => winning_move(board, symbol)
+            .or_else(|| winning_move(board, symbol.opponent()))
+            .or_else(random_move),
+        Difficulty::Hard => best_move(board, symbol),
+    }
+}
+
+// The computer takes a draw unless it can still force a win from here.
+pub fn accepts_draw(board: &Board, symbol: Symbol) -> bool {
+    negamax(board, symbol, 1, false) <= 0
+}
+
+fn winning_move(board: &Board, symbol: Symbol) -> Option<[usize; 2]> {
+    board
+        .empty_tiles()
+        .find(|position| board.clone().place(symbol, *position))
+}
+
+// The negamax score of every legal move for symbol.
+pub fn move_scores(board: &Board, symbol: Symbol) -> Vec<([usize; 2], i32)> {
+    scored_moves(board, symbol, false).collect()
+}
+
+// The same for misère, where completing a line loses.
+pub fn misere_move_scores(board: &Board, symbol: Symbol) -> Vec<([usize; 2], i32)> {
+    scored_moves(board, symbol, true).collect()
+}
+
+// A best misère move, picked at random among equally good ones.
+pub fn misere_move(board: &Board, symbol: Symbol, rng: &mut Rng) -> Option<[usize; 2]> {
+    if board.winner().is_some() {
+        return None;
+    }
+    let scores = misere_move_scores(board, symbol);
+    let best = scores.iter().map(|(_, score)| *score).max()?;
+    let best_moves = scores
+        .iter()
+        .filter(|(_, score)| *score == best)
+        .collect::<Vec<_>>();
+    Some(best_moves[rng.below(best_moves.len())].0)
+}
+
+pub fn misere_accepts_draw(board: &Board, symbol: Symbol) -> bool {
+    negamax(board, symbol, 1, true) <= 0
+}
+
+// Scores the moves as they are asked for, so the search does not allocate.
+fn scored_moves(
+    board: &Board,
+    symbol: Symbol,
+    misere: bool,
+) -> impl Iterator<Item = ([usize; 2], i32)> + '_ {
+    board.empty_tiles().map(move |position| {
+        let mut next = board.clone();
+        let score = if next.place(symbol, position) {
+            line_score(1, misere)
+        } else {
+            -negamax(&next, symbol.opponent(), 2, misere)
+        };
+        (position, score)
+    })
+}
+
+fn best_move(board: &Board, symbol: Symbol) -> Option<[usize; 2]> {
+    let mut best: Option<([usize; 2], i32)> = None;
+
+    for (position, score) in scored_moves(board, symbol, false) {
+        if best.is_none_or(|(_, best_score)| score > best_score) {
+            best = Some((position, score));
+        }
+    }
+
+    best.map(|(position, _)| position)
+}
+
+// Samples a move with probability growing with exp(score / temperature).
+// Higher temperatures spread the choice over weaker moves, and a temperature
+// of zero picks randomly among the best ones.
+pub fn sample_move(
+    board: &Board,
+    symbol: Symbol,
+    temperature: f64,
+    rng: &mut Rng,
+) -> Option<[usize; 2]> {
+    if board.winner().is_some() {
+        return None;
+    }
+
+    let scores = move_scores(board, symbol);
+    let best = scores.iter().map(|(_, score)| *score).max()?;
+
+    if temperature <= 0.0 {
+        let best_moves = scores
+            .iter()
+            .filter(|(_, score)| *score == best)
+            .collect::<Vec<_>>();
+        return Some(best_moves[rng.below(best_moves.len())].0);
+    }
+
+    // Shifting by the best score keeps exp from overflowing.
+    let weights = scores
+        .iter()
+        .map(|(_, score)| (f64::from(score - best) / temperature).exp())
+        .collect::<Vec<_>>();
+    let mut pick = rng.next_f64() * weights.iter().sum::<f64>();
+
+    for ((position, _), weight) in scores.iter().zip(&weights) {
+        if pick < *weight {
+            return Some(*position);
+        }
+        pick -= weight;
+    }
+
+    scores.last().map(|(position, _)| *position)
+}
+
+// Scores a board without a winner from the point of view of the symbol to move,
+// where depth is the ply about to be played. Quicker wins and slower losses
+// score better, so the computer doesn't toy with its opponent.
+// A full board is a draw, scoring 0.
+fn negamax(board: &Board, to_move: Symbol, depth: i32, misere: bool) -> i32 {
+    board
+        .empty_tiles()
+        .map(|position| {
+            let mut next = board.clone();
+            if next.place(to_move, position) {
+                line_score(depth, misere)
+            } else {
+                -negamax(&next, to_move.opponent(), depth + 1, misere)
+            }
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+// Completing a line at depth, a win that is better the sooner it comes, or
+// in misère a loss that is better the later it comes.
+fn line_score(depth: i32, misere: bool) -> i32 {
+    if misere {
+        depth - 10
+    } else {
+        10 - depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocations;
+    use crate::board::GameStatus;
+
+    #[test]
+    fn no_move_on_finished_board() {
+        let mut full_board = Board::new();
+        full_board.tiles = [[Symbol::Plus; 3]; 3];
+
+        let mut won_board = Board::new();
+        won_board.tiles[0] = [Symbol::Plus; 3];
+
+        for board in [full_board, won_board] {
+            for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+                assert_eq!(
+                    choose_move(&board, Symbol::Circle, difficulty, &mut Rng::new(1)),
+                    None
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn medium_and_hard_take_the_win() {
+        let mut board = Board::new();
+        board.tiles[0][0] = Symbol::Circle;
+        board.tiles[0][1] = Symbol::Circle;
+        board.tiles[1][0] = Symbol::Plus;
+        board.tiles[1][1] = Symbol::Plus;
+
+        for difficulty in [Difficulty::Medium, Difficulty::Hard] {
+            assert_eq!(
+                choose_move(&board, Symbol::Circle, difficulty, &mut Rng::new(1)),
+                Some([0, 2])
+            );
+        }
+    }
+
+    #[test]
+    fn medium_and_hard_block_the_opponent() {
+        let mut board = Board::new();
+        board.tiles[0][0] = Symbol::Plus;
+        board.tiles[1][1] = Symbol::Plus;
+        board.tiles[0][1] = Symbol::Circle;
+
+        for difficulty in [Difficulty::Medium, Difficulty::Hard] {
+            assert_eq!(
+                choose_move(&board, Symbol::Circle, difficulty, &mut Rng::new(1)),
+                Some([2, 2])
+            );
+        }
+    }
+
+    #[test]
+    fn draw_is_declined_when_a_win_is_forced() {
+        let mut board = Board::new();
+        board.tiles[0][0] = Symbol::Circle;
+        board.tiles[0][1] = Symbol::Circle;
+        board.tiles[1][0] = Symbol::Plus;
+        board.tiles[1][1] = Symbol::Plus;
+
+        assert!(!accepts_draw(&board, Symbol::Circle));
+        assert!(accepts_draw(&Board::new(), Symbol::Circle));
+    }
+
+    #[test]
+    fn sampling_at_zero_temperature_only_plays_best_moves() {
+        let board: Board = "oo-/++-/---".parse().unwrap();
+        let mut rng = Rng::new(4);
+
+        for _ in 0..20 {
+            assert_eq!(
+                sample_move(&board, Symbol::Circle, 0.0, &mut rng),
+                Some([0, 2])
+            );
+        }
+    }
+
+    #[test]
+    fn sampling_varies_but_avoids_blunders() {
+        let mut rng = Rng::new(5);
+        let board: Board = "+--/-o-/---".parse().unwrap();
+        let replies = (0..50)
+            .filter_map(|_| sample_move(&board, Symbol::Plus, 1.0, &mut rng))
+            .collect::<std::collections::HashSet<_>>();
+        // Every reply holds the draw, so several of them get played.
+        assert!(replies.len() > 3);
+
+        // Missing the block loses, which is far too costly to ever be picked.
+        let board: Board = "++-/-o-/---".parse().unwrap();
+        for _ in 0..50 {
+            assert_eq!(
+                sample_move(&board, Symbol::Circle, 1.0, &mut rng),
+                Some([0, 2])
+            );
+        }
+    }
+
+    #[test]
+    fn hard_never_loses_against_easy() {
+        let mut rng = Rng::new(3);
+
+        for _ in 0..20 {
+            let mut board = Board::new();
+            let mut to_move = Symbol::Plus;
+
+            while board.status() == GameStatus::InProgress {
+                let difficulty = if to_move == Symbol::Plus {
+                    Difficulty::Easy
+                } else {
+                    Difficulty::Hard
+                };
+                let position = choose_move(&board, to_move, difficulty, &mut rng).unwrap();
+                board.place(to_move, position);
+                to_move = to_move.opponent();
+            }
+
+            assert_ne!(board.winner(), Some(Symbol::Plus));
+        }
+    }
+
+    #[test]
+    fn misere_play_never_completes_a_line_against_easy() {
+        let mut rng = Rng::new(11);
+        // Completing the top row would lose in misère.
+        let board: Board = "oo-/+--/+--".parse().unwrap();
+        for _ in 0..20 {
+            assert_ne!(misere_move(&board, Symbol::Circle, &mut rng), Some([0, 2]));
+        }
+
+        for _ in 0..20 {
+            let mut board = Board::new();
+            let mut to_move = Symbol::Plus;
+
+            while board.status() == GameStatus::InProgress {
+                let position = if to_move == Symbol::Plus {
+                    choose_move(&board, to_move, Difficulty::Easy, &mut rng)
+                } else {
+                    misere_move(&board, to_move, &mut rng)
+                };
+                board.place(to_move, position.unwrap());
+                to_move = to_move.opponent();
+            }
+
+            assert_ne!(board.winner(), Some(Symbol::Circle));
+        }
+    }
+
+    #[test]
+    fn searching_does_not_allocate() {
+        let mut rng = Rng::new(5);
+        let board: Board = "+--/-o-/---".parse().unwrap();
+
+        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            let (choice, allocations) =
+                allocations::count(|| choose_move(&board, Symbol::Plus, difficulty, &mut rng));
+            assert!(choice.is_some());
+            assert_eq!(allocations, 0, "{difficulty} allocated");
+        }
+        assert_eq!(
+            allocations::count(|| accepts_draw(&board, Symbol::Plus)).1,
+            0
+        );
+    }
+}
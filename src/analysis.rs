@@ -0,0 +1,199 @@
+// Monte Carlo estimates: every legal move is followed by random playouts and
+// the results are counted. Much cheaper than solving the position exactly,
+// and the percentages are easier to read than a minimax score.
+
+use std::fmt::Write as _;
+
+use crate::board::{Board, GameStatus, Symbol};
+use crate::rng::Rng;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MoveEstimate {
+    pub position: [usize; 2],
+    // Counted for the side that made the move.
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MoveEstimate {
+    fn playouts(self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    fn percentage(self, count: u32) -> f64 {
+        f64::from(count) * 100.0 / f64::from(self.playouts().max(1))
+    }
+
+    pub fn win_percentage(self) -> f64 {
+        self.percentage(self.wins)
+    }
+
+    pub fn draw_percentage(self) -> f64 {
+        self.percentage(self.draws)
+    }
+
+    pub fn loss_percentage(self) -> f64 {
+        self.percentage(self.losses)
+    }
+
+    // Expected points with a draw as half a win, used for ranking the moves.
+    fn score(self) -> f64 {
+        self.win_percentage() + self.draw_percentage() / 2.0
+    }
+}
+
+// Plus moves first, so Plus is to move whenever both have as many marks.
+pub fn side_to_move(board: &Board) -> Symbol {
+    let count = |symbol| {
+        board
+            .tiles
+            .iter()
+            .flatten()
+            .filter(|tile| **tile == symbol)
+            .count()
+    };
+
+    if count(Symbol::Plus) > count(Symbol::Circle) {
+        Symbol::Circle
+    } else {
+        Symbol::Plus
+    }
+}
+
+// Plays random moves until the game ends and returns the winner, if any.
+fn playout(mut board: Board, mut to_move: Symbol, rng: &mut Rng) -> Option<Symbol> {
+    loop {
+        match board.status() {
+            GameStatus::Won(winner, _) => return Some(winner),
+            GameStatus::Draw => return None,
+            GameStatus::InProgress => {}
+        }
+
+        let empty = board.empty_positions();
+        board.place(to_move, empty[rng.below(empty.len())]);
+        to_move = to_move.opponent();
+    }
+}
+
+// Estimates every legal move for symbol, best first. Finished games have no
+// moves to estimate.
+pub fn estimate_moves(
+    board: &Board,
+    symbol: Symbol,
+    playouts: u32,
+    rng: &mut Rng,
+) -> Vec<MoveEstimate> {
+    if board.status() != GameStatus::InProgress {
+        return Vec::new();
+    }
+
+    let mut estimates = board
+        .empty_positions()
+        .into_iter()
+        .map(|position| {
+            let mut next = board.clone();
+            next.place(symbol, position);
+
+            let mut estimate = MoveEstimate {
+                position,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+            };
+            for _ in 0..playouts {
+                match playout(next.clone(), symbol.opponent(), rng) {
+                    Some(winner) if winner == symbol => estimate.wins += 1,
+                    Some(_) => estimate.losses += 1,
+                    None => estimate.draws += 1,
+                }
+            }
+            estimate
+        })
+        .collect::<Vec<_>>();
+
+    estimates.sort_by(|a, b| b.score().total_cmp(&a.score()));
+    estimates
+}
+
+pub fn format_estimates(estimates: &[MoveEstimate]) -> String {
+    let mut table = format!(
+        "{:<4}  {:>6}  {:>6}  {:>6}\n",
+        "Move", "Win", "Draw", "Loss"
+    );
+
+    for estimate in estimates {
+        let [row, column] = estimate.position;
+        let _ = writeln!(
+            table,
+            "{:<4}  {:>5.1}%  {:>5.1}%  {:>5.1}%",
+            format!("{row},{column}"),
+            estimate.win_percentage(),
+            estimate.draw_percentage(),
+            estimate.loss_percentage()
+        );
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn side_to_move_follows_the_mark_count() {
+        assert_eq!(side_to_move(&Board::new()), Symbol::Plus);
+        assert_eq!(
+            side_to_move(&"+--/---/---".parse().unwrap()),
+            Symbol::Circle
+        );
+        assert_eq!(side_to_move(&"+--/-o-/---".parse().unwrap()), Symbol::Plus);
+    }
+
+    #[test]
+    fn winning_moves_always_win() {
+        let board: Board = "++-/oo-/---".parse().unwrap();
+        let estimates = estimate_moves(&board, Symbol::Plus, 50, &mut Rng::new(1));
+
+        assert_eq!(estimates.len(), 5);
+        assert_eq!(estimates[0].position, [0, 2]);
+        assert_eq!(estimates[0].wins, 50);
+        assert_eq!(estimates[0].playouts(), 50);
+        // Not taking the win hands it to Circle unless the move blocks.
+        let [.., worst] = estimates.as_slice() else {
+            panic!("expected estimates");
+        };
+        assert!(worst.loss_percentage() > 40.0);
+    }
+
+    #[test]
+    fn the_center_is_the_best_opening() {
+        let estimates = estimate_moves(&Board::new(), Symbol::Plus, 2_000, &mut Rng::new(2));
+
+        assert_eq!(estimates.len(), 9);
+        assert_eq!(estimates[0].position, [1, 1]);
+    }
+
+    #[test]
+    fn finished_games_have_no_estimates() {
+        let board: Board = "+++/oo-/---".parse().unwrap();
+
+        assert!(estimate_moves(&board, Symbol::Circle, 10, &mut Rng::new(3)).is_empty());
+    }
+
+    #[test]
+    fn estimates_are_formatted_as_percentages() {
+        let estimate = MoveEstimate {
+            position: [1, 1],
+            wins: 3,
+            draws: 1,
+            losses: 0,
+        };
+
+        assert_eq!(
+            format_estimates(&[estimate]),
+            "Move     Win    Draw    Loss\n1,1    75.0%   25.0%    0.0%\n"
+        );
+    }
+}
@@ -0,0 +1,288 @@
+// Games on this terminal are saved after every move, so a game that is
+// interrupted with Ctrl-C, or lost to a crash, can be picked up again with
+// --resume. The save is removed once the game ends.
+
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::board::Symbol;
+use crate::bot::BotSpec;
+use crate::browser::GameRecord;
+use crate::paths;
+
+// Where games are saved unless TTT_AUTOSAVE says otherwise.
+pub const DEFAULT_AUTOSAVE: &str = "tic-tac-toe.autosave";
+
+// Whether a game is saved right now, for the interrupt handler.
+static GAME_SAVED: AtomicBool = AtomicBool::new(false);
+
+pub fn path() -> PathBuf {
+    std::env::var_os("TTT_AUTOSAVE")
+        .map_or_else(|| paths::data_file(DEFAULT_AUTOSAVE), PathBuf::from)
+}
+
+// A game in progress: who Player 2 is, None for a human, the moves so far,
+// whose turn it is and who still skips their first turn for a handicap.
+#[derive(Clone)]
+pub struct SavedGame {
+    pub opponent: Option<BotSpec>,
+    pub record: GameRecord,
+    pub to_move: Symbol,
+    pub skips: Option<Symbol>,
+}
+
+// The symbol of a "to_move" or "skips" line.
+fn parse_symbol(text: &str) -> Result<Symbol, String> {
+    let mut chars = text.chars();
+    match (chars.next().map(Symbol::try_from), chars.next()) {
+        (Some(Ok(symbol)), None) if symbol != Symbol::Empty => Ok(symbol),
+        _ => Err(format!("'{text}' is not + or o.")),
+    }
+}
+
+// "opponent <human|bot>", "record <game record>", "to_move <symbol>" and,
+// while it is pending, "skips <symbol>" lines.
+impl Display for SavedGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let opponent = self
+            .opponent
+            .as_ref()
+            .map_or_else(|| String::from("human"), BotSpec::argument);
+        let symbol_name = |symbol: Symbol| -> &str { symbol.into() };
+        writeln!(f, "opponent {opponent}")?;
+        writeln!(f, "record {}", self.record)?;
+        writeln!(f, "to_move {}", symbol_name(self.to_move))?;
+        if let Some(skips) = self.skips {
+            writeln!(f, "skips {}", symbol_name(skips))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SavedGame {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut opponent = None;
+        let mut record = None;
+        let mut to_move = None;
+        let mut skips = None;
+
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            match line.split_once(' ') {
+                Some(("opponent", "human")) => opponent = Some(None),
+                Some(("opponent", bot)) => opponent = Some(Some(bot.parse()?)),
+                Some(("record", text)) => record = Some(text.parse::<GameRecord>()?),
+                Some(("to_move", symbol)) => to_move = Some(parse_symbol(symbol)?),
+                Some(("skips", symbol)) => skips = Some(parse_symbol(symbol)?),
+                _ => return Err(format!("'{line}' is not part of a saved game.")),
+            }
+        }
+
+        let record = record.ok_or_else(|| String::from("The saved game has no moves."))?;
+        Ok(SavedGame {
+            opponent: opponent.ok_or_else(|| String::from("The saved game has no opponent."))?,
+            // Saves from before the turn was kept go by the moves.
+            to_move: to_move.unwrap_or_else(|| record.to_move()),
+            record,
+            skips,
+        })
+    }
+}
+
+// Where a game saves itself and who it is against.
+#[derive(Clone)]
+pub struct Autosave {
+    path: PathBuf,
+    opponent: Option<BotSpec>,
+}
+
+impl Autosave {
+    pub fn new(path: PathBuf, opponent: Option<BotSpec>) -> Autosave {
+        Autosave { path, opponent }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn save(
+        &self,
+        record: &GameRecord,
+        to_move: Symbol,
+        skips: Option<Symbol>,
+    ) -> Result<(), String> {
+        let game = SavedGame {
+            opponent: self.opponent.clone(),
+            record: record.clone(),
+            to_move,
+            skips,
+        };
+        fs::write(&self.path, game.to_string())
+            .map_err(|error| format!("Could not autosave to {}: {error}", self.path.display()))?;
+        GAME_SAVED.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    // Called once the game has ended, so there is nothing to resume.
+    pub fn remove(&self) {
+        GAME_SAVED.store(false, Ordering::SeqCst);
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+pub fn load(path: &Path) -> Result<SavedGame, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {error}", path.display()))?;
+    text.parse()
+        .map_err(|error| format!("{} is not a saved game: {error}", path.display()))
+}
+
+#[cfg(unix)]
+mod signal {
+    use std::ffi::{c_int, c_void};
+
+    pub const SIGINT: c_int = 2;
+
+    extern "C" {
+        // None is SIG_DFL, the default handler.
+        pub fn signal(signum: c_int, handler: Option<extern "C" fn(c_int)>) -> usize;
+        pub fn write(fd: c_int, buf: *const c_void, count: usize) -> isize;
+        pub fn _exit(status: c_int) -> !;
+    }
+}
+
+// Built before the handler is installed, since a signal handler must not allocate.
+#[cfg(unix)]
+static INTERRUPTED_MESSAGE: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+#[cfg(unix)]
+extern "C" fn interrupted(_: std::ffi::c_int) {
+    if GAME_SAVED.load(Ordering::SeqCst) {
+        if let Some(message) = INTERRUPTED_MESSAGE.get() {
+            // SAFETY: write is async-signal-safe and the message is never changed.
+            unsafe { signal::write(2, message.as_ptr().cast(), message.len()) };
+        }
+    }
+    // SAFETY: _exit is async-signal-safe and skips the destructors that exit would run.
+    unsafe { signal::_exit(130) }
+}
+
+// Ctrl-C handling for as long as a game is autosaved. Dropping it puts the
+// default handler back.
+pub struct Interrupts;
+
+impl Drop for Interrupts {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: the default handler needs nothing from the program.
+        unsafe {
+            signal::signal(signal::SIGINT, None);
+        }
+    }
+}
+
+impl Autosave {
+    // Makes Ctrl-C tell the player how to resume the saved game, until the
+    // result is dropped. Elsewhere the game is still saved, only without
+    // the message.
+    pub fn handle_interrupts(&self) -> Interrupts {
+        #[cfg(unix)]
+        {
+            INTERRUPTED_MESSAGE.get_or_init(|| {
+                format!(
+                    "\nInterrupted, the game is saved in {0}. Resume it with: tic-tac-toe-rust --resume {0}\n",
+                    self.path.display()
+                )
+            });
+            // SAFETY: interrupted only calls async-signal-safe functions.
+            unsafe {
+                signal::signal(signal::SIGINT, Some(interrupted));
+            }
+        }
+        Interrupts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+
+    #[test]
+    fn saved_games_round_trip() {
+        let game = SavedGame {
+            opponent: Some(BotSpec::Builtin(Difficulty::Hard)),
+            record: "---/---/--- +1,1 o0,0".parse().unwrap(),
+            to_move: Symbol::Plus,
+            skips: None,
+        };
+
+        let text = game.to_string();
+        assert_eq!(
+            text,
+            "opponent hard\nrecord ---/---/--- +1,1 o0,0\nto_move +\n"
+        );
+        let parsed: SavedGame = text.parse().unwrap();
+        assert_eq!(parsed.opponent, game.opponent);
+        assert_eq!(parsed.record.to_string(), game.record.to_string());
+        assert_eq!(parsed.to_move, Symbol::Plus);
+
+        let human: SavedGame = "opponent human\nrecord ---/---/---".parse().unwrap();
+        assert!(human.opponent.is_none());
+        assert_eq!(human.to_move, Symbol::Plus);
+    }
+
+    #[test]
+    fn handicap_turns_are_saved() {
+        // Player 1 was given an extra move: o still skips its first turn.
+        let game: SavedGame = "opponent human\nrecord ---/---/---\nto_move +\nskips o\n"
+            .parse()
+            .unwrap();
+        assert_eq!(game.to_move, Symbol::Plus);
+        assert_eq!(game.skips, Some(Symbol::Circle));
+        assert!(game.to_string().ends_with("to_move +\nskips o\n"));
+
+        // After the skip + moves again, which the moves alone do not tell.
+        let game: SavedGame = "opponent human\nrecord ---/---/--- +1,1\nto_move +"
+            .parse()
+            .unwrap();
+        assert_eq!(game.to_move, Symbol::Plus);
+        assert_eq!(game.skips, None);
+
+        for text in ["to_move -", "to_move x", "skips +o"] {
+            let text = format!("opponent human\nrecord ---/---/---\n{text}");
+            assert!(text.parse::<SavedGame>().is_err(), "{text}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn interrupts_are_only_handled_during_a_game() {
+        let autosave = Autosave::new(PathBuf::from("game.autosave"), None);
+        let interrupts = autosave.handle_interrupts();
+        // SAFETY: interrupted only calls async-signal-safe functions.
+        let installed = unsafe { signal::signal(signal::SIGINT, Some(interrupted)) };
+        assert_ne!(installed, 0);
+        drop(interrupts);
+        // SAFETY: the default handler needs nothing from the program.
+        let previous = unsafe { signal::signal(signal::SIGINT, None) };
+        // SIG_DFL is 0.
+        assert_eq!(previous, 0);
+    }
+
+    #[test]
+    fn incomplete_saves_are_rejected() {
+        for text in [
+            "",
+            "opponent hard",
+            "record ---/---/---",
+            "opponent hard\nmoves 1,1",
+        ] {
+            assert!(text.parse::<SavedGame>().is_err());
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// Measures how fast the engine is on a few representative positions, so a
+// slowdown in winner detection, move generation or the search shows up
+// without any outside tooling.
+
+use std::fmt::Write as _;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::ai::{self, Difficulty};
+use crate::board::{Board, Symbol};
+use crate::bot::BuiltinBot;
+use crate::rng::Rng;
+use crate::tournament;
+
+pub const DEFAULT_MILLIS: u64 = 500;
+
+// An empty board, an opening, a middle game, a win and a full board.
+const POSITIONS: [&str; 5] = [
+    "---/---/---",
+    "+--/-o-/---",
+    "+o-/-+-/--o",
+    "+++/oo-/---",
+    "+o+/+o+/o+o",
+];
+
+pub struct Measurement {
+    name: &'static str,
+    iterations: u64,
+    elapsed: Duration,
+}
+
+impl Measurement {
+    fn nanos_per_iteration(&self) -> f64 {
+        // Precision is not a concern at these magnitudes.
+        #[allow(clippy::cast_precision_loss)]
+        let iterations = self.iterations as f64;
+        self.elapsed.as_secs_f64() * 1e9 / iterations
+    }
+}
+
+// Runs f in growing batches until the budget is spent, so quick operations
+// are not dominated by reading the clock.
+fn measure(name: &'static str, budget: Duration, mut f: impl FnMut()) -> Measurement {
+    let mut iterations = 0;
+    let mut batch = 1;
+    let started = Instant::now();
+
+    loop {
+        for _ in 0..batch {
+            f();
+        }
+        iterations += batch;
+
+        let elapsed = started.elapsed();
+        if elapsed >= budget {
+            return Measurement {
+                name,
+                iterations,
+                elapsed,
+            };
+        }
+        batch *= 2;
+    }
+}
+
+// Every benchmark gets the budget. An iteration goes over the positions,
+// except for the full games which start from an empty board.
+pub fn run(budget: Duration) -> Vec<Measurement> {
+    let boards = POSITIONS.map(|position| position.parse::<Board>().expect("valid position"));
+    let playing = &boards[..3];
+    let mut rng = Rng::new(1);
+
+    vec![
+        measure("winner detection (status)", budget, || {
+            for board in &boards {
+                black_box(black_box(board).status());
+            }
+        }),
+        measure("last move check (wins_through)", budget, || {
+            for board in &boards {
+                black_box(black_box(board).wins_through([1, 1]));
+            }
+        }),
+        measure("move generation (empty_tiles)", budget, || {
+            for board in &boards {
+                black_box(black_box(board).empty_tiles().count());
+            }
+        }),
+        measure("move validation (is_valid_move)", budget, || {
+            for board in &boards {
+                black_box(black_box(board).is_valid_move([1, 1]).is_ok());
+            }
+        }),
+        measure("search, hard move", budget, || {
+            for board in playing {
+                black_box(ai::choose_move(
+                    black_box(board),
+                    Symbol::Plus,
+                    Difficulty::Hard,
+                    &mut rng,
+                ));
+            }
+        }),
+        measure("full game, easy against easy", budget, || {
+            let mut first = BuiltinBot::new(Difficulty::Easy, Rng::new(2));
+            let mut second = BuiltinBot::new(Difficulty::Easy, Rng::new(3));
+            black_box(tournament::play_game(&mut first, &mut second));
+        }),
+    ]
+}
+
+pub fn format_report(measurements: &[Measurement]) -> String {
+    let mut report = format!(
+        "{:<34}  {:>12}  {:>14}  {:>14}\n",
+        "Benchmark", "Iterations", "Time/iter", "Iter/s"
+    );
+
+    for measurement in measurements {
+        let nanos = measurement.nanos_per_iteration();
+        let time = if nanos < 1e3 {
+            format!("{nanos:.1} ns")
+        } else if nanos < 1e6 {
+            format!("{:.1} µs", nanos / 1e3)
+        } else {
+            format!("{:.1} ms", nanos / 1e6)
+        };
+        let _ = writeln!(
+            report,
+            "{:<34}  {:>12}  {time:>14}  {:>14.0}",
+            measurement.name,
+            measurement.iterations,
+            1e9 / nanos
+        );
+    }
+
+    let _ = write!(
+        report,
+        "\nEach iteration goes over the positions {},\nthe search only over the ones still being played.",
+        POSITIONS.join(", ")
+    );
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measuring_runs_until_the_budget_is_spent() {
+        let mut calls = 0;
+        let measurement = measure("count", Duration::from_millis(5), || calls += 1);
+
+        assert_eq!(measurement.iterations, calls);
+        assert!(measurement.iterations >= 1);
+        assert!(measurement.elapsed >= Duration::from_millis(5));
+    }
+
+    #[test]
+    fn report_has_a_row_per_benchmark() {
+        let measurements = [Measurement {
+            name: "winner detection (status)",
+            iterations: 4000,
+            elapsed: Duration::from_millis(2),
+        }];
+        let report = format_report(&measurements);
+        let row = report.lines().nth(1).unwrap();
+
+        assert!(row.starts_with("winner detection (status)"));
+        assert!(row.contains("500.0 ns"));
+        assert!(row.ends_with("2000000"));
+    }
+}
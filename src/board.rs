@@ -1,5 +1,11 @@
-use std::convert::Into;
-use std::fmt::Display;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::array;
+use core::fmt::Display;
+use core::str::FromStr;
+
+use crate::formatter::BoardFormatter;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Symbol {
@@ -8,10 +14,118 @@ pub enum Symbol {
     Circle,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+// Why a move cannot be played. The message is only put together when the
+// error is displayed, so rejecting moves does not allocate.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum PlayerMoveError {
-    FilledPosition(String),
-    OutsideBoard(String),
+    // The mark already on the tile, as rendered.
+    FilledPosition {
+        position: [usize; 2],
+        mark: &'static str,
+    },
+    // The highest row and column on the board.
+    OutsideBoard {
+        position: [usize; 2],
+        last: usize,
+    },
+}
+
+impl Display for PlayerMoveError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            PlayerMoveError::FilledPosition {
+                position: [row, column],
+                mark,
+            } => write!(f, "{row},{column} is already taken by {mark}."),
+            PlayerMoveError::OutsideBoard {
+                position: [row, column],
+                last,
+            } => write!(
+                f,
+                "{row},{column} is outside the board, rows and columns go from 0 to {last}."
+            ),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BoardParseError {
+    InvalidSymbol(String),
+    WrongTileCount(String),
+}
+
+// A board that cannot come up in a game. Either side may have started, so
+// the mark counts may differ by one either way.
+#[derive(Debug, PartialEq, Eq)]
+pub enum BoardValidationError {
+    UnbalancedMarks(String),
+    BothSidesWon(String),
+    PlayedAfterWin(String),
+}
+
+impl Display for BoardValidationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BoardValidationError::UnbalancedMarks(msg)
+            | BoardValidationError::BothSidesWon(msg)
+            | BoardValidationError::PlayedAfterWin(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+// What a tile holds. Boards are generic over it so variants with more
+// players, wild marks or other mark sets can reuse the rules; the game's own
+// marks are Symbol.
+pub trait Mark: Copy + Eq {
+    // The mark of a tile nobody has played on.
+    const EMPTY: Self;
+
+    // How the mark is drawn and written in positions, e.g. "+".
+    fn render(self) -> &'static str;
+
+    // The mark written as c in a position, the reverse of render.
+    fn parse(c: char) -> Option<Self>;
+
+    // The side the mark belongs to, counted from 0, for coloring it. None
+    // for empty tiles and marks without sides.
+    fn side(self) -> Option<usize> {
+        None
+    }
+}
+
+impl Mark for Symbol {
+    const EMPTY: Self = Symbol::Empty;
+
+    fn render(self) -> &'static str {
+        self.into()
+    }
+
+    fn parse(c: char) -> Option<Self> {
+        Symbol::try_from(c).ok()
+    }
+
+    fn side(self) -> Option<usize> {
+        match self {
+            Symbol::Empty => None,
+            Symbol::Plus => Some(0),
+            Symbol::Circle => Some(1),
+        }
+    }
+}
+
+impl TryFrom<char> for Symbol {
+    type Error = BoardParseError;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            '-' => Ok(Symbol::Empty),
+            '+' => Ok(Symbol::Plus),
+            'o' => Ok(Symbol::Circle),
+            _ => Err(BoardParseError::InvalidSymbol(format!(
+                "'{value}' is not a valid symbol."
+            ))),
+        }
+    }
 }
 
 impl From<Symbol> for &str {
@@ -24,87 +138,351 @@ impl From<Symbol> for &str {
     }
 }
 
-pub struct Board {
-    pub tiles: [[Symbol; 3]; 3],
+// The positions of a row, column or diagonal, one per tile across the board.
+pub type Line<const N: usize = 3> = [[usize; 2]; N];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GameStatus<const N: usize = 3, M = Symbol> {
+    InProgress,
+    Won(M, Line<N>),
+    Draw,
+}
+
+// Every row, column and diagonal that wins the game, rows first. There are
+// 2N + 2 of them, which stable Rust cannot use as an array length, so they
+// are generated as needed instead of kept in a table.
+pub fn lines<const N: usize>() -> impl Iterator<Item = Line<N>> {
+    let rows = (0..N).map(|row| array::from_fn(|i| [row, i]));
+    let columns = (0..N).map(|column| array::from_fn(|i| [i, column]));
+    // Left to right, then right to left.
+    let diagonals = [
+        array::from_fn(|i| [i, i]),
+        array::from_fn(|i| [i, N - 1 - i]),
+    ];
+
+    rows.chain(columns).chain(diagonals)
+}
+
+// An N by N board on the stack, the classic 3 by 3 one with + and o unless
+// given otherwise.
+#[derive(Clone)]
+pub struct Board<const N: usize = 3, M: Mark = Symbol> {
+    pub tiles: [[M; N]; N],
+}
+
+impl<const N: usize, M: Mark> Default for Board<N, M> {
+    fn default() -> Self {
+        Board::empty()
+    }
+}
+
+impl Symbol {
+    #[must_use]
+    pub fn opponent(self) -> Symbol {
+        match self {
+            Symbol::Empty => Symbol::Empty,
+            Symbol::Plus => Symbol::Circle,
+            Symbol::Circle => Symbol::Plus,
+        }
+    }
 }
 
 impl Board {
+    #[must_use]
     pub fn new() -> Board {
+        Board::empty()
+    }
+}
+
+impl<const N: usize, M: Mark> Board<N, M> {
+    // Checked when a board of that size is first made, so a board without
+    // tiles fails to compile.
+    const HAS_TILES: () = assert!(N > 0, "a board needs at least one tile");
+
+    #[must_use]
+    pub fn empty() -> Board<N, M> {
+        let () = Self::HAS_TILES;
         Board {
-            tiles: [[Symbol::Empty; 3]; 3],
+            tiles: [[M::EMPTY; N]; N],
         }
     }
 
-    pub fn place(&mut self, symbol: Symbol, player_move: [usize; 2]) {
+    // Places the symbol and reports whether that move completed a line.
+    pub fn place(&mut self, symbol: M, player_move: [usize; 2]) -> bool {
         self.tiles[player_move[0]][player_move[1]] = symbol;
+        self.wins_through(player_move)
+    }
+
+    // Only checks the lines going through the given position, so it is much
+    // cheaper than `winner` when the last move is known.
+    #[must_use]
+    pub fn wins_through(&self, position: [usize; 2]) -> bool {
+        let [row, column] = position;
+        let symbol = self.tiles[row][column];
+
+        if symbol == M::EMPTY {
+            return false;
+        }
+
+        let size = self.tiles.len();
+
+        let row_won = self.tiles[row].iter().all(|tile| *tile == symbol);
+        let column_won = self.tiles.iter().all(|tiles| tiles[column] == symbol);
+        let left_to_right_won = row == column && (0..size).all(|i| self.tiles[i][i] == symbol);
+        let right_to_left_won =
+            row + column == size - 1 && (0..size).all(|i| self.tiles[i][size - 1 - i] == symbol);
+
+        row_won || column_won || left_to_right_won || right_to_left_won
     }
 
     pub fn is_valid_move(&self, player_move: [usize; 2]) -> Result<bool, PlayerMoveError> {
-        if player_move[0] > 2 || player_move[1] > 2 {
-            return Err(PlayerMoveError::OutsideBoard(String::from(
-                "The move is invalid because it is outside the board.",
-            )));
+        let [row, column] = player_move;
+        let last = self.tiles.len() - 1;
+        if row > last || column > last {
+            return Err(PlayerMoveError::OutsideBoard {
+                position: player_move,
+                last,
+            });
         }
 
-        if self.tiles[player_move[0]][player_move[1]] != Symbol::Empty {
-            return Err(PlayerMoveError::FilledPosition(String::from(
-                "The position is already filled.",
-            )));
+        let symbol = self.tiles[row][column];
+        if symbol != M::EMPTY {
+            return Err(PlayerMoveError::FilledPosition {
+                position: player_move,
+                mark: symbol.render(),
+            });
         }
 
         Ok(true)
     }
 
-    pub fn winner(&self) -> Option<Symbol> {
-        let board_state = self.tiles;
-
-        // Check for row and column winner.
-        for i in 0..board_state.len() {
-            // Row winner
-            if board_state[i][0] == board_state[i][1] && board_state[i][0] == board_state[i][2] {
-                // Empty cannot be a winner :)
-                if board_state[i][0] != Symbol::Empty {
-                    return Some(board_state[i][0]);
-                }
-            }
+    #[must_use]
+    pub fn empty_positions(&self) -> Vec<[usize; 2]> {
+        self.empty_tiles().collect()
+    }
 
-            // Colum winner
-            if board_state[0][i] == board_state[1][i] && board_state[0][i] == board_state[2][i] {
-                // Empty cannot be a winner :)
-                if board_state[0][i] != Symbol::Empty {
-                    return Some(board_state[0][i]);
-                }
-            }
+    // The same positions as empty_positions without allocating, for searches
+    // and simulations that go through them millions of times.
+    pub fn empty_tiles(&self) -> impl Iterator<Item = [usize; 2]> + '_ {
+        self.tiles.iter().enumerate().flat_map(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, tile)| **tile == M::EMPTY)
+                .map(move |(j, _)| [i, j])
+        })
+    }
+
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.tiles
+            .iter()
+            .all(|row| row.iter().all(|tile| *tile != M::EMPTY))
+    }
+
+    #[must_use]
+    pub fn winner(&self) -> Option<M> {
+        self.winning_line().map(|(symbol, _)| symbol)
+    }
+
+    #[must_use]
+    pub fn status(&self) -> GameStatus<N, M> {
+        match self.winning_line() {
+            Some((symbol, line)) => GameStatus::Won(symbol, line),
+            None if self.is_full() => GameStatus::Draw,
+            None => GameStatus::InProgress,
         }
+    }
+
+    fn count(&self, symbol: M) -> usize {
+        self.tiles
+            .iter()
+            .flatten()
+            .filter(|tile| **tile == symbol)
+            .count()
+    }
+
+    // The winning symbol together with the positions of the line it completed.
+    #[must_use]
+    pub fn winning_line(&self) -> Option<(M, Line<N>)> {
+        lines::<N>().find_map(|line| {
+            let symbol = self.tiles[line[0][0]][line[0][1]];
+
+            // Empty cannot be a winner :)
+            let completed = symbol != M::EMPTY
+                && line
+                    .iter()
+                    .all(|position| self.tiles[position[0]][position[1]] == symbol);
+
+            completed.then_some((symbol, line))
+        })
+    }
+}
 
-        // Left to right winner
-        if board_state[0][0] != Symbol::Empty
-            && board_state[0][0] == board_state[1][1]
-            && board_state[0][0] == board_state[2][2]
-        {
-            return Some(board_state[0][0]);
+// Validation knows about two sides taking turns, so it is for + and o only.
+impl<const N: usize> Board<N> {
+    // Checks that the board can come up in a game: the mark counts differ by
+    // one at most, only one side has won, and nobody moved after the win.
+    pub fn validate(&self) -> Result<(), BoardValidationError> {
+        let (plus, circle) = (self.count(Symbol::Plus), self.count(Symbol::Circle));
+        if plus.abs_diff(circle) > 1 {
+            return Err(BoardValidationError::UnbalancedMarks(format!(
+                "The position has {plus} + and {circle} o, they can differ by one at most."
+            )));
         }
 
-        // Right to left winner
-        if board_state[0][2] != Symbol::Empty
-            && board_state[0][2] == board_state[1][1]
-            && board_state[0][2] == board_state[2][0]
-        {
-            return Some(board_state[0][2]);
+        let completed = lines::<N>()
+            .filter_map(|line| {
+                let symbol = self.tiles[line[0][0]][line[0][1]];
+                (symbol != Symbol::Empty
+                    && line
+                        .iter()
+                        .all(|position| self.tiles[position[0]][position[1]] == symbol))
+                .then_some((symbol, line))
+            })
+            .collect::<Vec<_>>();
+
+        let Some((winner, _)) = completed.first().copied() else {
+            return Ok(());
+        };
+        if completed.iter().any(|(symbol, _)| *symbol != winner) {
+            return Err(BoardValidationError::BothSidesWon(String::from(
+                "Both sides have completed a line, but the game ends with the first one.",
+            )));
         }
 
-        None
+        if self.count(winner) < self.count(winner.opponent()) {
+            let (winner, loser): (&str, &str) = (winner.into(), winner.opponent().into());
+            return Err(BoardValidationError::PlayedAfterWin(format!(
+                "{winner} has won, but {loser} has more marks and so kept playing after the game ended."
+            )));
+        }
+
+        Ok(())
     }
 }
 
-impl Display for Board {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let board_representation = self
-            .tiles
-            .map(|row| format!("| {} |", row.map(Into::<&str>::into).join(" | ")))
-            .join("\n");
+// `{board:#}` gives the compact single-line form instead of the grid.
+impl<const N: usize, M: Mark> Display for Board<N, M> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let formatter = if f.alternate() {
+            BoardFormatter::compact()
+        } else {
+            BoardFormatter::default()
+        };
 
-        write!(f, "{}", board_representation)
+        write!(f, "{}", formatter.format(self))
+    }
+}
+
+// Accepts the grid printed by Display as well as the compact form "+-o/---/o-+".
+// Borders, row separators and whitespace are skipped.
+impl<const N: usize, M: Mark> FromStr for Board<N, M> {
+    type Err = BoardParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let symbols = s
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '|' && *c != '/')
+            .map(|c| {
+                M::parse(c).ok_or_else(|| {
+                    BoardParseError::InvalidSymbol(format!("'{c}' is not a valid symbol."))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut board = Board::empty();
+
+        if symbols.len() != N * N {
+            return Err(BoardParseError::WrongTileCount(format!(
+                "A board needs {} tiles but {} were given.",
+                N * N,
+                symbols.len()
+            )));
+        }
+
+        for (i, symbol) in symbols.into_iter().enumerate() {
+            board.tiles[i / N][i % N] = symbol;
+        }
+
+        Ok(board)
+    }
+}
+
+// A board whose size is only known at runtime, e.g. from a setting, kept
+// as the board of that size so it still lives on the stack.
+#[derive(Clone)]
+pub enum AnyBoard {
+    Three(Board<3>),
+    Four(Board<4>),
+    Five(Board<5>),
+}
+
+// Runs the same code on whichever board is inside.
+macro_rules! on_board {
+    ($any:expr, $board:ident => $body:expr) => {
+        match $any {
+            AnyBoard::Three($board) => $body,
+            AnyBoard::Four($board) => $body,
+            AnyBoard::Five($board) => $body,
+        }
+    };
+}
+
+impl AnyBoard {
+    pub fn new(size: usize) -> Result<AnyBoard, String> {
+        match size {
+            3 => Ok(AnyBoard::Three(Board::empty())),
+            4 => Ok(AnyBoard::Four(Board::empty())),
+            5 => Ok(AnyBoard::Five(Board::empty())),
+            _ => Err(format!("A board is 3 to 5 tiles across, not {size}.")),
+        }
+    }
+
+    #[must_use]
+    pub fn size(&self) -> usize {
+        on_board!(self, board => board.tiles.len())
+    }
+
+    // Panics if the position is outside the board, like indexing the tiles.
+    #[must_use]
+    pub fn tile(&self, [row, column]: [usize; 2]) -> Symbol {
+        on_board!(self, board => board.tiles[row][column])
+    }
+
+    pub fn place(&mut self, symbol: Symbol, player_move: [usize; 2]) -> bool {
+        on_board!(self, board => board.place(symbol, player_move))
+    }
+
+    pub fn is_valid_move(&self, player_move: [usize; 2]) -> Result<bool, PlayerMoveError> {
+        on_board!(self, board => board.is_valid_move(player_move))
+    }
+
+    #[must_use]
+    pub fn empty_positions(&self) -> Vec<[usize; 2]> {
+        on_board!(self, board => board.empty_positions())
+    }
+
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        on_board!(self, board => board.is_full())
+    }
+
+    #[must_use]
+    pub fn winner(&self) -> Option<Symbol> {
+        on_board!(self, board => board.winner())
+    }
+
+    #[must_use]
+    pub fn winning_line(&self) -> Option<(Symbol, Vec<[usize; 2]>)> {
+        on_board!(self, board => board
+            .winning_line()
+            .map(|(symbol, line)| (symbol, line.to_vec())))
+    }
+}
+
+impl Display for AnyBoard {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        on_board!(self, board => board.fmt(f))
     }
 }
 
@@ -120,11 +498,7 @@ mod tests {
         let mut expected = [[Symbol::Empty; 3]; 3];
         expected[1][1] = Symbol::Plus;
 
-        (0..board.tiles.len()).for_each(|i| {
-            for j in 0..board.tiles[i].len() {
-                assert_eq!(board.tiles[i][j], expected[i][j]);
-            }
-        });
+        assert_eq!(board.tiles, expected);
     }
 
     #[test]
@@ -165,12 +539,15 @@ mod tests {
         let mut board = Board::new();
         board.tiles[1][1] = Symbol::Plus;
 
+        let error = board.is_valid_move([1, 1]).unwrap_err();
         assert_eq!(
-            board.is_valid_move([1, 1]),
-            Err(PlayerMoveError::FilledPosition(String::from(
-                "The position is already filled."
-            )))
+            error,
+            PlayerMoveError::FilledPosition {
+                position: [1, 1],
+                mark: "+"
+            }
         );
+        assert_eq!(error.to_string(), "1,1 is already taken by +.");
     }
 
     #[test]
@@ -180,11 +557,20 @@ mod tests {
         let invalid_moves = [[1, 3], [3, 1], [5, 5], [100, 100]];
 
         for invalid_move in invalid_moves {
+            let error = board.is_valid_move(invalid_move).unwrap_err();
             assert_eq!(
-                board.is_valid_move(invalid_move),
-                Err(PlayerMoveError::OutsideBoard(
-                    "The move is invalid because it is outside the board.".to_string()
-                ))
+                error,
+                PlayerMoveError::OutsideBoard {
+                    position: invalid_move,
+                    last: 2
+                }
+            );
+            assert_eq!(
+                error.to_string(),
+                format!(
+                    "{},{} is outside the board, rows and columns go from 0 to 2.",
+                    invalid_move[0], invalid_move[1]
+                )
             );
         }
     }
@@ -253,6 +639,132 @@ mod tests {
         assert_eq!(board.winner().unwrap(), Symbol::Plus);
     }
 
+    #[test]
+    fn place_reports_whether_the_move_won() {
+        let mut board = Board::new();
+
+        assert!(!board.place(Symbol::Plus, [0, 0]));
+        assert!(!board.place(Symbol::Plus, [0, 1]));
+        assert!(!board.place(Symbol::Circle, [1, 1]));
+        assert!(board.place(Symbol::Plus, [0, 2]));
+    }
+
+    #[test]
+    fn wins_through_checks_every_line_of_the_position() {
+        let lines = [
+            [[1, 0], [1, 1], [1, 2]],
+            [[0, 2], [1, 2], [2, 2]],
+            [[0, 0], [1, 1], [2, 2]],
+            [[0, 2], [1, 1], [2, 0]],
+        ];
+
+        for line in lines {
+            let mut board = Board::new();
+            for position in line {
+                board.tiles[position[0]][position[1]] = Symbol::Circle;
+            }
+
+            for position in line {
+                assert!(board.wins_through(position));
+            }
+            assert!(!board.wins_through([0, 1]));
+        }
+    }
+
+    #[test]
+    fn empty_positions_skips_filled_tiles() {
+        let mut board = Board::new();
+        board.tiles[0][0] = Symbol::Plus;
+        board.tiles[2][1] = Symbol::Circle;
+
+        let positions = board.empty_positions();
+
+        assert_eq!(positions.len(), 7);
+        assert!(!positions.contains(&[0, 0]));
+        assert!(!positions.contains(&[2, 1]));
+    }
+
+    #[test]
+    fn full_board_is_detected() {
+        let mut board = Board::new();
+        assert!(!board.is_full());
+
+        board.tiles = [[Symbol::Plus; 3]; 3];
+        assert!(board.is_full());
+    }
+
+    #[test]
+    fn winning_line_reports_the_completed_positions() {
+        let mut board = Board::new();
+        board.tiles[0][2] = Symbol::Circle;
+        board.tiles[1][1] = Symbol::Circle;
+        board.tiles[2][0] = Symbol::Circle;
+        board.tiles[0][0] = Symbol::Plus;
+
+        assert_eq!(
+            board.winning_line(),
+            Some((Symbol::Circle, [[0, 2], [1, 1], [2, 0]]))
+        );
+        assert_eq!(Board::new().winning_line(), None);
+    }
+
+    #[test]
+    fn status_covers_in_progress_won_and_draw() {
+        assert_eq!(Board::new().status(), GameStatus::InProgress);
+
+        let mut board: Board = "+o+/+oo/o++".parse().unwrap();
+        assert_eq!(board.status(), GameStatus::Draw);
+
+        board.tiles[2][1] = Symbol::Circle;
+        assert_eq!(
+            board.status(),
+            GameStatus::Won(Symbol::Circle, [[0, 1], [1, 1], [2, 1]])
+        );
+    }
+
+    #[test]
+    fn board_can_be_parsed_from_its_display_output() {
+        let mut board = Board::new();
+        board.tiles[0][1] = Symbol::Plus;
+        board.tiles[2][2] = Symbol::Circle;
+
+        let parsed: Board = board.to_string().parse().unwrap();
+
+        assert_eq!(parsed.tiles, board.tiles);
+    }
+
+    #[test]
+    fn board_can_be_parsed_from_compact_string() {
+        let board: Board = "+-o/---/o-+".parse().unwrap();
+
+        assert_eq!(
+            board.tiles[0],
+            [Symbol::Plus, Symbol::Empty, Symbol::Circle]
+        );
+        assert_eq!(board.tiles[1], [Symbol::Empty; 3]);
+        assert_eq!(
+            board.tiles[2],
+            [Symbol::Circle, Symbol::Empty, Symbol::Plus]
+        );
+        assert_eq!("+-o---o-+".parse::<Board>().unwrap().tiles, board.tiles);
+    }
+
+    #[test]
+    fn invalid_board_strings_are_rejected() {
+        assert_eq!(
+            "+-x/---/---".parse::<Board>().err(),
+            Some(BoardParseError::InvalidSymbol(
+                "'x' is not a valid symbol.".to_string()
+            ))
+        );
+        assert_eq!(
+            "+-o/---".parse::<Board>().err(),
+            Some(BoardParseError::WrongTileCount(
+                "A board needs 9 tiles but 6 were given.".to_string()
+            ))
+        );
+    }
+
     #[test]
     fn can_convert_from_board_symbol_to_string() {
         let assertions = [
@@ -265,4 +777,131 @@ mod tests {
             assert_eq!(expected, Into::<&str>::into(value));
         }
     }
+
+    #[test]
+    fn reachable_boards_are_valid() {
+        for board in [
+            "---/---/---",
+            "o--/---/---",
+            "+o+/o+o/o+o",
+            "+++/oo-/---",
+            // A single move completing two lines at once.
+            "+++/+oo/+oo",
+            "ooo/++-/+--",
+        ] {
+            assert_eq!(board.parse::<Board>().unwrap().validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn impossible_boards_are_rejected() {
+        let validate = |board: &str| board.parse::<Board>().unwrap().validate();
+
+        assert!(matches!(
+            validate("+++/++-/o--"),
+            Err(BoardValidationError::UnbalancedMarks(_))
+        ));
+        assert!(matches!(
+            validate("+++/ooo/---"),
+            Err(BoardValidationError::BothSidesWon(_))
+        ));
+        assert!(matches!(
+            validate("+++/ooo/+o+"),
+            Err(BoardValidationError::BothSidesWon(_))
+        ));
+        assert!(matches!(
+            validate("+++/oo-/oo-"),
+            Err(BoardValidationError::PlayedAfterWin(_))
+        ));
+    }
+
+    #[test]
+    fn lines_cover_every_row_column_and_diagonal() {
+        let classic = lines::<3>().collect::<Vec<_>>();
+        assert_eq!(classic.len(), 8);
+        assert_eq!(classic[0], [[0, 0], [0, 1], [0, 2]]);
+        assert_eq!(classic[3], [[0, 0], [1, 0], [2, 0]]);
+        assert_eq!(classic[7], [[0, 2], [1, 1], [2, 0]]);
+
+        assert_eq!(lines::<4>().count(), 10);
+        assert_eq!(lines::<4>().last(), Some([[0, 3], [1, 2], [2, 1], [3, 0]]));
+    }
+
+    #[test]
+    fn larger_boards_need_a_full_line() {
+        let mut board: Board<4> = "+++-/oo--/o---/----".parse().unwrap();
+        assert_eq!(board.status(), GameStatus::InProgress);
+
+        assert!(board.place(Symbol::Plus, [0, 3]));
+        assert_eq!(
+            board.status(),
+            GameStatus::Won(Symbol::Plus, [[0, 0], [0, 1], [0, 2], [0, 3]])
+        );
+        assert_eq!(format!("{board:#}"), "++++/oo--/o---/----");
+    }
+
+    #[test]
+    fn any_board_picks_its_size_at_runtime() {
+        let mut board = AnyBoard::new(5).unwrap();
+        assert_eq!(board.size(), 5);
+        assert_eq!(board.empty_positions().len(), 25);
+
+        for column in 0..5 {
+            board.place(Symbol::Circle, [4 - column, column]);
+        }
+        assert_eq!(board.winner(), Some(Symbol::Circle));
+        assert_eq!(board.winning_line().map(|(_, line)| line.len()), Some(5));
+        assert!(board.is_valid_move([0, 0]).unwrap());
+        assert!(board.is_valid_move([0, 5]).is_err());
+
+        assert!(AnyBoard::new(2).is_err());
+        assert!(AnyBoard::new(6).is_err());
+    }
+
+    // Marks for a three player variant.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Triple {
+        Empty,
+        X,
+        Y,
+        Z,
+    }
+
+    impl Mark for Triple {
+        const EMPTY: Self = Triple::Empty;
+
+        fn render(self) -> &'static str {
+            match self {
+                Triple::Empty => ".",
+                Triple::X => "x",
+                Triple::Y => "y",
+                Triple::Z => "z",
+            }
+        }
+
+        fn parse(c: char) -> Option<Self> {
+            [Triple::Empty, Triple::X, Triple::Y, Triple::Z]
+                .into_iter()
+                .find(|mark| mark.render().starts_with(c))
+        }
+    }
+
+    #[test]
+    fn boards_work_with_other_marks() {
+        let mut board: Board<4, Triple> = "xyz./xz../x.../....".parse().unwrap();
+        assert_eq!(board.empty_positions().len(), 10);
+        assert_eq!(board.status(), GameStatus::InProgress);
+        assert_eq!(
+            board.is_valid_move([0, 1]).unwrap_err().to_string(),
+            "0,1 is already taken by y."
+        );
+
+        assert!(board.place(Triple::X, [3, 0]));
+        assert_eq!(
+            board.status(),
+            GameStatus::Won(Triple::X, [[0, 0], [1, 0], [2, 0], [3, 0]])
+        );
+        assert_eq!(format!("{board:#}"), "xyz./xz../x.../x...");
+        assert!("xyo./..../..../....".parse::<Board<4, Triple>>().is_err());
+    }
 }
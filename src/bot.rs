@@ -0,0 +1,329 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::adaptive::{self, AdaptiveBot};
+use crate::ai::{self, Difficulty};
+use crate::board::{Board, Symbol};
+use crate::personality::Event;
+use crate::plugin::PluginBot;
+use crate::rng::Rng;
+use crate::tournament::Record;
+use crate::train::PolicyBot;
+
+// Anything that can play a side: the built-in AI as well as external engines.
+pub trait Bot {
+    fn name(&self) -> String;
+
+    // Picks an empty position for symbol, or None when it has nothing to play.
+    fn choose_move(&mut self, board: &Board, symbol: Symbol) -> Option<[usize; 2]>;
+
+    fn accepts_draw(&mut self, board: &Board, symbol: Symbol) -> bool {
+        ai::accepts_draw(board, symbol)
+    }
+
+    // How a game against a player ended, from the bot's side, for bots that
+    // learn from their results.
+    fn game_over(&mut self, _result: Record) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Something to say about the event, with who says it, for bots with a
+    // personality.
+    fn remark(&mut self, _event: Event) -> Option<String> {
+        None
+    }
+}
+
+pub struct BuiltinBot {
+    difficulty: Difficulty,
+    rng: Rng,
+}
+
+impl BuiltinBot {
+    pub fn new(difficulty: Difficulty, rng: Rng) -> BuiltinBot {
+        BuiltinBot { difficulty, rng }
+    }
+}
+
+impl Bot for BuiltinBot {
+    fn name(&self) -> String {
+        format!("{} computer", self.difficulty)
+    }
+
+    fn choose_move(&mut self, board: &Board, symbol: Symbol) -> Option<[usize; 2]> {
+        ai::choose_move(board, symbol, self.difficulty, &mut self.rng)
+    }
+}
+
+// Plays near-best moves at random, weighted by how good they are, so games
+// don't repeat the same line every time.
+pub struct StochasticBot {
+    temperature: f64,
+    rng: Rng,
+}
+
+impl StochasticBot {
+    pub fn new(temperature: f64, rng: Rng) -> StochasticBot {
+        StochasticBot { temperature, rng }
+    }
+}
+
+impl Bot for StochasticBot {
+    fn name(&self) -> String {
+        format!("Stochastic computer (temperature {})", self.temperature)
+    }
+
+    fn choose_move(&mut self, board: &Board, symbol: Symbol) -> Option<[usize; 2]> {
+        ai::sample_move(board, symbol, self.temperature, &mut self.rng)
+    }
+}
+
+// Plays misère perfectly, where completing a line loses.
+pub struct MisereBot {
+    rng: Rng,
+}
+
+impl MisereBot {
+    pub fn new(rng: Rng) -> MisereBot {
+        MisereBot { rng }
+    }
+}
+
+impl Bot for MisereBot {
+    fn name(&self) -> String {
+        String::from("Misère computer")
+    }
+
+    fn choose_move(&mut self, board: &Board, symbol: Symbol) -> Option<[usize; 2]> {
+        ai::misere_move(board, symbol, &mut self.rng)
+    }
+
+    fn accepts_draw(&mut self, board: &Board, symbol: Symbol) -> bool {
+        ai::misere_accepts_draw(board, symbol)
+    }
+}
+
+pub const DEFAULT_TEMPERATURE: f64 = 1.0;
+
+// Which bot to play against, as picked in the menu or given on the command line.
+#[derive(Debug, PartialEq, Clone)]
+pub enum BotSpec {
+    Builtin(Difficulty),
+    Stochastic(f64),
+    Adaptive,
+    // Plays perfect misère, for games where completing a line loses.
+    Misere,
+    Plugin(PathBuf),
+    Script(PathBuf),
+    Wasm(PathBuf),
+    Neural(PathBuf),
+    Policy(PathBuf),
+}
+
+impl BotSpec {
+    // The form FromStr parses, e.g. for --p2 or a saved game.
+    pub fn argument(&self) -> String {
+        match self {
+            BotSpec::Builtin(difficulty) => difficulty.to_string().to_lowercase(),
+            BotSpec::Stochastic(temperature) => format!("stochastic:{temperature}"),
+            BotSpec::Adaptive => String::from("adaptive"),
+            BotSpec::Misere => String::from("misere"),
+            BotSpec::Plugin(path) => format!("plugin:{}", path.display()),
+            BotSpec::Script(path) => format!("script:{}", path.display()),
+            BotSpec::Wasm(path) => format!("wasm:{}", path.display()),
+            BotSpec::Neural(path) => format!("nn:{}", path.display()),
+            BotSpec::Policy(path) => format!("policy:{}", path.display()),
+        }
+    }
+
+    pub fn load(&self) -> Result<Box<dyn Bot>, String> {
+        match self {
+            BotSpec::Builtin(difficulty) => {
+                Ok(Box::new(BuiltinBot::new(*difficulty, Rng::from_time())))
+            }
+            BotSpec::Stochastic(temperature) => {
+                Ok(Box::new(StochasticBot::new(*temperature, Rng::from_time())))
+            }
+            BotSpec::Adaptive => Ok(Box::new(AdaptiveBot::load(&adaptive::path())?)),
+            BotSpec::Misere => Ok(Box::new(MisereBot::new(Rng::from_time()))),
+            BotSpec::Plugin(path) => Ok(Box::new(PluginBot::load(path)?)),
+            #[cfg(feature = "scripting")]
+            BotSpec::Script(path) => Ok(Box::new(crate::script::ScriptBot::load(path)?)),
+            #[cfg(not(feature = "scripting"))]
+            BotSpec::Script(path) => Err(format!(
+                "Could not load script {}: this build does not include the scripting feature.",
+                path.display()
+            )),
+            #[cfg(feature = "wasm-bots")]
+            BotSpec::Wasm(path) => Ok(Box::new(crate::wasm::WasmBot::load(path)?)),
+            #[cfg(not(feature = "wasm-bots"))]
+            BotSpec::Wasm(path) => Err(format!(
+                "Could not load WASM bot {}: this build does not include the wasm-bots feature.",
+                path.display()
+            )),
+            BotSpec::Policy(path) => Ok(Box::new(PolicyBot::load(path)?)),
+            #[cfg(feature = "nn")]
+            BotSpec::Neural(path) => Ok(Box::new(crate::nn::NeuralBot::load(path)?)),
+            #[cfg(not(feature = "nn"))]
+            BotSpec::Neural(path) => Err(format!(
+                "Could not load network {}: this build does not include the nn feature.",
+                path.display()
+            )),
+        }
+    }
+}
+
+// Parses "easy", "medium", "hard", "stochastic[:<temperature>]", "adaptive", "misere",
+// "plugin:<path>", "script:<path>", "wasm:<path>", "nn:<path>" or "policy:<path>".
+impl FromStr for BotSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("stochastic") {
+            return Ok(BotSpec::Stochastic(DEFAULT_TEMPERATURE));
+        }
+
+        if let Some(temperature) = s.strip_prefix("stochastic:") {
+            return temperature
+                .parse::<f64>()
+                .ok()
+                .filter(|temperature| temperature.is_finite() && *temperature >= 0.0)
+                .map(BotSpec::Stochastic)
+                .ok_or_else(|| format!("'{temperature}' is not a valid temperature."));
+        }
+
+        if let Some(path) = s.strip_prefix("plugin:") {
+            return Ok(BotSpec::Plugin(PathBuf::from(path)));
+        }
+
+        if let Some(path) = s.strip_prefix("script:") {
+            return Ok(BotSpec::Script(PathBuf::from(path)));
+        }
+
+        if let Some(path) = s.strip_prefix("wasm:") {
+            return Ok(BotSpec::Wasm(PathBuf::from(path)));
+        }
+
+        if let Some(path) = s.strip_prefix("nn:") {
+            return Ok(BotSpec::Neural(PathBuf::from(path)));
+        }
+
+        if let Some(path) = s.strip_prefix("policy:") {
+            return Ok(BotSpec::Policy(PathBuf::from(path)));
+        }
+
+        match s.to_lowercase().as_str() {
+            "easy" => Ok(BotSpec::Builtin(Difficulty::Easy)),
+            "medium" => Ok(BotSpec::Builtin(Difficulty::Medium)),
+            "hard" => Ok(BotSpec::Builtin(Difficulty::Hard)),
+            "adaptive" => Ok(BotSpec::Adaptive),
+            "misere" => Ok(BotSpec::Misere),
+            _ => Err(format!("'{s}' is not a known bot.")),
+        }
+    }
+}
+
+impl Display for BotSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let file_name = |path: &PathBuf| {
+            path.file_stem()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned()
+        };
+
+        match self {
+            BotSpec::Builtin(difficulty) => write!(f, "{difficulty}"),
+            BotSpec::Stochastic(temperature) => write!(f, "Stochastic ({temperature})"),
+            BotSpec::Adaptive => write!(f, "Adaptive"),
+            BotSpec::Misere => write!(f, "Misère"),
+            BotSpec::Plugin(path) => write!(f, "Plugin: {}", file_name(path)),
+            BotSpec::Script(path) => write!(f, "Script: {}", file_name(path)),
+            BotSpec::Wasm(path) => write!(f, "WASM: {}", file_name(path)),
+            BotSpec::Neural(path) => write!(f, "Neural network: {}", file_name(path)),
+            BotSpec::Policy(path) => write!(f, "Policy: {}", file_name(path)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arguments_parse_back_to_the_same_bot() {
+        for spec in [
+            BotSpec::Builtin(Difficulty::Medium),
+            BotSpec::Stochastic(0.5),
+            BotSpec::Adaptive,
+            BotSpec::Misere,
+            BotSpec::Plugin(PathBuf::from("plugins/bot.so")),
+            BotSpec::Policy(PathBuf::from("policy.txt")),
+        ] {
+            assert_eq!(spec.argument().parse::<BotSpec>(), Ok(spec));
+        }
+    }
+
+    #[test]
+    fn builtin_bot_plays_like_the_ai() {
+        let board: Board = "oo-/++-/---".parse().unwrap();
+        let mut bot = BuiltinBot::new(Difficulty::Hard, Rng::new(1));
+
+        assert_eq!(bot.name(), "Hard computer");
+        assert_eq!(bot.choose_move(&board, Symbol::Circle), Some([0, 2]));
+        assert!(!bot.accepts_draw(&board, Symbol::Circle));
+    }
+
+    #[test]
+    fn bot_specs_are_parsed() {
+        assert_eq!("Hard".parse(), Ok(BotSpec::Builtin(Difficulty::Hard)));
+        assert_eq!(
+            "plugin:plugins/libbot.so".parse(),
+            Ok(BotSpec::Plugin(PathBuf::from("plugins/libbot.so")))
+        );
+        assert_eq!(
+            "script:mybot.rhai".parse(),
+            Ok(BotSpec::Script(PathBuf::from("mybot.rhai")))
+        );
+        assert_eq!(
+            "wasm:bots/perfect.wasm".parse(),
+            Ok(BotSpec::Wasm(PathBuf::from("bots/perfect.wasm")))
+        );
+        assert_eq!(
+            "nn:model.bin".parse(),
+            Ok(BotSpec::Neural(PathBuf::from("model.bin")))
+        );
+        assert_eq!(
+            "policy:policy.txt".parse(),
+            Ok(BotSpec::Policy(PathBuf::from("policy.txt")))
+        );
+        assert_eq!(
+            "stochastic".parse(),
+            Ok(BotSpec::Stochastic(DEFAULT_TEMPERATURE))
+        );
+        assert_eq!("stochastic:0.25".parse(), Ok(BotSpec::Stochastic(0.25)));
+        assert!("stochastic:-1".parse::<BotSpec>().is_err());
+        assert!("stochastic:hot".parse::<BotSpec>().is_err());
+        assert!("grandmaster".parse::<BotSpec>().is_err());
+    }
+
+    #[test]
+    fn bot_spec_labels() {
+        assert_eq!("Hard", format!("{}", BotSpec::Builtin(Difficulty::Hard)));
+        assert_eq!("Stochastic (0.5)", format!("{}", BotSpec::Stochastic(0.5)));
+        assert_eq!("Misère", format!("{}", BotSpec::Misere));
+        assert_eq!(
+            "Plugin: libbot",
+            format!("{}", BotSpec::Plugin(PathBuf::from("plugins/libbot.so")))
+        );
+        assert_eq!(
+            "Script: mybot",
+            format!("{}", BotSpec::Script(PathBuf::from("mybot.rhai")))
+        );
+        assert_eq!(
+            "WASM: perfect",
+            format!("{}", BotSpec::Wasm(PathBuf::from("bots/perfect.wasm")))
+        );
+    }
+}
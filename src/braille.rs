@@ -0,0 +1,138 @@
+// Boards in a single line of Braille patterns, one character per tile, small
+// enough for a status bar or a narrow pane. The marks are the Braille letters
+// x and o, so they also read as letters on a Braille display, and marked
+// tiles add the two bottom dots of eight-dot Braille. A line of plain text
+// says the same for screen readers.
+
+use crate::board::{Board, Mark, Symbol};
+use crate::formatter::Colors;
+use crate::palette;
+
+// The letters x and o, and the hyphen for empty tiles as in "+o-/---/---".
+fn cell(symbol: Symbol) -> char {
+    match symbol {
+        Symbol::Empty => '⠤',
+        Symbol::Plus => '⠭',
+        Symbol::Circle => '⠕',
+    }
+}
+
+// Dots 7 and 8, below the six dots of the letters.
+const MARKED_DOTS: u32 = 0xC0;
+
+fn marked_cell(symbol: Symbol) -> char {
+    char::from_u32(u32::from(cell(symbol)) | MARKED_DOTS).unwrap_or(cell(symbol))
+}
+
+fn format_positions(positions: &[[usize; 2]]) -> String {
+    positions
+        .iter()
+        .map(|position| format!("{},{}", position[0], position[1]))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// The board's rows separated by spaces, with the given tiles marked.
+pub fn render(board: &Board, marked: &[[usize; 2]], colors: Option<Colors>) -> String {
+    board
+        .tiles
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(j, symbol)| {
+                    let cell = if marked.contains(&[i, j]) {
+                        marked_cell(*symbol)
+                    } else {
+                        cell(*symbol)
+                    };
+                    let color = colors
+                        .zip(symbol.side())
+                        .and_then(|(colors, side)| colors.sides.get(side).copied());
+                    match color {
+                        Some(color) => palette::paint(&cell.to_string(), color),
+                        None => cell.to_string(),
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// The board followed by every move played so far.
+pub fn render_with_history(
+    board: &Board,
+    marked: &[[usize; 2]],
+    moves: &[[usize; 2]],
+    colors: Option<Colors>,
+) -> String {
+    let board = render(board, marked, colors);
+    if moves.is_empty() {
+        board
+    } else {
+        format!("{board}  {}", format_positions(moves))
+    }
+}
+
+// Where each side's marks are, e.g. "+ 0,0 1,1; o 0,1; marked 1,1".
+pub fn describe(board: &Board, marked: &[[usize; 2]]) -> String {
+    let tiles_of = |wanted: Symbol| {
+        let positions = board
+            .tiles
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(move |(_, symbol)| **symbol == wanted)
+                    .map(move |(j, _)| [i, j])
+            })
+            .collect::<Vec<_>>();
+        if positions.is_empty() {
+            String::from("none")
+        } else {
+            format_positions(&positions)
+        }
+    };
+
+    let mut description = format!(
+        "+ {}; o {}",
+        tiles_of(Symbol::Plus),
+        tiles_of(Symbol::Circle)
+    );
+    if !marked.is_empty() {
+        description.push_str("; marked ");
+        description.push_str(&format_positions(marked));
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_character_per_tile() {
+        let board: Board = "+o-/-+-/---".parse().unwrap();
+
+        assert_eq!(render(&board, &[], None), "⠭⠕⠤ ⠤⠭⠤ ⠤⠤⠤");
+        assert_eq!(render(&board, &[[1, 1]], None), "⠭⠕⠤ ⠤⣭⠤ ⠤⠤⠤");
+        assert_eq!(
+            render_with_history(&board, &[[1, 1]], &[[0, 0], [0, 1], [1, 1]], None),
+            "⠭⠕⠤ ⠤⣭⠤ ⠤⠤⠤  0,0 0,1 1,1"
+        );
+    }
+
+    #[test]
+    fn descriptions_list_the_marks_of_each_side() {
+        let board: Board = "+o-/-+-/---".parse().unwrap();
+
+        assert_eq!(describe(&board, &[]), "+ 0,0 1,1; o 0,1");
+        assert_eq!(
+            describe(&Board::new(), &[[2, 2]]),
+            "+ none; o none; marked 2,2"
+        );
+    }
+}
@@ -0,0 +1,427 @@
+// Steps back and forth through a finished game. Games end with a
+// "Game record:" line, so any recording can be browsed later too. Every game
+// of a recording can also be stepped through at once, side by side.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::analysis;
+use crate::board::{Board, Symbol};
+use crate::console::Console;
+use crate::game::{self, parse_player_move};
+use crate::recording::Frame;
+use crate::rng::Rng;
+use crate::view::Panel;
+
+pub const RECORD_PREFIX: &str = "Game record: ";
+
+const ANALYSIS_PLAYOUTS: u32 = 500;
+
+const HELP: &str = "Browser commands: n(ext), p(revious), f(irst), l(ast), \
+a(nalysis on/off), c(opy the position), q(uit). An empty line also moves to the next position.";
+
+// The starting board and every move after it, with the symbol that played
+// it since handicaps can give one side two moves in a row.
+#[derive(Clone)]
+pub struct GameRecord {
+    start: Board,
+    moves: Vec<(Symbol, [usize; 2])>,
+}
+
+impl GameRecord {
+    pub fn new(start: Board) -> GameRecord {
+        GameRecord {
+            start,
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, symbol: Symbol, position: [usize; 2]) {
+        self.moves.push((symbol, position));
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    pub fn moves(&self) -> &[(Symbol, [usize; 2])] {
+        &self.moves
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    // Removes the last move played by symbol and every move after it.
+    // Returns false, changing nothing, when symbol has not moved.
+    pub fn take_back(&mut self, symbol: Symbol) -> bool {
+        match self.moves.iter().rposition(|(mover, _)| *mover == symbol) {
+            Some(index) => {
+                self.moves.truncate(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // How many moves the symbol has made.
+    pub fn moves_by(&self, symbol: Symbol) -> usize {
+        self.moves
+            .iter()
+            .filter(|(mover, _)| *mover == symbol)
+            .count()
+    }
+
+    // The position after the last move.
+    pub fn board(&self) -> Board {
+        let mut board = self.start.clone();
+        for (symbol, position) in &self.moves {
+            board.place(*symbol, *position);
+        }
+        board
+    }
+
+    // Whoever did not make the last move, or the side to move by the mark
+    // count at the start.
+    pub fn to_move(&self) -> Symbol {
+        self.moves.last().map_or_else(
+            || analysis::side_to_move(&self.start),
+            |(symbol, _)| symbol.opponent(),
+        )
+    }
+
+    // The position before the first move and after every move.
+    fn positions(&self) -> Vec<Board> {
+        let mut board = self.start.clone();
+        let mut positions = vec![board.clone()];
+
+        for (symbol, position) in &self.moves {
+            board.place(*symbol, *position);
+            positions.push(board.clone());
+        }
+
+        positions
+    }
+}
+
+// "<start position> <symbol><row>,<column>...", e.g. "---/---/--- +1,1 o0,0".
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#}", self.start)?;
+
+        for (symbol, [row, column]) in &self.moves {
+            let symbol: &str = (*symbol).into();
+            write!(f, " {symbol}{row},{column}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let start = parts
+            .next()
+            .and_then(|start| start.parse::<Board>().ok())
+            .ok_or_else(|| String::from("The game record has no valid start position."))?;
+        start.validate().map_err(|error| {
+            format!("The game record starts from an impossible position: {error}")
+        })?;
+        let mut record = GameRecord::new(start);
+        let mut board = record.start.clone();
+
+        for part in parts {
+            let invalid = || format!("'{part}' is not a valid move in the game record.");
+            let mut chars = part.chars();
+            let symbol = chars
+                .next()
+                .and_then(|c| Symbol::try_from(c).ok())
+                .filter(|symbol| *symbol != Symbol::Empty)
+                .ok_or_else(invalid)?;
+            let position = parse_player_move(chars.as_str()).map_err(|_| invalid())?;
+            board.is_valid_move(position).map_err(|_| invalid())?;
+
+            board.place(symbol, position);
+            record.push(symbol, position);
+        }
+
+        Ok(record)
+    }
+}
+
+// Every game record found in a recording, in the order they were played.
+pub fn records_in(frames: &[Frame]) -> Result<Vec<GameRecord>, String> {
+    frames
+        .iter()
+        .flat_map(|frame| frame.text.lines())
+        .filter_map(|line| line.strip_prefix(RECORD_PREFIX))
+        .map(str::parse)
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum BrowserCommand {
+    Next,
+    Previous,
+    First,
+    Last,
+    ToggleAnalysis,
+    Copy,
+    Quit,
+}
+
+impl BrowserCommand {
+    fn parse(input: &str) -> Option<BrowserCommand> {
+        match input.trim().to_lowercase().as_str() {
+            "" | "n" | "next" => Some(BrowserCommand::Next),
+            "p" | "prev" | "previous" => Some(BrowserCommand::Previous),
+            "f" | "first" => Some(BrowserCommand::First),
+            "l" | "last" => Some(BrowserCommand::Last),
+            "a" | "analysis" => Some(BrowserCommand::ToggleAnalysis),
+            "c" | "copy" => Some(BrowserCommand::Copy),
+            "q" | "quit" => Some(BrowserCommand::Quit),
+            _ => None,
+        }
+    }
+
+    // The index of the position to show after this command.
+    fn apply(self, current: usize, last: usize) -> usize {
+        match self {
+            BrowserCommand::Next => (current + 1).min(last),
+            BrowserCommand::Previous => current.saturating_sub(1),
+            BrowserCommand::First => 0,
+            BrowserCommand::Last => last,
+            BrowserCommand::ToggleAnalysis | BrowserCommand::Copy | BrowserCommand::Quit => current,
+        }
+    }
+}
+
+fn describe(record: &GameRecord, index: usize) -> String {
+    if index == 0 {
+        return format!("Start of the game, {} moves in total.", record.moves.len());
+    }
+
+    let (symbol, [row, column]) = record.moves[index - 1];
+    let symbol: &str = symbol.into();
+    format!(
+        "Move {index} of {}: {symbol} plays {row},{column}.",
+        record.moves.len()
+    )
+}
+
+// Shows one position at a time until the player quits or stdin is closed.
+// With analysis on, every position comes with Monte Carlo estimates.
+pub fn run(console: &mut Console, record: &GameRecord, mut analysis: bool) {
+    let positions = record.positions();
+    let last = positions.len() - 1;
+    let mut index = 0;
+    let mut rng = Rng::from_time();
+    console.say(&format!("\n{HELP}"));
+
+    loop {
+        let board = &positions[index];
+        console.show(&format!("\n{}\n\n{board}\n", describe(record, index)));
+
+        if analysis {
+            let symbol = record
+                .moves
+                .get(index)
+                .map_or_else(|| analysis::side_to_move(board), |(symbol, _)| *symbol);
+            let estimates = analysis::estimate_moves(board, symbol, ANALYSIS_PLAYOUTS, &mut rng);
+            if !estimates.is_empty() {
+                let to_move: &str = symbol.into();
+                console.say(&format!(
+                    "{to_move} to move:\n{}",
+                    analysis::format_estimates(&estimates)
+                ));
+            }
+        }
+
+        let Some(input) = console.read_line() else {
+            return;
+        };
+
+        match BrowserCommand::parse(&input) {
+            Some(BrowserCommand::Quit) => return,
+            Some(BrowserCommand::ToggleAnalysis) => analysis = !analysis,
+            Some(BrowserCommand::Copy) => game::copy_position(console, board),
+            Some(BrowserCommand::Next) if index == last => {
+                console.say("That was the final position, q leaves the browser.");
+            }
+            Some(command) => index = command.apply(index, last),
+            None => console.warn(HELP),
+        }
+    }
+}
+
+// Where each game stands after step moves, games that are over stay at
+// their final position.
+fn panels(records: &[GameRecord], step: usize) -> Vec<Panel> {
+    records
+        .iter()
+        .enumerate()
+        .map(|(i, record)| {
+            let index = step.min(record.moves.len());
+            let status = if index == 0 {
+                String::from("Start")
+            } else {
+                let (symbol, [row, column]) = record.moves[index - 1];
+                let symbol: &str = symbol.into();
+                format!(
+                    "Move {index} of {}, {symbol} {row},{column}",
+                    record.moves.len()
+                )
+            };
+            Panel {
+                board: record.positions().swap_remove(index),
+                marked: record.moves[..index]
+                    .last()
+                    .map(|(_, position)| *position)
+                    .into_iter()
+                    .collect(),
+                status: format!("Game {}\n{status}", i + 1),
+            }
+        })
+        .collect()
+}
+
+// Shows every game at once, each a move further on every step, until the
+// player quits or stdin is closed.
+pub fn run_all(console: &mut Console, records: &[GameRecord]) {
+    let last = records
+        .iter()
+        .map(|record| record.moves.len())
+        .max()
+        .unwrap_or(0);
+    let mut step = 0;
+    console.say(&format!("\n{HELP}"));
+
+    loop {
+        let board = console.renderer().panels(&panels(records, step));
+        console.show(&format!("\n{board}\n"));
+
+        let Some(input) = console.read_line() else {
+            return;
+        };
+
+        match BrowserCommand::parse(&input) {
+            Some(BrowserCommand::Quit) => return,
+            Some(BrowserCommand::ToggleAnalysis | BrowserCommand::Copy) => {
+                console.warn("Analysis and copy need a single game, pick one with --game.");
+            }
+            Some(BrowserCommand::Next) if step == last => {
+                console.say("Every game is over, q leaves the browser.");
+            }
+            Some(command) => step = command.apply(step, last),
+            None => console.warn(HELP),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn game_records_round_trip() {
+        let mut record = GameRecord::new(Board::new());
+        record.push(Symbol::Plus, [1, 1]);
+        record.push(Symbol::Circle, [0, 0]);
+
+        let text = format!("{record}");
+        assert_eq!(text, "---/---/--- +1,1 o0,0");
+        let parsed: GameRecord = text.parse().unwrap();
+        assert_eq!(parsed.moves, record.moves);
+        assert_eq!(format!("{parsed}"), text);
+    }
+
+    #[test]
+    fn positions_follow_the_moves() {
+        let record: GameRecord = "+--/---/--- o1,1 +2,2".parse().unwrap();
+        let positions = record.positions();
+
+        assert_eq!(positions.len(), 3);
+        assert_eq!(positions[0].tiles[1][1], Symbol::Empty);
+        assert_eq!(positions[1].tiles[1][1], Symbol::Circle);
+        assert_eq!(positions[2].tiles[2][2], Symbol::Plus);
+        assert_eq!(describe(&record, 2), "Move 2 of 2: + plays 2,2.");
+    }
+
+    #[test]
+    fn takebacks_remove_the_reply_too() {
+        let mut record: GameRecord = "---/---/--- +1,1 o0,0 +2,2 o0,2".parse().unwrap();
+
+        assert!(record.take_back(Symbol::Plus));
+        assert_eq!(format!("{record}"), "---/---/--- +1,1 o0,0");
+        assert!(record.take_back(Symbol::Circle));
+        assert!(record.take_back(Symbol::Plus));
+        assert!(record.is_empty());
+        assert!(!record.take_back(Symbol::Circle));
+    }
+
+    #[test]
+    fn invalid_records_are_rejected() {
+        for text in [
+            "",
+            "---/---",
+            "+++/++-/---",
+            "---/---/--- 1,1",
+            "---/---/--- +1,1 o1,1",
+            "---/---/--- -0,0",
+        ] {
+            assert!(text.parse::<GameRecord>().is_err());
+        }
+    }
+
+    #[test]
+    fn records_are_found_in_recordings() {
+        let frame = |text: &str| Frame {
+            at: Duration::ZERO,
+            new_screen: false,
+            text: text.to_string(),
+        };
+        let frames = [
+            frame("Player 1, please do your move."),
+            frame("\nGame record: ---/---/--- +0,0"),
+            frame("Game record: ---/---/--- +2,2 o1,1"),
+        ];
+
+        let records = records_in(&frames).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].moves.len(), 2);
+    }
+
+    #[test]
+    fn games_shown_together_stop_at_their_last_move() {
+        let records = [
+            "---/---/--- +1,1".parse::<GameRecord>().unwrap(),
+            "---/---/--- +0,0 o2,2 +0,1".parse().unwrap(),
+        ];
+
+        let shown = panels(&records, 0);
+        assert_eq!(shown[1].status, "Game 2\nStart");
+        assert!(shown[1].marked.is_empty());
+
+        let shown = panels(&records, 3);
+        assert_eq!(shown[0].status, "Game 1\nMove 1 of 1, + 1,1");
+        assert_eq!(shown[0].marked, [[1, 1]]);
+        assert_eq!(shown[1].status, "Game 2\nMove 3 of 3, + 0,1");
+        assert_eq!(shown[1].board.tiles[2][2], Symbol::Circle);
+    }
+
+    #[test]
+    fn commands_stay_within_the_game() {
+        assert_eq!(BrowserCommand::parse(""), Some(BrowserCommand::Next));
+        assert_eq!(BrowserCommand::parse("P"), Some(BrowserCommand::Previous));
+        assert_eq!(BrowserCommand::parse("jump"), None);
+
+        assert_eq!(BrowserCommand::Next.apply(4, 4), 4);
+        assert_eq!(BrowserCommand::Previous.apply(0, 4), 0);
+        assert_eq!(BrowserCommand::Last.apply(1, 4), 4);
+        assert_eq!(BrowserCommand::First.apply(3, 4), 0);
+    }
+}
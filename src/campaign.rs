@@ -0,0 +1,267 @@
+// A single-player campaign against a ladder of named opponents, each harder
+// than the last and some with a rule of their own. Beating a level's goal
+// unlocks the next one, and the levels cleared are kept across sessions.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::ai::Difficulty;
+use crate::bot::BotSpec;
+use crate::console::Console;
+use crate::game::{self, GameSetup, Handicap, HandicapKind, Opponent, Outcome, Player};
+use crate::profile;
+use crate::sized;
+
+// Where progress is kept unless TTT_CAMPAIGN says otherwise.
+pub const DEFAULT_CAMPAIGN: &str = "tic-tac-toe.campaign";
+
+pub fn path() -> PathBuf {
+    std::env::var_os("TTT_CAMPAIGN").map_or_else(|| profile::file(DEFAULT_CAMPAIGN), PathBuf::from)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Goal {
+    Win,
+    // A draw is enough, for opponents that cannot be beaten.
+    Draw,
+}
+
+impl Goal {
+    fn met(self, outcome: Outcome) -> bool {
+        matches!(
+            (self, outcome.winner()),
+            (_, Some(Player::One)) | (Goal::Draw, None)
+        )
+    }
+}
+
+// A level's special rule.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Variant {
+    Classic,
+    // A head start for the opponent.
+    Handicap(Handicap),
+    // Completing a line loses.
+    Misere,
+    // A bigger board, against a built-in bot's simple play.
+    Board(usize),
+}
+
+struct Level {
+    opponent: &'static str,
+    bot: BotSpec,
+    variant: Variant,
+    goal: Goal,
+    intro: &'static str,
+}
+
+const LEVELS: [Level; 8] = [
+    Level {
+        opponent: "Rookie Rita",
+        bot: BotSpec::Builtin(Difficulty::Easy),
+        variant: Variant::Classic,
+        goal: Goal::Win,
+        intro: "Rita has only just learned the rules.",
+    },
+    Level {
+        opponent: "Steady Sam",
+        bot: BotSpec::Builtin(Difficulty::Medium),
+        variant: Variant::Classic,
+        goal: Goal::Win,
+        intro: "Sam never misses a win or a block, but plans no further.",
+    },
+    Level {
+        opponent: "Eager Eddie",
+        bot: BotSpec::Builtin(Difficulty::Medium),
+        variant: Variant::Handicap(Handicap {
+            player: Player::Two,
+            kind: HandicapKind::ExtraMove,
+        }),
+        goal: Goal::Win,
+        intro: "Eddie cannot wait his turn: he moves first.",
+    },
+    Level {
+        opponent: "Tricky Tess",
+        bot: BotSpec::Stochastic(1.0),
+        variant: Variant::Classic,
+        goal: Goal::Win,
+        intro: "Tess plays strong moves, but not always the strongest.",
+    },
+    Level {
+        opponent: "Perfect Petra",
+        bot: BotSpec::Builtin(Difficulty::Hard),
+        variant: Variant::Classic,
+        goal: Goal::Draw,
+        intro: "Petra never loses. Hold her to a draw.",
+    },
+    Level {
+        opponent: "Grandmaster Gus",
+        bot: BotSpec::Builtin(Difficulty::Hard),
+        variant: Variant::Handicap(Handicap {
+            player: Player::Two,
+            kind: HandicapKind::Marks(1),
+        }),
+        goal: Goal::Draw,
+        intro: "Gus plays perfectly and starts with a mark on the board. Hold him to a draw.",
+    },
+    Level {
+        opponent: "Contrary Clara",
+        bot: BotSpec::Misere,
+        variant: Variant::Misere,
+        goal: Goal::Draw,
+        intro: "Clara plays misère, where whoever completes a line loses, and plays it perfectly. Hold her to a draw.",
+    },
+    Level {
+        opponent: "Square Sid",
+        bot: BotSpec::Builtin(Difficulty::Medium),
+        variant: Variant::Board(4),
+        goal: Goal::Win,
+        intro: "Sid plays on a 4x4 board, where a line takes four marks. He never misses a win or a block.",
+    },
+];
+
+// Progress is a single "cleared <levels>" line.
+fn parse_progress(text: &str) -> Option<usize> {
+    text.trim()
+        .strip_prefix("cleared ")
+        .and_then(|cleared| cleared.parse::<usize>().ok())
+        .map(|cleared| cleared.min(LEVELS.len()))
+}
+
+// The number of levels cleared, none when nothing is saved yet.
+pub fn load(path: &Path) -> Result<usize, String> {
+    if !path.exists() {
+        return Ok(0);
+    }
+    let text = fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {error}", path.display()))?;
+    parse_progress(&text).ok_or_else(|| format!("{} is not campaign progress.", path.display()))
+}
+
+fn save(path: &Path, cleared: usize) -> Result<(), String> {
+    fs::write(path, format!("cleared {cleared}\n"))
+        .map_err(|error| format!("Could not save the campaign to {}: {error}", path.display()))
+}
+
+fn describe_goal(goal: Goal) -> &'static str {
+    match goal {
+        Goal::Win => "win a game",
+        Goal::Draw => "draw or win a game",
+    }
+}
+
+// Plays one game of the level. Returns None if the input was closed during it.
+fn play_level(
+    console: &mut Console,
+    level: &Level,
+    defaults: &GameSetup,
+) -> Result<Option<Outcome>, String> {
+    let setup = GameSetup {
+        single_game: true,
+        ..defaults.clone()
+    };
+    let setup = match level.variant {
+        Variant::Board(size) => {
+            let BotSpec::Builtin(difficulty) = level.bot else {
+                return Err(format!("{} needs a built-in bot.", level.opponent));
+            };
+            return Ok(sized::play(console, size, Some(difficulty)));
+        }
+        Variant::Classic => setup,
+        Variant::Handicap(handicap) => GameSetup {
+            handicap: Some(handicap),
+            ..setup
+        },
+        Variant::Misere => GameSetup {
+            misere: true,
+            ..setup
+        },
+    };
+    let bot = level.bot.load()?;
+    Ok(game::play_session(console, Opponent::Computer(bot), &setup))
+}
+
+// Plays the next level until the player stops, loses the input or clears
+// the last one. Games use the defaults' display settings.
+pub fn run(console: &mut Console, defaults: &GameSetup, path: &Path) -> Result<(), String> {
+    let mut cleared = load(path)?;
+
+    while let Some(level) = LEVELS.get(cleared) {
+        console.say(&format!(
+            "\nLevel {} of {}: {} ({}). {} To go on, {}.",
+            cleared + 1,
+            LEVELS.len(),
+            level.opponent,
+            level.bot,
+            level.intro,
+            describe_goal(level.goal)
+        ));
+        let Some(outcome) = play_level(console, level, defaults)? else {
+            return Ok(());
+        };
+
+        let question = if level.goal.met(outcome) {
+            cleared += 1;
+            save(path, cleared)?;
+            console.say(&format!("\nYou got past {}!", level.opponent));
+            "Play the next level?"
+        } else {
+            "Try again?"
+        };
+        if cleared < LEVELS.len() && !game::ask_yes_no(console, question).unwrap_or(false) {
+            return Ok(());
+        }
+    }
+
+    console.say(&format!(
+        "\nYou have beaten all {} opponents. Delete {} to start the campaign over.",
+        LEVELS.len(),
+        path.display()
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{DrawReason, WinReason};
+
+    #[test]
+    fn goals_are_met_by_good_enough_results() {
+        let won = Outcome::Winner(Player::One, WinReason::Line);
+        let drawn = Outcome::Draw(DrawReason::FullBoard);
+        let lost = Outcome::Winner(Player::Two, WinReason::Resignation);
+
+        assert!(Goal::Win.met(won) && !Goal::Win.met(drawn) && !Goal::Win.met(lost));
+        assert!(Goal::Draw.met(won) && Goal::Draw.met(drawn) && !Goal::Draw.met(lost));
+    }
+
+    #[test]
+    fn every_level_has_a_bot_that_loads() {
+        for level in &LEVELS {
+            assert!(level.bot.load().is_ok(), "{}", level.opponent);
+            if let Variant::Board(size) = level.variant {
+                assert!(
+                    matches!(level.bot, BotSpec::Builtin(_)) && (4..=5).contains(&size),
+                    "{}",
+                    level.opponent
+                );
+            }
+        }
+        // Misère levels are played by a bot that knows the rule.
+        for level in LEVELS
+            .iter()
+            .filter(|level| level.variant == Variant::Misere)
+        {
+            assert_eq!(level.bot, BotSpec::Misere, "{}", level.opponent);
+        }
+        assert!(LEVELS.iter().any(|level| level.variant == Variant::Misere));
+    }
+
+    #[test]
+    fn progress_is_capped_at_the_last_level() {
+        assert_eq!(parse_progress("cleared 2\n"), Some(2));
+        assert_eq!(parse_progress("cleared 99"), Some(LEVELS.len()));
+        assert_eq!(parse_progress("level two"), None);
+    }
+}
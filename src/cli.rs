@@ -0,0 +1,1960 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::ai::Difficulty;
+use crate::autosave;
+use crate::bench;
+use crate::board::{AnyBoard, Board, GameStatus, Symbol};
+use crate::bot::BotSpec;
+use crate::browser::GameRecord;
+use crate::clipboard;
+use crate::events::Events;
+use crate::game::{parse_player_move, GameSetup, StartPosition};
+use crate::gamelog::GameLog;
+use crate::irc::IrcOptions;
+use crate::mqtt::{Broker, Publisher};
+use crate::personality;
+use crate::selfplay::{DatasetFormat, SelfPlayOptions};
+use crate::simul;
+use crate::team::Rule;
+use crate::telnet::{self, TelnetOptions};
+use crate::tls::{Identity, Verify};
+use crate::train::TrainOptions;
+use crate::vote::{self, Tiebreak};
+
+pub const USAGE: &str = "Usage:
+  tic-tac-toe-rust                      Open the main menu.
+  tic-tac-toe-rust --record <file>      Open the main menu and record the games played to a file.
+  tic-tac-toe-rust --p2 <player> [--handicap <handicap>] [--position <position> [--to-move <+|o>]]
+                                        Skip the menu and play against <player>: human, easy, medium,
+                                        hard, stochastic[:<temperature>], adaptive, misere, plugin:<path>, script:<path> (scripting feature)
+                                        wasm:<path> (wasm-bots feature), nn:<model> (nn feature)
+                                        or policy:<file> (see train).
+                                        A handicap helps the weaker player: p1:1 or p1:2 start
+                                        Player 1 with marks on the board, p1:skip makes Player 2
+                                        skip the first turn (p2:... works the other way around).
+                                        A position such as +-o/---/o-+ starts every game from there,
+                                        with the side to move taken from the mark count unless given.
+  tic-tac-toe-rust --p2 <human|easy|medium> --size <4|5>
+                                        Play on a 4x4 or 5x5 board, where a line takes 4 or 5 marks.
+                                        Only the easy and medium bots play there, and those games are
+                                        not recorded or saved.
+  --confirm                             Show every move and ask before playing it, in the menu too.
+  --coach                               After each of your moves, explain a win you missed or a loss
+                                        you failed to block, in the menu too.
+  --personality <file>                  Let the computer comment on its blocks and on how games end,
+                                        with lines from a file such as examples/grumpy.personality.
+  --takebacks <accept|refuse>           How the computer answers undo requests (default accept).
+  --palette <palette>                   Color the marks: plain (the default), standard, or deuteranopia,
+                                        protanopia or tritanopia for colors that stay apart with that
+                                        kind of color blindness, or high-contrast for bold white on black
+                                        and inverse video, with prompts and results in bold as well. The
+                                        last move and a winning line are always bracketed, e.g. [o].
+  --view <grid|large|braille>           How the board is drawn: the usual grid, large print with
+                                        every tile drawn big enough to fill the terminal and the empty
+                                        ones labelled with their move, or braille for one line of
+                                        Braille letters x and o with the moves so far and a plain text
+                                        description, for small panes and Braille displays (default
+                                        grid).
+  --scale <1|2|3|auto>                  Draw the grid with tiles 1, 2 or 3 times as big, or auto for
+                                        the biggest that fits the terminal (default 1).
+  --keys <standard|vim>                 How moves are typed: row,column and command names, or vim
+                                        keys, with h, j, k and l moving a cursor, x placing at it, u
+                                        taking back and :w, :q, :wq, :hint or any other command after
+                                        a colon. Keys are sent with enter, e.g. 2lx (default standard).
+                                        The vim keys can be remapped in the menu's settings.
+  --volume <0-100>                      How loud the sounds for moves, mistakes, wins and draws are
+                                        (default 60). Builds with the audio feature play them through
+                                        aplay or paplay, afplay on macOS or PowerShell on Windows, and
+                                        stay silent when none of these can play.
+  --mute                                Play no sounds.
+  tic-tac-toe-rust --resume <file>      Finish a game saved when it was interrupted, see Ctrl-C.
+  --profile <name>                      Play as a profile, with its own statistics, achievements, rating
+                                        and settings, creating it if it is new. Goes with any command.
+                                        The menu asks for one when there are profiles.
+  --log-game <file>                     Append every prompt, answer and move of the session to a file,
+                                        e.g. for a bug report.
+  --mqtt <broker>                       Publish every game created, move played and game finished as
+                                        JSON to an MQTT broker, host[:port][/prefix], on the topic
+                                        <prefix>/game/<number> (default port 1883, prefix tic-tac-toe).
+  -v, --debug                           Show how moves are parsed and checked, and how the computer
+                                        picks its moves.
+  -q, --quiet                           Print only the final board and result of each game, e.g. for
+                                        scripts. Warnings still go to standard error.
+  --result-format <text|json>           How each game's result is printed (default text). json prints
+                                        only a JSON document per game with the moves, final board,
+                                        result and timings.
+  tic-tac-toe-rust replay [--render] <file>
+                                        Print a recorded session, with --render replay it with its original timing.
+  tic-tac-toe-rust svg <position>...    Print an SVG of one or more positions, e.g. +-o/---/o-+
+  tic-tac-toe-rust svg --moves <x,y>... Print an SVG film strip of a game played from an empty board.
+  tic-tac-toe-rust browse [--game <n> | --all] [--analysis] <file>
+                                        Step through a recorded game, the last one unless n is given,
+                                        with --analysis estimating the moves in every position, or
+                                        with --all through every game at once, side by side.
+  tic-tac-toe-rust tournament [--games <n>] <bot> <bot>...
+                                        Play every pairing of bots n times (default 10) and print a crosstable.
+  tic-tac-toe-rust team [--rule <confirm|alternate>] [--against <bot>] <name>...
+                                        Play a consultation game: the first two players share +, and
+                                        the next two o, or a bot (default hard) when only two are named.
+                                        With the confirm rule (the default) a teammate proposes each
+                                        move and the other answers yes or proposes another; with
+                                        alternate the teammates take turns making the moves.
+  tic-tac-toe-rust simul [--boards <n>] <bot>...
+                                        Play a board against each bot at once, moving on one board after
+                                        another, with n boards sharing out the bots in turn (up to 12).
+  tic-tac-toe-rust selfplay [options]   Write self-play games as a dataset, one row per position:
+    --bot <bot>                         the bot playing both sides (default hard)
+    --games <n>                         how many games to play (default 1000)
+    --format <csv|jsonl>                the output format (default csv)
+    --noise <percent>                   chance of replacing a move with a random one (default 0)
+    --seed <n>                          seed for the noise, for reproducible datasets
+    --out <file>                        write to a file instead of standard output
+    --stats <table|csv>                 also print game lengths, results by opening square and
+                                        winning lines to standard error
+    --stats <heatmap|heatmap-csv>       or how often each opening square was chosen and won, as a
+                                        colored grid or a row per square
+  tic-tac-toe-rust train [options]      Learn a policy with Q-learning through self-play:
+    --episodes <n>                      how many games to learn from (default 20000)
+    --epsilon <percent>                 chance of exploring a random move (default 10)
+    --report-every <n>                  test against the random and minimax bots every n games (default 5000)
+    --seed <n>                          seed for reproducible training
+    --out <file>                        where to save the policy (default policy.txt)
+  tic-tac-toe-rust analyze [--playouts <n>] <position>
+                                        Estimate the win, draw and loss chances of every move with
+                                        n random playouts each (default 1000).
+  tic-tac-toe-rust bench [--millis <n>]
+                                        Measure winner detection, move generation and the search on a
+                                        few positions, n milliseconds per benchmark (default 500).
+  tic-tac-toe-rust campaign            Play through a ladder of ever harder opponents, some with
+                                        special rules, also in the menu.
+  tic-tac-toe-rust profiles            List the profiles on this machine.
+  tic-tac-toe-rust paths               Show where settings, saved games and statistics are kept.
+  tic-tac-toe-rust export <file>       Write the profile's statistics, achievements, rating and settings
+                                        to one file, e.g. to take them to another machine.
+  tic-tac-toe-rust import [--replace] <file>
+                                        Merge an exported profile into this one: opening results are
+                                        added up, the best streaks and all achievements kept, the rating
+                                        from more games wins and your own settings stay. An export is
+                                        only merged once. With --replace it takes over instead.
+  tic-tac-toe-rust stats               Show your rating and your current and best win and unbeaten
+                                        streaks against each bot and over all of them.
+  tic-tac-toe-rust openings            Show how your openings, the first move of each side, have done
+                                        against each bot, the ones that lost the most games first.
+  tic-tac-toe-rust daily               Solve today's puzzle, the same for everyone: find the move that
+                                        forces a win, and keep up a streak of solved days.
+  tic-tac-toe-rust achievements        List the achievements for games against the computer, the ones
+                                        unlocked and the ones still locked.
+  tic-tac-toe-rust irc --server <host:port> --channel <#channel> [--nick <nick>] [--bot <bot>]
+                                        Idle in an IRC channel and referee games played with !challenge,
+                                        !accept and !move b2. A bare !challenge plays the bot (default medium).
+      [--crowd <seconds>] [--tiebreak <first|random|best>]
+                                        With --crowd the channel plays the bot together: everyone votes
+                                        with !vote b2, and the move with the most votes is played the
+                                        given seconds after the first vote. Ties go to the move voted
+                                        for first (the default), a random one or the best one.
+  tic-tac-toe-rust twitch --channel <channel> [--nick <nick>] [--bot <bot>] [--window <seconds>]
+      [--tiebreak <first|random|best>]
+                                        Let a Twitch channel's chat play the bot (default medium) by
+                                        voting with !move b2, showing the board and the votes as they
+                                        come in. Voting closes the given seconds (default 20) after the
+                                        first vote. Chat is read anonymously unless TTT_TWITCH_TOKEN
+                                        holds an OAuth token for the nick, which also posts the
+                                        results. Needs the twitch feature.
+  tic-tac-toe-rust telnet [--listen <address>] [--webhook <url>]... [--mqtt <broker>]
+      [--tokens <file> [--issue <name>]] [--saves <dir>] [--metrics <address>]
+      [--max-connections <n>] [--max-per-client <n>] [--rate <lines>] [--idle <minutes>]
+      [--tls-cert <file> --tls-key <file>]
+                                        Serve the menu to telnet and raw TCP clients, one game per
+                                        connection (default 127.0.0.1:2323). Up to 32 connections are
+                                        served, 4 from one address or one token's player, each may
+                                        send 4 lines a second after a burst of 10 and is cut off when
+                                        it keeps flooding, and connections idle for 5 minutes are
+                                        closed; the options change these limits. A player whose connection drops can connect
+                                        again within 2 minutes and type rejoin <code>, with the code
+                                        shown on connecting, to carry on. The first line sent names
+                                        the version and protocol; scripted clients can answer hello 1
+                                        and are cut off when the server does not speak theirs. Every
+                                        game created, move played and game finished is POSTed as JSON
+                                        to each http:// webhook URL, and published to the MQTT broker
+                                        as with --mqtt. Connections are plain text unless --tls-cert and
+                                        --tls-key give a certificate chain and its key as PEM files to
+                                        serve over TLS (tls feature), for players to join --tls. With
+                                        --tokens only players with a token from the file can play, and
+                                        only they can rejoin their games; --issue adds a new token for
+                                        a player to the file.
+                                        With --saves games in progress are saved to the directory, and
+                                        survive a restart: rejoin <code> picks them up again. Commands
+                                        typed into the server list the sessions, kick <code> a player,
+                                        say <text> to everyone, or drain the server so it stops once
+                                        the games in progress end. --metrics serves the connected
+                                        clients, active games, moves and invalid moves for Prometheus at
+                                        http://<address>/metrics.
+  tic-tac-toe-rust join [<address>] [--tls [--ca <file>] [--server-name <name>]]
+                                        Play on a telnet server (default 127.0.0.1:2323) from this
+                                        terminal. With --tls the connection is encrypted (tls feature)
+                                        and the server's certificate is checked against the usual web
+                                        roots, or the CA certificates in the PEM file given with --ca,
+                                        for the address's host or the name given with --server-name.
+  tic-tac-toe-rust watch <broker>       Draw every game published to an MQTT broker with --mqtt, side
+                                        by side, as they are played.
+  tic-tac-toe-rust correspond <file> [<x,y>]
+                                        Play a game one move per run, e.g. over email: show the game in
+                                        the file, or make the move for the side to move and save it.
+                                        A missing file starts a new game.
+  tic-tac-toe-rust qr [--light] <position|game record>
+                                        Print a QR code of a position or game record, e.g. from a
+                                        correspondence file (qr feature). It is drawn for dark terminals
+                                        unless --light is given.
+
+Games on this terminal are saved after every move to tic-tac-toe.autosave in the data
+directory, or the file in TTT_AUTOSAVE, until they end. The adaptive bot plays better after you
+win and eases off after you lose twice in a row.
+
+Your openings, achievements, streaks, rating, campaign progress, daily puzzle streak and the
+adaptive bot's strength are kept in tic-tac-toe.<openings|achievements|streaks|rating|campaign|
+daily|adaptive> in the data directory, or the profile's directory in it with --profile. Each can
+be moved with TTT_<OPENINGS|ACHIEVEMENTS|STREAKS|RATING|CAMPAIGN|DAILY|ADAPTIVE>. The settings
+file in the config directory sets the defaults, with lines such as confirm on, coach on,
+takebacks refuse, palette deuteranopia, view large, scale 2, keys vim, volume 30 and mute on. The data directory is $XDG_DATA_HOME/tic-tac-toe-rust (~/.local/share) and
+the config directory $XDG_CONFIG_HOME/tic-tac-toe-rust (~/.config), or both are in
+~/Library/Application Support on macOS and %APPDATA% on Windows. Files left in the working
+directory by older versions are moved there.
+
+When standard input is not a terminal, a game started with --p2 exits with 0 for a draw,
+1 when Player 1 won and 2 when Player 2 won the last game, or 11 when the input ended first.
+Errors exit with 12, and invalid arguments with 13.
+
+Wherever a position is expected (--position, analyze and qr), paste reads it from the clipboard.
+The copy command in games, the board editor and the browser puts the position there.";
+
+#[derive(Debug, PartialEq)]
+pub enum PlayerSpec {
+    Human,
+    Bot(BotSpec),
+}
+
+pub enum CliCommand {
+    Menu {
+        record_to: Option<PathBuf>,
+        // Settings for every game, without a handicap or position.
+        defaults: GameSetup,
+    },
+    Play {
+        opponent: PlayerSpec,
+        setup: GameSetup,
+        record_to: Option<PathBuf>,
+    },
+    // A game on a 4x4 or 5x5 board, against the bot when there is one.
+    Sized {
+        size: usize,
+        bot: Option<Difficulty>,
+        setup: GameSetup,
+    },
+    Replay {
+        path: PathBuf,
+        render: bool,
+    },
+    Svg(Vec<Board>),
+    Browse {
+        path: PathBuf,
+        // Counted from 1, the last game of the recording when not given.
+        game: Option<usize>,
+        // Every game at once.
+        all: bool,
+        analysis: bool,
+    },
+    Tournament {
+        bots: Vec<BotSpec>,
+        games: u32,
+    },
+    Team {
+        // Two teammates each.
+        teams: Vec<[String; 2]>,
+        rule: Rule,
+        // The opponent of a single team.
+        against: BotSpec,
+    },
+    Simul {
+        // One for each board.
+        bots: Vec<BotSpec>,
+    },
+    SelfPlay(SelfPlayOptions),
+    Train(TrainOptions),
+    Analyze {
+        position: Board,
+        playouts: u32,
+    },
+    Bench {
+        // How long each benchmark runs.
+        millis: u64,
+    },
+    Openings,
+    Achievements,
+    Daily,
+    Stats,
+    Profiles,
+    Paths,
+    Export {
+        path: PathBuf,
+    },
+    Import {
+        path: PathBuf,
+        // Take the export over instead of merging it.
+        replace: bool,
+    },
+    Campaign,
+    Irc(IrcOptions),
+    // Twitch chat is IRC with crowd voting always on.
+    Twitch(IrcOptions),
+    Telnet(TelnetOptions),
+    Join {
+        address: String,
+        // How to check the server, when joining over TLS.
+        tls: Option<Verify>,
+    },
+    Watch(Broker),
+    Qr {
+        // A position or a game record.
+        text: String,
+        invert: bool,
+    },
+    Correspond {
+        path: PathBuf,
+        // None just shows the game.
+        player_move: Option<[usize; 2]>,
+    },
+}
+
+const DEFAULT_TOURNAMENT_GAMES: u32 = 10;
+const DEFAULT_SELFPLAY_GAMES: u32 = 1000;
+const DEFAULT_TRAIN_EPISODES: u32 = 20_000;
+const DEFAULT_TRAIN_EPSILON: u32 = 10;
+const DEFAULT_TRAIN_REPORT_EVERY: u32 = 5000;
+const DEFAULT_POLICY_FILE: &str = "policy.txt";
+const DEFAULT_PLAYOUTS: u32 = 1000;
+const DEFAULT_IRC_NICK: &str = "tictacbot";
+const MAX_VOTING_SECONDS: u64 = 600;
+// Twitch's IRC gateway without TLS, which std cannot do.
+const TWITCH_SERVER: &str = "irc.chat.twitch.tv:6667";
+
+// Plays the moves from an empty board, Plus first, and returns every position along the way.
+fn frames_from_moves(moves: &[String]) -> Result<Vec<Board>, String> {
+    let mut board = Board::new();
+    let mut frames = vec![board.clone()];
+    let mut symbol = Symbol::Plus;
+
+    for (i, player_move) in moves.iter().enumerate() {
+        if board.status() != GameStatus::InProgress {
+            return Err(format!(
+                "Move {} is played after the game has ended.",
+                i + 1
+            ));
+        }
+
+        let position = parse_player_move(player_move)
+            .map_err(|_| format!("Move {} '{player_move}' is not in the x,y format.", i + 1))?;
+        board
+            .is_valid_move(position)
+            .map_err(|_| format!("Move {} '{player_move}' is not a legal move.", i + 1))?;
+
+        board.place(symbol, position);
+        frames.push(board.clone());
+        symbol = symbol.opponent();
+    }
+
+    Ok(frames)
+}
+
+fn parse_svg(args: &[String]) -> Result<CliCommand, String> {
+    let frames = match args.first().map(String::as_str) {
+        Some("--moves") => frames_from_moves(&args[1..])?,
+        _ => args
+            .iter()
+            .map(|position| {
+                position
+                    .parse::<Board>()
+                    .map_err(|_| format!("'{position}' is not a valid position."))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+
+    if frames.is_empty() {
+        return Err(String::from("svg needs at least one position."));
+    }
+
+    Ok(CliCommand::Svg(frames))
+}
+
+fn parse_replay(args: &[String]) -> Result<CliCommand, String> {
+    match args {
+        [path] => Ok(CliCommand::Replay {
+            path: PathBuf::from(path),
+            render: false,
+        }),
+        [flag, path] if flag == "--render" => Ok(CliCommand::Replay {
+            path: PathBuf::from(path),
+            render: true,
+        }),
+        _ => Err(String::from("replay needs the path of a recording.")),
+    }
+}
+
+fn parse_import(args: &[String]) -> Result<CliCommand, String> {
+    match args {
+        [path] => Ok(CliCommand::Import {
+            path: PathBuf::from(path),
+            replace: false,
+        }),
+        [flag, path] if flag == "--replace" => Ok(CliCommand::Import {
+            path: PathBuf::from(path),
+            replace: true,
+        }),
+        _ => Err(String::from(
+            "import needs the path of an exported profile.",
+        )),
+    }
+}
+
+fn parse_browse(args: &[String]) -> Result<CliCommand, String> {
+    let mut game = None;
+    let mut all = false;
+    let mut analysis = false;
+    let mut path = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--game" => {
+                game = Some(
+                    args.next()
+                        .and_then(|value| value.parse().ok())
+                        .filter(|game| *game > 0)
+                        .ok_or_else(|| String::from("--game needs a positive number."))?,
+                );
+            }
+            "--all" => all = true,
+            "--analysis" => analysis = true,
+            _ if path.is_none() => path = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument '{arg}'.")),
+        }
+    }
+
+    let path = path.ok_or_else(|| String::from("browse needs the path of a recording."))?;
+    if all && (game.is_some() || analysis) {
+        return Err(String::from(
+            "--all shows every game, it cannot be used with --game or --analysis.",
+        ));
+    }
+    Ok(CliCommand::Browse {
+        path,
+        game,
+        all,
+        analysis,
+    })
+}
+
+fn parse_tournament(args: &[String]) -> Result<CliCommand, String> {
+    let mut games = DEFAULT_TOURNAMENT_GAMES;
+    let mut bots = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--games" {
+            games = args
+                .next()
+                .and_then(|value| value.parse().ok())
+                .filter(|games| *games > 0)
+                .ok_or_else(|| String::from("--games needs a positive number."))?;
+        } else {
+            bots.push(arg.parse()?);
+        }
+    }
+
+    if bots.len() < 2 {
+        return Err(String::from("tournament needs at least two bots."));
+    }
+
+    Ok(CliCommand::Tournament { bots, games })
+}
+
+fn parse_team(args: &[String]) -> Result<CliCommand, String> {
+    let mut rule = Rule::default();
+    let mut against = None;
+    let mut names = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value."));
+        match arg.as_str() {
+            "--rule" => rule = value()?.parse()?,
+            "--against" => against = Some(value()?.parse::<BotSpec>()?),
+            _ => names.push(arg.clone()),
+        }
+    }
+
+    let teams = match names.as_slice() {
+        [first, second] => vec![[first.clone(), second.clone()]],
+        [first, second, third, fourth] if against.is_none() => vec![
+            [first.clone(), second.clone()],
+            [third.clone(), fourth.clone()],
+        ],
+        [_, _, _, _] => {
+            return Err(String::from(
+                "--against is for a single team, four players play each other.",
+            ))
+        }
+        _ => return Err(String::from("team needs the names of two or four players.")),
+    };
+
+    Ok(CliCommand::Team {
+        teams,
+        rule,
+        against: against.unwrap_or(BotSpec::Builtin(Difficulty::Hard)),
+    })
+}
+
+fn parse_simul(args: &[String]) -> Result<CliCommand, String> {
+    let mut boards = None;
+    let mut bots = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--boards" {
+            boards = Some(
+                args.next()
+                    .and_then(|value| value.parse().ok())
+                    .filter(|boards| (1..=simul::MAX_BOARDS).contains(boards))
+                    .ok_or_else(|| {
+                        format!("--boards needs a number from 1 to {}.", simul::MAX_BOARDS)
+                    })?,
+            );
+        } else {
+            bots.push(arg.parse::<BotSpec>()?);
+        }
+    }
+
+    if bots.is_empty() {
+        return Err(String::from("simul needs at least one bot."));
+    }
+    let boards = boards.unwrap_or(bots.len());
+    if boards > simul::MAX_BOARDS {
+        return Err(format!("A simul has at most {} boards.", simul::MAX_BOARDS));
+    }
+
+    Ok(CliCommand::Simul {
+        bots: bots.into_iter().cycle().take(boards).collect(),
+    })
+}
+
+fn parse_selfplay(args: &[String]) -> Result<CliCommand, String> {
+    let mut options = SelfPlayOptions {
+        bot: BotSpec::Builtin(Difficulty::Hard),
+        games: DEFAULT_SELFPLAY_GAMES,
+        format: DatasetFormat::Csv,
+        noise: 0,
+        seed: None,
+        out: None,
+        stats: None,
+    };
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{arg} needs a value."))?;
+        let number = || {
+            value
+                .parse()
+                .map_err(|_| format!("{arg} needs a number, not '{value}'."))
+        };
+
+        match arg.as_str() {
+            "--bot" => options.bot = value.parse()?,
+            "--games" => options.games = number()?,
+            "--format" => options.format = value.parse()?,
+            "--noise" => {
+                options.noise = number()?;
+                if options.noise > 100 {
+                    return Err(String::from("--noise is a percentage from 0 to 100."));
+                }
+            }
+            "--seed" => options.seed = Some(number()?.into()),
+            "--out" => options.out = Some(PathBuf::from(value)),
+            "--stats" => options.stats = Some(value.parse()?),
+            other => return Err(format!("Unknown selfplay option '{other}'.")),
+        }
+    }
+
+    Ok(CliCommand::SelfPlay(options))
+}
+
+fn parse_train(args: &[String]) -> Result<CliCommand, String> {
+    let mut options = TrainOptions {
+        episodes: DEFAULT_TRAIN_EPISODES,
+        epsilon: DEFAULT_TRAIN_EPSILON,
+        report_every: DEFAULT_TRAIN_REPORT_EVERY,
+        seed: None,
+        out: PathBuf::from(DEFAULT_POLICY_FILE),
+    };
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{arg} needs a value."))?;
+        let number = || {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|number| *number > 0)
+                .ok_or_else(|| format!("{arg} needs a positive number, not '{value}'."))
+        };
+
+        match arg.as_str() {
+            "--episodes" => options.episodes = number()?,
+            "--epsilon" => {
+                options.epsilon = value
+                    .parse()
+                    .ok()
+                    .filter(|epsilon| *epsilon <= 100)
+                    .ok_or_else(|| String::from("--epsilon is a percentage from 0 to 100."))?;
+            }
+            "--report-every" => options.report_every = number()?,
+            "--seed" => options.seed = Some(number()?.into()),
+            "--out" => options.out = PathBuf::from(value),
+            other => return Err(format!("Unknown train option '{other}'.")),
+        }
+    }
+
+    Ok(CliCommand::Train(options))
+}
+
+fn parse_analyze(args: &[String]) -> Result<CliCommand, String> {
+    let (playouts, position) = match args {
+        [position] => (DEFAULT_PLAYOUTS, position),
+        [flag, playouts, position] if flag == "--playouts" => (
+            playouts
+                .parse()
+                .ok()
+                .filter(|playouts| *playouts > 0)
+                .ok_or_else(|| String::from("--playouts needs a positive number."))?,
+            position,
+        ),
+        _ => return Err(String::from("analyze needs a position, e.g. +-o/---/o-+")),
+    };
+
+    let position = clipboard::position_argument(position)?;
+
+    Ok(CliCommand::Analyze { position, playouts })
+}
+
+fn parse_bench(args: &[String]) -> Result<CliCommand, String> {
+    let millis = match args {
+        [] => bench::DEFAULT_MILLIS,
+        [flag, millis] if flag == "--millis" => millis
+            .parse()
+            .ok()
+            .filter(|millis| *millis > 0)
+            .ok_or_else(|| String::from("--millis needs a positive number."))?,
+        _ => return Err(String::from("bench takes only --millis <n>.")),
+    };
+
+    Ok(CliCommand::Bench { millis })
+}
+
+// Only positions and game records are encoded, checked here so a typo does
+// not end up in a code that has to be scanned to be noticed.
+fn parse_qr(args: &[String]) -> Result<CliCommand, String> {
+    let (text, invert) = match args {
+        [text] => (text, true),
+        [flag, text] if flag == "--light" => (text, false),
+        _ => return Err(String::from("qr needs a position or a game record.")),
+    };
+    let text = if text.eq_ignore_ascii_case(clipboard::PASTE) {
+        clipboard::paste()?
+    } else {
+        text.clone()
+    };
+
+    let valid_position = text
+        .parse::<Board>()
+        .is_ok_and(|board| board.validate().is_ok());
+    if !valid_position && text.parse::<GameRecord>().is_err() {
+        return Err(format!("'{text}' is not a position or a game record."));
+    }
+
+    Ok(CliCommand::Qr { text, invert })
+}
+
+fn parse_correspond(args: &[String]) -> Result<CliCommand, String> {
+    let (path, player_move) = match args {
+        [path] => (path, None),
+        [path, player_move] => (
+            path,
+            Some(
+                parse_player_move(player_move)
+                    .map_err(|_| format!("'{player_move}' is not a move, use x,y."))?,
+            ),
+        ),
+        _ => {
+            return Err(String::from(
+                "correspond needs a game file and optionally a move.",
+            ))
+        }
+    };
+
+    Ok(CliCommand::Correspond {
+        path: PathBuf::from(path),
+        player_move,
+    })
+}
+
+fn parse_irc(args: &[String]) -> Result<CliCommand, String> {
+    let mut server = None;
+    let mut channel = None;
+    let mut nick = String::from(DEFAULT_IRC_NICK);
+    let mut bot = BotSpec::Builtin(Difficulty::Medium);
+    let mut crowd = None;
+    let mut tiebreak = Tiebreak::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{arg} needs a value."))?;
+
+        match arg.as_str() {
+            "--server" => server = Some(value.clone()),
+            "--channel" if value.starts_with('#') => channel = Some(value.clone()),
+            "--channel" => return Err(format!("'{value}' is not a channel, they start with #.")),
+            "--nick" => nick.clone_from(value),
+            "--bot" => bot = value.parse()?,
+            "--crowd" => {
+                crowd = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|seconds| (1..=MAX_VOTING_SECONDS).contains(seconds))
+                        .map(Duration::from_secs)
+                        .ok_or_else(|| {
+                            format!(
+                                "--crowd needs a number of seconds from 1 to {MAX_VOTING_SECONDS}."
+                            )
+                        })?,
+                );
+            }
+            "--tiebreak" => tiebreak = value.parse()?,
+            other => return Err(format!("Unknown irc option '{other}'.")),
+        }
+    }
+
+    Ok(CliCommand::Irc(IrcOptions {
+        server: server.ok_or_else(|| String::from("irc needs --server <host:port>."))?,
+        channel: channel.ok_or_else(|| String::from("irc needs --channel <#channel>."))?,
+        nick,
+        bot,
+        crowd,
+        tiebreak,
+    }))
+}
+
+fn parse_twitch(args: &[String]) -> Result<CliCommand, String> {
+    let mut options = IrcOptions {
+        server: String::from(TWITCH_SERVER),
+        channel: String::new(),
+        nick: String::from(DEFAULT_IRC_NICK),
+        bot: BotSpec::Builtin(Difficulty::Medium),
+        crowd: Some(vote::DEFAULT_WINDOW),
+        tiebreak: Tiebreak::default(),
+    };
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{arg} needs a value."))?;
+
+        match arg.as_str() {
+            // Twitch channels are named after the streamer, the # is optional.
+            "--channel" => options.channel = format!("#{}", value.trim_start_matches('#')),
+            "--nick" => options.nick.clone_from(value),
+            "--bot" => options.bot = value.parse()?,
+            "--window" => {
+                options.crowd = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|seconds| (1..=MAX_VOTING_SECONDS).contains(seconds))
+                        .map(Duration::from_secs)
+                        .ok_or_else(|| {
+                            format!("--window needs a number of seconds from 1 to {MAX_VOTING_SECONDS}.")
+                        })?,
+                );
+            }
+            "--tiebreak" => options.tiebreak = value.parse()?,
+            other => return Err(format!("Unknown twitch option '{other}'.")),
+        }
+    }
+
+    if options.channel.len() < 2 {
+        return Err(String::from("twitch needs --channel <channel>."));
+    }
+    Ok(CliCommand::Twitch(options))
+}
+
+fn parse_telnet(args: &[String]) -> Result<CliCommand, String> {
+    let mut options = TelnetOptions::new(telnet::DEFAULT_ADDRESS);
+    let mut chain = None;
+    let mut key = None;
+    let number = |flag: &str, value: &str| {
+        value
+            .parse::<u32>()
+            .ok()
+            .filter(|number| *number > 0)
+            .ok_or_else(|| format!("{flag} needs a positive number, not '{value}'."))
+    };
+
+    for pair in args.chunks(2) {
+        match pair {
+            [flag, value] if flag == "--listen" => options.address.clone_from(value),
+            [flag, value] if flag == "--webhook" => options.webhooks.push(value.parse()?),
+            [flag, value] if flag == "--mqtt" => options.mqtt = Some(value.parse()?),
+            [flag, value] if flag == "--tokens" => options.tokens = Some(PathBuf::from(value)),
+            [flag, value] if flag == "--issue" => options.issue = Some(value.clone()),
+            [flag, value] if flag == "--saves" => options.saves = Some(PathBuf::from(value)),
+            [flag, value] if flag == "--metrics" => options.metrics = Some(value.clone()),
+            [flag, value] if flag == "--tls-cert" => chain = Some(PathBuf::from(value)),
+            [flag, value] if flag == "--tls-key" => key = Some(PathBuf::from(value)),
+            [flag, value] if flag == "--max-connections" => {
+                options.limits.connections = number(flag, value)? as usize;
+            }
+            [flag, value] if flag == "--max-per-client" => {
+                options.limits.per_client = number(flag, value)? as usize;
+            }
+            [flag, value] if flag == "--rate" => {
+                options.limits.lines_per_second = number(flag, value)?;
+            }
+            [flag, value] if flag == "--idle" => {
+                options.limits.idle = Duration::from_mins(number(flag, value)?.into());
+            }
+            [flag, ..] => return Err(format!("Unknown telnet option '{flag}'.")),
+            [] => unreachable!("chunks are never empty"),
+        }
+    }
+    if options.issue.is_some() && options.tokens.is_none() {
+        return Err(String::from(
+            "--issue needs --tokens <file> to add the token to.",
+        ));
+    }
+    options.tls = match (chain, key) {
+        (Some(chain), Some(key)) => Some(Identity { chain, key }),
+        (None, None) => None,
+        _ => {
+            return Err(String::from(
+                "--tls-cert and --tls-key go together: the certificate and its key.",
+            ))
+        }
+    };
+    Ok(CliCommand::Telnet(options))
+}
+
+fn parse_join(args: &[String]) -> Result<CliCommand, String> {
+    let mut address = String::from(telnet::DEFAULT_ADDRESS);
+    let mut tls = false;
+    let mut verify = Verify::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value."));
+        match arg.as_str() {
+            "--tls" => tls = true,
+            "--ca" => verify.ca = Some(PathBuf::from(value()?)),
+            "--server-name" => verify.name = Some(value()?.clone()),
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown join option '{flag}'."));
+            }
+            _ => address.clone_from(arg),
+        }
+    }
+    if !tls && verify != Verify::default() {
+        return Err(String::from(
+            "--ca and --server-name check the server's certificate, so they need --tls.",
+        ));
+    }
+    Ok(CliCommand::Join {
+        address,
+        tls: tls.then_some(verify),
+    })
+}
+
+fn parse_player(value: &str) -> Result<PlayerSpec, String> {
+    if value.eq_ignore_ascii_case("human") {
+        Ok(PlayerSpec::Human)
+    } else {
+        value.parse().map(PlayerSpec::Bot)
+    }
+}
+
+// The options for an interactive game, without a subcommand.
+fn parse_game(args: &[String]) -> Result<CliCommand, String> {
+    let mut record_to = None;
+    let mut opponent = None;
+    let mut handicap = None;
+    let mut position = None;
+    let mut to_move = None;
+    let mut resume = None;
+    let mut size = 3;
+    // Options that go straight into the setup.
+    let mut setup = GameSetup::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value."));
+
+        match arg.as_str() {
+            "--confirm" => setup.confirm_moves = true,
+            "--coach" => setup.coach = true,
+            "--personality" => {
+                setup.personality = Some(personality::load(Path::new(value()?))?);
+            }
+            "-v" | "--debug" => setup.debug = true,
+            "-q" | "--quiet" => setup.quiet = true,
+            "--takebacks" => setup.takebacks = value()?.parse()?,
+            "--palette" => setup.palette = value()?.parse()?,
+            "--view" => setup.view = value()?.parse()?,
+            "--scale" => setup.scale = value()?.parse()?,
+            "--keys" => setup.keys = value()?.parse()?,
+            "--volume" => setup.volume = value()?.parse()?,
+            "--mute" => setup.mute = true,
+            "--result-format" => setup.result_format = value()?.parse()?,
+            "--log-game" => setup.log = Some(GameLog::open(Path::new(value()?))?),
+            "--mqtt" => {
+                let publisher = Publisher::connect(&value()?.parse()?)?;
+                setup.events = Events::new(Vec::new(), Some(publisher), None);
+            }
+            "--resume" => resume = Some(autosave::load(Path::new(value()?))?),
+            "--record" => record_to = Some(PathBuf::from(value()?)),
+            "--p2" => opponent = Some(parse_player(value()?)?),
+            "--size" => size = parse_size(value()?)?,
+            "--handicap" => handicap = Some(value()?.parse()?),
+            "--position" => position = Some(clipboard::position_argument(value()?)?),
+            "--to-move" => {
+                let value = value()?;
+                to_move = match value.as_str() {
+                    "+" => Some(Symbol::Plus),
+                    "o" => Some(Symbol::Circle),
+                    _ => return Err(format!("--to-move is + or o, not '{value}'.")),
+                };
+            }
+            other => return Err(format!("Unknown command '{other}'.")),
+        }
+    }
+
+    if size != 3 {
+        if record_to.is_some() || resume.is_some() || handicap.is_some() || position.is_some() {
+            return Err(String::from(
+                "--size plays without --record, --resume, --handicap or --position.",
+            ));
+        }
+        return parse_sized(size, opponent.as_ref(), setup);
+    }
+    if resume.is_some() && (opponent.is_some() || handicap.is_some() || position.is_some()) {
+        return Err(String::from(
+            "--resume plays the saved game as it was, without --p2, --handicap or --position.",
+        ));
+    }
+    if to_move.is_some() && position.is_none() {
+        return Err(String::from("--to-move needs --position."));
+    }
+    if handicap.is_some() && position.is_some() {
+        return Err(String::from(
+            "--handicap and --position cannot be combined.",
+        ));
+    }
+
+    let setup = GameSetup {
+        handicap,
+        position: position
+            .map(|board| StartPosition::new(board, to_move))
+            .transpose()?,
+        resume: resume.clone(),
+        ..setup
+    };
+    if let Some(game) = resume {
+        opponent = Some(game.opponent.map_or(PlayerSpec::Human, PlayerSpec::Bot));
+    }
+
+    Ok(match opponent {
+        Some(opponent) => CliCommand::Play {
+            opponent,
+            setup,
+            record_to,
+        },
+        None if setup.handicap.is_none() && setup.position.is_none() => CliCommand::Menu {
+            record_to,
+            defaults: setup,
+        },
+        None => {
+            return Err(String::from(
+                "--handicap and --position need --p2, the menu asks for a handicap itself.",
+            ))
+        }
+    })
+}
+
+fn parse_size(value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|size| AnyBoard::new(*size).is_ok())
+        .ok_or_else(|| format!("--size is 3, 4 or 5, not '{value}'."))
+}
+
+// A game on a bigger board, which only the easy and medium bots can play.
+fn parse_sized(
+    size: usize,
+    opponent: Option<&PlayerSpec>,
+    setup: GameSetup,
+) -> Result<CliCommand, String> {
+    let bot = match opponent {
+        Some(PlayerSpec::Human) => None,
+        Some(PlayerSpec::Bot(BotSpec::Builtin(
+            difficulty @ (Difficulty::Easy | Difficulty::Medium),
+        ))) => Some(*difficulty),
+        _ => {
+            return Err(format!(
+                "On a {size}x{size} board --p2 is human, easy or medium."
+            ))
+        }
+    };
+    Ok(CliCommand::Sized { size, bot, setup })
+}
+
+// Takes --profile <name> out of the arguments, since it goes with any command.
+pub fn take_profile(args: &[String]) -> Result<(Option<String>, Vec<String>), String> {
+    let mut profile = None;
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            let name = args
+                .next()
+                .ok_or_else(|| String::from("--profile needs a value."))?;
+            profile = Some(name.clone());
+        } else {
+            rest.push(arg.clone());
+        }
+    }
+
+    Ok((profile, rest))
+}
+
+pub fn parse(args: &[String]) -> Result<CliCommand, String> {
+    match args.first().map(String::as_str) {
+        Some("replay") => parse_replay(&args[1..]),
+        Some("svg") => parse_svg(&args[1..]),
+        Some("browse") => parse_browse(&args[1..]),
+        Some("tournament") => parse_tournament(&args[1..]),
+        Some("simul") => parse_simul(&args[1..]),
+        Some("team") => parse_team(&args[1..]),
+        Some("selfplay") => parse_selfplay(&args[1..]),
+        Some("train") => parse_train(&args[1..]),
+        Some("analyze") => parse_analyze(&args[1..]),
+        Some("bench") => parse_bench(&args[1..]),
+        Some("campaign") if args.len() == 1 => Ok(CliCommand::Campaign),
+        Some("campaign") => Err(String::from("campaign takes no arguments.")),
+        Some("profiles") if args.len() == 1 => Ok(CliCommand::Profiles),
+        Some("profiles") => Err(String::from("profiles takes no arguments.")),
+        Some("paths") if args.len() == 1 => Ok(CliCommand::Paths),
+        Some("paths") => Err(String::from("paths takes no arguments.")),
+        Some("export") => match &args[1..] {
+            [path] => Ok(CliCommand::Export {
+                path: PathBuf::from(path),
+            }),
+            _ => Err(String::from("export needs the path of the file to write.")),
+        },
+        Some("import") => parse_import(&args[1..]),
+        Some("stats") if args.len() == 1 => Ok(CliCommand::Stats),
+        Some("stats") => Err(String::from("stats takes no arguments.")),
+        Some("daily") if args.len() == 1 => Ok(CliCommand::Daily),
+        Some("daily") => Err(String::from("daily takes no arguments.")),
+        Some("achievements") if args.len() == 1 => Ok(CliCommand::Achievements),
+        Some("achievements") => Err(String::from("achievements takes no arguments.")),
+        Some("openings") if args.len() == 1 => Ok(CliCommand::Openings),
+        Some("openings") => Err(String::from("openings takes no arguments.")),
+        Some("irc") => parse_irc(&args[1..]),
+        Some("twitch") => parse_twitch(&args[1..]),
+        Some("telnet") => parse_telnet(&args[1..]),
+        Some("join") => parse_join(&args[1..]),
+        Some("watch") => match &args[1..] {
+            [broker] => Ok(CliCommand::Watch(broker.parse()?)),
+            _ => Err(String::from(
+                "watch takes an MQTT broker, e.g. localhost:1883.",
+            )),
+        },
+        Some("correspond") => parse_correspond(&args[1..]),
+        Some("qr") => parse_qr(&args[1..]),
+        _ => parse_game(args),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{ResultFormat, TakebackAnswer};
+    use crate::keys::Keys;
+    use crate::palette::Palette;
+    use crate::telnet::Limits;
+    use crate::view::{Scale, View};
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn no_arguments_opens_the_menu() {
+        assert!(matches!(
+            parse(&[]),
+            Ok(CliCommand::Menu {
+                record_to: None,
+                defaults: GameSetup {
+                    confirm_moves: false,
+                    ..
+                }
+            })
+        ));
+    }
+
+    #[test]
+    fn p2_skips_the_menu() {
+        let Ok(CliCommand::Play {
+            opponent,
+            setup,
+            record_to,
+        }) = parse(&args(&[
+            "--p2",
+            "script:mybot.rhai",
+            "--record",
+            "game.rec",
+        ]))
+        else {
+            panic!("expected a game");
+        };
+
+        assert_eq!(
+            opponent,
+            PlayerSpec::Bot(BotSpec::Script(PathBuf::from("mybot.rhai")))
+        );
+        assert_eq!(record_to, Some(PathBuf::from("game.rec")));
+        assert!(setup.handicap.is_none() && setup.position.is_none());
+        assert!(matches!(
+            parse(&args(&["--p2", "Human"])),
+            Ok(CliCommand::Play {
+                opponent: PlayerSpec::Human,
+                record_to: None,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn handicap_comes_with_a_game() {
+        let Ok(CliCommand::Play { setup, .. }) =
+            parse(&args(&["--p2", "hard", "--handicap", "p1:2"]))
+        else {
+            panic!("expected a game with a handicap");
+        };
+
+        assert_eq!(setup.handicap, Some("p1:2".parse().unwrap()));
+    }
+
+    #[test]
+    fn games_can_start_from_a_position() {
+        let Ok(CliCommand::Play { setup, .. }) = parse(&args(&[
+            "--p2",
+            "human",
+            "--position",
+            "+--/-o-/---",
+            "--to-move",
+            "o",
+        ])) else {
+            panic!("expected a game from a position");
+        };
+
+        assert!(setup.position.is_some());
+    }
+
+    #[test]
+    fn confirm_works_with_and_without_the_menu() {
+        assert!(matches!(
+            parse(&args(&["--confirm"])),
+            Ok(CliCommand::Menu {
+                defaults: GameSetup {
+                    confirm_moves: true,
+                    ..
+                },
+                ..
+            })
+        ));
+        let Ok(CliCommand::Play { setup, .. }) = parse(&args(&["--p2", "hard", "--confirm"]))
+        else {
+            panic!("expected a game");
+        };
+        assert!(setup.confirm_moves);
+    }
+
+    #[test]
+    fn coach_works_with_and_without_the_menu() {
+        assert!(matches!(
+            parse(&args(&["--coach"])),
+            Ok(CliCommand::Menu {
+                defaults: GameSetup { coach: true, .. },
+                ..
+            })
+        ));
+        let Ok(CliCommand::Play { setup, .. }) = parse(&args(&["--p2", "hard", "--coach"])) else {
+            panic!("expected a game");
+        };
+        assert!(setup.coach && !setup.confirm_moves);
+    }
+
+    #[test]
+    fn palettes_and_views_are_picked_by_name() {
+        let Ok(CliCommand::Play { setup, .. }) =
+            parse(&args(&["--p2", "hard", "--palette", "tritanopia"]))
+        else {
+            panic!("expected a game");
+        };
+        assert_eq!(setup.palette, Palette::Tritanopia);
+        let Ok(CliCommand::Play { setup, .. }) = parse(&args(&["--p2", "hard", "--view", "large"]))
+        else {
+            panic!("expected a game");
+        };
+        assert_eq!(setup.view, View::Large);
+        let Ok(CliCommand::Play { setup, .. }) =
+            parse(&args(&["--p2", "hard", "--view", "braille"]))
+        else {
+            panic!("expected a game");
+        };
+        assert_eq!(setup.view, View::Braille);
+        let Ok(CliCommand::Play { setup, .. }) = parse(&args(&["--p2", "hard", "--scale", "auto"]))
+        else {
+            panic!("expected a game");
+        };
+        assert_eq!(setup.scale, Scale::Auto);
+        assert!(parse(&args(&["--volume", "120"])).is_err());
+        let Ok(CliCommand::Play { setup, .. }) = parse(&args(&["--p2", "hard", "--keys", "vim"]))
+        else {
+            panic!("expected a game");
+        };
+        assert_eq!(setup.keys, Keys::Vim);
+        assert!(parse(&args(&["--palette", "sepia"])).is_err());
+    }
+
+    #[test]
+    fn personalities_are_loaded_from_files() {
+        let Ok(CliCommand::Play { setup, .. }) = parse(&args(&[
+            "--p2",
+            "hard",
+            "--personality",
+            "examples/grumpy.personality",
+        ])) else {
+            panic!("expected a game");
+        };
+        assert_eq!(
+            setup
+                .personality
+                .map(|personality| personality.name)
+                .as_deref(),
+            Some("Grumpy")
+        );
+        assert!(parse(&args(&["--personality", "examples/missing.personality"])).is_err());
+    }
+
+    #[test]
+    fn profiles_go_with_any_command() {
+        assert_eq!(
+            take_profile(&args(&["stats", "--profile", "ann"])),
+            Ok((Some(String::from("ann")), args(&["stats"])))
+        );
+        assert_eq!(
+            take_profile(&args(&["--p2", "hard"])),
+            Ok((None, args(&["--p2", "hard"])))
+        );
+        assert!(take_profile(&args(&["--profile"])).is_err());
+    }
+
+    #[test]
+    fn profiles_are_exported_and_imported_through_files() {
+        assert!(matches!(
+            parse(&args(&["export", "ann.profile"])),
+            Ok(CliCommand::Export { .. })
+        ));
+        assert!(matches!(
+            parse(&args(&["import", "ann.profile"])),
+            Ok(CliCommand::Import { replace: false, .. })
+        ));
+        assert!(matches!(
+            parse(&args(&["import", "--replace", "ann.profile"])),
+            Ok(CliCommand::Import { replace: true, .. })
+        ));
+        for command in [&["export"][..], &["import"], &["import", "a", "b"]] {
+            assert!(parse(&args(command)).is_err());
+        }
+    }
+
+    #[test]
+    fn debug_and_quiet_have_short_forms() {
+        for (flag, debug, quiet) in [
+            ("-v", true, false),
+            ("--debug", true, false),
+            ("-q", false, true),
+            ("--quiet", false, true),
+        ] {
+            let Ok(CliCommand::Play { setup, .. }) = parse(&args(&["--p2", "hard", flag])) else {
+                panic!("expected a game");
+            };
+            assert_eq!((setup.debug, setup.quiet), (debug, quiet));
+        }
+    }
+
+    #[test]
+    fn takebacks_set_the_computers_answer() {
+        let Ok(CliCommand::Play { setup, .. }) =
+            parse(&args(&["--p2", "hard", "--takebacks", "refuse"]))
+        else {
+            panic!("expected a game");
+        };
+        assert_eq!(setup.takebacks, TakebackAnswer::Refuse);
+        assert!(parse(&args(&["--takebacks", "maybe"])).is_err());
+    }
+
+    #[test]
+    fn result_format_can_be_json() {
+        let Ok(CliCommand::Play { setup, .. }) =
+            parse(&args(&["--p2", "hard", "--result-format", "JSON"]))
+        else {
+            panic!("expected a game");
+        };
+        assert_eq!(setup.result_format, ResultFormat::Json);
+        assert!(parse(&args(&["--result-format", "xml"])).is_err());
+    }
+
+    #[test]
+    fn record_and_replay_take_a_path() {
+        let Ok(CliCommand::Menu {
+            record_to: Some(path),
+            ..
+        }) = parse(&args(&["--record", "game.rec"]))
+        else {
+            panic!("expected a recorded menu");
+        };
+        assert_eq!(path, PathBuf::from("game.rec"));
+
+        assert!(matches!(
+            parse(&args(&["replay", "game.rec"])),
+            Ok(CliCommand::Replay { render: false, .. })
+        ));
+        assert!(matches!(
+            parse(&args(&["replay", "--render", "game.rec"])),
+            Ok(CliCommand::Replay { render: true, .. })
+        ));
+    }
+
+    #[test]
+    fn browse_picks_a_game_from_a_recording() {
+        assert!(matches!(
+            parse(&args(&["browse", "game.rec"])),
+            Ok(CliCommand::Browse {
+                game: None,
+                all: false,
+                analysis: false,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&args(&["browse", "--all", "game.rec"])),
+            Ok(CliCommand::Browse { all: true, .. })
+        ));
+        let Ok(CliCommand::Browse {
+            path,
+            game,
+            analysis,
+            ..
+        }) = parse(&args(&["browse", "--game", "2", "--analysis", "game.rec"]))
+        else {
+            panic!("expected a browse command");
+        };
+
+        assert_eq!(path, PathBuf::from("game.rec"));
+        assert_eq!(game, Some(2));
+        assert!(analysis);
+    }
+
+    #[test]
+    fn svg_accepts_positions() {
+        let Ok(CliCommand::Svg(frames)) = parse(&args(&["svg", "+--/---/---", "+--/-o-/---"]))
+        else {
+            panic!("expected an svg command");
+        };
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].tiles[1][1], Symbol::Circle);
+    }
+
+    #[test]
+    fn svg_accepts_a_move_list() {
+        let Ok(CliCommand::Svg(frames)) = parse(&args(&["svg", "--moves", "1,1", "0,0"])) else {
+            panic!("expected an svg command");
+        };
+
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[2].tiles[1][1], Symbol::Plus);
+        assert_eq!(frames[2].tiles[0][0], Symbol::Circle);
+    }
+
+    #[test]
+    fn teams_are_pairs_of_players() {
+        let Ok(CliCommand::Team {
+            teams,
+            rule,
+            against,
+        }) = parse(&args(&["team", "--rule", "alternate", "ann", "bob"]))
+        else {
+            panic!("expected a team game");
+        };
+        assert_eq!(teams, [[String::from("ann"), String::from("bob")]]);
+        assert_eq!(rule, Rule::Alternate);
+        assert_eq!(against, BotSpec::Builtin(Difficulty::Hard));
+        assert!(matches!(
+            parse(&args(&["team", "a", "b", "c", "d"])),
+            Ok(CliCommand::Team { teams, rule: Rule::Confirm, .. }) if teams.len() == 2
+        ));
+    }
+
+    #[test]
+    fn simul_shares_the_boards_out_between_the_bots() {
+        let Ok(CliCommand::Simul { bots }) =
+            parse(&args(&["simul", "--boards", "3", "easy", "hard"]))
+        else {
+            panic!("expected a simul");
+        };
+        assert_eq!(
+            bots,
+            [
+                BotSpec::Builtin(Difficulty::Easy),
+                BotSpec::Builtin(Difficulty::Hard),
+                BotSpec::Builtin(Difficulty::Easy)
+            ]
+        );
+        assert!(matches!(
+            parse(&args(&["simul", "medium", "hard"])),
+            Ok(CliCommand::Simul { bots }) if bots.len() == 2
+        ));
+    }
+
+    #[test]
+    fn tournament_takes_bots_and_a_game_count() {
+        let Ok(CliCommand::Tournament { bots, games }) = parse(&args(&[
+            "tournament",
+            "easy",
+            "--games",
+            "4",
+            "plugin:libbot.so",
+        ])) else {
+            panic!("expected a tournament");
+        };
+
+        assert_eq!(
+            bots,
+            vec![
+                BotSpec::Builtin(Difficulty::Easy),
+                BotSpec::Plugin(PathBuf::from("libbot.so"))
+            ]
+        );
+        assert_eq!(games, 4);
+        assert!(matches!(
+            parse(&args(&["tournament", "easy", "hard"])),
+            Ok(CliCommand::Tournament {
+                games: DEFAULT_TOURNAMENT_GAMES,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn selfplay_has_defaults_and_options() {
+        let Ok(CliCommand::SelfPlay(defaults)) = parse(&args(&["selfplay"])) else {
+            panic!("expected selfplay");
+        };
+        assert_eq!(defaults.games, DEFAULT_SELFPLAY_GAMES);
+        assert_eq!(defaults.format, DatasetFormat::Csv);
+
+        let Ok(CliCommand::SelfPlay(options)) = parse(&args(&[
+            "selfplay",
+            "--bot",
+            "medium",
+            "--games",
+            "50",
+            "--format",
+            "jsonl",
+            "--noise",
+            "15",
+            "--seed",
+            "42",
+            "--out",
+            "games.jsonl",
+            "--stats",
+            "table",
+        ])) else {
+            panic!("expected selfplay");
+        };
+        assert_eq!(
+            options,
+            SelfPlayOptions {
+                bot: BotSpec::Builtin(Difficulty::Medium),
+                games: 50,
+                format: DatasetFormat::Jsonl,
+                noise: 15,
+                seed: Some(42),
+                out: Some(PathBuf::from("games.jsonl")),
+                stats: Some(crate::selfplay::StatsFormat::Table),
+            }
+        );
+    }
+
+    #[test]
+    fn train_has_defaults_and_options() {
+        let Ok(CliCommand::Train(defaults)) = parse(&args(&["train"])) else {
+            panic!("expected train");
+        };
+        assert_eq!(defaults.episodes, DEFAULT_TRAIN_EPISODES);
+        assert_eq!(defaults.out, PathBuf::from(DEFAULT_POLICY_FILE));
+
+        let Ok(CliCommand::Train(options)) = parse(&args(&[
+            "train",
+            "--episodes",
+            "500",
+            "--epsilon",
+            "0",
+            "--report-every",
+            "100",
+            "--seed",
+            "7",
+            "--out",
+            "q.txt",
+        ])) else {
+            panic!("expected train");
+        };
+        assert_eq!(
+            options,
+            TrainOptions {
+                episodes: 500,
+                epsilon: 0,
+                report_every: 100,
+                seed: Some(7),
+                out: PathBuf::from("q.txt"),
+            }
+        );
+    }
+
+    #[test]
+    fn analyze_takes_a_position() {
+        let Ok(CliCommand::Analyze { position, playouts }) =
+            parse(&args(&["analyze", "--playouts", "200", "+--/-o-/---"]))
+        else {
+            panic!("expected an analysis");
+        };
+
+        assert_eq!(playouts, 200);
+        assert_eq!(position.tiles[1][1], Symbol::Circle);
+        assert!(matches!(
+            parse(&args(&["analyze", "---/---/---"])),
+            Ok(CliCommand::Analyze {
+                playouts: DEFAULT_PLAYOUTS,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn listing_commands_take_no_arguments() {
+        assert!(matches!(
+            parse(&args(&["openings"])),
+            Ok(CliCommand::Openings)
+        ));
+        assert!(matches!(
+            parse(&args(&["campaign"])),
+            Ok(CliCommand::Campaign)
+        ));
+        assert!(parse(&args(&["openings", "hard"])).is_err());
+        assert!(matches!(
+            parse(&args(&["achievements"])),
+            Ok(CliCommand::Achievements)
+        ));
+        assert!(matches!(parse(&args(&["daily"])), Ok(CliCommand::Daily)));
+        assert!(matches!(parse(&args(&["stats"])), Ok(CliCommand::Stats)));
+        assert!(matches!(
+            parse(&args(&["profiles"])),
+            Ok(CliCommand::Profiles)
+        ));
+        assert!(matches!(parse(&args(&["paths"])), Ok(CliCommand::Paths)));
+        assert!(parse(&args(&["campaign", "2"])).is_err());
+    }
+
+    #[test]
+    fn bench_takes_a_time_per_benchmark() {
+        assert!(matches!(
+            parse(&args(&["bench"])),
+            Ok(CliCommand::Bench { millis: 500 })
+        ));
+        assert!(matches!(
+            parse(&args(&["bench", "--millis", "20"])),
+            Ok(CliCommand::Bench { millis: 20 })
+        ));
+        assert!(parse(&args(&["bench", "--millis", "0"])).is_err());
+    }
+
+    #[test]
+    fn telnet_listens_on_localhost_by_default() {
+        let Ok(CliCommand::Telnet(options)) = parse(&args(&["telnet"])) else {
+            panic!("expected telnet");
+        };
+        assert_eq!(options.address, "127.0.0.1:2323");
+        assert!(options.webhooks.is_empty());
+        assert!(options.mqtt.is_none());
+        assert!(options.tokens.is_none());
+
+        let Ok(CliCommand::Telnet(options)) = parse(&args(&["telnet", "--listen", "0.0.0.0:23"]))
+        else {
+            panic!("expected telnet");
+        };
+        assert_eq!(options.address, "0.0.0.0:23");
+        assert!(parse(&args(&["telnet", "--listen"])).is_err());
+    }
+
+    #[test]
+    fn telnet_serves_over_tls_with_a_certificate_and_its_key() {
+        let Ok(CliCommand::Telnet(options)) = parse(&args(&[
+            "telnet",
+            "--tls-cert",
+            "chain.pem",
+            "--tls-key",
+            "key.pem",
+        ])) else {
+            panic!("expected telnet");
+        };
+        assert_eq!(
+            options.tls,
+            Some(Identity {
+                chain: PathBuf::from("chain.pem"),
+                key: PathBuf::from("key.pem"),
+            })
+        );
+        assert!(parse(&args(&["telnet", "--tls-cert", "chain.pem"])).is_err());
+    }
+
+    #[test]
+    fn join_checks_the_server_over_tls() {
+        let Ok(CliCommand::Join { address, tls }) = parse(&args(&["join"])) else {
+            panic!("expected join");
+        };
+        assert_eq!(address, "127.0.0.1:2323");
+        assert_eq!(tls, None);
+
+        let Ok(CliCommand::Join { address, tls }) = parse(&args(&[
+            "join",
+            "example.org:2323",
+            "--tls",
+            "--ca",
+            "ca.pem",
+            "--server-name",
+            "games.example.org",
+        ])) else {
+            panic!("expected join");
+        };
+        assert_eq!(address, "example.org:2323");
+        assert_eq!(
+            tls,
+            Some(Verify {
+                ca: Some(PathBuf::from("ca.pem")),
+                name: Some(String::from("games.example.org")),
+            })
+        );
+        assert!(parse(&args(&["join", "--ca", "ca.pem"])).is_err());
+        assert!(parse(&args(&["join", "--tls", "--ca"])).is_err());
+        assert!(parse(&args(&["join", "--insecure"])).is_err());
+    }
+
+    #[test]
+    fn telnet_posts_to_every_webhook() {
+        let Ok(CliCommand::Telnet(TelnetOptions { webhooks, .. })) = parse(&args(&[
+            "telnet",
+            "--webhook",
+            "http://localhost:8080/a",
+            "--webhook",
+            "http://recorder/b",
+        ])) else {
+            panic!("expected telnet");
+        };
+        assert_eq!(webhooks.len(), 2);
+
+        let Ok(CliCommand::Telnet(TelnetOptions { mqtt, .. })) =
+            parse(&args(&["telnet", "--mqtt", "broker.local/ttt"]))
+        else {
+            panic!("expected telnet");
+        };
+        assert_eq!(mqtt, Some("broker.local:1883/ttt".parse().unwrap()));
+        assert!(matches!(
+            parse(&args(&["watch", "broker.local"])),
+            Ok(CliCommand::Watch(_))
+        ));
+        assert!(parse(&args(&["watch"])).is_err());
+        assert!(parse(&args(&["telnet", "--webhook", "https://example.org/"])).is_err());
+    }
+
+    #[test]
+    fn bigger_boards_are_played_against_simple_opponents() {
+        assert!(matches!(
+            parse(&args(&["--p2", "medium", "--size", "5"])),
+            Ok(CliCommand::Sized {
+                size: 5,
+                bot: Some(Difficulty::Medium),
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&args(&["--size", "4", "--p2", "human"])),
+            Ok(CliCommand::Sized { bot: None, .. })
+        ));
+        assert!(matches!(
+            parse(&args(&["--p2", "hard", "--size", "3"])),
+            Ok(CliCommand::Play { .. })
+        ));
+        for arguments in [
+            &["--size", "4"][..],
+            &["--p2", "hard", "--size", "4"],
+            &["--p2", "human", "--size", "6"],
+            &["--p2", "human", "--size", "4", "--handicap", "p1:1"],
+        ] {
+            assert!(parse(&args(arguments)).is_err(), "{arguments:?}");
+        }
+    }
+
+    #[test]
+    fn telnet_limits_can_be_changed() {
+        let Ok(CliCommand::Telnet(options)) = parse(&args(&[
+            "telnet",
+            "--max-connections",
+            "100",
+            "--max-per-client",
+            "2",
+            "--rate",
+            "10",
+            "--idle",
+            "1",
+        ])) else {
+            panic!("expected telnet");
+        };
+        assert_eq!(
+            options.limits,
+            Limits {
+                connections: 100,
+                per_client: 2,
+                lines_per_second: 10,
+                idle: Duration::from_mins(1),
+            }
+        );
+        assert!(parse(&args(&["telnet", "--rate", "0"])).is_err());
+        assert!(parse(&args(&["telnet", "--idle", "soon"])).is_err());
+    }
+
+    #[test]
+    fn telnet_tokens_are_issued_into_a_file() {
+        let Ok(CliCommand::Telnet(options)) = parse(&args(&[
+            "telnet", "--tokens", "players", "--issue", "alice",
+        ])) else {
+            panic!("expected telnet");
+        };
+        assert_eq!(options.tokens, Some(PathBuf::from("players")));
+        assert_eq!(options.issue.as_deref(), Some("alice"));
+        assert!(parse(&args(&["telnet", "--issue", "alice"])).is_err());
+    }
+
+    #[test]
+    fn irc_needs_a_server_and_channel() {
+        let Ok(CliCommand::Irc(options)) = parse(&args(&[
+            "irc",
+            "--server",
+            "irc.example.org:6667",
+            "--channel",
+            "#ttt",
+            "--bot",
+            "hard",
+        ])) else {
+            panic!("expected irc");
+        };
+
+        assert_eq!(
+            options,
+            IrcOptions {
+                server: String::from("irc.example.org:6667"),
+                channel: String::from("#ttt"),
+                nick: String::from(DEFAULT_IRC_NICK),
+                bot: BotSpec::Builtin(Difficulty::Hard),
+                crowd: None,
+                tiebreak: Tiebreak::First,
+            }
+        );
+
+        let Ok(CliCommand::Irc(options)) = parse(&args(&[
+            "irc",
+            "--server",
+            "irc.example.org:6667",
+            "--channel",
+            "#ttt",
+            "--crowd",
+            "30",
+            "--tiebreak",
+            "best",
+        ])) else {
+            panic!("expected irc");
+        };
+        assert_eq!(options.crowd, Some(Duration::from_secs(30)));
+        assert_eq!(options.tiebreak, Tiebreak::Best);
+    }
+
+    #[test]
+    fn twitch_always_votes() {
+        let Ok(CliCommand::Twitch(options)) = parse(&args(&[
+            "twitch",
+            "--channel",
+            "streamer",
+            "--window",
+            "45",
+        ])) else {
+            panic!("expected twitch");
+        };
+
+        assert_eq!(options.server, "irc.chat.twitch.tv:6667");
+        assert_eq!(options.channel, "#streamer");
+        assert_eq!(options.crowd, Some(Duration::from_secs(45)));
+        assert!(parse(&args(&["twitch"])).is_err());
+        assert!(parse(&args(&["twitch", "--channel", "a", "--window", "0"])).is_err());
+    }
+
+    #[test]
+    fn correspond_takes_a_file_and_an_optional_move() {
+        assert!(matches!(
+            parse(&args(&["correspond", "game.txt"])),
+            Ok(CliCommand::Correspond {
+                player_move: None,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&args(&["correspond", "game.txt", "1,2"])),
+            Ok(CliCommand::Correspond {
+                player_move: Some([1, 2]),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn qr_takes_positions_and_game_records() {
+        assert!(matches!(
+            parse(&args(&["qr", "+-o/---/o-+"])),
+            Ok(CliCommand::Qr { invert: true, .. })
+        ));
+        assert!(matches!(
+            parse(&args(&["qr", "--light", "---/---/--- +1,1 o0,0"])),
+            Ok(CliCommand::Qr { invert: false, .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        let invalid = [
+            args(&["svg"]),
+            args(&["svg", "+-x/---/---"]),
+            args(&["svg", "--moves", "1,1", "1,1"]),
+            args(&["svg", "--moves", "0,0", "1,0", "0,1", "1,1", "0,2", "2,2"]),
+            args(&["unknown"]),
+            args(&["--record"]),
+            args(&["--p2"]),
+            args(&["--p2", "grandmaster"]),
+            args(&["--handicap", "p1:1"]),
+            args(&["--p2", "hard", "--handicap", "p1:5"]),
+            args(&["--p2", "hard", "--position", "+++/oo-/---"]),
+            args(&[
+                "--p2",
+                "hard",
+                "--position",
+                "+--/---/---",
+                "--to-move",
+                "+",
+            ]),
+            args(&[
+                "--p2",
+                "hard",
+                "--position",
+                "+--/---/---",
+                "--to-move",
+                "x",
+            ]),
+            args(&["--p2", "hard", "--to-move", "o"]),
+            args(&["--position", "+--/---/---"]),
+            args(&[
+                "--p2",
+                "hard",
+                "--position",
+                "---/---/---",
+                "--handicap",
+                "p1:1",
+            ]),
+            args(&["replay"]),
+            args(&["replay", "--fast", "game.rec"]),
+            args(&["browse"]),
+            args(&["browse", "--game", "0", "game.rec"]),
+            args(&["browse", "a.rec", "b.rec"]),
+            args(&["browse", "--all", "--game", "2", "game.rec"]),
+            args(&["simul"]),
+            args(&["team", "ann"]),
+            args(&["team", "--against", "easy", "a", "b", "c", "d"]),
+            args(&["team", "--rule", "vote", "a", "b"]),
+            args(&["simul", "--boards", "13", "easy"]),
+            args(&["simul", "--boards", "0", "easy"]),
+            args(&["irc", "--server", "localhost:6667"]),
+            args(&[
+                "irc",
+                "--server",
+                "localhost:6667",
+                "--channel",
+                "#ttt",
+                "--crowd",
+                "0",
+            ]),
+            args(&["correspond"]),
+            args(&["telnet", "--listen"]),
+            args(&["telnet", "--port", "23"]),
+            args(&["qr"]),
+            args(&["qr", "hello"]),
+            args(&["qr", "+++/+++/---"]),
+            args(&["correspond", "game.txt", "b2"]),
+            args(&["irc", "--server", "localhost:6667", "--channel", "ttt"]),
+            args(&["tournament", "hard"]),
+            args(&["tournament", "hard", "grandmaster"]),
+            args(&["tournament", "--games", "0", "easy", "hard"]),
+            args(&["tournament", "easy", "hard", "--games"]),
+            args(&["selfplay", "--noise", "101"]),
+            args(&["selfplay", "--games", "many"]),
+            args(&["selfplay", "--format", "xml"]),
+            args(&["selfplay", "--out"]),
+            args(&["train", "--episodes", "0"]),
+            args(&["train", "--epsilon", "150"]),
+            args(&["train", "--rate", "0.5"]),
+            args(&["analyze"]),
+            args(&["analyze", "+-x/---/---"]),
+            args(&["analyze", "--playouts", "0", "---/---/---"]),
+        ];
+
+        for arguments in invalid {
+            assert!(parse(&arguments).is_err());
+        }
+    }
+}
@@ -0,0 +1,97 @@
+// The system clipboard through the platform's command line tools, so positions
+// can be moved between the game, the analyzer and chats without a dependency.
+
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use crate::board::Board;
+
+// The word that reads a position from the clipboard wherever one is expected.
+pub const PASTE: &str = "paste";
+
+#[cfg(target_os = "macos")]
+const COPY_TOOLS: &[&[&str]] = &[&["pbcopy"]];
+#[cfg(target_os = "macos")]
+const PASTE_TOOLS: &[&[&str]] = &[&["pbpaste"]];
+
+#[cfg(windows)]
+const COPY_TOOLS: &[&[&str]] = &[&["clip"]];
+#[cfg(windows)]
+const PASTE_TOOLS: &[&[&str]] = &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard"]];
+
+// Wayland first, then X11.
+#[cfg(not(any(target_os = "macos", windows)))]
+const COPY_TOOLS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+#[cfg(not(any(target_os = "macos", windows)))]
+const PASTE_TOOLS: &[&[&str]] = &[
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-o"],
+    &["xsel", "--clipboard", "--output"],
+];
+
+fn no_tool(tools: &[&[&str]]) -> String {
+    let names = tools.iter().map(|tool| tool[0]).collect::<Vec<_>>();
+    format!(
+        "No clipboard tool worked, this needs one of: {}.",
+        names.join(", ")
+    )
+}
+
+fn run_copy(tool: &[&str], text: &str) -> io::Result<bool> {
+    let mut child = Command::new(tool[0])
+        .args(&tool[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    Ok(child.wait()?.success())
+}
+
+pub fn copy(text: &str) -> Result<(), String> {
+    // Tools that are missing or fail, e.g. X11 tools without a display, are skipped.
+    if COPY_TOOLS
+        .iter()
+        .any(|tool| run_copy(tool, text).unwrap_or(false))
+    {
+        Ok(())
+    } else {
+        Err(no_tool(COPY_TOOLS))
+    }
+}
+
+pub fn paste() -> Result<String, String> {
+    PASTE_TOOLS
+        .iter()
+        .find_map(|tool| {
+            let output = Command::new(tool[0])
+                .args(&tool[1..])
+                .stderr(Stdio::null())
+                .output()
+                .ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .ok_or_else(|| no_tool(PASTE_TOOLS))
+}
+
+// Parses a position argument, reading it from the clipboard for PASTE.
+pub fn position_argument(value: &str) -> Result<Board, String> {
+    let text = if value.eq_ignore_ascii_case(PASTE) {
+        paste()?
+    } else {
+        value.to_string()
+    };
+
+    text.parse()
+        .map_err(|_| format!("'{text}' is not a valid position."))
+}
@@ -0,0 +1,102 @@
+// Live coaching: every move a player types is checked for the two mistakes
+// that decide games, missing a win and leaving a win for the opponent, and
+// the better move is explained before the opponent replies.
+
+use crate::board::{Board, GameStatus, Line, Symbol};
+
+// The tiles where symbol wins on the spot, with the line each one completes.
+fn winning_moves(board: &Board, symbol: Symbol) -> Vec<([usize; 2], Line)> {
+    board
+        .empty_tiles()
+        .filter_map(|position| {
+            let mut next = board.clone();
+            next.place(symbol, position);
+            match next.status() {
+                GameStatus::Won(_, line) => Some((position, line)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn describe_line(line: Line) -> String {
+    let [first, .., last] = line;
+    if first[0] == last[0] {
+        format!("row {}", first[0])
+    } else if first[1] == last[1] {
+        format!("column {}", first[1])
+    } else {
+        format!(
+            "the diagonal from {},{} to {},{}",
+            first[0], first[1], last[0], last[1]
+        )
+    }
+}
+
+// What was wrong with symbol playing at played, or None for a move that
+// neither missed a win nor let the opponent win next.
+pub fn review(board: &Board, symbol: Symbol, played: [usize; 2]) -> Option<String> {
+    let wins = winning_moves(board, symbol);
+    if wins.iter().any(|(position, _)| *position == played) {
+        return None;
+    }
+    if let Some(([row, column], line)) = wins.first() {
+        return Some(format!(
+            "{row},{column} would have won right away by completing {}.",
+            describe_line(*line)
+        ));
+    }
+
+    let opponent: &str = symbol.opponent().into();
+    match winning_moves(board, symbol.opponent()).as_slice() {
+        [([row, column], line)] if [*row, *column] != played => Some(format!(
+            "{opponent} can now win by completing {} at {row},{column}. Blocking there was the move.",
+            describe_line(*line)
+        )),
+        [] | [_] => None,
+        [([row, column], _), ([other_row, other_column], _), ..] => Some(format!(
+            "{opponent} already had two ways to win, at {row},{column} and {other_row},{other_column}, \
+             so one of them cannot be stopped. Watch for forks like this a move earlier."
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(text: &str) -> Board {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn missed_wins_are_explained() {
+        let board = board("++-/oo-/---");
+
+        assert_eq!(
+            review(&board, Symbol::Plus, [2, 2]).as_deref(),
+            Some("0,2 would have won right away by completing row 0.")
+        );
+        assert_eq!(review(&board, Symbol::Plus, [0, 2]), None);
+    }
+
+    #[test]
+    fn missed_blocks_are_explained() {
+        let board = board("+--/+o-/o--");
+
+        assert_eq!(
+            review(&board, Symbol::Plus, [2, 2]).as_deref(),
+            Some("o can now win by completing the diagonal from 0,2 to 2,0 at 0,2. Blocking there was the move.")
+        );
+        assert_eq!(review(&board, Symbol::Plus, [0, 2]), None);
+        assert_eq!(review(&Board::new(), Symbol::Plus, [0, 0]), None);
+    }
+
+    #[test]
+    fn forks_cannot_be_blocked() {
+        let board = board("o-o/-+-/+-o");
+
+        let advice = review(&board, Symbol::Plus, [0, 1]).unwrap();
+        assert!(advice.starts_with("o already had two ways to win, at 0,1 and 1,2,"));
+    }
+}
@@ -0,0 +1,572 @@
+use std::io::{self, BufRead, Read, Write};
+
+use crate::gamelog::GameLog;
+use crate::palette::{self, Palette, Role};
+use crate::recording::Recording;
+use crate::sound::{Effect, Speaker};
+use crate::terminal::{self, Capabilities};
+use crate::view::{Renderer, Scale, View};
+
+// Longest line read from a remote player, so a client cannot fill the memory.
+const MAX_REMOTE_LINE: u64 = 1024;
+
+const IDLE_MESSAGE: &str = "\r\nClosing the connection, nothing was typed for too long.\r\n";
+const LONG_LINE_MESSAGE: &str =
+    "\r\nClosing the connection, lines must be shorter than 1024 bytes.\r\n";
+const NOT_TEXT_MESSAGE: &str = "\r\nClosing the connection, the input was not UTF-8 text.\r\n";
+
+// Text kept for a remote player who rejoins, beyond which it starts over.
+const MAX_REPLAY: usize = 16 * 1024;
+
+const REJOINED_MESSAGE: &str = "Welcome back, carrying on where you left off.";
+
+// A remote player's connection, e.g. a telnet client.
+pub struct Stream {
+    reader: Box<dyn BufRead + Send>,
+    writer: Box<dyn Write + Send>,
+}
+
+impl Stream {
+    pub fn new(reader: Box<dyn BufRead + Send>, writer: Box<dyn Write + Send>) -> Stream {
+        Stream { reader, writer }
+    }
+
+    // A connection that has gone elsewhere: nothing to read, and what is
+    // written goes nowhere.
+    pub fn closed() -> Stream {
+        Stream::new(Box::new(io::empty()), Box::new(io::sink()))
+    }
+
+    pub fn say(&mut self, text: &str) {
+        let _ = write!(self.writer, "{text}\r\n");
+        let _ = self.writer.flush();
+    }
+}
+
+// Lets a remote player whose connection dropped carry on over a new one, see
+// src/telnet.rs.
+pub trait Rejoin {
+    // Sees every line before the game does, and returns true for lines meant
+    // for the server instead. The connection can be handed to another
+    // session by swapping a closed stream in.
+    fn intercept(&mut self, line: &str, stream: &mut Stream) -> bool;
+
+    // Waits for the player to come back after the connection closed, None
+    // when they do not in time.
+    fn wait(&mut self) -> Option<Stream>;
+}
+
+// What came in from a remote player.
+enum Incoming {
+    Line(String),
+    // The connection closed or went idle; the player may come back.
+    Lost,
+    // The client broke the protocol and is not let back in.
+    Refused,
+}
+
+// Everything the game shows or reads goes through here, so it can be recorded
+// or sent to a remote player instead of the terminal.
+// The flags are independent switches, not states of one setting.
+#[allow(clippy::struct_excessive_bools)]
+pub struct Console {
+    recording: Option<Recording>,
+    stream: Option<Stream>,
+    rejoin: Option<Box<dyn Rejoin>>,
+    // What the remote player was sent since the screen was last cleared or
+    // the board last shown, sent again when they rejoin.
+    replay: String,
+    log: Option<GameLog>,
+    debug: bool,
+    quiet: bool,
+    // Whether results are printed, off when a document takes their place.
+    results: bool,
+    // Styles prompts, results and warnings as they are printed; recordings
+    // and logs keep the plain text. The board is drawn with it too.
+    palette: Palette,
+    view: View,
+    // Row and column numbers around the grid.
+    labels: bool,
+    scale: Scale,
+    // Remote terminals are taken to show everything.
+    capabilities: Capabilities,
+    // Sound effects, only ever on this terminal.
+    speaker: Option<Speaker>,
+}
+
+impl Console {
+    pub fn new(recording: Option<Recording>) -> Console {
+        Console {
+            recording,
+            stream: None,
+            rejoin: None,
+            replay: String::new(),
+            log: None,
+            debug: false,
+            quiet: false,
+            results: true,
+            palette: Palette::default(),
+            view: View::default(),
+            labels: false,
+            scale: Scale::default(),
+            capabilities: terminal::capabilities(),
+            speaker: None,
+        }
+    }
+
+    // Reads from and writes to a connection instead of stdin and stdout.
+    // Lines are sent with \r\n as network protocols expect.
+    pub fn over(reader: Box<dyn BufRead + Send>, writer: Box<dyn Write + Send>) -> Console {
+        Console::remote(Stream::new(reader, writer))
+    }
+
+    pub fn remote(stream: Stream) -> Console {
+        Console {
+            recording: None,
+            stream: Some(stream),
+            rejoin: None,
+            replay: String::new(),
+            log: None,
+            debug: false,
+            quiet: false,
+            results: true,
+            palette: Palette::default(),
+            view: View::default(),
+            labels: false,
+            scale: Scale::default(),
+            capabilities: Capabilities::default(),
+            speaker: None,
+        }
+    }
+
+    pub fn set_rejoin(&mut self, rejoin: Box<dyn Rejoin>) {
+        self.rejoin = Some(rejoin);
+    }
+
+    pub fn set_log(&mut self, log: Option<GameLog>) {
+        self.log = log;
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    pub fn set_view(&mut self, view: View) {
+        self.view = view;
+    }
+
+    pub fn set_labels(&mut self, labels: bool) {
+        self.labels = labels;
+    }
+
+    pub fn set_scale(&mut self, scale: Scale) {
+        self.scale = scale;
+    }
+
+    // Draws boards as the console is set up now, since players can change
+    // how it looks during a game.
+    pub fn renderer(&self) -> Renderer {
+        Renderer {
+            view: self.view,
+            palette: self.palette(),
+            labels: self.labels,
+            scale: self.scale,
+            unicode: self.capabilities.unicode,
+            size: self.size(),
+        }
+    }
+
+    // Terminals without escape codes get no colors.
+    fn palette(&self) -> Palette {
+        if self.capabilities.escapes {
+            self.palette
+        } else {
+            Palette::Plain
+        }
+    }
+
+    pub fn set_speaker(&mut self, speaker: Option<Speaker>) {
+        self.speaker = speaker;
+    }
+
+    // Plays a sound effect, except for remote players and in quiet mode.
+    pub fn play(&self, effect: Effect) {
+        if let Some(speaker) = self.speaker.as_ref().filter(|_| !self.quiet) {
+            speaker.play(effect);
+        }
+    }
+
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+    }
+
+    pub fn debugging(&self) -> bool {
+        self.debug
+    }
+
+    // Only results and warnings are printed while quiet; everything is
+    // still recorded and logged.
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.quiet = quiet;
+    }
+
+    pub fn is_quiet(&self) -> bool {
+        self.quiet
+    }
+
+    // Leaves standard output to documents, for tools that parse it.
+    pub fn hide_results(&mut self) {
+        self.quiet = true;
+        self.results = false;
+    }
+
+    // Explains an internal decision, shown only in debug mode and kept out of
+    // recordings so replays look like the game did.
+    pub fn debug(&mut self, text: &str) {
+        if self.debug {
+            self.print(&format!("debug: {text}"), true);
+            self.log("debug", text);
+        }
+    }
+
+    // Adds an event to the game log, when there is one.
+    pub fn log(&self, kind: &str, text: &str) {
+        if let Some(log) = &self.log {
+            log.event(kind, text);
+        }
+    }
+
+    pub fn is_remote(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    // Columns and rows to draw in. Remote terminals get the usual 80 by 24.
+    pub fn size(&self) -> (usize, usize) {
+        if self.is_remote() {
+            terminal::DEFAULT_SIZE
+        } else {
+            terminal::size()
+        }
+    }
+
+    pub fn into_recording(self) -> Option<Recording> {
+        self.recording
+    }
+
+    fn capture(&mut self, text: &str, new_screen: bool) {
+        if let Some(recording) = &mut self.recording {
+            recording.capture(text, new_screen);
+        }
+    }
+
+    fn print_as(&mut self, role: Role, text: &str, error: bool) {
+        match self.palette().style(role) {
+            Some(style) => self.print(&palette::paint(text, style), error),
+            None => self.print(text, error),
+        }
+    }
+
+    fn print(&mut self, text: &str, error: bool) {
+        match &mut self.stream {
+            // A dropped connection shows up as a closed input on the next read.
+            Some(stream) => {
+                let text = format!("{}\r\n", text.replace('\n', "\r\n"));
+                let _ = stream.writer.write_all(text.as_bytes());
+                let _ = stream.writer.flush();
+                if self.replay.len() + text.len() > MAX_REPLAY {
+                    self.replay.clear();
+                }
+                self.replay.push_str(&text);
+            }
+            None if error => eprintln!("{text}"),
+            None => println!("{text}"),
+        }
+    }
+
+    // Clears the terminal, or the remote player's screen through the same
+    // escape codes. Recordings are left alone since replays clear on their own.
+    pub fn clear(&mut self) {
+        self.replay.clear();
+        if self.capabilities.escapes {
+            self.print("\x1b[2J\x1b[H", false);
+        } else {
+            terminal::clear_without_escapes();
+        }
+    }
+
+    // Shows the board at the start of a turn, which a replay treats as a new screen.
+    pub fn show(&mut self, text: &str) {
+        self.replay.clear();
+        if !self.quiet {
+            self.print(text, false);
+        }
+        self.capture(text, true);
+        self.log("screen", text);
+    }
+
+    pub fn say(&mut self, text: &str) {
+        if !self.quiet {
+            self.print(text, false);
+        }
+        self.capture(text, false);
+        self.log("output", text);
+    }
+
+    // A question or request the player answers next.
+    pub fn prompt(&mut self, text: &str) {
+        if !self.quiet {
+            self.print_as(Role::Prompt, text, false);
+        }
+        self.capture(text, false);
+        self.log("output", text);
+    }
+
+    // How a game ended, which is printed even when quiet.
+    pub fn result(&mut self, text: &str) {
+        if self.results {
+            self.print_as(Role::Result, text, false);
+        }
+        self.capture(text, false);
+        self.log("result", text);
+    }
+
+    // Machine-readable output such as a JSON result, always printed and
+    // kept out of recordings.
+    pub fn document(&mut self, text: &str) {
+        self.print(text, false);
+        self.log("document", text);
+    }
+
+    pub fn warn(&mut self, text: &str) {
+        self.print_as(Role::Warning, text, true);
+        self.capture(text, false);
+        self.log("warning", text);
+    }
+
+    // Returns None when stdin is closed or the connection is gone.
+    pub fn read_line(&mut self) -> Option<String> {
+        let input = if self.is_remote() {
+            self.read_remote()
+        } else {
+            let mut input = String::new();
+            // Input that cannot be read, e.g. bytes that are not UTF-8, ends
+            // the game as a closed stdin does.
+            match io::stdin().read_line(&mut input) {
+                Ok(bytes_read) => (bytes_read > 0).then_some(input),
+                Err(error) => {
+                    eprintln!("Could not read the input: {error}");
+                    None
+                }
+            }
+        };
+        let Some(input) = input else {
+            self.log("closed", "");
+            return None;
+        };
+
+        self.capture(&format!("> {}", input.trim_end()), false);
+        self.log("input", input.trim_end_matches(['\r', '\n']));
+        Some(input)
+    }
+
+    fn read_remote(&mut self) -> Option<String> {
+        loop {
+            let stream = self.stream.as_mut()?;
+            match read_remote(stream) {
+                Incoming::Line(line) => {
+                    if !self
+                        .rejoin
+                        .as_mut()
+                        .is_some_and(|rejoin| rejoin.intercept(&line, stream))
+                    {
+                        return Some(line);
+                    }
+                }
+                Incoming::Lost => {
+                    self.stream = Some(self.rejoin.as_mut()?.wait()?);
+                    self.log("rejoined", "");
+                    // The new connection starts on an empty screen.
+                    let replay = std::mem::take(&mut self.replay);
+                    self.print(REJOINED_MESSAGE, false);
+                    if let Some(stream) = &mut self.stream {
+                        let _ = stream.writer.write_all(replay.as_bytes());
+                        let _ = stream.writer.flush();
+                    }
+                    self.replay.push_str(&replay);
+                }
+                Incoming::Refused => return None,
+            }
+        }
+    }
+}
+
+// A line from a remote player. The server never trusts a client to behave,
+// so a line that is too long or not text closes the connection as a closed
+// or idle one does, after telling the player why. Moves themselves are checked
+// against the server's board like anyone else's.
+fn read_remote(stream: &mut Stream) -> Incoming {
+    let mut bytes = Vec::new();
+    let (closing, incoming) = match (&mut stream.reader)
+        .take(MAX_REMOTE_LINE + 1)
+        .read_until(b'\n', &mut bytes)
+    {
+        Ok(0) => return Incoming::Lost,
+        Ok(bytes_read) if bytes_read as u64 > MAX_REMOTE_LINE => {
+            (LONG_LINE_MESSAGE, Incoming::Refused)
+        }
+        Ok(_) => match String::from_utf8(bytes) {
+            Ok(line) => return Incoming::Line(line),
+            Err(_) => (NOT_TEXT_MESSAGE, Incoming::Refused),
+        },
+        // The connection's read timeout, e.g. a telnet player who went away.
+        Err(error)
+            if matches!(
+                error.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ) =>
+        {
+            (IDLE_MESSAGE, Incoming::Lost)
+        }
+        Err(_) => return Incoming::Lost,
+    };
+    let _ = stream.writer.write_all(closing.as_bytes());
+    let _ = stream.writer.flush();
+    incoming
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+
+    // A writer the test can still read after handing it to the console.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn streams_get_network_line_endings() {
+        let output = Shared::default();
+        let mut console = Console::over(
+            Box::new(Cursor::new(b"1,1\r\n".to_vec())),
+            Box::new(output.clone()),
+        );
+
+        console.say("one\ntwo");
+        console.warn("oops");
+        assert_eq!(console.read_line().as_deref(), Some("1,1\r\n"));
+        assert_eq!(console.read_line(), None);
+        assert_eq!(
+            String::from_utf8(output.0.lock().unwrap().clone()).unwrap(),
+            "one\r\ntwo\r\noops\r\n"
+        );
+    }
+
+    #[test]
+    fn high_contrast_styles_prompts_results_and_warnings() {
+        let output = Shared::default();
+        let mut console = Console::over(Box::new(io::empty()), Box::new(output.clone()));
+        console.set_palette(Palette::HighContrast);
+
+        console.say("board");
+        console.prompt("move?");
+        console.result("won");
+        console.warn("oops");
+        assert_eq!(
+            String::from_utf8(output.0.lock().unwrap().clone()).unwrap(),
+            "board\r\n\x1b[1;97mmove?\x1b[0m\r\n\x1b[1;7mwon\x1b[0m\r\n\x1b[1;93moops\x1b[0m\r\n"
+        );
+    }
+
+    // Times out on every read, as an idle connection with a read timeout does.
+    struct Idle;
+
+    impl Read for Idle {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::WouldBlock.into())
+        }
+    }
+
+    #[test]
+    fn idle_connections_are_closed() {
+        let output = Shared::default();
+        let mut console =
+            Console::over(Box::new(io::BufReader::new(Idle)), Box::new(output.clone()));
+
+        assert_eq!(console.read_line(), None);
+        assert_eq!(
+            String::from_utf8(output.0.lock().unwrap().clone()).unwrap(),
+            IDLE_MESSAGE
+        );
+    }
+
+    // Comes back once over the stream it holds.
+    struct Returning(Option<Stream>);
+
+    impl Rejoin for Returning {
+        fn intercept(&mut self, _line: &str, _stream: &mut Stream) -> bool {
+            false
+        }
+
+        fn wait(&mut self) -> Option<Stream> {
+            self.0.take()
+        }
+    }
+
+    #[test]
+    fn rejoining_players_see_the_screen_again() {
+        let output = Shared::default();
+        let mut console = Console::over(Box::new(io::empty()), Box::new(io::sink()));
+        console.set_rejoin(Box::new(Returning(Some(Stream::new(
+            Box::new(Cursor::new(b"2,2\r\n".to_vec())),
+            Box::new(output.clone()),
+        )))));
+
+        console.say("before");
+        console.clear();
+        console.say("board");
+        console.prompt("move?");
+        assert_eq!(console.read_line().as_deref(), Some("2,2\r\n"));
+        assert_eq!(
+            String::from_utf8(output.0.lock().unwrap().clone()).unwrap(),
+            format!("{REJOINED_MESSAGE}\r\n\x1b[2J\x1b[H\r\nboard\r\nmove?\r\n")
+        );
+        assert_eq!(console.read_line(), None);
+    }
+
+    #[test]
+    fn protocol_violations_close_the_connection() {
+        let mut long_line = vec![b'a'; 5000];
+        long_line.push(b'\n');
+        let mut not_utf8 = b"1,1".to_vec();
+        not_utf8.extend_from_slice(&[0xff, 0xfe, b'\n']);
+
+        for (input, message) in [(long_line, LONG_LINE_MESSAGE), (not_utf8, NOT_TEXT_MESSAGE)] {
+            let output = Shared::default();
+            let mut console = Console::over(Box::new(Cursor::new(input)), Box::new(output.clone()));
+
+            assert_eq!(console.read_line(), None);
+            assert_eq!(
+                String::from_utf8(output.0.lock().unwrap().clone()).unwrap(),
+                message
+            );
+        }
+
+        let mut console = Console::over(
+            Box::new(Cursor::new(vec![b'a'; 1000])),
+            Box::new(io::sink()),
+        );
+        assert_eq!(console.read_line().map(|line| line.len()), Some(1000));
+    }
+}
@@ -0,0 +1,120 @@
+// Correspondence play: every invocation loads a game file, makes at most one
+// move and saves it again, so a game can be played over email or chat at
+// leisure. The file holds a single game record line, the same format games
+// end with in recordings.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::board::{Board, GameStatus};
+use crate::browser::GameRecord;
+
+fn load(path: &Path) -> Result<GameRecord, String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => contents
+            .trim()
+            .parse()
+            .map_err(|message| format!("{} is not a game file: {message}", path.display())),
+        // A missing file is a new game.
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(GameRecord::new(Board::new())),
+        Err(error) => Err(format!("Could not read {}: {error}", path.display())),
+    }
+}
+
+fn describe(record: &GameRecord) -> String {
+    let board = record.board();
+    let status = match board.status() {
+        GameStatus::Won(winner, _) => {
+            let winner: &str = winner.into();
+            format!("{winner} has won, the game is over.")
+        }
+        GameStatus::Draw => String::from("The board is full, it's a draw."),
+        GameStatus::InProgress => {
+            let to_move: &str = record.to_move().into();
+            format!("{to_move} to move.")
+        }
+    };
+
+    format!("{board}\n\n{status}")
+}
+
+// Applies player_move, if given, for the side to move and returns the text
+// to print. The game is unchanged when the move is rejected.
+fn play(record: &mut GameRecord, player_move: Option<[usize; 2]>) -> Result<String, String> {
+    let Some(position) = player_move else {
+        return Ok(describe(record));
+    };
+
+    let board = record.board();
+    if board.status() != GameStatus::InProgress {
+        return Err(String::from("The game is already over."));
+    }
+    board
+        .is_valid_move(position)
+        .map_err(|error| error.to_string())?;
+
+    let symbol = record.to_move();
+    record.push(symbol, position);
+    let symbol: &str = symbol.into();
+    Ok(format!(
+        "{symbol} plays {},{}.\n\n{}",
+        position[0],
+        position[1],
+        describe(record)
+    ))
+}
+
+pub fn run(path: &Path, player_move: Option<[usize; 2]>) -> Result<(), String> {
+    let mut record = load(path)?;
+    let text = play(&mut record, player_move)?;
+
+    if player_move.is_some() {
+        fs::write(path, format!("{record}\n"))
+            .map_err(|error| format!("Could not save {}: {error}", path.display()))?;
+    }
+
+    println!("{text}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_alternate_from_a_new_game() {
+        let mut record = GameRecord::new(Board::new());
+
+        play(&mut record, Some([1, 1])).unwrap();
+        let text = play(&mut record, Some([0, 0])).unwrap();
+
+        assert_eq!(format!("{record}"), "---/---/--- +1,1 o0,0");
+        assert!(text.starts_with("o plays 0,0."));
+        assert!(text.ends_with("+ to move."));
+    }
+
+    #[test]
+    fn illegal_moves_leave_the_game_unchanged() {
+        let mut record: GameRecord = "---/---/--- +1,1".parse().unwrap();
+
+        assert_eq!(
+            play(&mut record, Some([1, 1])),
+            Err(String::from("1,1 is already taken by +."))
+        );
+        assert_eq!(format!("{record}"), "---/---/--- +1,1");
+    }
+
+    #[test]
+    fn finished_games_take_no_more_moves() {
+        let mut record: GameRecord = "++-/oo-/--- +0,2".parse().unwrap();
+
+        assert!(play(&mut record, None)
+            .unwrap()
+            .ends_with("+ has won, the game is over."));
+        assert_eq!(
+            play(&mut record, Some([2, 2])),
+            Err(String::from("The game is already over."))
+        );
+    }
+}
@@ -0,0 +1,278 @@
+// A puzzle a day: a position where the side to move can force a win, but
+// not on the spot. It is derived from the date in UTC, so everyone gets the
+// same one, and solving it on consecutive days builds a streak.
+//
+// The streak file has "last_played <day>", "streak <days>" and "best <days>"
+// lines, with days counted from 1970-01-01.
+
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ai::{self, Difficulty};
+use crate::analysis;
+use crate::board::{Board, GameStatus, Symbol};
+use crate::console::Console;
+use crate::game::parse_player_move;
+use crate::profile;
+use crate::rng::Rng;
+
+// Where the streak is kept unless TTT_DAILY says otherwise.
+pub const DEFAULT_DAILY: &str = "tic-tac-toe.daily";
+
+// The score of a move that wins on the spot, see ai::move_scores.
+const IMMEDIATE_WIN: i32 = 9;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+pub fn path() -> PathBuf {
+    std::env::var_os("TTT_DAILY").map_or_else(|| profile::file(DEFAULT_DAILY), PathBuf::from)
+}
+
+// Days since 1970-01-01 in UTC.
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() / SECONDS_PER_DAY)
+}
+
+// The day as YYYY-MM-DD, after Howard Hinnant's civil_from_days.
+fn date(day: u64) -> String {
+    // Counted from 0000-03-01, so leap days come at the end of a year.
+    let days = day + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day_of_month:02}")
+}
+
+// The winning moves of the side to move, or None when the position is no
+// puzzle: already over, lost or drawn with best play, won on the spot, or
+// won whatever is played.
+fn winning_moves(board: &Board, symbol: Symbol) -> Option<Vec<[usize; 2]>> {
+    if board.status() != GameStatus::InProgress {
+        return None;
+    }
+
+    let scores = ai::move_scores(board, symbol);
+    let best = scores.iter().map(|(_, score)| *score).max()?;
+    let winning = scores
+        .iter()
+        .filter(|(_, score)| *score > 0)
+        .map(|(position, _)| *position)
+        .collect::<Vec<_>>();
+
+    (best > 0 && best < IMMEDIATE_WIN && winning.len() < scores.len()).then_some(winning)
+}
+
+// Plays random moves from an empty board with the day as the seed until a
+// position is a puzzle.
+fn puzzle(day: u64) -> (Board, Symbol, Vec<[usize; 2]>) {
+    let mut rng = Rng::new(day.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+
+    loop {
+        let mut board = Board::new();
+        let plies = 2 + rng.below(4);
+        for ply in 0..plies {
+            let symbol = if ply % 2 == 0 {
+                Symbol::Plus
+            } else {
+                Symbol::Circle
+            };
+            let Some(position) = ai::choose_move(&board, symbol, Difficulty::Easy, &mut rng) else {
+                break;
+            };
+            board.place(symbol, position);
+        }
+
+        let symbol = analysis::side_to_move(&board);
+        if let Some(winning) = winning_moves(&board, symbol) {
+            return (board, symbol, winning);
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Streak {
+    last_played: Option<u64>,
+    // Puzzles solved on consecutive days, up to the last one played.
+    current: u32,
+    best: u32,
+}
+
+impl Streak {
+    fn record(&mut self, day: u64, solved: bool) {
+        self.current = match (solved, self.last_played) {
+            (false, _) => 0,
+            (true, Some(last)) if last + 1 == day => self.current + 1,
+            (true, _) => 1,
+        };
+        self.best = self.best.max(self.current);
+        self.last_played = Some(day);
+    }
+}
+
+impl Display for Streak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(day) = self.last_played {
+            writeln!(f, "last_played {day}")?;
+        }
+        writeln!(f, "streak {}", self.current)?;
+        writeln!(f, "best {}", self.best)
+    }
+}
+
+impl FromStr for Streak {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut streak = Streak::default();
+
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            let invalid = || format!("'{line}' is not part of a puzzle streak.");
+            let (key, value) = line.split_once(' ').ok_or_else(invalid)?;
+            match key {
+                "last_played" => streak.last_played = Some(value.parse().map_err(|_| invalid())?),
+                "streak" => streak.current = value.parse().map_err(|_| invalid())?,
+                "best" => streak.best = value.parse().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(streak)
+    }
+}
+
+fn load(path: &Path) -> Result<Streak, String> {
+    if !path.exists() {
+        return Ok(Streak::default());
+    }
+    fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {error}", path.display()))?
+        .parse()
+        .map_err(|error| format!("{}: {error}", path.display()))
+}
+
+fn format_moves(moves: &[[usize; 2]]) -> String {
+    moves
+        .iter()
+        .map(|[row, column]| format!("{row},{column}"))
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+// Shows the day's puzzle and takes one answer, unless it was already
+// played today.
+pub fn run(console: &mut Console, path: &Path, day: u64) -> Result<(), String> {
+    let mut streak = load(path)?;
+    let (board, symbol, winning) = puzzle(day);
+    let to_move: &str = symbol.into();
+    console.say(&format!(
+        "\nDaily puzzle for {}: {to_move} to move and force a win.\n\n{board}\n",
+        date(day)
+    ));
+
+    if streak.last_played == Some(day) {
+        console.say(&format!(
+            "You have played today's puzzle, it is won with {}. Come back tomorrow!\nSolve streak: {} (best {}).",
+            format_moves(&winning),
+            streak.current,
+            streak.best
+        ));
+        return Ok(());
+    }
+
+    let answer = loop {
+        console.say("Which move wins? Answer with row,column.");
+        let Some(input) = console.read_line() else {
+            return Ok(());
+        };
+        match parse_player_move(&input) {
+            Ok(position) if board.is_valid_move(position).is_ok() => break position,
+            Ok(position) => {
+                if let Err(error) = board.is_valid_move(position) {
+                    console.warn(&format!("{error} Please try again."));
+                }
+            }
+            Err(_) => console.warn("Answer with a free tile, e.g. 0,2."),
+        }
+    };
+
+    let solved = winning.contains(&answer);
+    if solved {
+        console.say("\nCorrect, that move wins whatever the reply!");
+    } else {
+        console.say(&format!(
+            "\nNot quite, {},{} can be held to a draw or worse. The winning move was {}.",
+            answer[0],
+            answer[1],
+            format_moves(&winning)
+        ));
+    }
+
+    streak.record(day, solved);
+    fs::write(path, streak.to_string())
+        .map_err(|error| format!("Could not save the streak to {}: {error}", path.display()))?;
+    console.say(&format!(
+        "Solve streak: {} (best {}).",
+        streak.current, streak.best
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_are_shown_as_dates() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(59), "1970-03-01");
+        assert_eq!(date(11_016), "2000-02-29");
+        assert_eq!(date(20_741), "2026-10-15");
+    }
+
+    #[test]
+    fn every_day_has_a_fair_puzzle() {
+        for day in 20_000..20_030 {
+            let (board, symbol, winning) = puzzle(day);
+            assert_eq!(format!("{:#}", puzzle(day).0), format!("{board:#}"));
+            assert!(board.validate().is_ok());
+            assert_eq!(winning_moves(&board, symbol), Some(winning.clone()));
+            assert!(!winning.is_empty());
+        }
+        assert_ne!(
+            format!("{:#}", puzzle(20_000).0),
+            format!("{:#}", puzzle(20_001).0)
+        );
+    }
+
+    #[test]
+    fn streaks_need_consecutive_days() {
+        let mut streak = Streak::default();
+        streak.record(10, true);
+        streak.record(11, true);
+        assert_eq!((streak.current, streak.best), (2, 2));
+
+        streak.record(13, true);
+        assert_eq!((streak.current, streak.best), (1, 2));
+        streak.record(14, false);
+        assert_eq!((streak.current, streak.best), (0, 2));
+
+        assert_eq!(streak.to_string().parse(), Ok(streak));
+        assert!("streak many".parse::<Streak>().is_err());
+    }
+}
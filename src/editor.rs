@@ -0,0 +1,185 @@
+// A free-form board editor: marks can be placed and removed in any order,
+// and the position is only checked for legality when a game is started
+// from it.
+
+use crate::analysis;
+use crate::board::{Board, Symbol};
+use crate::clipboard;
+use crate::console::Console;
+use crate::game::{copy_position, parse_player_move, StartPosition};
+use crate::rng::Rng;
+
+const ANALYSIS_PLAYOUTS: u32 = 1000;
+
+const HELP: &str = "Editor commands:
+  x,y +      Put a + at row x and column y.
+  x,y o      Put an o there.
+  x,y -      Clear that tile.
+  clear      Clear the whole board.
+  analyze    Estimate every move for the side to move.
+  copy       Copy the position to the clipboard.
+  paste      Replace the board with a position from the clipboard.
+  play       Start a game from this position, play o to let o move first.
+  done       Leave the editor.";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum EditorCommand {
+    Set([usize; 2], Symbol),
+    Clear,
+    Analyze,
+    Copy,
+    Paste,
+    Play(Option<Symbol>),
+    Help,
+    Done,
+}
+
+fn parse_symbol(input: &str) -> Option<Symbol> {
+    let mut chars = input.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Symbol::try_from(c).ok(),
+        _ => None,
+    }
+}
+
+impl EditorCommand {
+    fn parse(input: &str, size: usize) -> Result<EditorCommand, String> {
+        let input = input.trim().to_lowercase();
+        let words = input.split_whitespace().collect::<Vec<_>>();
+
+        match words.as_slice() {
+            ["clear"] => Ok(EditorCommand::Clear),
+            ["analyze"] => Ok(EditorCommand::Analyze),
+            ["copy"] => Ok(EditorCommand::Copy),
+            ["paste"] => Ok(EditorCommand::Paste),
+            ["play"] => Ok(EditorCommand::Play(None)),
+            ["help"] => Ok(EditorCommand::Help),
+            ["done" | "quit"] => Ok(EditorCommand::Done),
+            ["play", symbol] => match parse_symbol(symbol) {
+                Some(symbol @ (Symbol::Plus | Symbol::Circle)) => {
+                    Ok(EditorCommand::Play(Some(symbol)))
+                }
+                _ => Err(format!("'{symbol}' cannot move, use + or o.")),
+            },
+            [position, symbol] => {
+                let position = parse_player_move(position)
+                    .ok()
+                    .filter(|[row, column]| *row < size && *column < size)
+                    .ok_or_else(|| format!("'{position}' is not a tile on the board."))?;
+                let symbol =
+                    parse_symbol(symbol).ok_or_else(|| format!("'{symbol}' is not +, o or -."))?;
+
+                Ok(EditorCommand::Set(position, symbol))
+            }
+            _ => Err(String::from("Unknown command, type help for the list.")),
+        }
+    }
+}
+
+fn analyze(console: &mut Console, board: &Board) {
+    let symbol = analysis::side_to_move(board);
+    let estimates =
+        analysis::estimate_moves(board, symbol, ANALYSIS_PLAYOUTS, &mut Rng::from_time());
+
+    if estimates.is_empty() {
+        console.say("\nThe game is already over in this position.");
+    } else {
+        let to_move: &str = symbol.into();
+        console.say(&format!(
+            "\n{to_move} to move:\n\n{}",
+            analysis::format_estimates(&estimates)
+        ));
+    }
+}
+
+// Runs the editor until a legal position is picked to play from, or returns
+// None when the player is done or stdin is closed.
+pub fn run(console: &mut Console) -> Option<StartPosition> {
+    let mut board = Board::new();
+    console.say(&format!("\n{HELP}"));
+
+    loop {
+        console.show(&format!("\nThe position being edited:\n\n{board}\n"));
+
+        let input = console.read_line()?;
+        let command = match EditorCommand::parse(&input, board.tiles.len()) {
+            Ok(command) => command,
+            Err(message) => {
+                console.warn(&message);
+                continue;
+            }
+        };
+
+        match command {
+            EditorCommand::Set([row, column], symbol) => {
+                board.tiles[row][column] = symbol;
+                // Editing often passes through impossible positions, so only warn.
+                if let Err(error) = board.validate() {
+                    console.warn(&format!("{error} This position cannot come up in a game."));
+                }
+            }
+            EditorCommand::Clear => board = Board::new(),
+            EditorCommand::Analyze => analyze(console, &board),
+            EditorCommand::Copy => copy_position(console, &board),
+            EditorCommand::Paste if console.is_remote() => {
+                console.warn("The clipboard is not available over the network.");
+            }
+            EditorCommand::Paste => match clipboard::position_argument(clipboard::PASTE) {
+                Ok(pasted) => board = pasted,
+                Err(message) => console.warn(&message),
+            },
+            EditorCommand::Help => console.say(&format!("\n{HELP}")),
+            EditorCommand::Done => return None,
+            EditorCommand::Play(to_move) => match StartPosition::new(board.clone(), to_move) {
+                Ok(position) => return Some(position),
+                Err(message) => console.warn(&format!("{message} Keep editing or type done.")),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<EditorCommand, String> {
+        EditorCommand::parse(input, 3)
+    }
+
+    #[test]
+    fn tiles_are_set_and_cleared() {
+        assert_eq!(parse("1,1 +"), Ok(EditorCommand::Set([1, 1], Symbol::Plus)));
+        assert_eq!(
+            parse(" 0,2 O\n"),
+            Ok(EditorCommand::Set([0, 2], Symbol::Circle))
+        );
+        assert_eq!(
+            parse("2,0 -"),
+            Ok(EditorCommand::Set([2, 0], Symbol::Empty))
+        );
+    }
+
+    #[test]
+    fn commands_are_parsed() {
+        assert_eq!(parse("clear"), Ok(EditorCommand::Clear));
+        assert_eq!(parse("Analyze"), Ok(EditorCommand::Analyze));
+        assert_eq!(parse("copy"), Ok(EditorCommand::Copy));
+        assert_eq!(parse("paste"), Ok(EditorCommand::Paste));
+        assert_eq!(parse("play"), Ok(EditorCommand::Play(None)));
+        assert_eq!(
+            parse("play o"),
+            Ok(EditorCommand::Play(Some(Symbol::Circle)))
+        );
+        assert_eq!(parse("help"), Ok(EditorCommand::Help));
+        assert_eq!(parse("done"), Ok(EditorCommand::Done));
+    }
+
+    #[test]
+    fn invalid_input_is_rejected() {
+        for input in [
+            "", "1,1", "3,0 +", "1,1 x", "1,1 ++", "play -", "play x", "undo",
+        ] {
+            assert!(parse(input).is_err());
+        }
+    }
+}
@@ -0,0 +1,201 @@
+// Game events for watchers outside the game: a game being created, each move
+// played and the game finishing, or being abandoned when its player leaves,
+// sent as one JSON object each to webhooks (src/webhook.rs) and an MQTT broker
+// (src/mqtt.rs), and counted for the server's metrics (src/metrics.rs). Games
+// are numbered from 1 for as long as the process runs, and every event carries
+// its game's number and the board.
+
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+use crate::board::{Board, Symbol};
+use crate::metrics::Metrics;
+use crate::mqtt::Publisher;
+use crate::webhook::{self, Endpoint};
+
+// Text as a JSON string, quotes included.
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", u32::from(c));
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+pub enum Event<'a> {
+    Created {
+        board: &'a Board,
+        to_move: Symbol,
+        // The bot's name, None for two players.
+        opponent: Option<String>,
+    },
+    Moved {
+        symbol: Symbol,
+        position: [usize; 2],
+        board: &'a Board,
+    },
+    Finished {
+        // player_one, player_two or draw, and why, as in --result-format json.
+        result: &'static str,
+        reason: &'static str,
+        board: &'a Board,
+    },
+    // The input closed before the game ended.
+    Abandoned {
+        board: &'a Board,
+    },
+}
+
+impl Event<'_> {
+    // The event as a JSON object, e.g. {"event":"move","game":3,...}.
+    pub fn json(&self, game: u64) -> String {
+        let symbol_name = |symbol: Symbol| -> &str { symbol.into() };
+        match self {
+            Event::Created {
+                board,
+                to_move,
+                opponent,
+            } => format!(
+                r#"{{"event":"created","game":{game},"board":"{board:#}","to_move":"{}","opponent":{}}}"#,
+                symbol_name(*to_move),
+                opponent
+                    .as_deref()
+                    .map_or_else(|| String::from("null"), json_string)
+            ),
+            Event::Moved {
+                symbol,
+                position: [row, column],
+                board,
+            } => format!(
+                r#"{{"event":"move","game":{game},"symbol":"{}","move":[{row},{column}],"board":"{board:#}"}}"#,
+                symbol_name(*symbol)
+            ),
+            Event::Finished {
+                result,
+                reason,
+                board,
+            } => format!(
+                r#"{{"event":"finished","game":{game},"result":"{result}","reason":"{reason}","board":"{board:#}"}}"#
+            ),
+            Event::Abandoned { board } => {
+                format!(r#"{{"event":"abandoned","game":{game},"board":"{board:#}"}}"#)
+            }
+        }
+    }
+}
+
+// Where events go, shared by every game of the process. Each game gets its
+// own copy, numbered with next_game.
+#[derive(Clone)]
+pub struct Events {
+    webhooks: Option<Sender<String>>,
+    mqtt: Option<Publisher>,
+    metrics: Option<Arc<Metrics>>,
+    games: Arc<AtomicU64>,
+    game: u64,
+}
+
+impl Events {
+    // None when there is nowhere to send events.
+    pub fn new(
+        webhooks: Vec<Endpoint>,
+        mqtt: Option<Publisher>,
+        metrics: Option<Arc<Metrics>>,
+    ) -> Option<Events> {
+        if webhooks.is_empty() && mqtt.is_none() && metrics.is_none() {
+            return None;
+        }
+        Some(Events {
+            webhooks: (!webhooks.is_empty()).then(|| webhook::start(webhooks)),
+            mqtt,
+            metrics,
+            games: Arc::new(AtomicU64::new(0)),
+            game: 0,
+        })
+    }
+
+    // A copy for the next game, with its own number.
+    pub fn next_game(&self) -> Events {
+        Events {
+            game: self.games.fetch_add(1, Ordering::SeqCst) + 1,
+            ..self.clone()
+        }
+    }
+
+    pub fn send(&self, event: &Event) {
+        if let Some(metrics) = &self.metrics {
+            metrics.count(event);
+        }
+        if self.webhooks.is_none() && self.mqtt.is_none() {
+            return;
+        }
+        let json = event.json(self.game);
+        if let Some(webhooks) = &self.webhooks {
+            // The thread only stops when every copy is gone.
+            let _ = webhooks.send(json.clone());
+        }
+        if let Some(mqtt) = &self.mqtt {
+            mqtt.publish(self.game, &json);
+        }
+    }
+
+    // A move that was turned down, which only the metrics count.
+    pub fn rejected(&self) {
+        if let Some(metrics) = &self.metrics {
+            metrics.rejected();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_json_objects() {
+        let board: Board = "+--/-o-/---".parse().unwrap();
+        let created = Event::Created {
+            board: &board,
+            to_move: Symbol::Plus,
+            opponent: Some(String::from("Say \"hi\"")),
+        };
+        assert_eq!(
+            created.json(7),
+            r#"{"event":"created","game":7,"board":"+--/-o-/---","to_move":"+","opponent":"Say \"hi\""}"#
+        );
+
+        let moved = Event::Moved {
+            symbol: Symbol::Circle,
+            position: [1, 1],
+            board: &board,
+        };
+        assert_eq!(
+            moved.json(7),
+            r#"{"event":"move","game":7,"symbol":"o","move":[1,1],"board":"+--/-o-/---"}"#
+        );
+        assert_eq!(
+            Event::Abandoned { board: &board }.json(7),
+            r#"{"event":"abandoned","game":7,"board":"+--/-o-/---"}"#
+        );
+        assert_eq!(json_string("a\nb\u{1}"), r#""a\nb\u0001""#);
+    }
+
+    #[test]
+    fn games_are_numbered_in_order() {
+        let events = Events::new(vec!["http://localhost/".parse().unwrap()], None, None).unwrap();
+        assert_eq!(events.next_game().game, 1);
+        assert_eq!(events.clone().next_game().game, 2);
+        assert!(Events::new(Vec::new(), None, None).is_none());
+    }
+}
@@ -0,0 +1,282 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::board::{Board, Mark};
+
+// ANSI SGR parameters, e.g. "1;34" for bold blue, for the marks of each side
+// and for the brackets around marked tiles. Marked tiles keep their brackets
+// so nothing is told by color alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Colors {
+    pub sides: &'static [&'static str],
+    pub marked: &'static str,
+}
+
+fn paint(text: &str, color: Option<&str>) -> String {
+    match color {
+        Some(color) => format!("\x1b[{color}m{text}\x1b[0m"),
+        None => String::from(text),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardFormatter {
+    pub cell_width: usize,
+    // Spaces on either side of a cell.
+    pub padding: usize,
+    pub column_separator: &'static str,
+    pub row_separator: &'static str,
+    // Draw the column separator on the outer edges as well.
+    pub borders: bool,
+    pub show_empty: bool,
+    // Draw a "---+---" line between rows.
+    pub row_divider: bool,
+    // Plain text when None.
+    pub colors: Option<Colors>,
+}
+
+impl Default for BoardFormatter {
+    // The look of the interactive game, e.g. "| + | - | o |".
+    fn default() -> Self {
+        BoardFormatter {
+            cell_width: 1,
+            padding: 1,
+            column_separator: "|",
+            row_separator: "\n",
+            borders: true,
+            show_empty: true,
+            row_divider: false,
+            colors: None,
+        }
+    }
+}
+
+impl BoardFormatter {
+    // A single line such as "+-o/---/o-+", which Board also parses.
+    #[must_use]
+    pub fn compact() -> Self {
+        BoardFormatter {
+            cell_width: 1,
+            padding: 0,
+            column_separator: "",
+            row_separator: "/",
+            borders: false,
+            show_empty: true,
+            row_divider: false,
+            colors: None,
+        }
+    }
+
+    // Marked cells swap their padding for brackets, e.g. "[+]".
+    fn cell<M: Mark>(&self, symbol: M, marked: bool) -> String {
+        let content = if symbol == M::EMPTY && !self.show_empty {
+            " "
+        } else {
+            symbol.render()
+        };
+        let color = self
+            .colors
+            .zip(symbol.side())
+            .and_then(|(colors, side)| colors.sides.get(side).copied());
+        let content = paint(
+            &format!("{content:^width$}", width = self.cell_width),
+            color,
+        );
+
+        if marked && self.padding > 0 {
+            let padding = " ".repeat(self.padding - 1);
+            let marked = self.colors.map(|colors| colors.marked);
+            format!(
+                "{padding}{}{content}{}{padding}",
+                paint("[", marked),
+                paint("]", marked)
+            )
+        } else {
+            let padding = " ".repeat(self.padding);
+            format!("{padding}{content}{padding}")
+        }
+    }
+
+    fn join_cells(&self, cells: &[String]) -> String {
+        let inner = cells.join(self.column_separator);
+
+        if self.borders {
+            format!("{0}{inner}{0}", self.column_separator)
+        } else {
+            inner
+        }
+    }
+
+    #[must_use]
+    pub fn format<const N: usize, M: Mark>(&self, board: &Board<N, M>) -> String {
+        self.format_marked(board, &[])
+    }
+
+    // Shows the previous board next to the current one, with the tiles that
+    // changed in between marked, so a returning player sees what happened.
+    #[must_use]
+    pub fn format_diff<const N: usize, M: Mark>(
+        &self,
+        previous: &Board<N, M>,
+        current: &Board<N, M>,
+    ) -> String {
+        let mut changed = Vec::new();
+        for (i, (previous_row, current_row)) in previous.tiles.iter().zip(current.tiles).enumerate()
+        {
+            for (j, (previous_tile, current_tile)) in
+                previous_row.iter().zip(current_row).enumerate()
+            {
+                if *previous_tile != current_tile {
+                    changed.push([i, j]);
+                }
+            }
+        }
+
+        let before = self.format(previous);
+        let after = self.format_marked(current, &changed);
+
+        if self.row_separator != "\n" {
+            return format!("{before} -> {after}");
+        }
+
+        let before_lines = before.lines().collect::<Vec<_>>();
+        let middle = before_lines.len() / 2;
+
+        before_lines
+            .iter()
+            .zip(after.lines())
+            .enumerate()
+            .map(|(i, (before_line, after_line))| {
+                let arrow = if i == middle { " -> " } else { "    " };
+                format!("{before_line}{arrow}{after_line}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // Draws the board with the given tiles marked.
+    #[must_use]
+    pub fn format_marked<const N: usize, M: Mark>(
+        &self,
+        board: &Board<N, M>,
+        marked: &[[usize; 2]],
+    ) -> String {
+        let rows = board
+            .tiles
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let cells = row
+                    .iter()
+                    .enumerate()
+                    .map(|(j, tile)| self.cell(*tile, marked.contains(&[i, j])))
+                    .collect::<Vec<_>>();
+                self.join_cells(&cells)
+            })
+            .collect::<Vec<_>>();
+
+        if !self.row_divider {
+            return rows.join(self.row_separator);
+        }
+
+        let divider_cell = "-".repeat(self.cell_width + 2 * self.padding);
+        let divider = self.join_cells(&vec![divider_cell; N]).replace(
+            self.column_separator,
+            &"+".repeat(self.column_separator.len()),
+        );
+
+        rows.join(&format!("{0}{divider}{0}", self.row_separator))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Symbol;
+
+    fn example_board() -> Board {
+        "+-o/---/o-+".parse().unwrap()
+    }
+
+    #[test]
+    fn default_formatter_renders_the_classic_grid() {
+        assert_eq!(
+            BoardFormatter::default().format(&example_board()),
+            "| + | - | o |\n| - | - | - |\n| o | - | + |"
+        );
+    }
+
+    #[test]
+    fn compact_formatter_renders_a_parsable_line() {
+        let compact = BoardFormatter::compact().format(&example_board());
+
+        assert_eq!(compact, "+-o/---/o-+");
+        assert_eq!(
+            compact.parse::<Board>().unwrap().tiles,
+            example_board().tiles
+        );
+    }
+
+    #[test]
+    fn spacious_formatter_with_dividers_and_hidden_empty_tiles() {
+        let formatter = BoardFormatter {
+            cell_width: 3,
+            borders: false,
+            show_empty: false,
+            row_divider: true,
+            ..BoardFormatter::default()
+        };
+
+        assert_eq!(
+            formatter.format(&example_board()),
+            [
+                "  +  |     |  o  ",
+                "-----+-----+-----",
+                "     |     |     ",
+                "-----+-----+-----",
+                "  o  |     |  +  ",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn colors_go_around_marks_and_brackets_only() {
+        let formatter = BoardFormatter {
+            colors: Some(Colors {
+                sides: &["31", "34"],
+                marked: "1",
+            }),
+            ..BoardFormatter::default()
+        };
+        let board: Board = "+o-/---/---".parse().unwrap();
+
+        assert_eq!(
+            formatter.format_marked(&board, &[[0, 1]]).lines().next(),
+            Some("| \x1b[31m+\x1b[0m |\x1b[1m[\x1b[0m\x1b[34mo\x1b[0m\x1b[1m]\x1b[0m| - |")
+        );
+    }
+
+    #[test]
+    fn diff_shows_both_boards_and_marks_changed_tiles() {
+        let previous = example_board();
+        let mut current = example_board();
+        current.place(Symbol::Circle, [1, 1]);
+
+        assert_eq!(
+            BoardFormatter::default().format_diff(&previous, &current),
+            [
+                "| + | - | o |    | + | - | o |",
+                "| - | - | - | -> | - |[o]| - |",
+                "| o | - | + |    | o | - | + |",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            BoardFormatter::compact().format_diff(&previous, &current),
+            "+-o/---/o-+ -> +-o/-o-/o-+"
+        );
+    }
+}
@@ -0,0 +1,104 @@
+// Arbitrary boards, positions and games built from fuzzer input, so the
+// rules can be fuzzed together with the checks in invariants.
+
+use alloc::vec::Vec;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::board::{Board, GameStatus, Mark, Symbol};
+
+impl<'a> Arbitrary<'a> for Symbol {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[Symbol::Empty, Symbol::Plus, Symbol::Circle])?)
+    }
+}
+
+// Any marks on any tiles, including positions no game can reach.
+impl<'a, const N: usize, M: Mark + Arbitrary<'a>> Arbitrary<'a> for Board<N, M> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut board = Board::empty();
+        for tile in board.tiles.iter_mut().flatten() {
+            *tile = M::arbitrary(u)?;
+        }
+        Ok(board)
+    }
+}
+
+// A tile on an N by N board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position<const N: usize = 3>(pub [usize; 2]);
+
+impl<'a, const N: usize> Arbitrary<'a> for Position<N> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Position([u.choose_index(N)?, u.choose_index(N)?]))
+    }
+}
+
+// A legal game from an empty board, Plus first, played until it ends or
+// the input runs out.
+#[derive(Debug, Clone)]
+pub struct PlayedGame<const N: usize = 3> {
+    pub moves: Vec<(Symbol, [usize; 2])>,
+}
+
+impl<'a, const N: usize> Arbitrary<'a> for PlayedGame<N> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut board = Board::<N>::empty();
+        let mut moves = Vec::new();
+        let mut symbol = Symbol::Plus;
+
+        while board.status() == GameStatus::InProgress && !u.is_empty() {
+            let empty = board.empty_tiles().count();
+            let Some(position) = board.empty_tiles().nth(u.choose_index(empty)?) else {
+                break;
+            };
+            board.place(symbol, position);
+            moves.push((symbol, position));
+            symbol = symbol.opponent();
+        }
+
+        Ok(PlayedGame { moves })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::invariants::{check_board, check_game};
+
+    // Fuzzer-like input without a fuzzer: bytes from a xorshift.
+    fn inputs(count: usize) -> impl Iterator<Item = Vec<u8>> {
+        let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+        (0..count).map(move |_| {
+            (0..64)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state.to_le_bytes()[0]
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn arbitrary_boards_keep_the_invariants() {
+        for input in inputs(500) {
+            let mut u = Unstructured::new(&input);
+            check_board(&Board::<3>::arbitrary(&mut u).unwrap()).unwrap();
+            check_board(&Board::<4>::arbitrary(&mut u).unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn arbitrary_games_are_legal() {
+        for input in inputs(500) {
+            let game = PlayedGame::<3>::arbitrary(&mut Unstructured::new(&input)).unwrap();
+            check_game::<3>(&game.moves).unwrap();
+
+            let Position([row, column]) =
+                Position::<5>::arbitrary(&mut Unstructured::new(&input)).unwrap();
+            assert!(row < 5 && column < 5);
+        }
+    }
+}
@@ -1,14 +1,45 @@
-use std::{fmt::Display, io};
+use std::fmt::Display;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-use crate::board::{Board, PlayerMoveError, Symbol};
+use crate::achievements::{self, AchievementTracker};
+use crate::ai;
+use crate::autosave::{Autosave, SavedGame};
+use crate::board::{Board, GameStatus, Symbol};
+use crate::bot::{Bot, BotSpec};
+use crate::browser::{self, GameRecord, RECORD_PREFIX};
+use crate::clipboard;
+use crate::coach;
+use crate::console::Console;
+use crate::events::{self, Events};
+use crate::gamelog::GameLog;
+use crate::keys::{self, Action, Bindings, Keys, VimAction};
+use crate::openings::{self, OpeningTracker};
+use crate::palette::{Palette, PALETTES};
+use crate::personality::{Event, Personality, PersonalityBot};
+use crate::rating::{self, RatingTracker};
+use crate::recording::Recording;
+use crate::rng::Rng;
+use crate::sound::{Effect, Speaker, Volume};
+use crate::streaks::{self, StreakTracker};
+use crate::terminal;
+use crate::tournament::Record;
+use crate::view::{Scale, View, SCALES, VIEWS};
 
 #[derive(Debug, PartialEq, Eq)]
-enum PlayerInputParseError {
+pub enum PlayerInputParseError {
     InvalidFormat(String),
 }
 
+pub enum Opponent {
+    Human,
+    Computer(Box<dyn Bot>),
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum Player {
+pub enum Player {
     One,
     Two,
 }
@@ -20,7 +51,15 @@ impl Display for Player {
             Player::Two => "Player 2",
         };
 
-        write!(f, "{}", alias)
+        write!(f, "{alias}")
+    }
+}
+
+// The player who plays the symbol, Player 1 unless it is o.
+fn player_with(symbol: Symbol) -> Player {
+    match symbol {
+        Symbol::Circle => Player::Two,
+        _ => Player::One,
     }
 }
 
@@ -33,7 +72,7 @@ impl From<Player> for Symbol {
     }
 }
 
-fn parse_player_move(player_move: &str) -> Result<[usize; 2], PlayerInputParseError> {
+pub fn parse_player_move(player_move: &str) -> Result<[usize; 2], PlayerInputParseError> {
     let positions = player_move.split(',').map(str::trim).collect::<Vec<_>>();
 
     let invalid_format_error_message = "Invalid format";
@@ -52,75 +91,1554 @@ fn parse_player_move(player_move: &str) -> Result<[usize; 2], PlayerInputParseEr
         }
     }
 
-    let x = match positions[0].chars().next().unwrap().to_digit(10) {
-        Some(n) => n,
-        None => {
-            return Err(PlayerInputParseError::InvalidFormat(String::from(
-                invalid_format_error_message,
-            )))
-        }
+    let Some(x) = positions[0].chars().next().unwrap().to_digit(10) else {
+        return Err(PlayerInputParseError::InvalidFormat(String::from(
+            invalid_format_error_message,
+        )));
     };
 
-    let y = match positions[1].chars().next().unwrap().to_digit(10) {
-        Some(n) => n,
-        None => {
-            return Err(PlayerInputParseError::InvalidFormat(String::from(
-                invalid_format_error_message,
-            )))
-        }
+    let Some(y) = positions[1].chars().next().unwrap().to_digit(10) else {
+        return Err(PlayerInputParseError::InvalidFormat(String::from(
+            invalid_format_error_message,
+        )));
     };
 
     Ok([x.try_into().unwrap(), y.try_into().unwrap()])
 }
 
-pub fn start() {
-    let mut player_turn = Player::One;
-    let mut board = Board::new();
+// Where handicap marks go, in order: the center is the strongest tile.
+const HANDICAP_TILES: [[usize; 2]; 2] = [[1, 1], [0, 0]];
 
-    loop {
-        println!("\nThe current board state is:\n\n{}\n", board);
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HandicapKind {
+    // Starts with this many marks on the board, the opponent moving first.
+    Marks(usize),
+    // Moves first and the opponent skips their first turn.
+    ExtraMove,
+}
+
+// An advantage for the weaker player, kept for every game of the session.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Handicap {
+    pub player: Player,
+    pub kind: HandicapKind,
+}
 
-        println!("{}, please do your move.", player_turn);
+impl Handicap {
+    fn first_player(self) -> Player {
+        match self.kind {
+            HandicapKind::Marks(_) => self.player.other(),
+            HandicapKind::ExtraMove => self.player,
+        }
+    }
+}
 
-        let mut player_input = String::new();
-        io::stdin()
-            .read_line(&mut player_input)
-            .expect("Failed to read line.");
+// Parses "<player>:<marks>" or "<player>:skip", e.g. "p1:2" or "p2:skip".
+impl FromStr for Handicap {
+    type Err = String;
 
-        let player_move = match parse_player_move(&player_input) {
-            Ok(parsed_move) => parsed_move,
-            Err(error) => match error {
-                PlayerInputParseError::InvalidFormat(x) => {
-                    eprintln!("{} {} please try again!", x, player_turn);
-                    continue;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{s}' is not a handicap, use e.g. p1:1, p1:2 or p1:skip.");
+        let (player, kind) = s.split_once(':').ok_or_else(invalid)?;
+
+        let player = match player.to_lowercase().as_str() {
+            "p1" => Player::One,
+            "p2" => Player::Two,
+            _ => return Err(invalid()),
+        };
+        let kind = match kind.to_lowercase().as_str() {
+            "skip" => HandicapKind::ExtraMove,
+            marks => match marks.parse::<usize>() {
+                Ok(count) if (1..=HANDICAP_TILES.len()).contains(&count) => {
+                    HandicapKind::Marks(count)
                 }
+                _ => return Err(invalid()),
             },
         };
 
-        match board.is_valid_move(player_move) {
-            Ok(_) => {}
-            Err(err) => match err {
-                PlayerMoveError::FilledPosition(msg) | PlayerMoveError::OutsideBoard(msg) => {
-                    eprintln!("{} {} please try again!", msg, player_turn);
-                    continue;
+        Ok(Handicap { player, kind })
+    }
+}
+
+impl Display for Handicap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            HandicapKind::Marks(1) => write!(f, "{} starts with a mark", self.player),
+            HandicapKind::Marks(count) => write!(f, "{} starts with {count} marks", self.player),
+            HandicapKind::ExtraMove => {
+                write!(f, "{} skips the first turn", self.player.other())
+            }
+        }
+    }
+}
+
+// A position to start games from instead of an empty board.
+#[derive(Clone)]
+pub struct StartPosition {
+    board: Board,
+    to_move: Player,
+}
+
+impl StartPosition {
+    // Checks that the position can come up in a game, with to_move inferred
+    // from the mark count when not given. Either side may have started, so
+    // the side with fewer marks, or either side on equal counts, is to move.
+    pub fn new(board: Board, to_move: Option<Symbol>) -> Result<StartPosition, String> {
+        let count = |symbol| {
+            board
+                .tiles
+                .iter()
+                .flatten()
+                .filter(|tile| **tile == symbol)
+                .count()
+        };
+        let (plus, circle) = (count(Symbol::Plus), count(Symbol::Circle));
+
+        board.validate().map_err(|error| error.to_string())?;
+        if board.status() != GameStatus::InProgress {
+            return Err(String::from("The game is already over in that position."));
+        }
+
+        let to_move = match (to_move, plus.cmp(&circle)) {
+            (None | Some(Symbol::Empty), std::cmp::Ordering::Greater) => Symbol::Circle,
+            (None | Some(Symbol::Empty), _) => Symbol::Plus,
+            (Some(symbol), ordering) => {
+                let has_more = match ordering {
+                    std::cmp::Ordering::Greater => Some(Symbol::Plus),
+                    std::cmp::Ordering::Less => Some(Symbol::Circle),
+                    std::cmp::Ordering::Equal => None,
+                };
+                if has_more == Some(symbol) {
+                    let symbol: &str = symbol.into();
+                    return Err(format!(
+                        "{symbol} has more marks, so it cannot be its turn."
+                    ));
                 }
+                symbol
+            }
+        };
+
+        Ok(StartPosition {
+            board,
+            to_move: if to_move == Symbol::Plus {
+                Player::One
+            } else {
+                Player::Two
             },
+        })
+    }
+}
+
+// How the computer answers when asked to take back a move.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum TakebackAnswer {
+    #[default]
+    Accept,
+    Refuse,
+}
+
+impl Display for TakebackAnswer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let answer = match self {
+            TakebackAnswer::Accept => "accept",
+            TakebackAnswer::Refuse => "refuse",
         };
 
-        board.place(player_turn.into(), player_move);
+        write!(f, "{answer}")
+    }
+}
+
+impl FromStr for TakebackAnswer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "accept" => Ok(TakebackAnswer::Accept),
+            "refuse" => Ok(TakebackAnswer::Refuse),
+            _ => Err(format!(
+                "'{s}' is not a takeback answer, use accept or refuse."
+            )),
+        }
+    }
+}
+
+// How the end of each game is reported: as text for people or as a JSON
+// document, one line per game, for other programs.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum ResultFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for ResultFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(ResultFormat::Text),
+            "json" => Ok(ResultFormat::Json),
+            _ => Err(format!("'{s}' is not a result format, use text or json.")),
+        }
+    }
+}
+
+// How every game of a session starts. A start position and a handicap
+// both set up the board, so only one of them is used.
+// The flags are independent switches, not states of one setting.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Default)]
+pub struct GameSetup {
+    pub handicap: Option<Handicap>,
+    pub position: Option<StartPosition>,
+    // Show every move before it is played and ask the player to confirm it.
+    pub confirm_moves: bool,
+    // Play one game without offering a rematch, e.g. a campaign level.
+    pub single_game: bool,
+    // Point out missed wins and blocks after every move a player types.
+    pub coach: bool,
+    // Completing a line loses instead of winning.
+    pub misere: bool,
+    pub takebacks: TakebackAnswer,
+    pub palette: Palette,
+    pub view: View,
+    // How big the grid is drawn.
+    pub scale: Scale,
+    pub keys: Keys,
+    // The vim keys, as remapped in the settings.
+    pub bindings: Bindings,
+    // Sound effects, with the audio feature.
+    pub volume: Volume,
+    pub mute: bool,
+    // Games on this terminal save themselves here after every move.
+    pub autosave: Option<Autosave>,
+    // Results against the computer by opening, from Player 1's side.
+    pub openings: Option<OpeningTracker>,
+    pub achievements: Option<AchievementTracker>,
+    pub streaks: Option<StreakTracker>,
+    // Only games against the built-in bots are rated.
+    pub rating: Option<RatingTracker>,
+    // A saved game to finish first, before any rematch.
+    pub resume: Option<SavedGame>,
+    pub log: Option<GameLog>,
+    // Where game events go, for watchers such as webhooks.
+    pub events: Option<Events>,
+    // Show how input is parsed and how the computer picks its moves.
+    pub debug: bool,
+    // Show only how each game ends, for scripts.
+    pub quiet: bool,
+    pub result_format: ResultFormat,
+    // Lines for the computer to say, see src/personality.rs.
+    pub personality: Option<Personality>,
+}
+
+impl GameSetup {
+    // Keeps the openings, achievements and streaks of games against spec in
+    // their usual files.
+    pub fn tracked_against(self, spec: &BotSpec) -> GameSetup {
+        GameSetup {
+            openings: Some(OpeningTracker::new(openings::path(), spec.argument())),
+            achievements: Some(AchievementTracker::new(achievements::path(), spec.clone())),
+            streaks: Some(StreakTracker::new(streaks::path(), spec.argument())),
+            rating: match spec {
+                BotSpec::Builtin(difficulty) => {
+                    Some(RatingTracker::new(rating::path(), *difficulty))
+                }
+                _ => None,
+            },
+            ..self
+        }
+    }
+
+    // The record and who skips their turn are for saving the game from vim.
+    fn read_turn(
+        &self,
+        console: &mut Console,
+        board: &Board,
+        player_turn: Player,
+        record: &GameRecord,
+        skips_turn: Option<Player>,
+    ) -> TurnInput {
+        match self.keys {
+            Keys::Standard => read_player_move(console, board, player_turn),
+            Keys::Vim => self.read_vim_turn(console, board, player_turn, record, skips_turn),
+        }
+    }
+
+    // Reads vim keys until they place a mark or give a command, drawing the
+    // board with the cursor bracketed after keys that only move it. The
+    // cursor starts on the last move, or in the middle of a new game.
+    fn read_vim_turn(
+        &self,
+        console: &mut Console,
+        board: &Board,
+        player_turn: Player,
+        record: &GameRecord,
+        skips_turn: Option<Player>,
+    ) -> TurnInput {
+        let size = board.tiles.len();
+        let mut cursor = record
+            .moves()
+            .last()
+            .map_or([size / 2; 2], |(_, position)| *position);
+
+        let key = |action| self.bindings.key(action);
+        loop {
+            console.prompt(&format!(
+                "{player_turn}, move with {}, {}, {} and {} and place with {}. The cursor is on {},{}.",
+                key(Action::Left),
+                key(Action::Down),
+                key(Action::Up),
+                key(Action::Right),
+                key(Action::Place),
+                cursor[0],
+                cursor[1]
+            ));
+            let Some(line) = console.read_line() else {
+                return TurnInput::Closed;
+            };
+            let action = match keys::parse_vim(&line, &mut cursor, size, &self.bindings) {
+                Ok(action) => action,
+                Err(message) => {
+                    console.warn(&message);
+                    console.play(Effect::Invalid);
+                    self.reject();
+                    continue;
+                }
+            };
+            match action {
+                None => {
+                    let renderer = console.renderer();
+                    console.show(&format!("\n{}\n", renderer.board(board, &[cursor])));
+                }
+                Some(VimAction::Place(position)) => match board.is_valid_move(position) {
+                    Ok(_) => return TurnInput::Move(position),
+                    Err(error) => {
+                        console.warn(&format!("{error} {player_turn} please try again!"));
+                        console.play(Effect::Invalid);
+                        self.reject();
+                    }
+                },
+                Some(VimAction::Undo) => return TurnInput::Command(Command::Takeback),
+                Some(VimAction::Save) => {
+                    self.save_for_later(console, record, player_turn, skips_turn);
+                }
+                Some(VimAction::SaveAndQuit) => {
+                    self.save_for_later(console, record, player_turn, skips_turn);
+                    return TurnInput::Closed;
+                }
+                Some(VimAction::Quit) => return TurnInput::Closed,
+                Some(VimAction::Command(name)) => match Command::parse(&name) {
+                    Some(command) => return TurnInput::Command(command),
+                    None => console.warn(&format!("':{name}' is not a command, see :help.")),
+                },
+            }
+        }
+    }
+
+    // Saves the game now and says how to finish it, for :w.
+    fn save_for_later(
+        &self,
+        console: &mut Console,
+        record: &GameRecord,
+        to_move: Player,
+        skips_turn: Option<Player>,
+    ) {
+        match self.autosave.as_ref().map(|autosave| {
+            let saved = autosave.save(record, to_move.into(), skips_turn.map(Symbol::from));
+            (autosave, saved)
+        }) {
+            // A remote player cannot see the server's files.
+            Some((_, Ok(()))) if console.is_remote() => console.say(
+                "\nSaved, finish the game later by connecting again and typing rejoin with your code.",
+            ),
+            Some((autosave, Ok(()))) => console.say(&format!(
+                "\nSaved, finish the game later with --resume {}.",
+                autosave.path().display()
+            )),
+            Some((_, Err(message))) => console.warn(&message),
+            None => console.warn("Games here are not saved."),
+        }
+    }
+
+    fn notify(&self, event: &events::Event) {
+        if let Some(events) = &self.events {
+            events.send(event);
+        }
+    }
+
+    fn reject(&self) {
+        if let Some(events) = &self.events {
+            events.rejected();
+        }
+    }
+
+    fn save(
+        &self,
+        console: &mut Console,
+        record: &GameRecord,
+        to_move: Player,
+        skips_turn: Option<Player>,
+    ) {
+        let skips = skips_turn.map(Symbol::from);
+        if let Some(Err(message)) = self
+            .autosave
+            .as_ref()
+            .map(|autosave| autosave.save(record, to_move.into(), skips))
+        {
+            console.warn(&message);
+        }
+    }
+
+    // Explains a typed move's mistake before the opponent replies.
+    fn coach(&self, console: &mut Console, board: &Board, player: Player, player_move: [usize; 2]) {
+        if let Some(advice) = self
+            .coach
+            // Its advice is about the usual game, not misère.
+            .then(|| coach::review(board, player.into(), player_move))
+            .flatten()
+            .filter(|_| !self.misere)
+        {
+            console.say(&format!("\nCoach: {advice}"));
+        }
+    }
+
+    // Whether the game started on an empty board without a handicap. Others
+    // start out lopsided, so they are left out of the statistics: a head
+    // start makes achievements too easy and would skew the rating, streaks
+    // and openings.
+    fn on_equal_terms(&self, record: &GameRecord) -> bool {
+        self.handicap.is_none()
+            && self.position.is_none()
+            && record.start().tiles == Board::new().tiles
+    }
+
+    fn track_achievements(&self, console: &mut Console, record: &GameRecord, outcome: Outcome) {
+        let Some(achievements) = self
+            .achievements
+            .as_ref()
+            .filter(|_| self.on_equal_terms(record))
+        else {
+            return;
+        };
+        match achievements.track(record, outcome) {
+            Ok(unlocked) => {
+                for achievement in unlocked {
+                    console.say(&format!("\nAchievement unlocked: {achievement}!"));
+                }
+            }
+            Err(message) => console.warn(&message),
+        }
+    }
+
+    fn track_rating(&self, console: &mut Console, record: &GameRecord, outcome: Outcome) {
+        let Some(rating) = self.rating.as_ref().filter(|_| self.on_equal_terms(record)) else {
+            return;
+        };
+        match rating.track(outcome.record_for(Player::One)) {
+            Ok((rating, change)) => {
+                console.say(&format!("\nYour rating is now {rating:.0} ({change:+.0})."));
+            }
+            Err(message) => console.warn(&message),
+        }
+    }
+
+    fn track_streaks(&self, console: &mut Console, record: &GameRecord, outcome: Outcome) {
+        let Some(streaks) = self
+            .streaks
+            .as_ref()
+            .filter(|_| self.on_equal_terms(record))
+        else {
+            return;
+        };
+        match streaks.track(outcome.record_for(Player::One)) {
+            Ok(records) => {
+                for record in records {
+                    console.say(&format!("\n{record}"));
+                }
+            }
+            Err(message) => console.warn(&message),
+        }
+    }
+
+    fn track_opening(&self, console: &mut Console, record: &GameRecord, outcome: Outcome) {
+        let Some(openings) = self
+            .openings
+            .as_ref()
+            .filter(|_| self.on_equal_terms(record))
+        else {
+            return;
+        };
+        if let Err(message) = openings.track(record, outcome.record_for(Player::One)) {
+            console.warn(&message);
+        }
+    }
 
-        if board.winner().is_some() {
-            println!("The winner is: {}", player_turn);
-            break;
+    fn first_player(&self) -> Player {
+        if let Some(game) = &self.resume {
+            return player_with(game.to_move);
         }
 
-        player_turn = match player_turn {
+        match (&self.position, self.handicap) {
+            (Some(position), _) => position.to_move,
+            (None, Some(handicap)) => handicap.first_player(),
+            (None, None) => Player::One,
+        }
+    }
+
+    // The commands that leave the turn with the player. Resigning is handled
+    // by the game loop since it ends the game.
+    fn run_command(
+        &self,
+        console: &mut Console,
+        command: Command,
+        board: &Board,
+        player_turn: Player,
+        draw_offer: &mut Option<Player>,
+    ) {
+        match command {
+            Command::Help => console.say(&format!("\n{}", help_text(board.tiles.len()))),
+            Command::Copy => copy_position(console, board),
+            Command::Moves => console.say(&format!(
+                "\nLegal moves: {}",
+                format_moves(&board.empty_positions())
+            )),
+            Command::OfferDraw => {
+                if draw_offer.is_some() {
+                    console.warn(&format!("{player_turn} has already offered a draw."));
+                } else {
+                    *draw_offer = Some(player_turn);
+                    console.say(&format!("{player_turn} offers a draw, now make your move."));
+                }
+            }
+            Command::Hint => {
+                let scores = if self.misere {
+                    ai::misere_move_scores(board, player_turn.into())
+                } else {
+                    ai::move_scores(board, player_turn.into())
+                };
+                if let Some(([row, column], _)) = scores
+                    .into_iter()
+                    // The first of equally good moves.
+                    .rev()
+                    .max_by_key(|(_, score)| *score)
+                {
+                    console.say(&format!("\nHint: {row},{column}."));
+                }
+            }
+            Command::Palette | Command::View | Command::Labels | Command::Scale => {
+                change_look(console, command);
+            }
+            Command::Resign | Command::Takeback | Command::Pause => {}
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum WinReason {
+    Line,
+    // The loser completed a line in misère.
+    Misere,
+    Resignation,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DrawReason {
+    FullBoard,
+    Agreement,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Outcome {
+    Winner(Player, WinReason),
+    Draw(DrawReason),
+}
+
+impl Outcome {
+    // The game as a one game record for player.
+    pub fn record_for(self, player: Player) -> Record {
+        match self.winner() {
+            Some(winner) if winner == player => Record {
+                wins: 1,
+                ..Record::default()
+            },
+            Some(_) => Record {
+                losses: 1,
+                ..Record::default()
+            },
+            None => Record {
+                draws: 1,
+                ..Record::default()
+            },
+        }
+    }
+
+    pub fn winner(self) -> Option<Player> {
+        match self {
+            Outcome::Winner(winner, _) => Some(winner),
+            Outcome::Draw(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Command {
+    Help,
+    Moves,
+    Copy,
+    Resign,
+    OfferDraw,
+    Takeback,
+    Pause,
+    Hint,
+    Palette,
+    View,
+    Labels,
+    Scale,
+}
+
+// Every command available at the move prompt. Parsing and `help` both read from here.
+const COMMANDS: [(Command, &str, &str); 12] = [
+    (Command::Help, "help", "List the available commands."),
+    (Command::Moves, "moves", "List every legal move."),
+    (
+        Command::Copy,
+        "copy",
+        "Copy the position to the clipboard, e.g. for analyze paste.",
+    ),
+    (
+        Command::Resign,
+        "resign",
+        "Concede the game to your opponent.",
+    ),
+    (
+        Command::OfferDraw,
+        "draw",
+        "Offer a draw, your opponent answers before their move.",
+    ),
+    (
+        Command::Takeback,
+        "undo",
+        "Ask your opponent to take back your last move and their reply.",
+    ),
+    (
+        Command::Pause,
+        "pause",
+        "Stop the clock and hide the board until you press enter.",
+    ),
+    (Command::Hint, "hint", "Suggest the best move."),
+    (
+        Command::Palette,
+        "palette",
+        "Switch to the next palette and draw the board again.",
+    ),
+    (
+        Command::View,
+        "view",
+        "Switch between the grid, large print and Braille.",
+    ),
+    (
+        Command::Labels,
+        "labels",
+        "Show or hide row and column numbers around the grid.",
+    ),
+    (
+        Command::Scale,
+        "scale",
+        "Draw the grid 1, 2 or 3 times as big, or as big as fits.",
+    ),
+];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum TurnInput {
+    Move([usize; 2]),
+    Command(Command),
+    Retry,
+    // Not a move, or not a legal one, asked for again.
+    Rejected,
+    Closed,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct SessionScore {
+    player_one_wins: u32,
+    player_two_wins: u32,
+    draws: u32,
+    agreed_draws: u32,
+    resignations: u32,
+}
+
+impl Player {
+    pub fn other(self) -> Player {
+        match self {
             Player::One => Player::Two,
             Player::Two => Player::One,
         }
     }
 }
 
+impl Command {
+    fn parse(input: &str) -> Option<Command> {
+        let input = input.trim().to_lowercase();
+
+        COMMANDS
+            .iter()
+            .find(|(_, name, _)| *name == input)
+            .map(|(command, _, _)| *command)
+    }
+}
+
+// The moves' range is given for a board size tiles across.
+fn help_text(size: usize) -> String {
+    let mut lines = vec![format!(
+        "  {:<8} Place your symbol at row x and column y, both from 0 to {}.",
+        "x,y",
+        size - 1
+    )];
+
+    for (_, name, description) in COMMANDS {
+        lines.push(format!("  {name:<8} {description}"));
+    }
+
+    format!("Available commands:\n{}", lines.join("\n"))
+}
+
+fn format_moves(positions: &[[usize; 2]]) -> String {
+    positions
+        .iter()
+        .map(|position| format!("{},{}", position[0], position[1]))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl SessionScore {
+    fn record(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Winner(Player::One, _) => self.player_one_wins += 1,
+            Outcome::Winner(Player::Two, _) => self.player_two_wins += 1,
+            Outcome::Draw(DrawReason::FullBoard) => self.draws += 1,
+            Outcome::Draw(DrawReason::Agreement) => self.agreed_draws += 1,
+        }
+
+        if let Outcome::Winner(_, WinReason::Resignation) = outcome {
+            self.resignations += 1;
+        }
+    }
+}
+
+// Times the game and every move for the summary shown when it ends. A turn
+// starts when the previous move was made, so commands and answering a draw
+// offer count as thinking time.
+struct GameClock {
+    started: Instant,
+    turn_started: Instant,
+    // Indexed by player, Player 1 first.
+    think_time: [Duration; 2],
+    moves: [u32; 2],
+    // Time spent paused, which counts as neither think time nor game time.
+    paused: Duration,
+}
+
+impl GameClock {
+    fn start() -> GameClock {
+        let now = Instant::now();
+        GameClock {
+            started: now,
+            turn_started: now,
+            think_time: [Duration::ZERO; 2],
+            moves: [0; 2],
+            paused: Duration::ZERO,
+        }
+    }
+
+    fn resumed(&mut self, paused: Duration) {
+        self.turn_started += paused;
+        self.paused += paused;
+    }
+
+    fn duration(&self) -> Duration {
+        self.started.elapsed().saturating_sub(self.paused)
+    }
+
+    fn moved(&mut self, player: Player) {
+        let now = Instant::now();
+        let index = usize::from(player == Player::Two);
+        self.think_time[index] += now - self.turn_started;
+        self.moves[index] += 1;
+        self.turn_started = now;
+    }
+
+    // Forgets moves that were taken back. The time spent on them still counts.
+    fn taken_back(&mut self, player: Player, moves: usize) {
+        let index = usize::from(player == Player::Two);
+        self.moves[index] =
+            self.moves[index].saturating_sub(u32::try_from(moves).unwrap_or(u32::MAX));
+    }
+
+    fn average_think_time(&self, player: Player) -> Option<Duration> {
+        let index = usize::from(player == Player::Two);
+        (self.moves[index] > 0).then(|| self.think_time[index] / self.moves[index])
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds < 60 {
+        format!("{:.1}s", duration.as_secs_f64())
+    } else {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    }
+}
+
+fn describe_outcome(outcome: Outcome) -> String {
+    match outcome {
+        Outcome::Winner(winner, WinReason::Line) => format!("{winner} completed a line"),
+        Outcome::Winner(winner, WinReason::Misere) => {
+            format!("{} completed a line in misère", winner.other())
+        }
+        Outcome::Winner(winner, WinReason::Resignation) => {
+            format!("{} resigned or forfeited", winner.other())
+        }
+        Outcome::Draw(DrawReason::FullBoard) => String::from("draw, the board is full"),
+        Outcome::Draw(DrawReason::Agreement) => String::from("draw by agreement"),
+    }
+}
+
+fn game_summary(clock: &GameClock, outcome: Outcome) -> String {
+    let average = |player| {
+        clock
+            .average_think_time(player)
+            .map_or_else(|| String::from("no moves"), format_duration)
+    };
+
+    format!(
+        "Game summary:\n  Moves: {}\n  Duration: {}\n  Average think time: {} {}, {} {}\n  Ended by: {}",
+        clock.moves[0] + clock.moves[1],
+        format_duration(clock.duration()),
+        Player::One,
+        average(Player::One),
+        Player::Two,
+        average(Player::Two),
+        describe_outcome(outcome)
+    )
+}
+
+// The result and what ended the game, as JSON names.
+fn outcome_names(outcome: Outcome) -> (&'static str, &'static str) {
+    let result = match outcome {
+        Outcome::Winner(Player::One, _) => "player_one",
+        Outcome::Winner(Player::Two, _) => "player_two",
+        Outcome::Draw(_) => "draw",
+    };
+    let reason = match outcome {
+        Outcome::Winner(_, WinReason::Line) => "line",
+        Outcome::Winner(_, WinReason::Misere) => "misere",
+        Outcome::Winner(_, WinReason::Resignation) => "resignation",
+        Outcome::Draw(DrawReason::FullBoard) => "full_board",
+        Outcome::Draw(DrawReason::Agreement) => "agreement",
+    };
+    (result, reason)
+}
+
+// The whole game as one line of JSON: the start position, every move, the
+// final board, the result and the timings in milliseconds.
+fn result_json(record: &GameRecord, clock: &GameClock, outcome: Outcome) -> String {
+    let moves = record
+        .moves()
+        .iter()
+        .map(|(symbol, [row, column])| {
+            let symbol: &str = (*symbol).into();
+            format!(r#"{{"symbol":"{symbol}","move":[{row},{column}]}}"#)
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    let board = record.board();
+    let line = match board.status() {
+        GameStatus::Won(_, line) => format!(
+            "[{}]",
+            line.map(|[row, column]| format!("[{row},{column}]"))
+                .join(",")
+        ),
+        _ => String::from("null"),
+    };
+    let (result, reason) = outcome_names(outcome);
+
+    format!(
+        r#"{{"start":"{:#}","moves":[{moves}],"final_board":"{board:#}","result":"{result}","reason":"{reason}","line":{line},"duration_ms":{},"think_time_ms":{{"player_one":{},"player_two":{}}}}}"#,
+        record.start(),
+        clock.duration().as_millis(),
+        clock.think_time[0].as_millis(),
+        clock.think_time[1].as_millis()
+    )
+}
+
+impl Display for SessionScore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} - {} {} (draws: {}, agreed draws: {}, resignations: {})",
+            Player::One,
+            self.player_one_wins,
+            self.player_two_wins,
+            Player::Two,
+            self.draws,
+            self.agreed_draws,
+            self.resignations
+        )
+    }
+}
+
+fn parse_yes_no(input: &str) -> Option<bool> {
+    match input.trim().to_lowercase().as_str() {
+        "y" | "yes" => Some(true),
+        "n" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+// Shows the board at the start of a turn: what the computer's reply
+// changed, or the board with the last move bracketed.
+fn show_board(
+    console: &mut Console,
+    board: &Board,
+    before_reply: Option<Board>,
+    record: &GameRecord,
+) {
+    // Drawn to the size the terminal has now.
+    terminal::take_resize();
+    let renderer = console.renderer();
+    if let Some(previous) = before_reply {
+        console.show(&format!(
+            "\nThe board before and after that move:\n\n{}\n",
+            renderer.diff(&previous, board)
+        ));
+    } else {
+        let moves = record
+            .moves()
+            .iter()
+            .map(|(_, position)| *position)
+            .collect::<Vec<_>>();
+        console.show(&format!(
+            "\nThe current board state is:\n\n{}\n",
+            renderer.position(board, &moves[moves.len().saturating_sub(1)..], &moves)
+        ));
+    }
+}
+
+// Announces how the game ended when player_turn's move ended it, with
+// the winning line bracketed, and plays the sound of the move. In misère
+// the line loses.
+fn finish(
+    console: &mut Console,
+    board: &Board,
+    player_turn: Player,
+    misere: bool,
+) -> Option<Outcome> {
+    let renderer = console.renderer();
+    match board.status() {
+        GameStatus::Won(_, line) if misere => {
+            let winner = player_turn.other();
+            console.result(&format!(
+                "\n{}\n\n{player_turn} completed the line {}, so the winner is: {winner}",
+                renderer.board(board, &line),
+                format_moves(&line)
+            ));
+            console.play(Effect::Win);
+            Some(Outcome::Winner(winner, WinReason::Misere))
+        }
+        GameStatus::Won(_, line) => {
+            console.result(&format!(
+                "\n{}\n\nThe winner is: {player_turn} with the line {}",
+                renderer.board(board, &line),
+                format_moves(&line)
+            ));
+            console.play(Effect::Win);
+            Some(Outcome::Winner(player_turn, WinReason::Line))
+        }
+        GameStatus::Draw => {
+            console.result(&format!(
+                "\n{}\n\nThe board is full, it's a draw!",
+                renderer.board(board, &[])
+            ));
+            console.play(Effect::Draw);
+            Some(Outcome::Draw(DrawReason::FullBoard))
+        }
+        GameStatus::InProgress => {
+            console.play(Effect::Move);
+            None
+        }
+    }
+}
+
+fn read_player_move(console: &mut Console, board: &Board, player_turn: Player) -> TurnInput {
+    console.prompt(&format!("{player_turn}, please do your move."));
+
+    let Some(player_input) = console.read_line() else {
+        return TurnInput::Closed;
+    };
+
+    let typed = player_input.trim();
+    // Enter after a resize draws the board to the new size.
+    if typed.is_empty() && terminal::take_resize() {
+        return TurnInput::Retry;
+    }
+    if let Some(command) = Command::parse(&player_input) {
+        console.debug(&format!("'{typed}' is the {command:?} command."));
+        return TurnInput::Command(command);
+    }
+
+    let player_move = match parse_player_move(&player_input) {
+        Ok(parsed_move) => parsed_move,
+        Err(error) => match error {
+            PlayerInputParseError::InvalidFormat(x) => {
+                console.debug(&format!("'{typed}' is neither a command nor a move."));
+                console.warn(&format!("{x} {player_turn} please try again!"));
+                console.play(Effect::Invalid);
+                return TurnInput::Rejected;
+            }
+        },
+    };
+    console.debug(&format!(
+        "'{typed}' parsed as row {}, column {}.",
+        player_move[0], player_move[1]
+    ));
+
+    let validation = board.is_valid_move(player_move);
+    console.debug(&format!("Validation: {validation:?}."));
+    match validation {
+        Ok(_) => TurnInput::Move(player_move),
+        Err(error) => {
+            let nearby = nearby_empty_positions(board, player_move);
+            let suggestion = if nearby.is_empty() {
+                String::new()
+            } else {
+                format!(" Nearby empty tiles: {}.", format_moves(&nearby))
+            };
+            console.warn(&format!(
+                "{error}{suggestion} {player_turn} please try again!"
+            ));
+            console.play(Effect::Invalid);
+            TurnInput::Rejected
+        }
+    }
+}
+
+// The empty tiles next to a rejected move. Moves outside the board are
+// pulled back to the closest tile first.
+fn nearby_empty_positions(board: &Board, [row, column]: [usize; 2]) -> Vec<[usize; 2]> {
+    let last = board.tiles.len() - 1;
+    let (row, column) = (row.min(last), column.min(last));
+
+    board
+        .empty_positions()
+        .into_iter()
+        .filter(|[i, j]| i.abs_diff(row) <= 1 && j.abs_diff(column) <= 1)
+        .collect()
+}
+
+// The option after current, starting over after the last.
+fn next<T: PartialEq + Copy>(options: &[T], current: T) -> T {
+    let index = options.iter().position(|option| *option == current);
+    options[index.map_or(0, |index| (index + 1) % options.len())]
+}
+
+// Changes how the board is drawn for the rest of the session. The game loop
+// draws it again before the next prompt.
+fn change_look(console: &mut Console, command: Command) {
+    let renderer = console.renderer();
+    match command {
+        Command::Palette => {
+            let palette = next(&PALETTES, renderer.palette);
+            console.set_palette(palette);
+            console.say(&format!("\nPalette: {palette}."));
+        }
+        Command::View => {
+            let view = next(&VIEWS, renderer.view);
+            console.set_view(view);
+            console.say(&format!("\nView: {view}."));
+        }
+        Command::Scale => {
+            let scale = next(&SCALES, renderer.scale);
+            console.set_scale(scale);
+            console.say(&format!("\nScale: {scale}."));
+        }
+        _ => {
+            console.set_labels(!renderer.labels);
+            console.say(&format!(
+                "\nRow and column numbers are {}.",
+                if renderer.labels { "off" } else { "on" }
+            ));
+        }
+    }
+}
+
+// Returns whether player_turn accepts, or None when stdin is closed.
+fn answer_draw_offer(
+    console: &mut Console,
+    opponent: &mut Opponent,
+    board: &Board,
+    player_turn: Player,
+    offered_by: Player,
+) -> Option<bool> {
+    let accepted = match (player_turn, opponent) {
+        (Player::Two, Opponent::Computer(bot)) => bot.accepts_draw(board, player_turn.into()),
+        _ => ask_yes_no(
+            console,
+            &format!("{offered_by} offers a draw. {player_turn}, do you accept?"),
+        )?,
+    };
+
+    if accepted {
+        console.result(&format!("\n{player_turn} accepts the draw."));
+    } else {
+        console.say(&format!("\n{player_turn} declines the draw."));
+    }
+    Some(accepted)
+}
+
+// Clears the screen so the board stays private in hot-seat games, and leaves
+// the time until the game resumes off the clock. Returns None when stdin is closed.
+fn pause(console: &mut Console, clock: &mut GameClock) -> Option<()> {
+    let paused_at = Instant::now();
+    console.clear();
+    console.say("The game is paused. Press enter to resume.");
+    console.read_line()?;
+    clock.resumed(paused_at.elapsed());
+    Some(())
+}
+
+// Asks the opponent whether player_turn may take back their last move, and
+// takes it back with every move after it when they agree. Returns None when
+// stdin is closed.
+fn take_back(
+    console: &mut Console,
+    opponent: &Opponent,
+    setup: &GameSetup,
+    record: &mut GameRecord,
+    clock: &mut GameClock,
+    player_turn: Player,
+) -> Option<bool> {
+    let mut rewound = record.clone();
+    if !rewound.take_back(player_turn.into()) {
+        console.warn(&format!("{player_turn} has no move to take back."));
+        return Some(false);
+    }
+
+    let other = player_turn.other();
+    let accepted = match opponent {
+        Opponent::Computer(_) => setup.takebacks == TakebackAnswer::Accept,
+        Opponent::Human => ask_yes_no(
+            console,
+            &format!("{player_turn} asks to take back their last move. {other}, do you accept?"),
+        )?,
+    };
+
+    if accepted {
+        for player in [Player::One, Player::Two] {
+            let symbol = player.into();
+            clock.taken_back(player, record.moves_by(symbol) - rewound.moves_by(symbol));
+        }
+        *record = rewound;
+        console.say(&format!("\n{other} accepts, the move is taken back."));
+    } else {
+        console.say(&format!("\n{other} refuses the takeback."));
+    }
+    Some(accepted)
+}
+
+pub fn copy_position(console: &mut Console, board: &Board) {
+    let position = format!("{board:#}");
+    // The clipboard belongs to whoever runs the server, so remote players get the text.
+    if console.is_remote() {
+        console.say(&format!("The position is {position}"));
+        return;
+    }
+    match clipboard::copy(&position) {
+        Ok(()) => console.say(&format!("Copied {position} to the clipboard.")),
+        Err(message) => console.warn(&message),
+    }
+}
+
+// Shows the board with the move placed and marked. Returns None when stdin is closed.
+fn confirm_move(
+    console: &mut Console,
+    board: &Board,
+    player_turn: Player,
+    [row, column]: [usize; 2],
+) -> Option<bool> {
+    let mut tentative = board.clone();
+    tentative.place(player_turn.into(), [row, column]);
+    let renderer = console.renderer();
+    console.say(&format!(
+        "\n{}",
+        renderer.board(&tentative, &[[row, column]])
+    ));
+    ask_yes_no(console, &format!("{player_turn}, play {row},{column}?"))
+}
+
+// Returns None when stdin is closed.
+pub fn ask_yes_no(console: &mut Console, question: &str) -> Option<bool> {
+    loop {
+        console.prompt(&format!("\n{question} (y/n)"));
+
+        let input = console.read_line()?;
+
+        match parse_yes_no(&input) {
+            Some(answer) => return Some(answer),
+            None => console.warn("Please answer y or n."),
+        }
+    }
+}
+
+// Asks for a rematch, where b steps through the game that just ended before
+// asking again.
+fn ask_rematch(console: &mut Console, record: &GameRecord) -> Option<bool> {
+    loop {
+        console.prompt("\nRematch? (y/n, or b to step through the game first)");
+
+        let input = console.read_line()?;
+
+        if input.trim().eq_ignore_ascii_case("b") {
+            browser::run(console, record, false);
+            continue;
+        }
+        match parse_yes_no(&input) {
+            Some(answer) => return Some(answer),
+            None => console.warn("Please answer y, n or b."),
+        }
+    }
+}
+
+// Asks the bot for its move. External bots can misbehave, so anything
+// other than a legal move forfeits the game.
+fn computer_move(
+    console: &mut Console,
+    bot: &mut dyn Bot,
+    board: &Board,
+    player_turn: Player,
+) -> Result<[usize; 2], Outcome> {
+    let started = Instant::now();
+    let chosen = bot.choose_move(board, player_turn.into());
+    if console.debugging() {
+        let scores = ai::move_scores(board, player_turn.into())
+            .iter()
+            .map(|([row, column], score)| format!("{row},{column}: {score}"))
+            .collect::<Vec<_>>();
+        console.debug(&format!(
+            "{} chose {chosen:?} in {:?}. Minimax scores: {}.",
+            bot.name(),
+            started.elapsed(),
+            scores.join(", ")
+        ));
+    }
+    let legal_move = chosen.filter(|position| board.is_valid_move(*position).is_ok());
+
+    let Some(computer_move) = legal_move else {
+        let winner = player_turn.other();
+        console.result(&format!(
+            "\n{} did not play a legal move and forfeits. The winner is: {winner}",
+            bot.name()
+        ));
+        return Err(Outcome::Winner(winner, WinReason::Resignation));
+    };
+
+    console.say(&format!(
+        "{player_turn} ({}) plays {},{}.",
+        bot.name(),
+        computer_move[0],
+        computer_move[1]
+    ));
+    let blocks = board
+        .clone()
+        .place(player_turn.other().into(), computer_move);
+    if blocks && !board.clone().place(player_turn.into(), computer_move) {
+        remark(console, bot, Event::Block);
+    }
+    Ok(computer_move)
+}
+
+fn remark(console: &mut Console, bot: &mut dyn Bot, event: Event) {
+    if let Some(remark) = bot.remark(event) {
+        console.say(&remark);
+    }
+}
+
+// The starting board and the player who skips their first turn, if any.
+// Said up front so recordings keep how the game was set up.
+fn set_up_board(console: &mut Console, setup: &GameSetup) -> (Board, Option<Player>) {
+    if let Some(game) = &setup.resume {
+        console.say("\nResuming the saved game.");
+        return (game.record.board(), game.skips.map(player_with));
+    }
+
+    if let Some(position) = &setup.position {
+        console.say(&format!(
+            "\nStarting from the position {:#}.",
+            position.board
+        ));
+        return (position.board.clone(), None);
+    }
+
+    let mut board = Board::new();
+    let Some(handicap) = setup.handicap else {
+        return (board, None);
+    };
+
+    console.say(&format!("\nHandicap: {handicap}."));
+    match handicap.kind {
+        HandicapKind::Marks(count) => {
+            for position in HANDICAP_TILES.iter().take(count) {
+                board.place(handicap.player.into(), *position);
+            }
+            (board, None)
+        }
+        HandicapKind::ExtraMove => (board, Some(handicap.player.other())),
+    }
+}
+
+// Plays a single game. Returns None if the input was closed before the game ended.
+fn play(
+    console: &mut Console,
+    opponent: &mut Opponent,
+    first_player: Player,
+    setup: &GameSetup,
+    record: &mut GameRecord,
+    clock: &mut GameClock,
+) -> Option<Outcome> {
+    let mut player_turn = first_player;
+    let (mut board, mut skips_turn) = set_up_board(console, setup);
+    let first_skip = skips_turn;
+    *record = setup.resume.as_ref().map_or_else(
+        || GameRecord::new(board.clone()),
+        |game| game.record.clone(),
+    );
+    *clock = GameClock::start();
+    console.log("start", &format!("{board:#}, {first_player} to move"));
+    setup.notify(&events::Event::Created {
+        board: &board,
+        to_move: first_player.into(),
+        opponent: match opponent {
+            Opponent::Computer(bot) => Some(bot.name()),
+            Opponent::Human => None,
+        },
+    });
+    let mut draw_offer: Option<Player> = None;
+    // The board as it was before the computer's reply, for showing what changed.
+    let mut before_reply: Option<Board> = None;
+
+    loop {
+        setup.save(console, record, player_turn, skips_turn);
+        show_board(console, &board, before_reply.take(), record);
+
+        // A draw offer is answered by the opponent before they make their move.
+        if let Some(offered_by) = draw_offer.take_if(|offered_by| *offered_by != player_turn) {
+            if answer_draw_offer(console, opponent, &board, player_turn, offered_by)? {
+                return Some(Outcome::Draw(DrawReason::Agreement));
+            }
+        }
+
+        let player_move = match (player_turn, &mut *opponent) {
+            (Player::Two, Opponent::Computer(bot)) => {
+                match computer_move(console, bot.as_mut(), &board, player_turn) {
+                    Ok(computer_move) => {
+                        before_reply = Some(board.clone());
+                        computer_move
+                    }
+                    Err(outcome) => return Some(outcome),
+                }
+            }
+            _ => match setup.read_turn(console, &board, player_turn, record, skips_turn) {
+                TurnInput::Move(player_move) if setup.confirm_moves => {
+                    if !confirm_move(console, &board, player_turn, player_move)? {
+                        continue;
+                    }
+                    player_move
+                }
+                TurnInput::Move(player_move) => player_move,
+                TurnInput::Command(Command::Resign) => {
+                    let winner = player_turn.other();
+                    console.result(&format!("\n{player_turn} resigns. The winner is: {winner}"));
+                    return Some(Outcome::Winner(winner, WinReason::Resignation));
+                }
+                TurnInput::Command(Command::Pause) => {
+                    pause(console, clock)?;
+                    continue;
+                }
+                TurnInput::Command(Command::Takeback) => {
+                    if take_back(console, opponent, setup, record, clock, player_turn)? {
+                        board = record.board();
+                        // An offer made before the takeback was about another position.
+                        draw_offer = None;
+                        // Back at the start, the handicap's skipped turn comes up again.
+                        if record.is_empty() {
+                            skips_turn = first_skip;
+                        }
+                    }
+                    continue;
+                }
+                TurnInput::Command(command) => {
+                    setup.run_command(console, command, &board, player_turn, &mut draw_offer);
+                    continue;
+                }
+                TurnInput::Retry => continue,
+                TurnInput::Rejected => {
+                    setup.reject();
+                    continue;
+                }
+                TurnInput::Closed => return None,
+            },
+        };
+
+        if !matches!(opponent, Opponent::Computer(_) if player_turn == Player::Two) {
+            setup.coach(console, &board, player_turn, player_move);
+        }
+        board.place(player_turn.into(), player_move);
+        record.push(player_turn.into(), player_move);
+        console.log("move", &format!("{record}"));
+        setup.notify(&events::Event::Moved {
+            symbol: player_turn.into(),
+            position: player_move,
+            board: &board,
+        });
+        clock.moved(player_turn);
+
+        if let Some(outcome) = finish(console, &board, player_turn, setup.misere) {
+            return Some(outcome);
+        }
+
+        player_turn = player_turn.other();
+        if skips_turn
+            .take_if(|player| *player == player_turn)
+            .is_some()
+        {
+            console.say(&format!("\n{player_turn} skips the first turn."));
+            player_turn = player_turn.other();
+        }
+    }
+}
+
+// Plays games on console until the players stop asking for a rematch. With
+// a handicap or start position the turn order is fixed instead of alternating.
+// Returns how the last game ended, or None if the input was closed during it.
+pub fn play_session(
+    console: &mut Console,
+    opponent: Opponent,
+    setup: &GameSetup,
+) -> Option<Outcome> {
+    // The personality goes on whichever bot was picked.
+    let mut opponent = match (opponent, &setup.personality) {
+        (Opponent::Computer(bot), Some(personality)) => Opponent::Computer(Box::new(
+            PersonalityBot::new(personality.clone(), bot, Rng::from_time()),
+        )),
+        (opponent, _) => opponent,
+    };
+    let mut setup = setup.clone();
+    // Ctrl-C only mentions the save while a game on this terminal has one.
+    let _interrupts = setup
+        .autosave
+        .as_ref()
+        .filter(|_| !console.is_remote())
+        .map(Autosave::handle_interrupts);
+    // Anywhere but a terminal, the resize message would end up in the output.
+    let _resizes =
+        (!console.is_remote() && io::stdout().is_terminal()).then(terminal::watch_resizes);
+    let mut score = SessionScore::default();
+    let mut first_player = setup.first_player();
+    let mut record = GameRecord::new(Board::new());
+    let mut clock = GameClock::start();
+
+    loop {
+        setup.events = setup.events.as_ref().map(Events::next_game);
+        let Some(outcome) = play(
+            console,
+            &mut opponent,
+            first_player,
+            &setup,
+            &mut record,
+            &mut clock,
+        ) else {
+            setup.notify(&events::Event::Abandoned {
+                board: &record.board(),
+            });
+            return None;
+        };
+        if let Some(autosave) = &setup.autosave {
+            autosave.remove();
+        }
+        console.log("end", &describe_outcome(outcome));
+        let (result, reason) = outcome_names(outcome);
+        setup.notify(&events::Event::Finished {
+            result,
+            reason,
+            board: &record.board(),
+        });
+        setup.resume = None;
+        score.record(outcome);
+        setup.track_opening(console, &record, outcome);
+        setup.track_achievements(console, &record, outcome);
+        setup.track_streaks(console, &record, outcome);
+        setup.track_rating(console, &record, outcome);
+        if let Opponent::Computer(bot) = &mut opponent {
+            remark(
+                console,
+                bot.as_mut(),
+                Event::ending(outcome.record_for(Player::Two)),
+            );
+            if let Err(message) = bot.game_over(outcome.record_for(Player::Two)) {
+                console.warn(&message);
+            }
+        }
+        console.result(&format!("Final position: {:#}", record.board()));
+        if setup.result_format == ResultFormat::Json {
+            console.document(&result_json(&record, &clock, outcome));
+        }
+        console.say(&format!("{RECORD_PREFIX}{record}"));
+        console.say(&format!("\n{}", game_summary(&clock, outcome)));
+        console.say(&format!("\nSession score: {score}"));
+
+        if setup.single_game || !ask_rematch(console, &record).unwrap_or(false) {
+            return Some(outcome);
+        }
+
+        if setup.handicap.is_none() && setup.position.is_none() {
+            first_player = first_player.other();
+        }
+    }
+}
+
+// A console on this terminal with the setup's log, debug and output settings.
+pub fn local_console(setup: &GameSetup, recording: Option<Recording>) -> Console {
+    let mut console = Console::new(recording);
+    console.set_log(setup.log.clone());
+    console.set_debug(setup.debug);
+    console.set_quiet(setup.quiet);
+    console.set_palette(setup.palette);
+    console.set_view(setup.view);
+    console.set_scale(setup.scale);
+    console.set_speaker(Speaker::new(setup.volume, setup.mute));
+    if setup.result_format == ResultFormat::Json {
+        console.hide_results();
+    }
+    console
+}
+
+// Plays a session on the terminal. When record_to is given, the whole session
+// is written there for `replay` once it ends. Returns how the last game ended.
+pub fn start(opponent: Opponent, setup: &GameSetup, record_to: Option<&Path>) -> Option<Outcome> {
+    let mut console = local_console(setup, record_to.map(|_| Recording::new()));
+    let outcome = play_session(&mut console, opponent, setup);
+    let quiet = console.is_quiet();
+
+    if let (Some(path), Some(recording)) = (record_to, console.into_recording()) {
+        match recording.save(path) {
+            Ok(()) if quiet => {}
+            Ok(()) => println!("\nThe session was recorded to {}.", path.display()),
+            Err(error) => eprintln!("\nCould not save the recording: {error}"),
+        }
+    }
+    outcome
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,4 +1680,329 @@ mod tests {
         assert_eq!("Player 1", format!("{}", Player::One));
         assert_eq!("Player 2", format!("{}", Player::Two));
     }
+
+    #[test]
+    fn yes_no_answers_test() {
+        for answer in ["y", "Y", "yes", " YES\n"] {
+            assert_eq!(parse_yes_no(answer), Some(true));
+        }
+
+        for answer in ["n", "N", "no", "No\n"] {
+            assert_eq!(parse_yes_no(answer), Some(false));
+        }
+
+        for answer in ["", "maybe", "yy"] {
+            assert_eq!(parse_yes_no(answer), None);
+        }
+    }
+
+    #[test]
+    fn takebacks_are_forgotten_by_the_clock_and_draw_offers_test() {
+        // Player 1 offers a draw, then takes back their move and the reply.
+        // Nobody is asked about the offer after that.
+        let input = "0,0\n1,1\ndraw\nundo\ny\n0,0\n1,0\n0,1\n2,2\n0,2\n";
+        let mut console = Console::over(
+            Box::new(std::io::Cursor::new(input.as_bytes().to_vec())),
+            Box::new(std::io::sink()),
+        );
+        let mut record = GameRecord::new(Board::new());
+        let mut clock = GameClock::start();
+
+        let outcome = play(
+            &mut console,
+            &mut Opponent::Human,
+            Player::One,
+            &GameSetup::default(),
+            &mut record,
+            &mut clock,
+        );
+
+        assert_eq!(outcome, Some(Outcome::Winner(Player::One, WinReason::Line)));
+        assert_eq!(clock.moves, [3, 2]);
+        assert!(game_summary(&clock, Outcome::Draw(DrawReason::FullBoard)).contains("Moves: 5\n"));
+    }
+
+    #[test]
+    fn games_from_a_position_are_not_tracked_test() {
+        let path =
+            std::env::temp_dir().join(format!("tic-tac-toe-untracked-{}", std::process::id()));
+        let position =
+            StartPosition::new("++-/oo-/---".parse().unwrap(), Some(Symbol::Plus)).unwrap();
+        let record: GameRecord = "++-/oo-/--- +0,2".parse().unwrap();
+        let setup = GameSetup {
+            position: Some(position),
+            achievements: Some(AchievementTracker::new(
+                path.clone(),
+                BotSpec::Builtin(ai::Difficulty::Hard),
+            )),
+            ..GameSetup::default()
+        };
+        let mut console = Console::over(Box::new(std::io::empty()), Box::new(std::io::sink()));
+
+        assert!(!setup.on_equal_terms(&record));
+        setup.track_achievements(
+            &mut console,
+            &record,
+            Outcome::Winner(Player::One, WinReason::Line),
+        );
+        assert!(!path.exists());
+
+        // Nor are games resumed from a save that started from one.
+        let resumed = GameSetup {
+            position: None,
+            ..setup
+        };
+        assert!(!resumed.on_equal_terms(&record));
+        assert!(resumed.on_equal_terms(&"---/---/--- +1,1".parse().unwrap()));
+    }
+
+    #[test]
+    fn completing_a_line_loses_in_misere_test() {
+        // Player 1 completes the top row, which hands Player 2 the game.
+        let input = "0,0\n1,0\n0,1\n1,1\n0,2\n";
+        let mut console = Console::over(
+            Box::new(std::io::Cursor::new(input.as_bytes().to_vec())),
+            Box::new(std::io::sink()),
+        );
+        let setup = GameSetup {
+            misere: true,
+            ..GameSetup::default()
+        };
+
+        let outcome = play(
+            &mut console,
+            &mut Opponent::Human,
+            Player::One,
+            &setup,
+            &mut GameRecord::new(Board::new()),
+            &mut GameClock::start(),
+        );
+
+        let outcome = outcome.unwrap();
+        assert_eq!(outcome, Outcome::Winner(Player::Two, WinReason::Misere));
+        assert_eq!(
+            describe_outcome(outcome),
+            format!("{} completed a line in misère", Player::One)
+        );
+        assert_eq!(outcome_names(outcome), ("player_two", "misere"));
+    }
+
+    #[test]
+    fn rematch_question_can_step_through_the_game_test() {
+        let record: GameRecord = "---/---/--- +1,1 o0,0".parse().unwrap();
+        let console = |input: &str| {
+            Console::over(
+                Box::new(std::io::Cursor::new(input.as_bytes().to_vec())),
+                Box::new(std::io::sink()),
+            )
+        };
+
+        assert_eq!(
+            ask_rematch(&mut console("b\nn\nq\ny\n"), &record),
+            Some(true)
+        );
+        assert_eq!(
+            ask_rematch(&mut console("maybe\nn\n"), &record),
+            Some(false)
+        );
+        assert_eq!(ask_rematch(&mut console("b\n"), &record), None);
+    }
+
+    #[test]
+    fn session_score_is_carried_between_games_test() {
+        let mut score = SessionScore::default();
+        score.record(Outcome::Winner(Player::One, WinReason::Line));
+        score.record(Outcome::Draw(DrawReason::FullBoard));
+        score.record(Outcome::Draw(DrawReason::Agreement));
+        score.record(Outcome::Winner(Player::One, WinReason::Line));
+        score.record(Outcome::Winner(Player::Two, WinReason::Resignation));
+
+        assert_eq!(
+            format!("{score}"),
+            "Player 1 2 - 1 Player 2 (draws: 1, agreed draws: 1, resignations: 1)"
+        );
+    }
+
+    #[test]
+    fn resign_command_test() {
+        for input in ["resign", "Resign\n", " RESIGN "] {
+            assert_eq!(Command::parse(input), Some(Command::Resign));
+        }
+
+        assert_eq!(Command::parse("draw\n"), Some(Command::OfferDraw));
+        assert_eq!(Command::parse("HELP"), Some(Command::Help));
+
+        for input in ["resig", "1,1", ""] {
+            assert_eq!(Command::parse(input), None);
+        }
+    }
+
+    #[test]
+    fn handicaps_are_parsed_test() {
+        assert_eq!(
+            "p1:2".parse(),
+            Ok(Handicap {
+                player: Player::One,
+                kind: HandicapKind::Marks(2)
+            })
+        );
+        assert_eq!(
+            "P2:skip".parse(),
+            Ok(Handicap {
+                player: Player::Two,
+                kind: HandicapKind::ExtraMove
+            })
+        );
+
+        for invalid in ["p1", "p3:1", "p1:0", "p1:3", "p1:lots", ""] {
+            assert!(invalid.parse::<Handicap>().is_err());
+        }
+    }
+
+    #[test]
+    fn handicap_decides_who_starts_test() {
+        let marks: Handicap = "p1:1".parse().unwrap();
+        let extra_move: Handicap = "p1:skip".parse().unwrap();
+
+        assert_eq!(marks.first_player(), Player::Two);
+        assert_eq!(extra_move.first_player(), Player::One);
+        assert_eq!(format!("{marks}"), "Player 1 starts with a mark");
+        assert_eq!(format!("{extra_move}"), "Player 2 skips the first turn");
+    }
+
+    #[test]
+    fn start_positions_are_validated_test() {
+        let position = |board: &str, to_move| StartPosition::new(board.parse().unwrap(), to_move);
+
+        let inferred = position("+--/-o-/--+", None).unwrap();
+        assert_eq!(inferred.to_move, Player::Two);
+        assert_eq!(position("+--/-o-/---", None).unwrap().to_move, Player::One);
+        assert_eq!(
+            position("+--/-o-/---", Some(Symbol::Circle))
+                .unwrap()
+                .to_move,
+            Player::Two
+        );
+
+        assert!(position("+--/-o-/--+", Some(Symbol::Plus)).is_err());
+        assert!(position("++-/+--/---", None).is_err());
+        assert!(position("+++/oo-/---", None).is_err());
+        assert!(position("+o+/+oo/o+o", None).is_err());
+    }
+
+    #[test]
+    fn setup_decides_who_starts_test() {
+        let position = StartPosition::new("+--/---/---".parse().unwrap(), None).unwrap();
+        let setup = GameSetup {
+            handicap: None,
+            position: Some(position),
+            ..GameSetup::default()
+        };
+
+        assert_eq!(setup.first_player(), Player::Two);
+        assert_eq!(GameSetup::default().first_player(), Player::One);
+    }
+
+    #[test]
+    fn help_lists_every_command_test() {
+        let help = help_text(3);
+        assert!(help.contains("both from 0 to 2."));
+        assert!(help_text(5).contains("both from 0 to 4."));
+
+        for (_, name, description) in COMMANDS {
+            assert!(help.contains(name));
+            assert!(help.contains(description));
+        }
+    }
+
+    #[test]
+    fn legal_moves_are_listed_in_input_format_test() {
+        let mut board = Board::new();
+        board.tiles = [[Symbol::Plus; 3]; 3];
+        board.tiles[0][2] = Symbol::Empty;
+        board.tiles[2][1] = Symbol::Empty;
+
+        let listed = format_moves(&board.empty_positions());
+
+        assert_eq!(listed, "0,2 2,1");
+        for listed_move in listed.split(' ') {
+            assert!(parse_player_move(listed_move).is_ok());
+        }
+    }
+
+    #[test]
+    fn rejected_moves_suggest_nearby_empty_tiles_test() {
+        let mut board = Board::new();
+        board.tiles[0][0] = Symbol::Plus;
+        board.tiles[0][1] = Symbol::Circle;
+
+        assert_eq!(nearby_empty_positions(&board, [0, 0]), [[1, 0], [1, 1]]);
+        // Outside the board counts from the closest tile, here 2,2.
+        assert_eq!(
+            nearby_empty_positions(&board, [5, 9]),
+            [[1, 1], [1, 2], [2, 1], [2, 2]]
+        );
+    }
+
+    #[test]
+    fn durations_are_formatted_for_the_summary_test() {
+        assert_eq!(format_duration(Duration::from_millis(2_340)), "2.3s");
+        assert_eq!(format_duration(Duration::from_secs(65)), "1m 05s");
+    }
+
+    #[test]
+    fn think_time_is_averaged_per_player_test() {
+        let mut clock = GameClock::start();
+        clock.think_time = [Duration::from_secs(6), Duration::ZERO];
+        clock.moves = [3, 0];
+
+        assert_eq!(
+            clock.average_think_time(Player::One),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(clock.average_think_time(Player::Two), None);
+
+        let summary = game_summary(&clock, Outcome::Winner(Player::One, WinReason::Line));
+        assert!(summary.contains("Moves: 3"));
+        assert!(summary.contains("Player 1 2.0s, Player 2 no moves"));
+        assert!(summary.contains("Ended by: Player 1 completed a line"));
+    }
+
+    #[test]
+    fn results_are_written_as_json_test() {
+        let record: GameRecord = "---/---/--- +0,0 o1,0 +1,1 o2,0 +2,2".parse().unwrap();
+        let mut clock = GameClock::start();
+        clock.think_time = [Duration::from_millis(1500), Duration::from_millis(20)];
+
+        let json = result_json(
+            &record,
+            &clock,
+            Outcome::Winner(Player::One, WinReason::Line),
+        );
+        assert!(json.starts_with(
+            r#"{"start":"---/---/---","moves":[{"symbol":"+","move":[0,0]},{"symbol":"o","move":[1,0]},"#
+        ));
+        assert!(json.contains(
+            r#""final_board":"+--/o+-/o-+","result":"player_one","reason":"line","line":[[0,0],[1,1],[2,2]],"duration_ms":"#
+        ));
+        assert!(json.ends_with(r#""think_time_ms":{"player_one":1500,"player_two":20}}"#));
+
+        let json = result_json(&record, &clock, Outcome::Draw(DrawReason::Agreement));
+        assert!(json.contains(r#""result":"draw","reason":"agreement""#));
+        assert!(!json.contains('\n'));
+    }
+
+    #[test]
+    fn paused_time_is_off_the_clock_test() {
+        let mut clock = GameClock::start();
+        clock.started -= Duration::from_secs(10);
+        clock.turn_started -= Duration::from_secs(10);
+
+        clock.resumed(Duration::from_secs(8));
+        clock.moved(Player::One);
+
+        let seconds = clock.think_time[0].as_secs_f64();
+        assert!((2.0..3.0).contains(&seconds));
+        assert!((2.0..3.0).contains(&clock.duration().as_secs_f64()));
+    }
 }
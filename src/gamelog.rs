@@ -0,0 +1,54 @@
+// A log of everything that happens in a session for bug reports: every line
+// shown, every line typed and every change to the board, appended to a file
+// as it happens so it survives a crash.
+//
+// One event per line, tab separated: the milliseconds since the session
+// started, the kind of event and the text with newlines escaped as in
+// recordings. A session starts with a "session" event holding the Unix time.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::recording::escape;
+
+// Shared by every console of the session, so games and menus log to one file.
+#[derive(Clone)]
+pub struct GameLog {
+    file: Arc<Mutex<File>>,
+    started: Instant,
+}
+
+impl GameLog {
+    pub fn open(path: &Path) -> Result<GameLog, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|error| format!("Could not open the game log {}: {error}", path.display()))?;
+        let log = GameLog {
+            file: Arc::new(Mutex::new(file)),
+            started: Instant::now(),
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        log.event("session", &now.as_secs().to_string());
+        Ok(log)
+    }
+
+    // A log that cannot be written to should not end the game, so errors are dropped.
+    pub fn event(&self, kind: &str, text: &str) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(
+                file,
+                "{}\t{kind}\t{}",
+                self.started.elapsed().as_millis(),
+                escape(text)
+            );
+        }
+    }
+}
@@ -0,0 +1,171 @@
+// Checks that the rules agree with each other on a board or a game, for
+// fuzzing and property tests: the different ways of finding a winner, the
+// empty tiles and move validation, and parsing what is displayed.
+
+use alloc::format;
+use alloc::string::String;
+
+use crate::board::{Board, GameStatus, PlayerMoveError, Symbol};
+
+// Checks any board, whether or not it can come up in a game.
+pub fn check_board<const N: usize>(board: &Board<N>) -> Result<(), String> {
+    let empty = board
+        .tiles
+        .iter()
+        .flatten()
+        .filter(|tile| **tile == Symbol::Empty)
+        .count();
+    if board.empty_positions().len() != empty || board.empty_tiles().count() != empty {
+        return Err(format!("{board:#} does not list its {empty} empty tiles."));
+    }
+    if board.is_full() != (empty == 0) {
+        return Err(format!("{board:#} is_full disagrees with its empty tiles."));
+    }
+
+    match board.status() {
+        GameStatus::Won(symbol, line) => {
+            if line
+                .iter()
+                .any(|[row, column]| board.tiles[*row][*column] != symbol)
+            {
+                return Err(format!("{board:#} reports a line that is not complete."));
+            }
+            if !line.iter().all(|position| board.wins_through(*position)) {
+                return Err(format!("{board:#} has a line wins_through misses."));
+            }
+            if board.winner() != Some(symbol) {
+                return Err(format!("{board:#} disagrees on the winner."));
+            }
+        }
+        GameStatus::Draw if empty > 0 => {
+            return Err(format!("{board:#} is a draw with empty tiles."));
+        }
+        GameStatus::InProgress if empty == 0 => {
+            return Err(format!("{board:#} is full but still in progress."));
+        }
+        GameStatus::Draw | GameStatus::InProgress => {
+            if board.winner().is_some() {
+                return Err(format!("{board:#} has a winner but no winning line."));
+            }
+        }
+    }
+
+    for (row, tiles) in board.tiles.iter().enumerate() {
+        for (column, tile) in tiles.iter().enumerate() {
+            let valid = board.is_valid_move([row, column]);
+            let expected_valid = *tile == Symbol::Empty;
+            if valid.is_ok() != expected_valid
+                || matches!(valid, Err(PlayerMoveError::OutsideBoard { .. }))
+            {
+                return Err(format!(
+                    "{board:#} misjudges the move {row},{column}: {valid:?}."
+                ));
+            }
+        }
+    }
+    if !matches!(
+        board.is_valid_move([N, 0]),
+        Err(PlayerMoveError::OutsideBoard { .. })
+    ) {
+        return Err(format!("{board:#} accepts a move below the board."));
+    }
+
+    match format!("{board:#}").parse::<Board<N>>() {
+        Ok(parsed) if parsed.tiles == board.tiles => Ok(()),
+        _ => Err(format!(
+            "{board:#} does not parse back from its compact form."
+        )),
+    }
+}
+
+// Replays moves from an empty board, checking every position along the way
+// and that each move is legal, before the end, and wins exactly when the
+// game says so. Returns the final board.
+pub fn check_game<const N: usize>(moves: &[(Symbol, [usize; 2])]) -> Result<Board<N>, String> {
+    let mut board = Board::<N>::empty();
+
+    for (i, (symbol, position)) in moves.iter().enumerate() {
+        if board.status() != GameStatus::InProgress {
+            return Err(format!(
+                "Move {} is played after {board:#} ended the game.",
+                i + 1
+            ));
+        }
+        if let Err(error) = board.is_valid_move(*position) {
+            return Err(format!("Move {} is illegal on {board:#}: {error}", i + 1));
+        }
+
+        let won = board.place(*symbol, *position);
+        check_board(&board)?;
+        board
+            .validate()
+            .map_err(|error| format!("Move {} reached {board:#}: {error}", i + 1))?;
+        if won != matches!(board.status(), GameStatus::Won(..)) {
+            return Err(format!(
+                "Move {} disagrees with the status of {board:#}.",
+                i + 1
+            ));
+        }
+    }
+
+    Ok(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    // A tiny xorshift, since the random number generator lives in the game.
+    fn random_games<const N: usize>(
+        games: usize,
+    ) -> impl Iterator<Item = Vec<(Symbol, [usize; 2])>> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        (0..games).map(move |_| {
+            let mut board = Board::<N>::empty();
+            let mut moves = Vec::new();
+            let mut symbol = Symbol::Plus;
+
+            while board.status() == GameStatus::InProgress {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let empty = board.empty_positions();
+                let position = empty[usize::try_from(state).unwrap() % empty.len()];
+                board.place(symbol, position);
+                moves.push((symbol, position));
+                symbol = symbol.opponent();
+            }
+            moves
+        })
+    }
+
+    #[test]
+    fn random_games_keep_the_invariants() {
+        for moves in random_games::<3>(300) {
+            check_game::<3>(&moves).unwrap();
+        }
+        for moves in random_games::<4>(50) {
+            check_game::<4>(&moves).unwrap();
+        }
+    }
+
+    #[test]
+    fn broken_games_are_caught() {
+        let moves = [
+            (Symbol::Plus, [0, 0]),
+            (Symbol::Circle, [1, 0]),
+            (Symbol::Plus, [0, 1]),
+            (Symbol::Circle, [1, 1]),
+            (Symbol::Plus, [0, 2]),
+        ];
+        assert!(check_game::<3>(&moves).is_ok());
+
+        let after_the_end = [&moves[..], &[(Symbol::Circle, [1, 2])]].concat();
+        assert!(check_game::<3>(&after_the_end).is_err());
+        let twice = [(Symbol::Plus, [1, 1]), (Symbol::Circle, [1, 1])];
+        assert!(check_game::<3>(&twice).is_err());
+        let out_of_turn = [(Symbol::Plus, [1, 1]), (Symbol::Plus, [0, 0])];
+        assert!(check_game::<3>(&out_of_turn).is_err());
+    }
+}
@@ -0,0 +1,712 @@
+// An IRC bot that idles in a channel and referees games there. Players play
+// each other with !challenge <nick> and !accept, or the bot itself with a bare
+// !challenge. In crowd mode the whole channel plays the bot instead, voting
+// for each move. Squares are named like a chess board: columns a to c from the
+// left, rows 1 to 3 from the top.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::board::{Board, GameStatus, Symbol};
+use crate::bot::{Bot, BotSpec};
+use crate::game::parse_player_move;
+use crate::rng::Rng;
+use crate::vote::{self, CrowdGame, Round, Tiebreak};
+
+const HELP: &str = "Commands: !challenge [nick] (no nick to play me), !accept, \
+!move <square> e.g. !move b2, !board, !resign, !help";
+
+const CROWD_HELP: &str = "The channel plays me together: !vote <square> e.g. !vote b2 \
+(or !move b2), !board, !help. Voting closes a while after the first vote.";
+
+#[derive(Debug, PartialEq)]
+pub struct IrcOptions {
+    // host:port of the IRC server.
+    pub server: String,
+    pub channel: String,
+    pub nick: String,
+    // The opponent for a bare !challenge.
+    pub bot: BotSpec,
+    // The voting window when the channel plays the bot as a crowd.
+    pub crowd: Option<Duration>,
+    pub tiebreak: Tiebreak,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ChatCommand {
+    Challenge(Option<String>),
+    Accept,
+    Move(String),
+    Vote(String),
+    Board,
+    Resign,
+    Help,
+}
+
+impl ChatCommand {
+    pub fn parse(text: &str) -> Option<ChatCommand> {
+        let mut words = text.split_whitespace();
+        let command = words.next()?.strip_prefix('!')?.to_lowercase();
+        let argument = words.next().map(ToString::to_string);
+
+        match (command.as_str(), argument) {
+            ("challenge", nick) => Some(ChatCommand::Challenge(nick)),
+            ("accept", _) => Some(ChatCommand::Accept),
+            ("move", Some(square)) => Some(ChatCommand::Move(square)),
+            ("vote", Some(square)) => Some(ChatCommand::Vote(square)),
+            ("board", _) => Some(ChatCommand::Board),
+            ("resign", _) => Some(ChatCommand::Resign),
+            ("help" | "move" | "vote", _) => Some(ChatCommand::Help),
+            _ => None,
+        }
+    }
+}
+
+// "b2" style squares, with the game's own "row,column" form accepted too.
+pub fn parse_square(square: &str) -> Option<[usize; 2]> {
+    let mut chars = square.chars();
+    if let (Some(column @ 'a'..='c'), Some(row @ '1'..='3'), None) = (
+        chars.next().map(|c| c.to_ascii_lowercase()),
+        chars.next(),
+        chars.next(),
+    ) {
+        return Some([row as usize - '1' as usize, column as usize - 'a' as usize]);
+    }
+
+    parse_player_move(square).ok()
+}
+
+pub fn square_name([row, column]: [usize; 2]) -> String {
+    let column = char::from(b'a' + u8::try_from(column).unwrap_or(0));
+    format!("{column}{}", row + 1)
+}
+
+// One message per line, with dots for empty squares so the columns stay
+// aligned in proportional fonts too.
+fn render(board: &Board) -> Vec<String> {
+    let mut lines = vec![String::from("   a b c")];
+
+    for (row, tiles) in board.tiles.iter().enumerate() {
+        let tiles = tiles
+            .iter()
+            .map(|tile| match tile {
+                Symbol::Empty => ".",
+                symbol => (*symbol).into(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        lines.push(format!("{}  {tiles}", row + 1));
+    }
+
+    lines
+}
+
+struct ChannelGame {
+    // Plus moves first and is always the challenger.
+    plus: String,
+    // None when playing against the bot.
+    circle: Option<String>,
+    board: Board,
+    to_move: Symbol,
+}
+
+impl ChannelGame {
+    fn player(&self, symbol: Symbol) -> Option<&str> {
+        match symbol {
+            Symbol::Plus => Some(&self.plus),
+            _ => self.circle.as_deref(),
+        }
+    }
+
+    fn symbol_of(&self, nick: &str) -> Option<Symbol> {
+        if self.plus == nick {
+            Some(Symbol::Plus)
+        } else if self.circle.as_deref() == Some(nick) {
+            Some(Symbol::Circle)
+        } else {
+            None
+        }
+    }
+}
+
+// The games of one channel: at most one running and one open challenge.
+pub struct Table {
+    nick: String,
+    bot: Box<dyn Bot>,
+    game: Option<ChannelGame>,
+    // The challenger and the player they challenged.
+    challenge: Option<(String, String)>,
+}
+
+impl Table {
+    pub fn new(nick: &str, bot: Box<dyn Bot>) -> Table {
+        Table {
+            nick: nick.to_string(),
+            bot,
+            game: None,
+            challenge: None,
+        }
+    }
+
+    // Handles a message from sender and returns the lines to say in reply.
+    fn handle(&mut self, sender: &str, text: &str) -> Vec<String> {
+        let Some(command) = ChatCommand::parse(text) else {
+            return Vec::new();
+        };
+
+        match command {
+            ChatCommand::Help => vec![HELP.to_string()],
+            ChatCommand::Challenge(opponent) => self.challenge(sender, opponent),
+            ChatCommand::Accept => self.accept(sender),
+            ChatCommand::Board => match &self.game {
+                Some(game) => render(&game.board),
+                None => vec![String::from(
+                    "No game is running, start one with !challenge.",
+                )],
+            },
+            ChatCommand::Resign => match self.game.as_ref().and_then(|game| game.symbol_of(sender))
+            {
+                Some(symbol) => {
+                    let game = self.game.take().expect("the game was just looked up");
+                    let winner = game.player(symbol.opponent()).unwrap_or(&self.nick);
+                    vec![format!("{sender} resigns. {winner} wins!")]
+                }
+                None => vec![format!("{sender}, you are not playing.")],
+            },
+            ChatCommand::Move(square) | ChatCommand::Vote(square) => self.play(sender, &square),
+        }
+    }
+
+    fn challenge(&mut self, sender: &str, opponent: Option<String>) -> Vec<String> {
+        if let Some(game) = &self.game {
+            let circle = game.circle.as_deref().unwrap_or(&self.nick);
+            return vec![format!(
+                "{} and {circle} are still playing, wait for their game to end.",
+                game.plus
+            )];
+        }
+
+        match opponent {
+            Some(opponent) if opponent == sender => {
+                vec![String::from("You cannot challenge yourself.")]
+            }
+            Some(opponent) if opponent != self.nick => {
+                let reply =
+                    format!("{sender} challenges {opponent}! {opponent}, type !accept to play.");
+                self.challenge = Some((sender.to_string(), opponent));
+                vec![reply]
+            }
+            _ => {
+                self.challenge = None;
+                self.start(sender.to_string(), None)
+            }
+        }
+    }
+
+    fn accept(&mut self, sender: &str) -> Vec<String> {
+        match self.challenge.take() {
+            Some((challenger, invited)) if invited == sender && self.game.is_none() => {
+                self.start(challenger, Some(invited))
+            }
+            challenge => {
+                self.challenge = challenge;
+                vec![format!("{sender}, nobody has challenged you.")]
+            }
+        }
+    }
+
+    fn start(&mut self, plus: String, circle: Option<String>) -> Vec<String> {
+        let opponent = circle.clone().unwrap_or_else(|| self.nick.clone());
+        let mut reply = vec![format!(
+            "New game: {plus} (+) against {opponent} (o). {plus} moves first."
+        )];
+        let board = Board::new();
+        reply.extend(render(&board));
+
+        self.game = Some(ChannelGame {
+            plus,
+            circle,
+            board,
+            to_move: Symbol::Plus,
+        });
+        reply
+    }
+
+    fn play(&mut self, sender: &str, square: &str) -> Vec<String> {
+        let Some(game) = &mut self.game else {
+            return vec![String::from(
+                "No game is running, start one with !challenge.",
+            )];
+        };
+        if game.player(game.to_move) != Some(sender) {
+            return match game.symbol_of(sender) {
+                Some(_) => vec![format!("{sender}, it is not your turn.")],
+                None => vec![format!("{sender}, you are not playing.")],
+            };
+        }
+        let Some(position) = parse_square(square) else {
+            return vec![format!("'{square}' is not a square, use a1 to c3.")];
+        };
+        // parse_square only gives squares on the board, so the square is taken.
+        if game.board.is_valid_move(position).is_err() {
+            let [row, column] = position;
+            let symbol: &str = game.board.tiles[row][column].into();
+            return vec![format!(
+                "{} is already taken by {symbol}.",
+                square_name(position)
+            )];
+        }
+
+        let mut reply = Vec::new();
+        game.board.place(game.to_move, position);
+        game.to_move = game.to_move.opponent();
+
+        // The bot answers straight away.
+        if game.circle.is_none() && game.board.status() == GameStatus::InProgress {
+            match self.bot.choose_move(&game.board, Symbol::Circle) {
+                Some(reply_move) if game.board.is_valid_move(reply_move).is_ok() => {
+                    game.board.place(Symbol::Circle, reply_move);
+                    game.to_move = Symbol::Plus;
+                    reply.push(format!("{} plays {}.", self.nick, square_name(reply_move)));
+                }
+                _ => {
+                    self.game = None;
+                    return vec![format!("I have no move to play, {sender} wins!")];
+                }
+            }
+        }
+
+        reply.extend(render(&game.board));
+        match game.board.status() {
+            GameStatus::Won(symbol, _) => {
+                let winner = game.player(symbol).unwrap_or(&self.nick).to_string();
+                reply.push(format!("{winner} wins!"));
+                self.game = None;
+            }
+            GameStatus::Draw => {
+                reply.push(String::from("The board is full, it's a draw!"));
+                self.game = None;
+            }
+            GameStatus::InProgress => {
+                let next = game.player(game.to_move).unwrap_or(&self.nick);
+                reply.push(format!("{next} to move."));
+            }
+        }
+        reply
+    }
+}
+
+// The channel playing the bot, one vote per player and move.
+pub struct Crowd {
+    nick: String,
+    game: CrowdGame,
+}
+
+impl Crowd {
+    pub fn new(nick: &str, game: CrowdGame) -> Crowd {
+        Crowd {
+            nick: nick.to_string(),
+            game,
+        }
+    }
+
+    fn handle(&mut self, sender: &str, text: &str, now: Instant) -> Vec<String> {
+        let Some(command) = ChatCommand::parse(text) else {
+            return Vec::new();
+        };
+
+        match command {
+            ChatCommand::Help => vec![CROWD_HELP.to_string()],
+            ChatCommand::Board => {
+                let mut lines = render(self.game.board());
+                lines.extend(self.tally());
+                lines
+            }
+            ChatCommand::Move(square) | ChatCommand::Vote(square) => {
+                let Some(position) = parse_square(&square) else {
+                    return vec![format!("'{square}' is not a square, use a1 to c3.")];
+                };
+                match self.game.vote(sender, position, now) {
+                    Ok(true) => {
+                        let seconds = self
+                            .game
+                            .closes()
+                            .map_or(0, |closes| closes.duration_since(now).as_secs());
+                        vec![format!(
+                            "Voting is open for {seconds} seconds, vote with !vote <square>."
+                        )]
+                    }
+                    Ok(false) => Vec::new(),
+                    Err(_) => vec![format!(
+                        "{sender}, {} is already taken.",
+                        square_name(position)
+                    )],
+                }
+            }
+            ChatCommand::Challenge(_) | ChatCommand::Accept | ChatCommand::Resign => {
+                vec![format!(
+                    "The channel is playing {} together, vote with !vote <square>.",
+                    self.nick
+                )]
+            }
+        }
+    }
+
+    fn tally(&self) -> Vec<String> {
+        vote::format_tally(&self.game.tally(), square_name)
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    // Plays the crowd's move once voting has closed.
+    fn tick(&mut self, now: Instant) -> Vec<String> {
+        let Some(Round {
+            tally,
+            played,
+            reply,
+            board,
+            status,
+            forfeit,
+        }) = self.game.close(now)
+        else {
+            return Vec::new();
+        };
+
+        let mut lines = vec![String::from("Votes:")];
+        lines.extend(
+            vote::format_tally(&tally, square_name)
+                .lines()
+                .map(String::from),
+        );
+        lines.push(format!("The crowd plays {}.", square_name(played)));
+        if let Some(reply) = reply {
+            lines.push(format!("{} plays {}.", self.nick, square_name(reply)));
+        }
+        lines.extend(render(&board));
+        lines.push(match status {
+            _ if forfeit => format!(
+                "{} has no move to play, the crowd wins! Vote for the first move of a new game.",
+                self.nick
+            ),
+            GameStatus::Won(Symbol::Plus, _) => {
+                String::from("The crowd wins! Vote for the first move of a new game.")
+            }
+            GameStatus::Won(..) => {
+                format!("{} wins! Vote for the first move of a new game.", self.nick)
+            }
+            GameStatus::Draw => String::from(
+                "The board is full, it's a draw! Vote for the first move of a new game.",
+            ),
+            GameStatus::InProgress => String::from("Vote for the next move with !vote <square>."),
+        });
+        lines
+    }
+}
+
+// The parts of an IRC line the bot cares about.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Message<'a> {
+    // The nick in the prefix, if there is one.
+    pub sender: Option<&'a str>,
+    pub command: &'a str,
+    pub params: Vec<&'a str>,
+}
+
+impl<'a> Message<'a> {
+    pub fn parse(line: &'a str) -> Option<Message<'a>> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let (prefix, rest) = match line.strip_prefix(':') {
+            Some(rest) => {
+                let (prefix, rest) = rest.split_once(' ')?;
+                (Some(prefix), rest)
+            }
+            None => (None, line),
+        };
+
+        let (middle, trailing) = match rest.split_once(" :") {
+            Some((middle, trailing)) => (middle, Some(trailing)),
+            None => (rest, None),
+        };
+        let mut words = middle.split_whitespace();
+        let command = words.next()?;
+        let mut params = words.collect::<Vec<_>>();
+        params.extend(trailing);
+
+        Some(Message {
+            sender: prefix.map(|prefix| prefix.split('!').next().unwrap_or(prefix)),
+            command,
+            params,
+        })
+    }
+}
+
+pub fn send(stream: &mut TcpStream, line: &str) -> io::Result<()> {
+    write!(stream, "{line}\r\n")
+}
+
+// Reads the server's lines on their own thread, so votes can close while
+// the channel is quiet.
+pub fn read_lines(stream: &TcpStream) -> io::Result<Receiver<io::Result<String>>> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in reader.lines() {
+            if sender.send(line).is_err() {
+                return;
+            }
+        }
+    });
+    Ok(lines)
+}
+
+// The next line, or a timeout once until has passed.
+pub fn next_line(
+    lines: &Receiver<io::Result<String>>,
+    until: Option<Instant>,
+) -> Result<io::Result<String>, RecvTimeoutError> {
+    match until {
+        Some(until) => lines.recv_timeout(until.saturating_duration_since(Instant::now())),
+        None => lines.recv().map_err(|_| RecvTimeoutError::Disconnected),
+    }
+}
+
+// Connects, joins the channel and plays until the server closes the connection.
+pub fn run(options: &IrcOptions) -> Result<(), String> {
+    let failed = |error: io::Error| format!("IRC connection to {} failed: {error}", options.server);
+    let mut stream = TcpStream::connect(&options.server).map_err(failed)?;
+    let lines = read_lines(&stream).map_err(failed)?;
+    let mut nick = options.nick.clone();
+    let mut table = Table::new(&nick, options.bot.load()?);
+    let mut crowd = match options.crowd {
+        Some(window) => Some(Crowd::new(
+            &nick,
+            CrowdGame::new(
+                options.bot.load()?,
+                window,
+                options.tiebreak,
+                Rng::from_time(),
+            ),
+        )),
+        None => None,
+    };
+
+    send(&mut stream, &format!("NICK {nick}")).map_err(failed)?;
+    send(&mut stream, &format!("USER {nick} 0 * :tic-tac-toe-rust")).map_err(failed)?;
+
+    loop {
+        let closes = crowd.as_ref().and_then(|crowd| crowd.game.closes());
+        let line = next_line(&lines, closes);
+        if let Some(crowd) = &mut crowd {
+            for reply in crowd.tick(Instant::now()) {
+                send(
+                    &mut stream,
+                    &format!("PRIVMSG {} :{reply}", options.channel),
+                )
+                .map_err(failed)?;
+            }
+        }
+        let line = match line {
+            Ok(line) => line.map_err(failed)?,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let Some(message) = Message::parse(&line) else {
+            continue;
+        };
+
+        match (message.command, message.params.as_slice()) {
+            ("PING", params) => send(&mut stream, &format!("PONG :{}", params.join(" "))),
+            // Welcome, registration is done.
+            ("001", _) => {
+                println!("Connected to {} as {nick}.", options.server);
+                send(&mut stream, &format!("JOIN {}", options.channel))
+            }
+            // Nickname in use.
+            ("433", _) => {
+                nick.push('_');
+                table.nick.clone_from(&nick);
+                if let Some(crowd) = &mut crowd {
+                    crowd.nick.clone_from(&nick);
+                }
+                send(&mut stream, &format!("NICK {nick}"))
+            }
+            ("PRIVMSG", [target, text]) if target.eq_ignore_ascii_case(&options.channel) => {
+                let sender = message.sender.unwrap_or_default();
+                match &mut crowd {
+                    Some(crowd) => crowd.handle(sender, text, Instant::now()),
+                    None => table.handle(sender, text),
+                }
+                .iter()
+                .try_for_each(|reply| send(&mut stream, &format!("PRIVMSG {target} :{reply}")))
+            }
+            _ => Ok(()),
+        }
+        .map_err(failed)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::bot::BuiltinBot;
+    use crate::rng::Rng;
+
+    fn table() -> Table {
+        Table::new(
+            "tictacbot",
+            Box::new(BuiltinBot::new(Difficulty::Hard, Rng::new(1))),
+        )
+    }
+
+    #[test]
+    fn commands_and_squares_are_parsed() {
+        assert_eq!(
+            ChatCommand::parse("!challenge bob"),
+            Some(ChatCommand::Challenge(Some(String::from("bob"))))
+        );
+        assert_eq!(
+            ChatCommand::parse("!Challenge"),
+            Some(ChatCommand::Challenge(None))
+        );
+        assert_eq!(
+            ChatCommand::parse("!move b2"),
+            Some(ChatCommand::Move(String::from("b2")))
+        );
+        assert_eq!(ChatCommand::parse("!move"), Some(ChatCommand::Help));
+        assert_eq!(ChatCommand::parse("hello !move b2"), None);
+
+        assert_eq!(parse_square("a1"), Some([0, 0]));
+        assert_eq!(parse_square("C2"), Some([1, 2]));
+        assert_eq!(parse_square("2,1"), Some([2, 1]));
+        assert_eq!(parse_square("d1"), None);
+        assert_eq!(square_name([1, 2]), "c2");
+    }
+
+    #[test]
+    fn irc_lines_are_parsed() {
+        assert_eq!(
+            Message::parse(":alice!a@example.org PRIVMSG #ttt :!move b2\r\n"),
+            Some(Message {
+                sender: Some("alice"),
+                command: "PRIVMSG",
+                params: vec!["#ttt", "!move b2"],
+            })
+        );
+        assert_eq!(
+            Message::parse("PING :irc.example.org"),
+            Some(Message {
+                sender: None,
+                command: "PING",
+                params: vec!["irc.example.org"],
+            })
+        );
+        assert_eq!(Message::parse(""), None);
+    }
+
+    #[test]
+    fn players_must_accept_and_move_in_turn() {
+        let mut table = table();
+
+        assert!(table.handle("alice", "!challenge bob")[0].contains("bob, type !accept"));
+        assert_eq!(
+            table.handle("carol", "!accept"),
+            vec!["carol, nobody has challenged you."]
+        );
+        assert!(
+            table.handle("bob", "!accept")[0].starts_with("New game: alice (+) against bob (o)")
+        );
+
+        assert_eq!(
+            table.handle("bob", "!move a1"),
+            vec!["bob, it is not your turn."]
+        );
+        assert_eq!(
+            table.handle("carol", "!move a1"),
+            vec!["carol, you are not playing."]
+        );
+        for (player, square) in [
+            ("alice", "a1"),
+            ("bob", "a2"),
+            ("alice", "b1"),
+            ("bob", "b2"),
+        ] {
+            assert!(table
+                .handle(player, &format!("!move {square}"))
+                .last()
+                .unwrap()
+                .ends_with("to move."));
+        }
+        assert_eq!(
+            table.handle("alice", "!move b2"),
+            vec!["b2 is already taken by o."]
+        );
+
+        let reply = table.handle("alice", "!move c1");
+        assert_eq!(reply[1], "1  + + +");
+        assert_eq!(reply.last().unwrap(), "alice wins!");
+        assert!(table.game.is_none());
+    }
+
+    #[test]
+    fn the_crowd_votes_for_its_moves() {
+        let window = Duration::from_secs(10);
+        let mut crowd = Crowd::new(
+            "tictacbot",
+            CrowdGame::new(
+                Box::new(BuiltinBot::new(Difficulty::Hard, Rng::new(1))),
+                window,
+                Tiebreak::First,
+                Rng::new(1),
+            ),
+        );
+        let start = Instant::now();
+
+        assert_eq!(
+            crowd.handle("alice", "!vote b2", start),
+            vec!["Voting is open for 10 seconds, vote with !vote <square>."]
+        );
+        assert!(crowd.handle("bob", "!move a1", start).is_empty());
+        assert!(crowd.handle("carol", "!vote b2", start).is_empty());
+        assert!(crowd.handle("bob", "!challenge", start)[0].contains("playing tictacbot"));
+        assert_eq!(
+            crowd.handle("dave", "!board", start)[4..],
+            ["b2 ## 2", "a1 # 1"]
+        );
+        assert!(crowd.tick(start).is_empty());
+
+        let lines = crowd.tick(start + window);
+        assert_eq!(
+            lines[..4],
+            ["Votes:", "b2 ## 2", "a1 # 1", "The crowd plays b2."]
+        );
+        assert!(lines[4].starts_with("tictacbot plays "));
+        assert_eq!(
+            lines.last().unwrap(),
+            "Vote for the next move with !vote <square>."
+        );
+        assert!(crowd.handle("alice", "!vote b2", start)[0].contains("b2 is already taken"));
+    }
+
+    #[test]
+    fn the_bot_answers_every_move() {
+        let mut table = table();
+        table.handle("alice", "!challenge");
+
+        let reply = table.handle("alice", "!move a1");
+        assert_eq!(reply[0], "tictacbot plays b2.");
+        assert_eq!(reply.last().unwrap(), "alice to move.");
+
+        assert_eq!(
+            table.handle("alice", "!resign"),
+            vec!["alice resigns. tictacbot wins!"]
+        );
+        assert!(table.game.is_none());
+    }
+}
@@ -0,0 +1,300 @@
+// Ways of typing at the move prompt. The standard keys are a move as
+// "row,column" or a command's name. The vim keys move a cursor with h, j, k
+// and l, place with x, take back with u and take : commands such as :w, :q
+// and :hint. Input is still read a line at a time, so keys are sent with
+// enter, e.g. "2lx" to place two tiles to the right of the cursor. Every vim
+// key goes through Bindings, so the settings can remap them.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Keys {
+    #[default]
+    Standard,
+    Vim,
+}
+
+pub const KEYS: [Keys; 2] = [Keys::Standard, Keys::Vim];
+
+impl Display for Keys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Keys::Standard => "standard",
+            Keys::Vim => "vim",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Keys {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        KEYS.into_iter()
+            .find(|keys| keys.to_string() == s)
+            .ok_or_else(|| format!("'{s}' is not a keybinding, use standard or vim."))
+    }
+}
+
+// What a vim key can be bound to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Action {
+    Left,
+    Down,
+    Up,
+    Right,
+    Place,
+    Undo,
+    Hint,
+    Quit,
+}
+
+pub const ACTIONS: [Action; 8] = [
+    Action::Left,
+    Action::Down,
+    Action::Up,
+    Action::Right,
+    Action::Place,
+    Action::Undo,
+    Action::Hint,
+    Action::Quit,
+];
+
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Action::Left => "left",
+            Action::Down => "down",
+            Action::Up => "up",
+            Action::Right => "right",
+            Action::Place => "place",
+            Action::Undo => "undo",
+            Action::Hint => "hint",
+            Action::Quit => "quit",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ACTIONS
+            .into_iter()
+            .find(|action| action.to_string() == s)
+            .ok_or_else(|| {
+                format!("'{s}' is not a key action, use left, down, up, right, place, undo, hint or quit.")
+            })
+    }
+}
+
+// The key of every action, in the order of ACTIONS.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Bindings([char; 8]);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings(['h', 'j', 'k', 'l', 'x', 'u', '?', 'Q'])
+    }
+}
+
+impl Bindings {
+    pub fn key(&self, action: Action) -> char {
+        self.0[action as usize]
+    }
+
+    pub fn action(&self, key: char) -> Option<Action> {
+        ACTIONS.into_iter().find(|action| self.key(*action) == key)
+    }
+
+    // Digits are counts and : starts a command, so neither can be bound,
+    // and a key is bound to one action at most.
+    pub fn bind(&mut self, action: Action, key: char) -> Result<(), String> {
+        if key.is_ascii_digit() || key == ':' || key.is_whitespace() {
+            return Err(format!("'{key}' cannot be bound, digits and : are taken."));
+        }
+        if let Some(other) = self.action(key).filter(|other| *other != action) {
+            return Err(format!("'{key}' is already the key for {other}."));
+        }
+        self.0[action as usize] = key;
+        Ok(())
+    }
+
+    // The actions bound to another key than by default.
+    pub fn changed(&self) -> impl Iterator<Item = (Action, char)> + '_ {
+        ACTIONS
+            .into_iter()
+            .map(|action| (action, self.key(action)))
+            .filter(|(action, key)| Bindings::default().key(*action) != *key)
+    }
+}
+
+// The keys in the order of ACTIONS, e.g. "h j k l x u ? Q".
+impl Display for Bindings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keys = self.0.map(String::from);
+        write!(f, "{}", keys.join(" "))
+    }
+}
+
+// What a line of vim keys asks for, once the cursor has moved.
+#[derive(Debug, PartialEq, Eq)]
+pub enum VimAction {
+    Place([usize; 2]),
+    Undo,
+    Save,
+    Quit,
+    SaveAndQuit,
+    // Any other : command, e.g. "hint" or "resign".
+    Command(String),
+}
+
+// Moves the cursor on a board of size tiles a side by the keys in line and
+// returns what they end with, None when they only moved the cursor. A count
+// before a movement key repeats it, as in vim.
+pub fn parse_vim(
+    line: &str,
+    cursor: &mut [usize; 2],
+    size: usize,
+    bindings: &Bindings,
+) -> Result<Option<VimAction>, String> {
+    let line = line.trim();
+    if let Some(command) = line.strip_prefix(':') {
+        return Ok(Some(match command.trim() {
+            "w" => VimAction::Save,
+            "q" | "q!" => VimAction::Quit,
+            "wq" | "x" => VimAction::SaveAndQuit,
+            other => VimAction::Command(other.to_string()),
+        }));
+    }
+
+    let mut count = 0usize;
+    for key in line.chars().filter(|key| !key.is_whitespace()) {
+        if let Some(digit) = key.to_digit(10) {
+            count = count.saturating_mul(10).saturating_add(digit as usize);
+            continue;
+        }
+        let steps = count.max(1);
+        count = 0;
+        match bindings.action(key) {
+            Some(Action::Left) => cursor[1] = cursor[1].saturating_sub(steps),
+            Some(Action::Right) => cursor[1] = (cursor[1] + steps).min(size - 1),
+            Some(Action::Up) => cursor[0] = cursor[0].saturating_sub(steps),
+            Some(Action::Down) => cursor[0] = (cursor[0] + steps).min(size - 1),
+            Some(Action::Place) => return Ok(Some(VimAction::Place(*cursor))),
+            Some(Action::Undo) => return Ok(Some(VimAction::Undo)),
+            Some(Action::Hint) => return Ok(Some(VimAction::Command(String::from("hint")))),
+            Some(Action::Quit) => return Ok(Some(VimAction::Quit)),
+            None => {
+                let bound = |action| bindings.key(action);
+                return Err(format!(
+                    "'{key}' is not a key, use {}, {}, {} and {} to move, {} to place, {} to undo or : commands.",
+                    bound(Action::Left),
+                    bound(Action::Down),
+                    bound(Action::Up),
+                    bound(Action::Right),
+                    bound(Action::Place),
+                    bound(Action::Undo)
+                ));
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_round_trip() {
+        for keys in KEYS {
+            assert_eq!(keys.to_string().parse(), Ok(keys));
+        }
+        assert!("emacs".parse::<Keys>().is_err());
+    }
+
+    #[test]
+    fn the_cursor_moves_within_the_board() {
+        let mut cursor = [1, 1];
+        assert_eq!(
+            parse_vim("l", &mut cursor, 3, &Bindings::default()),
+            Ok(None)
+        );
+        assert_eq!(cursor, [1, 2]);
+        assert_eq!(
+            parse_vim("l k", &mut cursor, 3, &Bindings::default()),
+            Ok(None)
+        );
+        assert_eq!(cursor, [0, 2]);
+        assert_eq!(
+            parse_vim("2jhx", &mut cursor, 3, &Bindings::default()),
+            Ok(Some(VimAction::Place([2, 1])))
+        );
+        assert_eq!(
+            parse_vim("9h", &mut cursor, 3, &Bindings::default()),
+            Ok(None)
+        );
+        assert_eq!(cursor, [2, 0]);
+        assert!(parse_vim("jq", &mut cursor, 3, &Bindings::default()).is_err());
+    }
+
+    #[test]
+    fn remapped_keys_go_through_the_bindings() {
+        let mut bindings = Bindings::default();
+        bindings.bind(Action::Left, 'a').unwrap();
+        assert!(bindings.bind(Action::Place, ' ').is_err());
+        assert!(bindings.bind(Action::Right, 'a').is_err());
+        assert!(bindings.bind(Action::Hint, '5').is_err());
+        assert_eq!(
+            bindings.changed().collect::<Vec<_>>(),
+            [(Action::Left, 'a')]
+        );
+        assert_eq!(bindings.to_string(), "a j k l x u ? Q");
+
+        let mut cursor = [1, 1];
+        assert_eq!(parse_vim("a", &mut cursor, 3, &bindings), Ok(None));
+        assert_eq!(cursor, [1, 0]);
+        assert!(parse_vim("h", &mut cursor, 3, &bindings)
+            .unwrap_err()
+            .contains("use a, j, k and l to move"));
+        assert_eq!(
+            parse_vim("?", &mut cursor, 3, &bindings),
+            Ok(Some(VimAction::Command(String::from("hint"))))
+        );
+        assert_eq!(
+            parse_vim("Q", &mut cursor, 3, &bindings),
+            Ok(Some(VimAction::Quit))
+        );
+        assert_eq!("undo".parse(), Ok(Action::Undo));
+    }
+
+    #[test]
+    fn colon_commands_and_undo() {
+        let mut cursor = [1, 1];
+        assert_eq!(
+            parse_vim("u", &mut cursor, 3, &Bindings::default()),
+            Ok(Some(VimAction::Undo))
+        );
+        assert_eq!(
+            parse_vim(":w", &mut cursor, 3, &Bindings::default()),
+            Ok(Some(VimAction::Save))
+        );
+        assert_eq!(
+            parse_vim(":q", &mut cursor, 3, &Bindings::default()),
+            Ok(Some(VimAction::Quit))
+        );
+        assert_eq!(
+            parse_vim(":wq", &mut cursor, 3, &Bindings::default()),
+            Ok(Some(VimAction::SaveAndQuit))
+        );
+        assert_eq!(
+            parse_vim(":hint", &mut cursor, 3, &Bindings::default()),
+            Ok(Some(VimAction::Command(String::from("hint"))))
+        );
+    }
+}
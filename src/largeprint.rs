@@ -0,0 +1,217 @@
+// Boards drawn with every tile several rows and columns big, for projectors
+// and players who need large print. Marks are block shapes, empty tiles show
+// the move that plays there and highlighted tiles get corner brackets.
+
+use std::fmt::Write as _;
+
+use crate::board::{Board, Mark, Symbol};
+use crate::formatter::Colors;
+
+// Tile heights, odd so marks have a middle row. Tiles are twice as wide as
+// they are high, since terminal characters are about twice as high as wide.
+const HEIGHTS: [usize; 4] = [11, 9, 7, 5];
+
+// Lines kept free for the text shown with the board.
+const TEXT_LINES: usize = 8;
+
+// The characters a board is drawn with.
+struct Glyphs {
+    block: char,
+    // Top left, top right, bottom left and bottom right.
+    corners: [char; 4],
+    horizontal: &'static str,
+    vertical: &'static str,
+    cross: &'static str,
+}
+
+const UNICODE: Glyphs = Glyphs {
+    block: '█',
+    corners: ['┌', '┐', '└', '┘'],
+    horizontal: "─",
+    vertical: "│",
+    cross: "┼",
+};
+
+// For terminals without Unicode, e.g. the legacy Windows console.
+const ASCII: Glyphs = Glyphs {
+    block: '#',
+    corners: ['+', '+', '+', '+'],
+    horizontal: "-",
+    vertical: "|",
+    cross: "+",
+};
+
+// The largest tile height at which a board of n tiles a side fits the terminal.
+pub fn fit(n: usize, (columns, rows): (usize, usize)) -> usize {
+    HEIGHTS
+        .into_iter()
+        .find(|height| n * height + n - 1 + TEXT_LINES <= rows && n * 2 * height + n - 1 <= columns)
+        .unwrap_or(HEIGHTS[HEIGHTS.len() - 1])
+}
+
+// The smallest terminal a board of n tiles a side fits, leaving the text
+// shown with it to scroll.
+pub fn minimum_size(n: usize) -> (usize, usize) {
+    let height = HEIGHTS[HEIGHTS.len() - 1];
+    (n * 2 * height + n - 1, n * height + n - 1)
+}
+
+// Whether a mark covers the character at x, y of a tile.
+#[allow(clippy::cast_precision_loss)]
+fn covers(symbol: Symbol, [x, y]: [usize; 2], [width, height]: [usize; 2]) -> bool {
+    let inside = y >= 1 && y + 1 < height && x >= 2 && x + 2 < width;
+    match symbol {
+        Symbol::Empty => false,
+        Symbol::Plus => inside && (y == height / 2 || x == width / 2 - 1 || x == width / 2),
+        Symbol::Circle => {
+            let dx = (x as f64 + 0.5 - width as f64 / 2.0) / (width as f64 / 2.0 - 2.0);
+            let dy = (y as f64 + 0.5 - height as f64 / 2.0) / (height as f64 / 2.0 - 0.5);
+            (0.55..=1.05).contains(&(dx * dx + dy * dy).sqrt())
+        }
+    }
+}
+
+// One line of a tile as characters with their colors.
+fn tile_line(
+    symbol: Symbol,
+    position: [usize; 2],
+    marked: bool,
+    y: usize,
+    [width, height]: [usize; 2],
+    colors: Option<Colors>,
+    glyphs: &Glyphs,
+) -> Vec<(char, Option<&'static str>)> {
+    let side = colors
+        .zip(symbol.side())
+        .and_then(|(colors, side)| colors.sides.get(side).copied());
+    let mark = colors.map(|colors| colors.marked);
+    let label = format!("{},{}", position[0], position[1]);
+    let label_start = (width - label.len()) / 2;
+
+    (0..width)
+        .map(|x| {
+            let corner = match (x, y) {
+                (0, 0) => Some(glyphs.corners[0]),
+                (x, 0) if x + 1 == width => Some(glyphs.corners[1]),
+                (0, y) if y + 1 == height => Some(glyphs.corners[2]),
+                (x, y) if x + 1 == width && y + 1 == height => Some(glyphs.corners[3]),
+                _ => None,
+            };
+            match corner {
+                Some(corner) if marked => (corner, mark),
+                _ if covers(symbol, [x, y], [width, height]) => (glyphs.block, side),
+                _ if symbol == Symbol::Empty
+                    && y == height / 2
+                    && (label_start..label_start + label.len()).contains(&x) =>
+                {
+                    (label.as_bytes()[x - label_start].into(), None)
+                }
+                _ => (' ', None),
+            }
+        })
+        .collect()
+}
+
+// Joins characters into text, with one color code per run of a color.
+fn paint(characters: &[(char, Option<&str>)]) -> String {
+    let mut text = String::new();
+    let mut current = None;
+    for &(c, color) in characters {
+        if color != current {
+            if current.is_some() {
+                text.push_str("\x1b[0m");
+            }
+            if let Some(color) = color {
+                let _ = write!(text, "\x1b[{color}m");
+            }
+            current = color;
+        }
+        text.push(c);
+    }
+    if current.is_some() {
+        text.push_str("\x1b[0m");
+    }
+    text
+}
+
+// Draws the board with tiles height lines high and the given tiles marked,
+// in ASCII unless unicode is set.
+pub fn render(
+    board: &Board,
+    marked: &[[usize; 2]],
+    height: usize,
+    colors: Option<Colors>,
+    unicode: bool,
+) -> String {
+    let glyphs = if unicode { &UNICODE } else { &ASCII };
+    let width = 2 * height;
+    let n = board.tiles.len();
+    let divider = vec![glyphs.horizontal.repeat(width); n].join(glyphs.cross);
+
+    let mut lines = Vec::new();
+    for (i, row) in board.tiles.iter().enumerate() {
+        if i > 0 {
+            lines.push(divider.clone());
+        }
+        for y in 0..height {
+            let tiles = row
+                .iter()
+                .enumerate()
+                .map(|(j, symbol)| {
+                    let line = tile_line(
+                        *symbol,
+                        [i, j],
+                        marked.contains(&[i, j]),
+                        y,
+                        [width, height],
+                        colors,
+                        glyphs,
+                    );
+                    paint(&line)
+                })
+                .collect::<Vec<_>>();
+            lines.push(tiles.join(glyphs.vertical));
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_grow_with_the_terminal() {
+        assert_eq!(fit(3, (80, 24)), 5);
+        assert_eq!(fit(3, (120, 40)), 9);
+        assert_eq!(fit(3, (200, 60)), 11);
+        // Too small for any size still draws the smallest.
+        assert_eq!(fit(3, (20, 10)), 5);
+        assert_eq!(minimum_size(3), (32, 17));
+    }
+
+    #[test]
+    fn marks_are_drawn_in_blocks_and_empty_tiles_labelled() {
+        let board: Board = "+o-/---/---".parse().unwrap();
+        let text = render(&board, &[[0, 1]], 5, None, true);
+        let lines = text.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 3 * 5 + 2);
+        assert_eq!(lines[0], "          │┌   ██   ┐│          ");
+        assert_eq!(lines[2], "  ██████  │  █    █  │   0,2    ");
+        assert_eq!(lines[4], "          │└   ██   ┘│          ");
+        assert_eq!(lines[1], "    ██    │  ██  ██  │          ");
+        assert_eq!(lines[5], "──────────┼──────────┼──────────");
+    }
+
+    #[test]
+    fn terminals_without_unicode_get_ascii() {
+        let board: Board = "+o-/---/---".parse().unwrap();
+        let text = render(&board, &[[0, 1]], 5, None, false);
+        let lines = text.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[0], "          |+   ##   +|          ");
+        assert_eq!(lines[5], "----------+----------+----------");
+        assert!(text.is_ascii());
+    }
+}
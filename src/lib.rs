@@ -0,0 +1,16 @@
+// The board and its rules, kept free of the standard library so embedded and
+// WebAssembly builds can reuse exactly the rules the game plays by. Only
+// alloc is needed, for the error messages and formatted boards.
+
+#![cfg_attr(not(test), no_std)]
+#![warn(clippy::all, clippy::pedantic)]
+// Errors are explained in comments and messages, as in the rest of the crate.
+#![allow(clippy::missing_errors_doc)]
+
+extern crate alloc;
+
+pub mod board;
+pub mod formatter;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+pub mod invariants;
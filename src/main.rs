@@ -1,10 +1,393 @@
 #![warn(clippy::all, clippy::pedantic)]
 
-mod board;
+mod achievements;
+mod adaptive;
+mod ai;
+#[cfg(test)]
+mod allocations;
+mod analysis;
+mod autosave;
+mod bench;
+mod bot;
+mod braille;
+mod browser;
+mod campaign;
+mod cli;
+mod clipboard;
+mod coach;
+mod console;
+mod correspondence;
+mod daily;
+mod editor;
+mod events;
 mod game;
+mod gamelog;
+mod irc;
+mod keys;
+mod largeprint;
+mod menu;
+mod metrics;
+mod mqtt;
+#[cfg(feature = "nn")]
+mod nn;
+mod openings;
+mod palette;
+mod paths;
+mod personality;
+mod plugin;
+mod profile;
+#[cfg(feature = "qr")]
+mod qr;
+mod rating;
+mod recording;
+mod rng;
+#[cfg(feature = "scripting")]
+mod script;
+mod selfplay;
+mod simul;
+mod sized;
+mod sound;
+mod streaks;
+mod svg;
+mod team;
+mod telnet;
+mod terminal;
+mod tls;
+mod tournament;
+mod train;
+mod transfer;
+#[cfg(feature = "twitch")]
+mod twitch;
+mod view;
+mod vote;
+#[cfg(feature = "wasm-bots")]
+mod wasm;
+mod webhook;
 
-use crate::game::start;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::Path;
+use std::time::Duration;
+use std::{env, process};
+
+use tic_tac_toe_rust::{board, formatter};
+
+use crate::ai::Difficulty;
+use crate::autosave::Autosave;
+use crate::bot::BotSpec;
+use crate::cli::{CliCommand, PlayerSpec};
+use crate::game::{GameSetup, Opponent, Outcome, Player};
+
+// Exit codes. Games with piped input end with 0 to 2 for how the last game
+// ended, so errors start above 10 to keep them apart.
+const EXIT_OK: i32 = 0;
+const EXIT_DRAW: i32 = 0;
+const EXIT_PLAYER_ONE_WON: i32 = 1;
+const EXIT_PLAYER_TWO_WON: i32 = 2;
+// The input ended before the game did.
+const EXIT_INPUT_ENDED: i32 = 11;
+const EXIT_FAILED: i32 = 12;
+const EXIT_USAGE: i32 = 13;
+
+fn run_team(teams: Vec<[String; 2]>, rule: team::Rule, against: &BotSpec) -> Result<(), String> {
+    let mut sides = teams.into_iter().map(team::Side::Team).collect::<Vec<_>>();
+    if sides.len() == 1 {
+        sides.push(team::Side::Computer(against.load()?));
+    }
+    let Ok(sides) = <[team::Side; 2]>::try_from(sides) else {
+        return Err(String::from("A team game needs two sides."));
+    };
+    let setup = profile::settings()?.apply(GameSetup::default());
+    team::run(&mut game::local_console(&setup, None), sides, rule);
+    Ok(())
+}
+
+fn run_twitch(options: &irc::IrcOptions) -> Result<(), String> {
+    #[cfg(feature = "twitch")]
+    {
+        let setup = profile::settings()?.apply(GameSetup::default());
+        twitch::run(&mut game::local_console(&setup, None), options)
+    }
+    #[cfg(not(feature = "twitch"))]
+    {
+        let _ = options;
+        Err(String::from(
+            "Could not join Twitch: this build does not include the twitch feature.",
+        ))
+    }
+}
+
+fn run_watch(broker: &mqtt::Broker) -> Result<(), String> {
+    let setup = profile::settings()?.apply(GameSetup::default());
+    mqtt::watch(&mut game::local_console(&setup, None), broker)
+}
+
+fn run_simul(specs: &[BotSpec]) -> Result<(), String> {
+    let bots = specs
+        .iter()
+        .map(BotSpec::load)
+        .collect::<Result<Vec<_>, _>>()?;
+    let setup = profile::settings()?.apply(GameSetup::default());
+    simul::run(&mut game::local_console(&setup, None), bots);
+    Ok(())
+}
+
+fn run_selfplay(options: &selfplay::SelfPlayOptions) -> Result<(), String> {
+    let mut bots = [options.bot.load()?, options.bot.load()?];
+    let mut writer: Box<dyn Write> = match &options.out {
+        Some(path) => {
+            Box::new(BufWriter::new(File::create(path).map_err(|error| {
+                format!("Could not create {}: {error}", path.display())
+            })?))
+        }
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    let aggregates = selfplay::run(&mut bots, options, &mut writer)
+        .map_err(|error| format!("Could not write the dataset: {error}"))?;
+
+    // Kept apart from the dataset, which may be going to standard output.
+    if let Some(format) = options.stats {
+        eprint!("{}", aggregates.format(format));
+    }
+    Ok(())
+}
+
+fn run_browser(path: &Path, game: Option<usize>, all: bool, analysis: bool) -> Result<(), String> {
+    let records = browser::records_in(&recording::load(path)?)?;
+    if all && !records.is_empty() {
+        browser::run_all(&mut console::Console::new(None), &records);
+        return Ok(());
+    }
+    let record = match game {
+        Some(game) => records.get(game - 1),
+        None => records.last(),
+    }
+    .ok_or_else(|| {
+        format!(
+            "{} has {} finished games to browse.",
+            path.display(),
+            records.len()
+        )
+    })?;
+
+    browser::run(&mut console::Console::new(None), record, analysis);
+    Ok(())
+}
+
+fn analyze(position: &board::Board, playouts: u32) {
+    let symbol = analysis::side_to_move(position);
+    let estimates =
+        analysis::estimate_moves(position, symbol, playouts, &mut rng::Rng::from_time());
+
+    if estimates.is_empty() {
+        println!("The game is already over.");
+    } else {
+        println!(
+            "{} to move, {playouts} random playouts per move:\n",
+            Into::<&str>::into(symbol)
+        );
+        print!("{}", analysis::format_estimates(&estimates));
+    }
+}
+
+fn show_stats() -> Result<(), String> {
+    let rating = rating::load(&rating::path())?;
+    println!(
+        "Rating: {:.0} after {} rated games against the built-in bots.\n\n{}",
+        rating.rating,
+        rating.games,
+        streaks::load(&streaks::path())?.report()
+    );
+    Ok(())
+}
+
+fn list_profiles() {
+    let profiles = profile::list();
+    if profiles.is_empty() {
+        println!("There are no profiles yet, start one with --profile <name>.");
+    } else {
+        println!("{}", profiles.join("\n"));
+    }
+}
+
+// Plays a session from the command line. Piped games exit with how the last
+// game ended, so scripts can tell without reading the output.
+fn play(opponent: PlayerSpec, setup: GameSetup, record_to: Option<&Path>) -> Result<i32, String> {
+    let (opponent, spec) = match opponent {
+        PlayerSpec::Human => (Opponent::Human, None),
+        PlayerSpec::Bot(spec) => (Opponent::Computer(spec.load()?), Some(spec)),
+    };
+    // A game left over from an interrupted run is not overwritten.
+    let path = autosave::path();
+    let kept = setup.resume.is_none() && path.exists();
+    if kept {
+        eprintln!(
+            "An unfinished game is saved in {0}, so this one is not. Resume it with --resume {0}.",
+            path.display()
+        );
+    }
+    let setup = match &spec {
+        Some(spec) => setup.tracked_against(spec),
+        None => setup,
+    };
+    let setup = GameSetup {
+        autosave: (!kept).then(|| Autosave::new(path, spec)),
+        ..setup
+    };
+
+    Ok(exit_code(game::start(opponent, &setup, record_to)))
+}
+
+fn play_sized(size: usize, bot: Option<Difficulty>, setup: GameSetup) -> Result<i32, String> {
+    let setup = profile::settings()?.apply(setup);
+    let mut console = game::local_console(&setup, None);
+    let outcome = sized::play(&mut console, size, bot);
+    if let (Some(difficulty), Some(outcome)) = (bot, outcome) {
+        sized::track_achievements(&mut console, difficulty, size, outcome);
+    }
+    Ok(exit_code(outcome))
+}
+
+// How a piped game ended, or EXIT_OK at the terminal.
+fn exit_code(outcome: Option<Outcome>) -> i32 {
+    if io::stdin().is_terminal() {
+        return EXIT_OK;
+    }
+    match outcome.map(Outcome::winner) {
+        Some(None) => EXIT_DRAW,
+        Some(Some(Player::One)) => EXIT_PLAYER_ONE_WON,
+        Some(Some(Player::Two)) => EXIT_PLAYER_TWO_WON,
+        None => EXIT_INPUT_ENDED,
+    }
+}
+
+// Runs a parsed command, returning the exit code or the message to print if it fails.
+fn run(command: CliCommand) -> Result<i32, String> {
+    match command {
+        CliCommand::Menu {
+            record_to,
+            defaults,
+        } => menu::run(record_to.as_deref(), defaults),
+        CliCommand::Play {
+            opponent,
+            setup,
+            record_to,
+        } => {
+            return play(
+                opponent,
+                profile::settings()?.apply(setup),
+                record_to.as_deref(),
+            )
+        }
+        CliCommand::Sized { size, bot, setup } => return play_sized(size, bot, setup),
+        CliCommand::Replay { path, render } => recording::replay(&recording::load(&path)?, render),
+        CliCommand::Browse {
+            path,
+            game,
+            all,
+            analysis,
+        } => run_browser(&path, game, all, analysis)?,
+        CliCommand::Svg(frames) => print!("{}", svg::render(&frames)),
+        CliCommand::SelfPlay(options) => run_selfplay(&options)?,
+        CliCommand::Qr { text, invert } => {
+            #[cfg(feature = "qr")]
+            println!("{}\n{text}", qr::render(&text, invert)?);
+            #[cfg(not(feature = "qr"))]
+            {
+                let _ = (text, invert);
+                return Err(String::from(
+                    "Could not make a QR code: this build does not include the qr feature.",
+                ));
+            }
+        }
+        CliCommand::Correspond { path, player_move } => correspondence::run(&path, player_move)?,
+        CliCommand::Bench { millis } => {
+            println!(
+                "{}",
+                bench::format_report(&bench::run(Duration::from_millis(millis)))
+            );
+        }
+        CliCommand::Campaign => {
+            let setup = GameSetup::default();
+            campaign::run(
+                &mut game::local_console(&setup, None),
+                &setup,
+                &campaign::path(),
+            )?;
+        }
+        CliCommand::Stats => show_stats()?,
+        CliCommand::Profiles => list_profiles(),
+        CliCommand::Paths => println!("{}", paths::report()),
+        CliCommand::Export { path } => {
+            transfer::export(&path)?;
+            println!("Exported to {}.", path.display());
+        }
+        CliCommand::Import { path, replace } => {
+            for line in transfer::import(&path, replace)? {
+                println!("{line}");
+            }
+        }
+        CliCommand::Daily => daily::run(
+            &mut console::Console::new(None),
+            &daily::path(),
+            daily::today(),
+        )?,
+        CliCommand::Achievements => {
+            println!("{}", achievements::load(&achievements::path())?.report());
+        }
+        CliCommand::Openings => println!("{}", openings::load(&openings::path())?.report()),
+        CliCommand::Irc(options) => irc::run(&options)?,
+        CliCommand::Twitch(options) => run_twitch(&options)?,
+        CliCommand::Telnet(options) => telnet::run(options)?,
+        CliCommand::Join { address, tls } => {
+            telnet::join(&mut console::Console::new(None), &address, tls.as_ref())?;
+        }
+        CliCommand::Watch(broker) => run_watch(&broker)?,
+        CliCommand::Train(options) => train::run(&options)?,
+        CliCommand::Analyze { position, playouts } => analyze(&position, playouts),
+        CliCommand::Simul { bots } => run_simul(&bots)?,
+        CliCommand::Team {
+            teams,
+            rule,
+            against,
+        } => run_team(teams, rule, &against)?,
+        CliCommand::Tournament { bots, games } => {
+            let mut bots = bots
+                .iter()
+                .map(BotSpec::load)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            print!(
+                "{}",
+                tournament::crosstable(&tournament::run(&mut bots, games))
+            );
+        }
+    }
+
+    Ok(EXIT_OK)
+}
 
 fn main() {
-    start();
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let code = match cli::take_profile(&args).and_then(|(name, args)| {
+        if let Some(name) = name {
+            profile::select(&name)?;
+        }
+        for line in paths::prepare() {
+            eprintln!("{line}");
+        }
+        cli::parse(&args)
+    }) {
+        Ok(command) => run(command).unwrap_or_else(|message| {
+            eprintln!("{message}");
+            EXIT_FAILED
+        }),
+        Err(message) => {
+            eprintln!("{message}\n\n{}", cli::USAGE);
+            EXIT_USAGE
+        }
+    };
+    process::exit(code);
 }
@@ -0,0 +1,850 @@
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::ai::Difficulty;
+use crate::autosave::{self, Autosave, SavedGame};
+use crate::bot::{Bot, BotSpec, DEFAULT_TEMPERATURE};
+use crate::campaign;
+use crate::console::Console;
+use crate::editor;
+use crate::events::Events;
+use crate::game::{self, GameSetup, Handicap, HandicapKind, Opponent, Player, TakebackAnswer};
+use crate::keys::{Action, ACTIONS, KEYS};
+use crate::palette::PALETTES;
+use crate::paths;
+use crate::plugin;
+use crate::profile::{self, on_off, Settings};
+use crate::recording;
+use crate::sized;
+use crate::telnet::{self, TelnetOptions};
+use crate::view::{SCALES, VIEWS};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum MenuEntry {
+    LocalTwoPlayer,
+    VsComputer,
+    Network,
+    Campaign,
+    Editor,
+    Replay,
+    Settings,
+    Quit,
+}
+
+const MENU_ENTRIES: [MenuEntry; 8] = [
+    MenuEntry::LocalTwoPlayer,
+    MenuEntry::VsComputer,
+    MenuEntry::Network,
+    MenuEntry::Campaign,
+    MenuEntry::Editor,
+    MenuEntry::Replay,
+    MenuEntry::Settings,
+    MenuEntry::Quit,
+];
+
+const DIFFICULTIES: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+impl Display for MenuEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            MenuEntry::LocalTwoPlayer => "Local 2-player",
+            MenuEntry::VsComputer => "vs Computer",
+            MenuEntry::Network => "Network host/join",
+            MenuEntry::Campaign => "Campaign",
+            MenuEntry::Editor => "Board editor",
+            MenuEntry::Replay => "Replay",
+            MenuEntry::Settings => "Settings",
+            MenuEntry::Quit => "Quit",
+        };
+
+        write!(f, "{label}")
+    }
+}
+
+// Turns a 1-based menu choice into an index into the listed options.
+fn parse_choice(input: &str, option_count: usize) -> Option<usize> {
+    match input.trim().parse::<usize>() {
+        Ok(choice) if (1..=option_count).contains(&choice) => Some(choice - 1),
+        _ => None,
+    }
+}
+
+// Where the menu runs: on this terminal, or for a remote player who gets
+// neither the recordings nor the plugins of the machine it runs on.
+struct Session<'a> {
+    console: Console,
+    remote: bool,
+    record_to: Option<&'a Path>,
+    // The settings every game starts with, before a handicap or position is picked.
+    defaults: GameSetup,
+    // Where games are saved as they are played. None while an older save is
+    // kept for later, and for remote players unless the server keeps games.
+    autosave: Option<PathBuf>,
+    // The bot and board size from the settings, None to ask for the bot
+    // and play on 3x3.
+    difficulty: Option<Difficulty>,
+    board_size: Option<usize>,
+}
+
+impl Session<'_> {
+    // Plays against the bot when one is given, or a second human.
+    fn start_game(&mut self, bot: Option<ChosenBot>, setup: &GameSetup) {
+        let (opponent, spec) = match bot {
+            Some((spec, bot)) => (Opponent::Computer(bot), Some(spec)),
+            None => (Opponent::Human, None),
+        };
+        let defaults = match &spec {
+            // Remote players' games are theirs, not this machine's player's.
+            Some(spec) if !self.remote => self.defaults.clone().tracked_against(spec),
+            _ => self.defaults.clone(),
+        };
+        let setup = GameSetup {
+            handicap: setup.handicap,
+            position: setup.position.clone(),
+            resume: setup.resume.clone(),
+            autosave: self.autosave.clone().map(|path| Autosave::new(path, spec)),
+            ..defaults
+        };
+        if self.remote {
+            game::play_session(&mut self.console, opponent, &setup);
+        } else {
+            game::start(opponent, &setup, self.record_to);
+        }
+    }
+}
+
+fn replay(console: &mut Console) {
+    console.say("\nWhich recording should be replayed?");
+
+    let Some(input) = console.read_line() else {
+        return;
+    };
+
+    match recording::load(Path::new(input.trim())) {
+        Ok(frames) => recording::replay(&frames, true),
+        Err(message) => console.warn(&message),
+    }
+}
+
+// Lists the options and keeps asking until a valid one is picked.
+// Returns None when stdin is closed.
+fn choose<T: Display + Clone>(console: &mut Console, title: &str, options: &[T]) -> Option<T> {
+    loop {
+        let listing = options
+            .iter()
+            .enumerate()
+            .map(|(i, option)| format!("  {}) {option}", i + 1))
+            .collect::<Vec<_>>();
+        console.prompt(&format!("\n{title}"));
+        console.say(&format!("\n{}", listing.join("\n")));
+
+        let input = console.read_line()?;
+
+        match parse_choice(&input, options.len()) {
+            Some(index) => return Some(options[index].clone()),
+            None => console.warn(&format!(
+                "Please pick a number between 1 and {}.",
+                options.len()
+            )),
+        }
+    }
+}
+
+// A bot picked in the menu, with its spec for saving the game.
+type ChosenBot = (BotSpec, Box<dyn Bot>);
+
+// Offers the built-in difficulties followed by any bots found in the plugin
+// directory, which remote players do not get to run, unless the settings
+// pick a difficulty.
+fn choose_computer(session: &mut Session) -> Option<ChosenBot> {
+    let plugins = if session.remote {
+        Vec::new()
+    } else {
+        plugin::discover(&plugin::plugin_dir())
+    };
+    let choices = DIFFICULTIES
+        .into_iter()
+        .map(BotSpec::Builtin)
+        .chain([BotSpec::Stochastic(DEFAULT_TEMPERATURE)])
+        // It learns this machine's player, not a remote one.
+        .chain((!session.remote).then_some(BotSpec::Adaptive))
+        .chain(plugins.into_iter().map(BotSpec::Plugin))
+        .collect::<Vec<_>>();
+
+    let spec = match session.difficulty {
+        Some(difficulty) => BotSpec::Builtin(difficulty),
+        None => choose(&mut session.console, "Choose a difficulty:", &choices)?,
+    };
+    match spec.load() {
+        Ok(bot) => Some((spec, bot)),
+        Err(message) => {
+            session.console.warn(&message);
+            None
+        }
+    }
+}
+
+// Plays a game on the bigger board the settings ask for, where only the
+// easy and medium bots play.
+fn play_sized(session: &mut Session, size: usize, against_computer: bool) {
+    let bot = if against_computer {
+        match session.difficulty {
+            Some(difficulty @ (Difficulty::Easy | Difficulty::Medium)) => Some(difficulty),
+            _ => match choose(
+                &mut session.console,
+                "Choose a difficulty:",
+                &[Difficulty::Easy, Difficulty::Medium],
+            ) {
+                Some(difficulty) => Some(difficulty),
+                None => return,
+            },
+        }
+    } else {
+        None
+    };
+    let outcome = sized::play(&mut session.console, size, bot);
+    if let (Some(difficulty), Some(outcome), false) = (bot, outcome, session.remote) {
+        sized::track_achievements(&mut session.console, difficulty, size, outcome);
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct HandicapChoice(Option<Handicap>);
+
+impl Display for HandicapChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(handicap) => write!(f, "{handicap}"),
+            None => write!(f, "No handicap"),
+        }
+    }
+}
+
+// Every handicap that can help one of players. Returns None when stdin is closed.
+fn choose_handicap(console: &mut Console, players: &[Player]) -> Option<HandicapChoice> {
+    let kinds = [
+        HandicapKind::Marks(1),
+        HandicapKind::Marks(2),
+        HandicapKind::ExtraMove,
+    ];
+    let choices = [HandicapChoice(None)]
+        .into_iter()
+        .chain(players.iter().flat_map(|player| {
+            kinds.map(|kind| {
+                HandicapChoice(Some(Handicap {
+                    player: *player,
+                    kind,
+                }))
+            })
+        }))
+        .collect::<Vec<_>>();
+
+    choose(console, "Choose a handicap:", &choices)
+}
+
+// Starts a game from a position made in the editor, against whoever is picked.
+fn edit_and_play(session: &mut Session) {
+    let Some(position) = editor::run(&mut session.console) else {
+        return;
+    };
+
+    let bot = match choose(
+        &mut session.console,
+        "Play from this position:",
+        &[MenuEntry::LocalTwoPlayer, MenuEntry::VsComputer],
+    ) {
+        Some(MenuEntry::LocalTwoPlayer) => None,
+        Some(MenuEntry::VsComputer) => match choose_computer(session) {
+            Some(bot) => Some(bot),
+            None => return,
+        },
+        _ => return,
+    };
+
+    let setup = GameSetup {
+        position: Some(position),
+        ..GameSetup::default()
+    };
+    session.start_game(bot, &setup);
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum NetworkChoice {
+    Host,
+    Join,
+}
+
+impl Display for NetworkChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            NetworkChoice::Host => "Host games for others to join",
+            NetworkChoice::Join => "Join a game hosted elsewhere",
+        };
+
+        write!(f, "{text}")
+    }
+}
+
+// Serves the menu to other players, as the telnet command does, or plays on
+// someone else's server. Hosting goes on until the server is drained.
+fn play_network(console: &mut Console) {
+    let Some(choice) = choose(
+        console,
+        "Host or join?",
+        &[NetworkChoice::Host, NetworkChoice::Join],
+    ) else {
+        return;
+    };
+    console.prompt(&format!(
+        "\nThe address, or nothing for {}:",
+        telnet::DEFAULT_ADDRESS
+    ));
+    let Some(input) = console.read_line() else {
+        return;
+    };
+    let address = match input.trim() {
+        "" => telnet::DEFAULT_ADDRESS,
+        address => address,
+    };
+    let result = match choice {
+        NetworkChoice::Host => telnet::host(TelnetOptions::new(address)),
+        NetworkChoice::Join => telnet::join(console, address, None),
+    };
+    if let Err(message) = result {
+        console.warn(&message);
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum RecoveryChoice {
+    Resume,
+    Delete,
+    Keep,
+}
+
+impl Display for RecoveryChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            RecoveryChoice::Resume => "Resume it",
+            RecoveryChoice::Delete => "Delete it",
+            RecoveryChoice::Keep => "Keep it for later, new games are not saved meanwhile",
+        };
+
+        write!(f, "{text}")
+    }
+}
+
+// Finishes a saved game. Returns false when its bot cannot be loaded.
+fn resume(session: &mut Session, game: SavedGame) -> bool {
+    let bot = match game.opponent.clone() {
+        Some(spec) => match spec.load() {
+            Ok(bot) => Some((spec, bot)),
+            Err(message) => {
+                session.console.warn(&message);
+                return false;
+            }
+        },
+        None => None,
+    };
+    let setup = GameSetup {
+        resume: Some(game),
+        ..GameSetup::default()
+    };
+    session.start_game(bot, &setup);
+    true
+}
+
+// Offers to resume a game left over from a run that did not end cleanly.
+// Returns whether games may be autosaved, which would replace a kept save.
+fn recover(session: &mut Session) -> bool {
+    let path = autosave::path();
+    if !path.exists() {
+        return true;
+    }
+
+    let game = match autosave::load(&path) {
+        Ok(game) => game,
+        Err(message) => {
+            session.console.warn(&format!(
+                "{message} It is left alone, delete it to autosave again."
+            ));
+            return false;
+        }
+    };
+    let opponent = game
+        .opponent
+        .as_ref()
+        .map_or_else(|| String::from("a second player"), ToString::to_string);
+    session.console.say(&format!(
+        "\nAn unfinished game against {opponent} was found in {}:\n\n{}",
+        path.display(),
+        game.record.board()
+    ));
+
+    let choices = [
+        RecoveryChoice::Resume,
+        RecoveryChoice::Delete,
+        RecoveryChoice::Keep,
+    ];
+    match choose(&mut session.console, "What should happen to it?", &choices) {
+        Some(RecoveryChoice::Resume) => resume(session, game),
+        Some(RecoveryChoice::Delete) => {
+            if let Err(error) = fs::remove_file(&path) {
+                session
+                    .console
+                    .warn(&format!("Could not delete {}: {error}", path.display()));
+                return false;
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Setting {
+    Palette,
+    View,
+    Scale,
+    Keys,
+    Bindings,
+    Confirm,
+    Coach,
+    Takebacks,
+    Volume,
+    Mute,
+    Difficulty,
+    BoardSize,
+    Done,
+}
+
+// A line of the settings screen, with the setting's current value.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct SettingLine(Setting, String);
+
+impl Display for SettingLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self.0 {
+            Setting::Palette => "Palette",
+            Setting::View => "Board view",
+            Setting::Scale => "Grid scale",
+            Setting::Keys => "Keys",
+            Setting::Bindings => "Vim bindings",
+            Setting::Confirm => "Confirm moves",
+            Setting::Coach => "Coach",
+            Setting::Takebacks => "Takebacks",
+            Setting::Volume => "Volume",
+            Setting::Mute => "Mute",
+            Setting::Difficulty => "Difficulty",
+            Setting::BoardSize => "Board size",
+            Setting::Done => return write!(f, "Save and go back"),
+        };
+
+        write!(f, "{label:<15}{}", self.1)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct DifficultyChoice(Option<Difficulty>);
+
+impl Display for DifficultyChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(difficulty) => write!(f, "{difficulty}"),
+            None => write!(f, "Ask every game"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct BoardSizeChoice(usize);
+
+impl Display for BoardSizeChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{0}x{0}", self.0)
+    }
+}
+
+fn setting_lines(settings: Settings) -> Vec<SettingLine> {
+    vec![
+        SettingLine(Setting::Palette, settings.palette.to_string()),
+        SettingLine(Setting::View, settings.view.to_string()),
+        SettingLine(Setting::Scale, settings.scale.to_string()),
+        SettingLine(Setting::Keys, settings.keys.to_string()),
+        SettingLine(Setting::Bindings, settings.bindings.to_string()),
+        SettingLine(Setting::Confirm, on_off(settings.confirm_moves).to_string()),
+        SettingLine(Setting::Coach, on_off(settings.coach).to_string()),
+        SettingLine(Setting::Takebacks, settings.takebacks.to_string()),
+        SettingLine(Setting::Volume, settings.volume.to_string()),
+        SettingLine(Setting::Mute, on_off(settings.mute).to_string()),
+        SettingLine(
+            Setting::Difficulty,
+            DifficultyChoice(settings.difficulty).to_string(),
+        ),
+        SettingLine(
+            Setting::BoardSize,
+            BoardSizeChoice(settings.board_size.unwrap_or(3)).to_string(),
+        ),
+        SettingLine(Setting::Done, String::new()),
+    ]
+}
+
+// A line of the key bindings screen.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct BindingLine(Action, char);
+
+impl Display for BindingLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:<15}{}", self.0.to_string(), self.1)
+    }
+}
+
+// Shows the vim keys and remaps the one picked. Returns None when stdin is closed.
+fn change_binding(console: &mut Console, settings: &mut Settings) -> Option<()> {
+    let lines = ACTIONS.map(|action| BindingLine(action, settings.bindings.key(action)));
+    let BindingLine(action, _) = choose(console, "Choose a key to change:", &lines)?;
+    console.prompt(&format!("\nThe new key for {action}:"));
+    let input = console.read_line()?;
+    let mut keys = input.trim().chars();
+    match (keys.next(), keys.next()) {
+        (Some(key), None) => {
+            if let Err(message) = settings.bindings.bind(action, key) {
+                console.warn(&message);
+            }
+        }
+        _ => console.warn("Please type a single key."),
+    }
+    Some(())
+}
+
+// Changes the settings one at a time and saves them to the settings file,
+// where they stay for later runs. The session's games use them at once.
+fn change_settings(session: &mut Session) {
+    let mut settings = match profile::settings() {
+        Ok(settings) => settings,
+        Err(message) => {
+            session.console.warn(&message);
+            return;
+        }
+    };
+
+    loop {
+        let console = &mut session.console;
+        let Some(SettingLine(setting, _)) = choose(console, "Settings:", &setting_lines(settings))
+        else {
+            return;
+        };
+        match setting {
+            Setting::Palette => {
+                settings.palette =
+                    choose(console, "Choose a palette:", &PALETTES).unwrap_or(settings.palette);
+            }
+            Setting::View => {
+                settings.view =
+                    choose(console, "Choose a board view:", &VIEWS).unwrap_or(settings.view);
+            }
+            Setting::Scale => {
+                settings.scale = choose(console, "Choose how big the grid is drawn:", &SCALES)
+                    .unwrap_or(settings.scale);
+            }
+            Setting::Keys => {
+                settings.keys = choose(console, "Choose the keys:", &KEYS).unwrap_or(settings.keys);
+            }
+            Setting::Bindings => {
+                if change_binding(console, &mut settings).is_none() {
+                    return;
+                }
+            }
+            Setting::Confirm => settings.confirm_moves = !settings.confirm_moves,
+            Setting::Coach => settings.coach = !settings.coach,
+            Setting::Takebacks => {
+                settings.takebacks = choose(
+                    console,
+                    "Answer takeback requests with:",
+                    &[TakebackAnswer::Accept, TakebackAnswer::Refuse],
+                )
+                .unwrap_or(settings.takebacks);
+            }
+            Setting::Volume => {
+                console.prompt("\nVolume, from 0 to 100:");
+                let Some(input) = console.read_line() else {
+                    return;
+                };
+                match input.trim().parse() {
+                    Ok(volume) => settings.volume = volume,
+                    Err(message) => console.warn(&message),
+                }
+            }
+            Setting::Mute => settings.mute = !settings.mute,
+            Setting::Difficulty => {
+                let choices = [DifficultyChoice(None)]
+                    .into_iter()
+                    .chain(DIFFICULTIES.map(|difficulty| DifficultyChoice(Some(difficulty))))
+                    .collect::<Vec<_>>();
+                if let Some(DifficultyChoice(difficulty)) =
+                    choose(console, "Choose the computer to play:", &choices)
+                {
+                    settings.difficulty = difficulty;
+                }
+            }
+            Setting::BoardSize => {
+                if let Some(BoardSizeChoice(size)) = choose(
+                    console,
+                    "Choose the board size, bigger boards need longer lines:",
+                    &[3, 4, 5].map(BoardSizeChoice),
+                ) {
+                    settings.board_size = (size > 3).then_some(size);
+                }
+            }
+            Setting::Done => break,
+        }
+    }
+
+    match profile::save_settings(settings) {
+        Ok(()) => session.console.say(&format!(
+            "\nSaved the settings to {}.",
+            profile::settings_path().display()
+        )),
+        Err(message) => session.console.warn(&message),
+    }
+    session.defaults = settings.set(session.defaults.clone());
+    session.difficulty = settings.difficulty;
+    session.board_size = settings.board_size;
+    session.console.set_palette(settings.palette);
+    session.console.set_view(settings.view);
+    session.console.set_scale(settings.scale);
+}
+
+fn run_session(session: &mut Session) {
+    let entries = MENU_ENTRIES
+        .into_iter()
+        // Replays, campaign progress and settings are files on this machine,
+        // and remote players are already on a network.
+        .filter(|entry| {
+            !(session.remote
+                && matches!(
+                    entry,
+                    MenuEntry::Network
+                        | MenuEntry::Replay
+                        | MenuEntry::Campaign
+                        | MenuEntry::Settings
+                ))
+        })
+        .collect::<Vec<_>>();
+
+    while let Some(entry) = choose(&mut session.console, "Main menu:", &entries) {
+        if let (MenuEntry::LocalTwoPlayer | MenuEntry::VsComputer, Some(size)) =
+            (entry, session.board_size)
+        {
+            play_sized(session, size, entry == MenuEntry::VsComputer);
+            continue;
+        }
+        match entry {
+            MenuEntry::LocalTwoPlayer => {
+                let players = [Player::One, Player::Two];
+                if let Some(HandicapChoice(handicap)) =
+                    choose_handicap(&mut session.console, &players)
+                {
+                    let setup = GameSetup {
+                        handicap,
+                        ..GameSetup::default()
+                    };
+                    session.start_game(None, &setup);
+                }
+            }
+            MenuEntry::VsComputer => {
+                if let Some(bot) = choose_computer(session) {
+                    // Only the human can be given a head start over the computer.
+                    if let Some(HandicapChoice(handicap)) =
+                        choose_handicap(&mut session.console, &[Player::One])
+                    {
+                        let setup = GameSetup {
+                            handicap,
+                            ..GameSetup::default()
+                        };
+                        session.start_game(Some(bot), &setup);
+                    }
+                }
+            }
+            MenuEntry::Network => play_network(&mut session.console),
+            MenuEntry::Campaign => {
+                if let Err(message) =
+                    campaign::run(&mut session.console, &session.defaults, &campaign::path())
+                {
+                    session.console.warn(&message);
+                }
+            }
+            MenuEntry::Editor => edit_and_play(session),
+            MenuEntry::Replay => replay(&mut session.console),
+            MenuEntry::Settings => change_settings(session),
+            MenuEntry::Quit => break,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum ProfileChoice {
+    Profile(String),
+    New,
+    Guest,
+}
+
+impl Display for ProfileChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileChoice::Profile(name) => write!(f, "{name}"),
+            ProfileChoice::New => write!(f, "New profile"),
+            ProfileChoice::Guest => write!(f, "Play without a profile"),
+        }
+    }
+}
+
+// Asks who is playing when there are profiles and none was given. Returns
+// None when stdin is closed.
+fn choose_profile(console: &mut Console) -> Option<()> {
+    let profiles = profile::list();
+    if profile::selected().is_some() || profiles.is_empty() {
+        return Some(());
+    }
+
+    let choices = profiles
+        .into_iter()
+        .map(ProfileChoice::Profile)
+        .chain([ProfileChoice::New, ProfileChoice::Guest])
+        .collect::<Vec<_>>();
+    loop {
+        let name = match choose(console, "Who is playing?", &choices)? {
+            ProfileChoice::Profile(name) => name,
+            ProfileChoice::New => {
+                console.say("\nWhat should the profile be called?");
+                console.read_line()?.trim().to_string()
+            }
+            ProfileChoice::Guest => return Some(()),
+        };
+        match profile::select(&name) {
+            Ok(()) => {
+                for line in paths::prepare() {
+                    console.say(&line);
+                }
+                return Some(());
+            }
+            Err(message) => console.warn(&message),
+        }
+    }
+}
+
+// Games started from the menu are recorded to record_to when it is given.
+// The player picks a profile, and a game left over from an interrupted run
+// is offered, first.
+pub fn run(record_to: Option<&Path>, defaults: GameSetup) {
+    let mut session = Session {
+        console: game::local_console(&defaults, None),
+        remote: false,
+        record_to,
+        defaults,
+        autosave: Some(autosave::path()),
+        difficulty: None,
+        board_size: None,
+    };
+    if choose_profile(&mut session.console).is_none() {
+        return;
+    }
+    match profile::settings() {
+        Ok(settings) => {
+            session.defaults = settings.apply(session.defaults.clone());
+            session.difficulty = settings.difficulty;
+            session.board_size = settings.board_size;
+            session.console.set_palette(session.defaults.palette);
+            session.console.set_view(session.defaults.view);
+            session.console.set_scale(session.defaults.scale);
+        }
+        Err(message) => session.console.warn(&message),
+    }
+    if !recover(&mut session) {
+        session.autosave = None;
+    }
+    run_session(&mut session);
+}
+
+// The menu for a remote player on console, e.g. over telnet. Games are saved
+// to autosave when the server keeps them, and a saved game is finished first.
+pub fn run_remote(
+    console: Console,
+    events: Option<Events>,
+    autosave: Option<PathBuf>,
+    saved: Option<SavedGame>,
+) {
+    let mut session = Session {
+        console,
+        remote: true,
+        record_to: None,
+        defaults: GameSetup {
+            events,
+            ..GameSetup::default()
+        },
+        autosave,
+        difficulty: None,
+        board_size: None,
+    };
+    if let Some(game) = saved {
+        resume(&mut session, game);
+    }
+    run_session(&mut session);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_menu_choices_test() {
+        assert_eq!(parse_choice("1", 3), Some(0));
+        assert_eq!(parse_choice(" 3\n", 3), Some(2));
+    }
+
+    #[test]
+    fn invalid_menu_choices_test() {
+        for input in ["0", "4", "", "a", "-1", "1.5"] {
+            assert_eq!(parse_choice(input, 3), None);
+        }
+    }
+
+    #[test]
+    fn menu_entry_labels_test() {
+        assert_eq!("Local 2-player", format!("{}", MenuEntry::LocalTwoPlayer));
+        assert_eq!("vs Computer", format!("{}", MenuEntry::VsComputer));
+        assert_eq!("Network host/join", format!("{}", MenuEntry::Network));
+        assert_eq!("Board editor", format!("{}", MenuEntry::Editor));
+        assert_eq!("Replay", format!("{}", MenuEntry::Replay));
+        assert_eq!("Settings", format!("{}", MenuEntry::Settings));
+    }
+
+    #[test]
+    fn settings_are_listed_with_their_values() {
+        let lines = setting_lines(Settings::default());
+        assert_eq!(lines[0].to_string(), "Palette        plain");
+        assert_eq!(lines[2].to_string(), "Grid scale     1");
+        assert_eq!(lines[4].to_string(), "Vim bindings   h j k l x u ? Q");
+        assert_eq!(lines[5].to_string(), "Confirm moves  off");
+        assert_eq!(lines[10].to_string(), "Difficulty     Ask every game");
+        assert_eq!(lines[11].to_string(), "Board size     3x3");
+        assert_eq!(lines.last().unwrap().to_string(), "Save and go back");
+    }
+
+    #[test]
+    fn vim_keys_are_remapped_one_at_a_time() {
+        let mut console = Console::over(
+            Box::new(std::io::Cursor::new(b"1\na\n4\na\n".to_vec())),
+            Box::new(std::io::sink()),
+        );
+        let mut settings = Settings::default();
+
+        assert_eq!(change_binding(&mut console, &mut settings), Some(()));
+        assert_eq!(settings.bindings.key(Action::Left), 'a');
+        // a is taken by left, so right keeps its key.
+        assert_eq!(change_binding(&mut console, &mut settings), Some(()));
+        assert_eq!(settings.bindings.key(Action::Right), 'l');
+        assert_eq!(change_binding(&mut console, &mut settings), None);
+    }
+}
@@ -0,0 +1,149 @@
+// Counters and gauges for server mode, served over HTTP at /metrics in the
+// Prometheus text format. Moves per second is a rate over the moves counter,
+// e.g. rate(tic_tac_toe_moves_total[1m]).
+
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::events::Event;
+
+// How long a scraper gets to send its request.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Counted from the events of every game on the server.
+#[derive(Default)]
+pub struct Metrics {
+    games_started: AtomicU64,
+    // Finished or abandoned.
+    games_ended: AtomicU64,
+    moves: AtomicU64,
+    rejected_moves: AtomicU64,
+}
+
+impl Metrics {
+    pub fn count(&self, event: &Event) {
+        let counter = match event {
+            Event::Created { .. } => &self.games_started,
+            Event::Moved { .. } => &self.moves,
+            Event::Finished { .. } | Event::Abandoned { .. } => &self.games_ended,
+        };
+        counter.fetch_add(1, Ordering::SeqCst);
+    }
+
+    // A move that was turned down: not a move, or not a legal one.
+    pub fn rejected(&self) {
+        self.rejected_moves.fetch_add(1, Ordering::SeqCst);
+    }
+
+    // The metrics in the text format, with the clients the server counts itself.
+    pub fn render(&self, clients: usize) -> String {
+        let started = self.games_started.load(Ordering::SeqCst);
+        let active = started.saturating_sub(self.games_ended.load(Ordering::SeqCst));
+        let mut text = String::new();
+        for (name, kind, help, value) in [
+            (
+                "connected_clients",
+                "gauge",
+                "Connections being served.",
+                clients as u64,
+            ),
+            ("active_games", "gauge", "Games being played.", active),
+            ("games_total", "counter", "Games started.", started),
+            (
+                "moves_total",
+                "counter",
+                "Moves played.",
+                self.moves.load(Ordering::SeqCst),
+            ),
+            (
+                "invalid_moves_total",
+                "counter",
+                "Moves rejected as invalid.",
+                self.rejected_moves.load(Ordering::SeqCst),
+            ),
+        ] {
+            let _ = write!(
+                text,
+                "# HELP tic_tac_toe_{name} {help}\n# TYPE tic_tac_toe_{name} {kind}\ntic_tac_toe_{name} {value}\n"
+            );
+        }
+        text
+    }
+}
+
+// Answers GET /metrics with the report, and anything else with a 404.
+fn answer(stream: &TcpStream, report: &str) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // The headers are of no interest, but are read so the client is not reset.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let response = match request.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+        ["GET", "/metrics"] => format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{report}",
+            report.len()
+        ),
+        _ => String::from(
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ),
+    };
+    (&*stream).write_all(response.as_bytes())
+}
+
+// Serves the report on a thread of its own, one scrape at a time.
+pub fn serve(address: &str, report: impl Fn() -> String + Send + 'static) -> Result<(), String> {
+    let listener = TcpListener::bind(address)
+        .map_err(|error| format!("Could not serve metrics on {address}: {error}"))?;
+    thread::spawn(move || {
+        for stream in listener.incoming().map_while(Result::ok) {
+            if let Err(error) = answer(&stream, &report()) {
+                eprintln!("Metrics request failed: {error}");
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Board, Symbol};
+
+    #[test]
+    fn events_are_counted() {
+        let metrics = Metrics::default();
+        let board = Board::new();
+        metrics.count(&Event::Created {
+            board: &board,
+            to_move: Symbol::Plus,
+            opponent: None,
+        });
+        metrics.count(&Event::Moved {
+            symbol: Symbol::Plus,
+            position: [1, 1],
+            board: &board,
+        });
+        metrics.rejected();
+
+        let text = metrics.render(3);
+        assert!(text.contains(
+            "# TYPE tic_tac_toe_connected_clients gauge\ntic_tac_toe_connected_clients 3\n"
+        ));
+        assert!(text.contains("\ntic_tac_toe_active_games 1\n"));
+        assert!(text.contains("\ntic_tac_toe_moves_total 1\n"));
+        assert!(text.contains("\ntic_tac_toe_invalid_moves_total 1\n"));
+
+        metrics.count(&Event::Abandoned { board: &board });
+        assert!(metrics.render(0).contains("\ntic_tac_toe_active_games 0\n"));
+    }
+}
@@ -0,0 +1,483 @@
+// Game events over MQTT, so displays such as an LED matrix can mirror live
+// games. Every event (see src/events.rs) is published as JSON to a topic per
+// game, <prefix>/game/<number>, and `watch` subscribes to them all and draws
+// every game's board as it changes.
+//
+// A minimal MQTT 3.1.1 client over plain TCP: QoS 0, no retained messages,
+// no TLS and no keep alive, so nothing needs to be sent while a game waits.
+// Events are published from a thread of their own, so a slow broker never
+// holds up a game; while the broker is away they are dropped.
+
+use std::io::{self, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::str::FromStr;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::board::Board;
+use crate::console::Console;
+use crate::game::parse_player_move;
+use crate::view::Panel;
+
+const DEFAULT_PORT: u16 = 1883;
+const DEFAULT_PREFIX: &str = "tic-tac-toe";
+
+// How long the broker gets to accept a connection or a message.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// How often a lost broker is tried again, as events come in.
+const RETRY: Duration = Duration::from_secs(10);
+
+// Packets beyond this are not events, so a broker cannot fill the memory.
+const MAX_PACKET: usize = 64 * 1024;
+
+// The most games watched at once, so they still fit a terminal.
+const MAX_GAMES: usize = 12;
+
+// Packet types, in the high four bits of the first byte.
+const CONNECT: u8 = 1;
+const CONNACK: u8 = 2;
+const PUBLISH: u8 = 3;
+const SUBSCRIBE: u8 = 8;
+
+// A broker and the topic prefix, from [mqtt://]host[:port][/prefix].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Broker {
+    address: String,
+    prefix: String,
+}
+
+impl FromStr for Broker {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s.strip_prefix("mqtt://").unwrap_or(s);
+        let (host, prefix) = rest.split_once('/').unwrap_or((rest, DEFAULT_PREFIX));
+        let prefix = prefix.trim_end_matches('/');
+        if host.is_empty() || prefix.is_empty() {
+            return Err(format!(
+                "'{s}' is not an MQTT broker, use e.g. localhost:1883/tic-tac-toe."
+            ));
+        }
+        // Wildcards only go in subscriptions, and topics have a length limit.
+        if prefix.contains(['+', '#']) || prefix.len() > 200 {
+            return Err(format!("'{prefix}' cannot start an MQTT topic."));
+        }
+        let address = if host
+            .rsplit_once(':')
+            .is_some_and(|(_, port)| !port.contains(']'))
+        {
+            host.to_string()
+        } else {
+            format!("{host}:{DEFAULT_PORT}")
+        };
+
+        Ok(Broker {
+            address,
+            prefix: prefix.to_string(),
+        })
+    }
+}
+
+// Lengths are sent 7 bits at a time, the lowest first.
+fn remaining_length(mut length: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = u8::try_from(length % 128).expect("below 128");
+        length /= 128;
+        if length == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+// A string with its length in front, as topics and names are sent.
+fn string(text: &str) -> Vec<u8> {
+    let length = u16::try_from(text.len()).expect("topics are kept short");
+    [&length.to_be_bytes()[..], text.as_bytes()].concat()
+}
+
+fn packet(first: u8, body: &[u8]) -> Vec<u8> {
+    [&[first][..], &remaining_length(body.len()), body].concat()
+}
+
+// A clean session without keep alive.
+fn connect_packet(client: &str) -> Vec<u8> {
+    let body = [&string("MQTT")[..], &[4, 0x02, 0, 0], &string(client)].concat();
+    packet(CONNECT << 4, &body)
+}
+
+fn publish_packet(topic: &str, payload: &[u8]) -> Vec<u8> {
+    packet(PUBLISH << 4, &[&string(topic)[..], payload].concat())
+}
+
+// Subscribes to the filter at QoS 0, as packet 1.
+fn subscribe_packet(filter: &str) -> Vec<u8> {
+    let body = [&[0, 1][..], &string(filter), &[0]].concat();
+    packet(SUBSCRIBE << 4 | 0x02, &body)
+}
+
+// The next packet's first byte and body.
+fn read_packet(reader: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    let first = byte[0];
+
+    let mut length = 0;
+    for shift in [0, 7, 14, 21] {
+        reader.read_exact(&mut byte)?;
+        length |= usize::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    if length > MAX_PACKET {
+        return Err(io::Error::other(format!(
+            "a {length} byte packet is too big"
+        )));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok((first, body))
+}
+
+// The topic and payload of a PUBLISH packet's body.
+fn parse_publish(first: u8, body: &[u8]) -> Option<(String, &[u8])> {
+    let length = usize::from(u16::from_be_bytes([*body.first()?, *body.get(1)?]));
+    let topic = String::from_utf8(body.get(2..2 + length)?.to_vec()).ok()?;
+    // QoS 1 and 2 messages carry a packet id after the topic.
+    let start = if first & 0x06 == 0 {
+        2 + length
+    } else {
+        4 + length
+    };
+    Some((topic, body.get(start..)?))
+}
+
+// Connects with a client id made of the role and the process id.
+fn open(broker: &Broker, role: &str) -> Result<TcpStream, String> {
+    let failed = |error: io::Error| {
+        format!(
+            "Could not connect to the MQTT broker {}: {error}",
+            broker.address
+        )
+    };
+    let mut stream = TcpStream::connect(&broker.address).map_err(failed)?;
+    stream.set_read_timeout(Some(TIMEOUT)).map_err(failed)?;
+    stream.set_write_timeout(Some(TIMEOUT)).map_err(failed)?;
+    stream
+        .write_all(&connect_packet(&format!("ttt{role}{}", std::process::id())))
+        .map_err(failed)?;
+
+    match read_packet(&mut stream).map_err(failed)? {
+        (first, body) if first >> 4 == CONNACK && body.get(1) == Some(&0) => {
+            stream.set_read_timeout(None).map_err(failed)?;
+            Ok(stream)
+        }
+        (_, body) => Err(format!(
+            "The MQTT broker {} refused the connection (code {}).",
+            broker.address,
+            body.get(1).copied().unwrap_or_default()
+        )),
+    }
+}
+
+// Sends events to the broker, reconnecting at most every RETRY after the
+// connection is lost. Losing and regaining the broker is reported once each.
+fn deliver(broker: &Broker, stream: TcpStream, events: &mpsc::Receiver<(u64, String)>) {
+    let mut stream = Some(stream);
+    let mut retry_at = Instant::now();
+    for (game, json) in events {
+        if stream.is_none() && Instant::now() >= retry_at {
+            retry_at = Instant::now() + RETRY;
+            stream = open(broker, "pub").ok();
+            if stream.is_some() {
+                eprintln!("Reconnected to the MQTT broker {}.", broker.address);
+            }
+        }
+        let Some(connection) = &mut stream else {
+            continue;
+        };
+        let topic = format!("{}/game/{game}", broker.prefix);
+        if let Err(error) = connection.write_all(&publish_packet(&topic, json.as_bytes())) {
+            eprintln!(
+                "Lost the MQTT broker {}, events are dropped until it is back: {error}",
+                broker.address
+            );
+            stream = None;
+            retry_at = Instant::now() + RETRY;
+        }
+    }
+}
+
+// The publishing thread, which finishes sending what is queued once every
+// publisher is gone, so the last events of a game are not lost at exit.
+struct Delivery {
+    events: Option<Sender<(u64, String)>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Delivery {
+    fn drop(&mut self) {
+        self.events.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Publishes events, shared by every game of the process.
+#[derive(Clone)]
+pub struct Publisher {
+    delivery: Arc<Delivery>,
+}
+
+impl Publisher {
+    // Connects right away, so a wrong broker is an error before any game.
+    pub fn connect(broker: &Broker) -> Result<Publisher, String> {
+        let stream = open(broker, "pub")?;
+        let broker = broker.clone();
+        let (events, received) = mpsc::channel();
+        let thread = thread::spawn(move || deliver(&broker, stream, &received));
+
+        Ok(Publisher {
+            delivery: Arc::new(Delivery {
+                events: Some(events),
+                thread: Some(thread),
+            }),
+        })
+    }
+
+    pub fn publish(&self, game: u64, json: &str) {
+        if let Some(events) = &self.delivery.events {
+            let _ = events.send((game, json.to_string()));
+        }
+    }
+}
+
+// The value of a field in the flat JSON objects events are sent as, strings
+// without their quotes.
+fn field<'a>(json: &'a str, name: &str) -> Option<&'a str> {
+    let key = format!("\"{name}\":");
+    let rest = &json[json.find(&key)? + key.len()..];
+    if let Some(text) = rest.strip_prefix('"') {
+        text.split('"').next()
+    } else if rest.starts_with('[') {
+        rest.find(']').map(|end| &rest[..=end])
+    } else {
+        rest.split([',', '}']).next()
+    }
+}
+
+// A game as the last event showed it.
+struct Watched {
+    number: u64,
+    board: Board,
+    marked: Vec<[usize; 2]>,
+    state: String,
+    finished: bool,
+}
+
+// Every game seen, oldest first.
+#[derive(Default)]
+struct Watcher {
+    games: Vec<Watched>,
+}
+
+impl Watcher {
+    // Takes in an event and returns whether it was one.
+    fn update(&mut self, json: &str) -> bool {
+        let (Some(event), Some(Ok(number)), Some(Ok(board))) = (
+            field(json, "event"),
+            field(json, "game").map(str::parse),
+            field(json, "board").map(str::parse::<Board>),
+        ) else {
+            return false;
+        };
+        let marked = field(json, "move")
+            .and_then(|position| parse_player_move(position.trim_matches(['[', ']'])).ok())
+            .into_iter()
+            .collect();
+        let state = match (event, field(json, "result")) {
+            ("created", _) => format!("{} to move", field(json, "to_move").unwrap_or("+")),
+            ("move", _) if field(json, "symbol") == Some("+") => String::from("o to move"),
+            ("move", _) => String::from("+ to move"),
+            ("abandoned", _) => String::from("Abandoned"),
+            (_, Some("player_one")) => String::from("Player 1 won"),
+            (_, Some("player_two")) => String::from("Player 2 won"),
+            _ => String::from("Drawn"),
+        };
+
+        let game = Watched {
+            number,
+            board,
+            marked,
+            state,
+            finished: matches!(event, "finished" | "abandoned"),
+        };
+        if let Some(watched) = self.games.iter_mut().find(|game| game.number == number) {
+            *watched = game;
+            return true;
+        }
+        if self.games.len() == MAX_GAMES {
+            // The oldest finished game makes room, or else the oldest.
+            let oldest = self.games.iter().position(|game| game.finished);
+            self.games.remove(oldest.unwrap_or(0));
+        }
+        self.games.push(game);
+        true
+    }
+
+    fn panels(&self) -> Vec<Panel> {
+        self.games
+            .iter()
+            .map(|game| Panel {
+                board: game.board.clone(),
+                marked: game.marked.clone(),
+                status: format!("Game {}\n{}", game.number, game.state),
+            })
+            .collect()
+    }
+}
+
+// Draws every game published under the broker's prefix until the broker
+// closes the connection.
+pub fn watch(console: &mut Console, broker: &Broker) -> Result<(), String> {
+    let mut stream = open(broker, "sub")?;
+    let filter = format!("{}/game/+", broker.prefix);
+    stream
+        .write_all(&subscribe_packet(&filter))
+        .map_err(|error| format!("Could not subscribe to {filter}: {error}"))?;
+    console.say(&format!(
+        "Watching {filter} on {}, waiting for games.",
+        broker.address
+    ));
+
+    let mut reader = BufReader::new(stream);
+    let mut watcher = Watcher::default();
+    loop {
+        let (first, body) = match read_packet(&mut reader) {
+            Ok(packet) => packet,
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(error) => return Err(format!("The MQTT connection failed: {error}")),
+        };
+        // Acknowledgements and the like.
+        if first >> 4 != PUBLISH {
+            continue;
+        }
+        let Some((_, payload)) = parse_publish(first, &body) else {
+            continue;
+        };
+        if watcher.update(&String::from_utf8_lossy(payload)) {
+            let boards = console.renderer().panels(&watcher.panels());
+            console.clear();
+            console.show(&format!("\n{boards}\n"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn brokers_default_the_port_and_prefix() {
+        let broker: Broker = "localhost".parse().unwrap();
+        assert_eq!(broker.address, "localhost:1883");
+        assert_eq!(broker.prefix, "tic-tac-toe");
+
+        let broker: Broker = "mqtt://10.0.0.2:1884/home/ttt/".parse().unwrap();
+        assert_eq!(broker.address, "10.0.0.2:1884");
+        assert_eq!(broker.prefix, "home/ttt");
+
+        for broker in ["", "mqtt://", "localhost/#", "localhost/a+b"] {
+            assert!(broker.parse::<Broker>().is_err(), "{broker}");
+        }
+    }
+
+    #[test]
+    fn packets_are_encoded_as_the_standard_says() {
+        assert_eq!(remaining_length(127), [0x7f]);
+        assert_eq!(remaining_length(321), [0xc1, 0x02]);
+        assert_eq!(
+            connect_packet("ttt"),
+            b"\x10\x0f\x00\x04MQTT\x04\x02\x00\x00\x00\x03ttt"
+        );
+        assert_eq!(publish_packet("a/b", b"{}"), b"\x30\x07\x00\x03a/b{}");
+        assert_eq!(subscribe_packet("a/+"), b"\x82\x08\x00\x01\x00\x03a/+\x00");
+    }
+
+    #[test]
+    fn published_events_are_read_back() {
+        let payload = vec![b'x'; 200];
+        let mut reader = Cursor::new(publish_packet("ttt/game/4", &payload));
+        let (first, body) = read_packet(&mut reader).unwrap();
+
+        assert_eq!(
+            parse_publish(first, &body),
+            Some((String::from("ttt/game/4"), &payload[..]))
+        );
+        assert!(read_packet(&mut reader).is_err());
+        let huge = [&[0x30][..], &remaining_length(MAX_PACKET + 1)].concat();
+        assert!(read_packet(&mut Cursor::new(huge)).is_err());
+    }
+
+    #[test]
+    fn queued_events_are_published_before_the_publisher_goes() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let broker: Broker = format!("{}/ttt", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            read_packet(&mut stream).unwrap();
+            stream.write_all(&[CONNACK << 4, 2, 0, 0]).unwrap();
+            let mut topics = Vec::new();
+            while let Ok((first, body)) = read_packet(&mut stream) {
+                topics.push(parse_publish(first, &body).unwrap().0);
+            }
+            topics
+        });
+
+        let publisher = Publisher::connect(&broker).unwrap();
+        publisher.publish(1, "{}");
+        publisher.clone().publish(2, "{}");
+        drop(publisher);
+        assert_eq!(server.join().unwrap(), ["ttt/game/1", "ttt/game/2"]);
+    }
+
+    #[test]
+    fn watched_games_follow_their_events() {
+        let mut watcher = Watcher::default();
+        assert!(watcher.update(
+            r#"{"event":"created","game":3,"board":"---/---/---","to_move":"+","opponent":null}"#
+        ));
+        assert!(watcher.update(
+            r#"{"event":"move","game":3,"symbol":"+","move":[1,1],"board":"---/-+-/---"}"#
+        ));
+        assert!(!watcher.update("{}"));
+
+        let panels = watcher.panels();
+        assert_eq!(panels.len(), 1);
+        assert_eq!(panels[0].marked, [[1, 1]]);
+        assert_eq!(panels[0].status, "Game 3\no to move");
+
+        watcher.update(
+            r#"{"event":"finished","game":3,"result":"player_two","reason":"line","board":"---/-+-/---"}"#,
+        );
+        for game in 4..=15 {
+            watcher.update(&format!(
+                r#"{{"event":"created","game":{game},"board":"---/---/---","to_move":"o","opponent":null}}"#
+            ));
+        }
+        let panels = watcher.panels();
+        assert_eq!(panels.len(), MAX_GAMES);
+        assert_eq!(panels[0].status, "Game 4\no to move");
+    }
+}
@@ -0,0 +1,304 @@
+// A small neural network as the AI's evaluation function, available with the
+// `nn` feature. It is a plain multilayer perceptron, meant for experimenting
+// rather than for strength.
+//
+// Model files are little-endian:
+//
+//   b"TTTNN1"                     magic
+//   u32 layer count
+//   per layer:
+//     u32 inputs, u32 outputs
+//     f32 weights[outputs][inputs]
+//     f32 biases[outputs]
+//
+// The first layer takes one input per tile, seen from the side to move: 1 for
+// its own marks, -1 for the opponent's and 0 for empty tiles. Hidden layers use
+// ReLU and the single output is squashed with tanh into a score from -1 (lost)
+// to 1 (won) for the side to move.
+
+use std::fs;
+use std::path::Path;
+
+use crate::board::{Board, Symbol};
+use crate::bot::Bot;
+
+const MAGIC: &[u8] = b"TTTNN1";
+// Keeps a corrupt header from asking for gigabytes of weights.
+const MAX_LAYER_WIDTH: usize = 4096;
+
+struct Layer {
+    inputs: usize,
+    outputs: usize,
+    weights: Vec<f32>,
+    biases: Vec<f32>,
+}
+
+impl Layer {
+    fn apply(&self, input: &[f32]) -> Vec<f32> {
+        (0..self.outputs)
+            .map(|output| {
+                let weights = &self.weights[output * self.inputs..(output + 1) * self.inputs];
+                self.biases[output]
+                    + weights
+                        .iter()
+                        .zip(input)
+                        .map(|(weight, value)| weight * value)
+                        .sum::<f32>()
+            })
+            .collect()
+    }
+}
+
+pub struct Network {
+    layers: Vec<Layer>,
+}
+
+// Reads the model file front to back.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take(&mut self, count: usize) -> Result<&[u8], String> {
+        if self.bytes.len() < count {
+            return Err(String::from("the file ends too early"));
+        }
+
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<usize, String> {
+        let bytes = self.take(4)?;
+        let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        usize::try_from(value).map_err(|error| error.to_string())
+    }
+
+    fn f32s(&mut self, count: usize) -> Result<Vec<f32>, String> {
+        let bytes = self.take(count * 4)?;
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect())
+    }
+}
+
+impl Network {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Network, String> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(String::from("it is not a tic-tac-toe network"));
+        }
+
+        let mut layers = Vec::new();
+        for _ in 0..reader.u32()? {
+            let inputs = reader.u32()?;
+            let outputs = reader.u32()?;
+            if inputs == 0 || outputs == 0 || inputs > MAX_LAYER_WIDTH || outputs > MAX_LAYER_WIDTH
+            {
+                return Err(format!(
+                    "a layer has an unsupported shape {inputs}x{outputs}"
+                ));
+            }
+            if layers
+                .last()
+                .is_some_and(|previous: &Layer| previous.outputs != inputs)
+            {
+                return Err(String::from("the layer sizes do not line up"));
+            }
+
+            layers.push(Layer {
+                inputs,
+                outputs,
+                weights: reader.f32s(inputs * outputs)?,
+                biases: reader.f32s(outputs)?,
+            });
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(String::from("there is data after the last layer"));
+        }
+        if layers.last().is_none_or(|last| last.outputs != 1) {
+            return Err(String::from("the last layer must have a single output"));
+        }
+
+        Ok(Network { layers })
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.layers[0].inputs
+    }
+
+    // Scores the board from -1 to 1 for symbol, the side to move.
+    pub fn evaluate(&self, board: &Board, symbol: Symbol) -> f32 {
+        let mut values = board
+            .tiles
+            .iter()
+            .flatten()
+            .map(|tile| match *tile {
+                Symbol::Empty => 0.0,
+                tile if tile == symbol => 1.0,
+                _ => -1.0,
+            })
+            .collect::<Vec<f32>>();
+
+        for (i, layer) in self.layers.iter().enumerate() {
+            values = layer.apply(&values);
+            if i + 1 < self.layers.len() {
+                for value in &mut values {
+                    *value = value.max(0.0);
+                }
+            }
+        }
+
+        values[0].tanh()
+    }
+}
+
+// Plays the move after which the network likes the opponent's position least,
+// always taking a win when there is one.
+pub struct NeuralBot {
+    name: String,
+    network: Network,
+}
+
+impl NeuralBot {
+    pub fn load(path: &Path) -> Result<NeuralBot, String> {
+        let failed =
+            |reason: String| format!("Could not load network {}: {reason}", path.display());
+        let bytes = fs::read(path).map_err(|error| failed(error.to_string()))?;
+        let network = Network::from_bytes(&bytes).map_err(failed)?;
+
+        let tiles = Board::new().tiles.iter().flatten().count();
+        if network.inputs() != tiles {
+            return Err(failed(format!(
+                "it takes {} inputs but the board has {tiles} tiles",
+                network.inputs()
+            )));
+        }
+
+        let stem = path.file_stem().map_or_else(
+            || String::from("network"),
+            |stem| stem.to_string_lossy().into_owned(),
+        );
+
+        Ok(NeuralBot {
+            name: format!("Neural network ({stem})"),
+            network,
+        })
+    }
+}
+
+impl Bot for NeuralBot {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn choose_move(&mut self, board: &Board, symbol: Symbol) -> Option<[usize; 2]> {
+        let mut best: Option<([usize; 2], f32)> = None;
+
+        for position in board.empty_positions() {
+            let mut next = board.clone();
+            let score = if next.place(symbol, position) {
+                f32::INFINITY
+            } else {
+                -self.network.evaluate(&next, symbol.opponent())
+            };
+
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((position, score));
+            }
+        }
+
+        best.map(|(position, _)| position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(layers: &[(usize, usize, Vec<f32>, Vec<f32>)]) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        let count = |value: usize| u32::try_from(value).unwrap().to_le_bytes();
+
+        bytes.extend(count(layers.len()));
+        for (inputs, outputs, weights, biases) in layers {
+            bytes.extend(count(*inputs));
+            bytes.extend(count(*outputs));
+            for value in weights.iter().chain(biases) {
+                bytes.extend(value.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    // Likes having marks in the center and dislikes the opponent having them.
+    fn center_lover() -> Vec<u8> {
+        let mut weights = vec![0.1; 9];
+        weights[4] = 1.0;
+        model(&[(9, 1, weights, vec![0.0])])
+    }
+
+    #[test]
+    fn evaluation_is_from_the_side_to_move() {
+        let network = Network::from_bytes(&center_lover()).unwrap();
+        let board: Board = "---/-+-/---".parse().unwrap();
+
+        assert!(network.evaluate(&board, Symbol::Plus) > 0.5);
+        assert!(network.evaluate(&board, Symbol::Circle) < -0.5);
+        assert!(network.evaluate(&Board::new(), Symbol::Plus).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn hidden_layers_use_relu() {
+        // The hidden unit only passes positive sums, so the output is never negative.
+        let network = Network::from_bytes(&model(&[
+            (9, 1, vec![1.0; 9], vec![0.0]),
+            (1, 1, vec![1.0], vec![0.0]),
+        ]))
+        .unwrap();
+        let board: Board = "o--/---/---".parse().unwrap();
+
+        assert!(network.evaluate(&board, Symbol::Plus).abs() < f32::EPSILON);
+        assert!(network.evaluate(&board, Symbol::Circle) > 0.0);
+    }
+
+    #[test]
+    fn bot_follows_the_network_but_takes_wins() {
+        let mut bot = NeuralBot {
+            name: String::from("test"),
+            network: Network::from_bytes(&center_lover()).unwrap(),
+        };
+
+        assert_eq!(bot.choose_move(&Board::new(), Symbol::Plus), Some([1, 1]));
+        assert_eq!(
+            bot.choose_move(&"++-/---/oo-".parse().unwrap(), Symbol::Plus),
+            Some([0, 2])
+        );
+    }
+
+    #[test]
+    fn malformed_models_are_rejected() {
+        let mut truncated = center_lover();
+        truncated.pop();
+        let mut trailing = center_lover();
+        trailing.push(0);
+
+        for bytes in [
+            Vec::new(),
+            b"NOTANN".to_vec(),
+            truncated,
+            trailing,
+            model(&[]),
+            model(&[(9, 2, vec![0.0; 18], vec![0.0; 2])]),
+            model(&[
+                (9, 2, vec![0.0; 18], vec![0.0; 2]),
+                (3, 1, vec![0.0; 3], vec![0.0]),
+            ]),
+        ] {
+            assert!(Network::from_bytes(&bytes).is_err());
+        }
+    }
+}
@@ -0,0 +1,264 @@
+// Results of games against the computer by their opening, the first move of
+// each side, kept across sessions so the openings that lose games stand out.
+//
+// The file has a line per opponent and opening, tab separated: the bot as
+// given to --p2, the opening as in game records and the wins, draws and
+// losses of the human player.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write as _};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::board::Symbol;
+use crate::browser::GameRecord;
+use crate::profile;
+use crate::tournament::Record;
+
+// Where the statistics are kept unless TTT_OPENINGS says otherwise.
+pub const DEFAULT_OPENINGS: &str = "tic-tac-toe.openings";
+
+// How many plies make up an opening.
+const OPENING_PLIES: usize = 2;
+
+pub fn path() -> PathBuf {
+    std::env::var_os("TTT_OPENINGS").map_or_else(|| profile::file(DEFAULT_OPENINGS), PathBuf::from)
+}
+
+// The first moves of a game from an empty board, e.g. "+1,1 o0,0". Games
+// from a set up position have no opening.
+pub fn opening(record: &GameRecord) -> Option<String> {
+    if record.is_empty()
+        || !record
+            .start()
+            .tiles
+            .iter()
+            .flatten()
+            .all(|tile| *tile == Symbol::Empty)
+    {
+        return None;
+    }
+
+    let plies = record
+        .moves()
+        .iter()
+        .take(OPENING_PLIES)
+        .map(|(symbol, [row, column])| {
+            let symbol: &str = (*symbol).into();
+            format!("{symbol}{row},{column}")
+        })
+        .collect::<Vec<_>>();
+    Some(plies.join(" "))
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct OpeningStats {
+    // Keyed by opponent, then opening.
+    results: BTreeMap<(String, String), Record>,
+}
+
+impl OpeningStats {
+    pub fn add(&mut self, opponent: &str, opening: &str, result: Record) {
+        self.results
+            .entry((opponent.to_string(), opening.to_string()))
+            .or_default()
+            .add(result);
+    }
+
+    // Adds the games tracked elsewhere, e.g. in an imported profile.
+    pub fn merge(&mut self, other: OpeningStats) {
+        for ((opponent, opening), result) in other.results {
+            self.add(&opponent, &opening, result);
+        }
+    }
+
+    // A table per opponent, the openings that lost the most games first.
+    pub fn report(&self) -> String {
+        if self.results.is_empty() {
+            return String::from("No games against the computer have been tracked yet.");
+        }
+
+        let mut by_opponent: BTreeMap<&str, Vec<(&str, Record)>> = BTreeMap::new();
+        for ((opponent, opening), record) in &self.results {
+            by_opponent
+                .entry(opponent)
+                .or_default()
+                .push((opening, *record));
+        }
+
+        let mut report = String::new();
+        for (opponent, mut openings) in by_opponent {
+            openings.sort_by_key(|(opening, record)| {
+                (
+                    std::cmp::Reverse(record.losses),
+                    std::cmp::Reverse(record.games()),
+                    *opening,
+                )
+            });
+
+            let _ = write!(
+                report,
+                "{}Openings against {opponent}:\n{:<11}  {:>5}  {:>4}  {:>4}  {:>4}  {:>5}\n",
+                if report.is_empty() { "" } else { "\n" },
+                "Opening",
+                "Games",
+                "Won",
+                "Drew",
+                "Lost",
+                "Lost%"
+            );
+            for (opening, record) in openings {
+                let _ = writeln!(
+                    report,
+                    "{opening:<11}  {:>5}  {:>4}  {:>4}  {:>4}  {:>4.0}%",
+                    record.games(),
+                    record.wins,
+                    record.draws,
+                    record.losses,
+                    f64::from(record.losses) * 100.0 / f64::from(record.games().max(1))
+                );
+            }
+        }
+
+        report.trim_end().to_string()
+    }
+}
+
+impl Display for OpeningStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for ((opponent, opening), record) in &self.results {
+            writeln!(
+                f,
+                "{opponent}\t{opening}\t{}\t{}\t{}",
+                record.wins, record.draws, record.losses
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for OpeningStats {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut stats = OpeningStats::default();
+
+        for (i, line) in s
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+        {
+            let invalid = || format!("Line {} of the opening statistics is invalid.", i + 1);
+            let [opponent, opening, wins, draws, losses] = line.split('\t').collect::<Vec<_>>()[..]
+            else {
+                return Err(invalid());
+            };
+            let count = |value: &str| value.parse::<u32>().map_err(|_| invalid());
+
+            stats.add(
+                opponent,
+                opening,
+                Record {
+                    wins: count(wins)?,
+                    draws: count(draws)?,
+                    losses: count(losses)?,
+                },
+            );
+        }
+
+        Ok(stats)
+    }
+}
+
+// Nothing tracked yet is not an error.
+pub fn load(path: &Path) -> Result<OpeningStats, String> {
+    if !path.exists() {
+        return Ok(OpeningStats::default());
+    }
+    fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {error}", path.display()))?
+        .parse()
+        .map_err(|error| format!("{}: {error}", path.display()))
+}
+
+// Where a game's opening is tracked and who it is against.
+#[derive(Clone)]
+pub struct OpeningTracker {
+    path: PathBuf,
+    opponent: String,
+}
+
+impl OpeningTracker {
+    pub fn new(path: PathBuf, opponent: String) -> OpeningTracker {
+        OpeningTracker { path, opponent }
+    }
+
+    // Adds the human player's result to the game's opening, if it has one.
+    pub fn track(&self, record: &GameRecord, result: Record) -> Result<(), String> {
+        let Some(opening) = opening(record) else {
+            return Ok(());
+        };
+
+        let mut stats = load(&self.path)?;
+        stats.add(&self.opponent, &opening, result);
+        fs::write(&self.path, stats.to_string()).map_err(|error| {
+            format!(
+                "Could not save the openings to {}: {error}",
+                self.path.display()
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn win() -> Record {
+        Record {
+            wins: 1,
+            ..Record::default()
+        }
+    }
+
+    fn loss() -> Record {
+        Record {
+            losses: 1,
+            ..Record::default()
+        }
+    }
+
+    #[test]
+    fn openings_are_the_first_two_plies_from_an_empty_board() {
+        let record: GameRecord = "---/---/--- +1,1 o0,0 +2,2".parse().unwrap();
+        assert_eq!(opening(&record).as_deref(), Some("+1,1 o0,0"));
+
+        let record: GameRecord = "---/---/--- o0,2".parse().unwrap();
+        assert_eq!(opening(&record).as_deref(), Some("o0,2"));
+
+        for record in ["---/---/---", "+--/---/--- o1,1 +2,2"] {
+            assert_eq!(opening(&record.parse().unwrap()), None);
+        }
+    }
+
+    #[test]
+    fn stats_round_trip_and_report_losing_openings_first() {
+        let mut stats = OpeningStats::default();
+        stats.add("hard", "+0,0 o1,1", win());
+        stats.add("hard", "+1,1 o0,0", loss());
+        stats.add("hard", "+1,1 o0,0", loss());
+        stats.add("easy", "o1,1 +0,0", win());
+
+        let parsed: OpeningStats = stats.to_string().parse().unwrap();
+        assert_eq!(parsed, stats);
+
+        let report = stats.report();
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "Openings against easy:");
+        assert_eq!(lines[4], "Openings against hard:");
+        assert!(lines[6].starts_with("+1,1 o0,0        2     0     0     2"));
+        assert!(lines[7].starts_with("+0,0 o1,1        1     1"));
+        assert!("hard\t+1,1\tmany\t0\t0".parse::<OpeningStats>().is_err());
+    }
+}
@@ -0,0 +1,161 @@
+// Color schemes for the board. The standard one tells the sides apart by red
+// and blue; the others keep to colors that stay apart with each kind of
+// color blindness. Highlighted tiles are bracketed in every scheme, so
+// nothing depends on seeing the colors.
+//
+// High contrast is for low vision: bold white on black against inverse video
+// on the board, and prompts, results and warnings in bold too.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::formatter::{BoardFormatter, Colors};
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Palette {
+    // No colors at all, e.g. for terminals without them.
+    #[default]
+    Plain,
+    Standard,
+    // Red-green color blindness, blue against orange.
+    Deuteranopia,
+    // Red-green with reds seen dark, blue against yellow.
+    Protanopia,
+    // Blue-yellow color blindness, red against teal.
+    Tritanopia,
+    HighContrast,
+}
+
+pub const PALETTES: [Palette; 6] = [
+    Palette::Plain,
+    Palette::Standard,
+    Palette::Deuteranopia,
+    Palette::Protanopia,
+    Palette::Tritanopia,
+    Palette::HighContrast,
+];
+
+// What a line of text is, for styling it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Role {
+    Prompt,
+    Result,
+    Warning,
+}
+
+// Wraps every line of text in the style, starting it again after resets
+// from colors inside, e.g. a colored board in a result.
+pub fn paint(text: &str, style: &str) -> String {
+    let start = format!("\x1b[{style}m");
+    text.split('\n')
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                let line = line.replace("\x1b[0m", &format!("\x1b[0m{start}"));
+                format!("{start}{line}\x1b[0m")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl Palette {
+    // Colors from the 256 color palette, with bold brackets for highlights.
+    pub fn colors(self) -> Option<Colors> {
+        let sides: &'static [&'static str] = match self {
+            Palette::Plain => return None,
+            Palette::Standard => &["1;31", "1;34"],
+            Palette::Deuteranopia => &["1;38;5;33", "1;38;5;208"],
+            Palette::Protanopia => &["1;38;5;33", "1;38;5;220"],
+            Palette::Tritanopia => &["1;38;5;160", "1;38;5;37"],
+            Palette::HighContrast => &["1;97;40", "1;7"],
+        };
+        let marked = match self {
+            Palette::HighContrast => "1;93;40",
+            _ => "1",
+        };
+        Some(Colors { sides, marked })
+    }
+
+    // The style of text outside the board, None to leave it as it is.
+    pub fn style(self, role: Role) -> Option<&'static str> {
+        match (self, role) {
+            (Palette::HighContrast, Role::Prompt) => Some("1;97"),
+            (Palette::HighContrast, Role::Result) => Some("1;7"),
+            (Palette::HighContrast, Role::Warning) => Some("1;93"),
+            _ => None,
+        }
+    }
+
+    // The game's board formatter in these colors.
+    pub fn formatter(self) -> BoardFormatter {
+        BoardFormatter {
+            colors: self.colors(),
+            ..BoardFormatter::default()
+        }
+    }
+}
+
+impl Display for Palette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Palette::Plain => "plain",
+            Palette::Standard => "standard",
+            Palette::Deuteranopia => "deuteranopia",
+            Palette::Protanopia => "protanopia",
+            Palette::Tritanopia => "tritanopia",
+            Palette::HighContrast => "high-contrast",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PALETTES
+            .into_iter()
+            .find(|palette| palette.to_string() == s)
+            .ok_or_else(|| {
+                format!(
+                    "'{s}' is not a palette, use plain, standard, deuteranopia, protanopia, tritanopia or high-contrast."
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palettes_round_trip() {
+        for palette in PALETTES {
+            assert_eq!(palette.to_string().parse(), Ok(palette));
+        }
+        assert!("rainbow".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn every_palette_tells_the_sides_apart() {
+        assert_eq!(Palette::Plain.colors(), None);
+        for palette in &PALETTES[1..] {
+            let colors = palette.colors().unwrap();
+            assert_eq!(colors.sides.len(), 2);
+            assert_ne!(colors.sides[0], colors.sides[1]);
+        }
+    }
+
+    #[test]
+    fn styles_carry_on_past_colors_inside() {
+        assert_eq!(
+            paint("a \x1b[31m+\x1b[0m b\n\nc", "1"),
+            "\x1b[1ma \x1b[31m+\x1b[0m\x1b[1m b\x1b[0m\n\n\x1b[1mc\x1b[0m"
+        );
+        assert_eq!(Palette::Standard.style(Role::Result), None);
+        assert!(Palette::HighContrast.style(Role::Prompt).is_some());
+    }
+}
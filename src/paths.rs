@@ -0,0 +1,142 @@
+// Where the files kept between runs live, following each platform's
+// conventions: $XDG_DATA_HOME (~/.local/share) and $XDG_CONFIG_HOME
+// (~/.config) elsewhere, ~/Library/Application Support on macOS and %APPDATA% on
+// Windows. Statistics, progress and saved games are data, settings are
+// configuration.
+//
+// Older versions kept these files in the working directory, and profile
+// settings with the profile's data. Files found there are moved the next
+// time the program runs from that directory.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    achievements, adaptive, autosave, campaign, daily, openings, profile, rating, streaks, transfer,
+};
+
+const APP_DIR: &str = "tic-tac-toe-rust";
+
+// The files older versions kept in the working directory.
+const LEGACY_FILES: [&str; 9] = [
+    autosave::DEFAULT_AUTOSAVE,
+    openings::DEFAULT_OPENINGS,
+    achievements::DEFAULT_ACHIEVEMENTS,
+    streaks::DEFAULT_STREAKS,
+    rating::DEFAULT_RATING,
+    campaign::DEFAULT_CAMPAIGN,
+    daily::DEFAULT_DAILY,
+    adaptive::DEFAULT_ADAPTIVE,
+    transfer::IMPORTS,
+];
+
+// The platform's directory for one kind of file, xdg naming the variable
+// and fallback the directory under the home directory used elsewhere.
+fn platform_dir(xdg: &str, fallback: &str) -> Option<PathBuf> {
+    let home = || std::env::var_os("HOME").map(PathBuf::from);
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library/Application Support"))
+    } else {
+        std::env::var_os(xdg)
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| home().map(|home| home.join(fallback)))
+    };
+    base.map(|base| base.join(APP_DIR))
+}
+
+pub fn data_dir() -> Option<PathBuf> {
+    platform_dir("XDG_DATA_HOME", ".local/share")
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    platform_dir("XDG_CONFIG_HOME", ".config")
+}
+
+// A data file, in the working directory when there is no data directory.
+pub fn data_file(name: &str) -> PathBuf {
+    data_dir().map_or_else(|| PathBuf::from(name), |dir| dir.join(name))
+}
+
+// Moves a file unless something is already at the destination, returning
+// what happened when anything did.
+fn migrate(from: &Path, to: &Path) -> Option<String> {
+    if !from.is_file() || from == to {
+        return None;
+    }
+    if to.exists() {
+        return Some(format!(
+            "Left {} where it is, since {} already exists.",
+            from.display(),
+            to.display()
+        ));
+    }
+
+    let moved = to
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        // A rename fails across file systems, so fall back to copying.
+        .and_then(|()| {
+            fs::rename(from, to).or_else(|_| fs::copy(from, to).and_then(|_| fs::remove_file(from)))
+        });
+    Some(match moved {
+        Ok(()) => format!("Moved {} to {}.", from.display(), to.display()),
+        Err(error) => format!("Could not move {}: {error}", from.display()),
+    })
+}
+
+// Creates the data directory and moves files from where older versions kept
+// them, returning a line for every file moved or left behind.
+pub fn prepare() -> Vec<String> {
+    let Some(data) = data_dir() else {
+        return Vec::new();
+    };
+    if let Err(error) = fs::create_dir_all(&data) {
+        return vec![format!(
+            "Could not create the data directory {}: {error}",
+            data.display()
+        )];
+    }
+
+    let mut moves = LEGACY_FILES
+        .iter()
+        .filter_map(|name| migrate(Path::new(name), &data.join(name)))
+        .collect::<Vec<_>>();
+    if let Some(name) = profile::selected() {
+        let legacy = data.join("profiles").join(name).join(profile::SETTINGS);
+        moves.extend(migrate(&legacy, &profile::settings_path()));
+    }
+    moves
+}
+
+// Where everything lives, for the paths command.
+pub fn report() -> String {
+    let unknown = || String::from("(none, using the working directory)");
+    let show = |dir: Option<PathBuf>| dir.map_or_else(unknown, |dir| dir.display().to_string());
+
+    let mut report = format!(
+        "Data:    {}\nConfig:  {}\nProfile: {}\n\n",
+        show(data_dir()),
+        show(config_dir()),
+        profile::selected().unwrap_or_else(|| String::from("none"))
+    );
+    let files = [
+        ("Settings", profile::settings_path()),
+        ("Saved game", autosave::path()),
+        ("Openings", openings::path()),
+        ("Achievements", achievements::path()),
+        ("Streaks", streaks::path()),
+        ("Rating", rating::path()),
+        ("Campaign", campaign::path()),
+        ("Daily puzzle", daily::path()),
+        ("Adaptive bot", adaptive::path()),
+        ("Imports", profile::file(transfer::IMPORTS)),
+    ];
+    for (name, path) in files {
+        let _ = writeln!(report, "{name:<14}{}", path.display());
+    }
+    report.trim_end().to_string()
+}
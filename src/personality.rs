@@ -0,0 +1,187 @@
+// Bot personalities: short lines a bot says when something notable happens,
+// read from a data file so new ones need no code. Without a personality a
+// bot plays in silence.
+//
+// Each line of the file is "name <name>" or an event followed by something
+// to say, and lines starting with # are comments.
+
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::board::{Board, Symbol};
+use crate::bot::Bot;
+use crate::rng::Rng;
+use crate::tournament::Record;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Event {
+    // The bot took the tile the player needed to win.
+    Block,
+    Win,
+    Loss,
+    Draw,
+}
+
+impl Event {
+    // A finished game's event, from a result on the bot's side.
+    pub fn ending(result: Record) -> Event {
+        if result.wins > 0 {
+            Event::Win
+        } else if result.losses > 0 {
+            Event::Loss
+        } else {
+            Event::Draw
+        }
+    }
+}
+
+impl FromStr for Event {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "block" => Ok(Event::Block),
+            "win" => Ok(Event::Win),
+            "loss" => Ok(Event::Loss),
+            "draw" => Ok(Event::Draw),
+            _ => Err(format!(
+                "'{s}' is not an event, use block, win, loss or draw."
+            )),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Personality {
+    pub name: String,
+    lines: Vec<(Event, String)>,
+}
+
+impl FromStr for Personality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut name = None;
+        let mut lines = Vec::new();
+
+        for line in s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        {
+            let Some((key, text)) = line.split_once(' ') else {
+                return Err(format!("'{line}' has nothing to say."));
+            };
+            match key {
+                "name" => name = Some(text.trim().to_string()),
+                event => lines.push((event.parse()?, text.trim().to_string())),
+            }
+        }
+
+        Ok(Personality {
+            name: name.ok_or_else(|| String::from("The personality has no name."))?,
+            lines,
+        })
+    }
+}
+
+pub fn load(path: &Path) -> Result<Personality, String> {
+    fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {error}", path.display()))?
+        .parse()
+        .map_err(|error| format!("{}: {error}", path.display()))
+}
+
+// Any bot with a personality on top, playing exactly as the bot does.
+pub struct PersonalityBot {
+    personality: Personality,
+    bot: Box<dyn Bot>,
+    rng: Rng,
+}
+
+impl PersonalityBot {
+    pub fn new(personality: Personality, bot: Box<dyn Bot>, rng: Rng) -> PersonalityBot {
+        PersonalityBot {
+            personality,
+            bot,
+            rng,
+        }
+    }
+}
+
+impl Bot for PersonalityBot {
+    fn name(&self) -> String {
+        format!("{}, {}", self.personality.name, self.bot.name())
+    }
+
+    fn choose_move(&mut self, board: &Board, symbol: Symbol) -> Option<[usize; 2]> {
+        self.bot.choose_move(board, symbol)
+    }
+
+    fn accepts_draw(&mut self, board: &Board, symbol: Symbol) -> bool {
+        self.bot.accepts_draw(board, symbol)
+    }
+
+    fn game_over(&mut self, result: Record) -> Result<(), String> {
+        self.bot.game_over(result)
+    }
+
+    fn remark(&mut self, event: Event) -> Option<String> {
+        let lines = self
+            .personality
+            .lines
+            .iter()
+            .filter(|(line_event, _)| *line_event == event)
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            return None;
+        }
+        let (_, line) = lines[self.rng.below(lines.len())];
+        Some(format!("{}: {line}", self.personality.name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::bot::BuiltinBot;
+
+    const GRUMPY: &str = include_str!("../examples/grumpy.personality");
+
+    #[test]
+    fn personalities_are_read_from_data_files() {
+        let personality: Personality = GRUMPY.parse().unwrap();
+        assert_eq!(personality.name, "Grumpy");
+        assert_eq!(
+            personality.lines[0],
+            (Event::Block, String::from("Not so fast."))
+        );
+
+        for text in ["block Nope.", "name Ann\ngloat Ha!", "name Ann\nwin"] {
+            assert!(text.parse::<Personality>().is_err());
+        }
+    }
+
+    #[test]
+    fn remarks_are_picked_for_the_event() {
+        let personality: Personality = "name Ann\nwin Yes!\nwin Again!".parse().unwrap();
+        let bot = Box::new(BuiltinBot::new(Difficulty::Hard, Rng::new(1)));
+        let mut bot = PersonalityBot::new(personality, bot, Rng::new(3));
+
+        assert_eq!(bot.name(), "Ann, Hard computer");
+        for _ in 0..10 {
+            let remark = bot.remark(Event::Win).unwrap();
+            assert!(remark == "Ann: Yes!" || remark == "Ann: Again!");
+        }
+        assert_eq!(bot.remark(Event::Block), None);
+        assert_eq!(
+            Event::ending(Record {
+                losses: 1,
+                ..Record::default()
+            }),
+            Event::Loss
+        );
+    }
+}
@@ -0,0 +1,257 @@
+// Bots compiled as shared libraries (cdylib) and loaded at runtime.
+//
+// A plugin exports three C functions:
+//
+//   uint32_t ttt_bot_abi_version(void);      must return PLUGIN_ABI_VERSION
+//   const char *ttt_bot_name(void);          a static NUL-terminated name
+//   int32_t ttt_bot_choose_move(const uint8_t *tiles, size_t size, uint8_t symbol);
+//
+// tiles holds size * size bytes row by row, 0 for empty, 1 for Plus and 2
+// for Circle. symbol is the one to play, and the return value is the index
+// row * size + column of the chosen tile, or -1 to pass. See
+// examples/plugin_bot.rs for a complete plugin. WASM bots (wasm.rs) use
+// the same board and move encoding.
+
+use std::ffi::{c_char, CStr, CString};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::board::{Board, Symbol};
+use crate::bot::Bot;
+
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+// Directory searched for plugins unless TTT_PLUGIN_DIR says otherwise.
+const DEFAULT_PLUGIN_DIR: &str = "plugins";
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type NameFn = unsafe extern "C" fn() -> *const c_char;
+type ChooseMoveFn = unsafe extern "C" fn(*const u8, usize, u8) -> i32;
+
+pub fn encode_symbol(symbol: Symbol) -> u8 {
+    match symbol {
+        Symbol::Empty => 0,
+        Symbol::Plus => 1,
+        Symbol::Circle => 2,
+    }
+}
+
+pub fn encode_tiles(board: &Board) -> Vec<u8> {
+    board
+        .tiles
+        .iter()
+        .flat_map(|row| row.iter().map(|tile| encode_symbol(*tile)))
+        .collect()
+}
+
+pub fn decode_move(index: i32, size: usize) -> Option<[usize; 2]> {
+    let index = usize::try_from(index).ok()?;
+
+    (index < size * size).then_some([index / size, index % size])
+}
+
+fn is_plugin_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("so" | "dylib" | "dll")
+    )
+}
+
+pub fn plugin_dir() -> PathBuf {
+    std::env::var_os("TTT_PLUGIN_DIR")
+        .map_or_else(|| PathBuf::from(DEFAULT_PLUGIN_DIR), PathBuf::from)
+}
+
+// Lists the shared libraries in the plugin directory, sorted by path.
+pub fn discover(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut plugins = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| is_plugin_file(path))
+        .collect::<Vec<_>>();
+    plugins.sort();
+    plugins
+}
+
+#[cfg(unix)]
+mod dl {
+    use std::ffi::{c_char, c_int, c_void, CStr};
+
+    pub const RTLD_NOW: c_int = 2;
+
+    extern "C" {
+        pub fn dlopen(filename: *const c_char, flag: c_int) -> *mut c_void;
+        pub fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+        pub fn dlclose(handle: *mut c_void) -> c_int;
+        fn dlerror() -> *mut c_char;
+    }
+
+    pub fn last_error() -> String {
+        // SAFETY: dlerror returns null or a NUL-terminated string owned by libc.
+        unsafe {
+            let error = dlerror();
+            if error.is_null() {
+                String::from("unknown error")
+            } else {
+                CStr::from_ptr(error).to_string_lossy().into_owned()
+            }
+        }
+    }
+}
+
+pub struct PluginBot {
+    name: String,
+    choose_move: ChooseMoveFn,
+    #[cfg(unix)]
+    handle: *mut std::ffi::c_void,
+}
+
+#[cfg(unix)]
+impl PluginBot {
+    pub fn load(path: &Path) -> Result<PluginBot, String> {
+        let failed = |reason: String| format!("Could not load plugin {}: {reason}", path.display());
+        let c_path = CString::new(path.as_os_str().as_encoded_bytes())
+            .map_err(|_| failed(String::from("the path contains a NUL byte")))?;
+
+        // SAFETY: c_path is a valid NUL-terminated string for the duration of the call.
+        let handle = unsafe { dl::dlopen(c_path.as_ptr(), dl::RTLD_NOW) };
+        if handle.is_null() {
+            return Err(failed(dl::last_error()));
+        }
+
+        let symbol = |name: &CStr| {
+            // SAFETY: handle came from a successful dlopen and name is NUL-terminated.
+            let pointer = unsafe { dl::dlsym(handle, name.as_ptr()) };
+            if pointer.is_null() {
+                Err(failed(format!("missing symbol {}", name.to_string_lossy())))
+            } else {
+                Ok(pointer)
+            }
+        };
+
+        let loaded = (|| {
+            // SAFETY: the plugin ABI documented above fixes the signatures of these symbols.
+            let (abi_version, name, choose_move) = unsafe {
+                (
+                    std::mem::transmute::<*mut std::ffi::c_void, AbiVersionFn>(symbol(
+                        c"ttt_bot_abi_version",
+                    )?),
+                    std::mem::transmute::<*mut std::ffi::c_void, NameFn>(symbol(c"ttt_bot_name")?),
+                    std::mem::transmute::<*mut std::ffi::c_void, ChooseMoveFn>(symbol(
+                        c"ttt_bot_choose_move",
+                    )?),
+                )
+            };
+
+            // SAFETY: calling into the plugin through its documented ABI.
+            let version = unsafe { abi_version() };
+            if version != PLUGIN_ABI_VERSION {
+                return Err(failed(format!(
+                    "it uses ABI version {version} but version {PLUGIN_ABI_VERSION} is required"
+                )));
+            }
+
+            // SAFETY: the ABI requires a static NUL-terminated name or null.
+            let name = unsafe {
+                let name = name();
+                if name.is_null() {
+                    return Err(failed(String::from("it has no name")));
+                }
+                CStr::from_ptr(name).to_string_lossy().into_owned()
+            };
+
+            Ok((name, choose_move))
+        })();
+
+        match loaded {
+            Ok((name, choose_move)) => Ok(PluginBot {
+                name,
+                choose_move,
+                handle,
+            }),
+            Err(error) => {
+                // SAFETY: handle is valid and nothing from the library is kept.
+                unsafe { dl::dlclose(handle) };
+                Err(error)
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+impl PluginBot {
+    pub fn load(path: &Path) -> Result<PluginBot, String> {
+        Err(format!(
+            "Could not load plugin {}: plugins are only supported on Unix-like systems.",
+            path.display()
+        ))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for PluginBot {
+    fn drop(&mut self) {
+        // SAFETY: handle came from dlopen and choose_move is not used after this.
+        unsafe { dl::dlclose(self.handle) };
+    }
+}
+
+impl Bot for PluginBot {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn choose_move(&mut self, board: &Board, symbol: Symbol) -> Option<[usize; 2]> {
+        let tiles = encode_tiles(board);
+        let size = board.tiles.len();
+
+        // SAFETY: tiles holds size * size bytes and outlives the call.
+        let index = unsafe { (self.choose_move)(tiles.as_ptr(), size, encode_symbol(symbol)) };
+
+        decode_move(index, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_are_encoded_row_by_row() {
+        let board: Board = "+-o/---/o-+".parse().unwrap();
+
+        assert_eq!(encode_tiles(&board), vec![1, 0, 2, 0, 0, 0, 2, 0, 1]);
+    }
+
+    #[test]
+    fn moves_are_decoded_from_indices() {
+        assert_eq!(decode_move(0, 3), Some([0, 0]));
+        assert_eq!(decode_move(5, 3), Some([1, 2]));
+        assert_eq!(decode_move(8, 3), Some([2, 2]));
+        assert_eq!(decode_move(9, 3), None);
+        assert_eq!(decode_move(-1, 3), None);
+    }
+
+    #[test]
+    fn only_shared_libraries_are_plugins() {
+        assert!(is_plugin_file(Path::new("plugins/libbot.so")));
+        assert!(is_plugin_file(Path::new("bot.dylib")));
+        assert!(is_plugin_file(Path::new("bot.dll")));
+        assert!(!is_plugin_file(Path::new("bot.rs")));
+        assert!(!is_plugin_file(Path::new("README")));
+    }
+
+    #[test]
+    fn missing_plugin_dir_has_no_plugins() {
+        assert!(discover(Path::new("this/directory/does/not/exist")).is_empty());
+    }
+
+    #[test]
+    fn loading_a_missing_plugin_fails() {
+        assert!(PluginBot::load(Path::new("this/plugin/does/not/exist.so")).is_err());
+    }
+}
@@ -0,0 +1,455 @@
+// Named local profiles, so everyone sharing a machine keeps their own
+// statistics, achievements, rating and settings. A profile is a directory
+// under the data directory holding the files that are otherwise kept in the
+// data directory itself, and one under the config directory for its
+// settings. Without a profile nothing changes.
+
+use std::fmt::Display;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::ai::Difficulty;
+use crate::game::{GameSetup, TakebackAnswer};
+use crate::keys::{Bindings, Keys};
+use crate::palette::Palette;
+use crate::paths;
+use crate::sound::Volume;
+use crate::view::{Scale, View};
+
+// The directory of the profile picked for this run.
+static PROFILE: OnceLock<PathBuf> = OnceLock::new();
+
+pub const SETTINGS: &str = "settings";
+
+// The settings file without a config directory.
+const LOCAL_SETTINGS: &str = "tic-tac-toe.settings";
+
+fn profiles_dir() -> Result<PathBuf, String> {
+    paths::data_dir()
+        .map(|dir| dir.join("profiles"))
+        .ok_or_else(|| String::from("Could not find a data directory for profiles."))
+}
+
+// Names become directory names, so they are kept to letters, digits, - and _.
+fn check_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 32
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{name}' is not a profile name, use up to 32 letters, digits, - and _."
+        ))
+    }
+}
+
+// Uses the profile for the rest of the run, creating it if it is new.
+pub fn select(name: &str) -> Result<(), String> {
+    check_name(name)?;
+    let dir = profiles_dir()?.join(name);
+    fs::create_dir_all(&dir)
+        .map_err(|error| format!("Could not create the profile {}: {error}", dir.display()))?;
+    PROFILE
+        .set(dir)
+        .map_err(|_| String::from("A profile has already been picked."))
+}
+
+pub fn selected() -> Option<String> {
+    PROFILE
+        .get()
+        .and_then(|dir| dir.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+// A data file of the profile, or of the data directory without one.
+pub fn file(name: &str) -> PathBuf {
+    PROFILE
+        .get()
+        .map_or_else(|| paths::data_file(name), |dir| dir.join(name))
+}
+
+// The profile's settings, or the settings for playing without a profile.
+pub fn settings_path() -> PathBuf {
+    match (paths::config_dir(), selected()) {
+        (Some(dir), Some(name)) => dir.join("profiles").join(name).join(SETTINGS),
+        (Some(dir), None) => dir.join(SETTINGS),
+        (None, _) => file(LOCAL_SETTINGS),
+    }
+}
+
+// The profiles on this machine, sorted by name.
+pub fn list() -> Vec<String> {
+    let Ok(entries) =
+        profiles_dir().and_then(|dir| fs::read_dir(dir).map_err(|error| error.to_string()))
+    else {
+        return Vec::new();
+    };
+    let mut names = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| check_name(name).is_ok())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+// Defaults for every game, kept as TOML: one "<setting> = <value>" line
+// each, and a [bindings] table with an "<action> = <key>" line for each
+// remapped vim key. Options given on the command line are added on top.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Settings {
+    pub confirm_moves: bool,
+    pub coach: bool,
+    pub takebacks: TakebackAnswer,
+    pub palette: Palette,
+    pub view: View,
+    pub scale: Scale,
+    pub keys: Keys,
+    pub bindings: Bindings,
+    pub volume: Volume,
+    pub mute: bool,
+    // The menu's bot, None to ask every game.
+    pub difficulty: Option<Difficulty>,
+    // 4 or 5 for the menu's games, None for the usual 3x3.
+    pub board_size: Option<usize>,
+}
+
+impl Settings {
+    pub fn apply(self, setup: GameSetup) -> GameSetup {
+        GameSetup {
+            confirm_moves: setup.confirm_moves || self.confirm_moves,
+            coach: setup.coach || self.coach,
+            takebacks: if setup.takebacks == TakebackAnswer::default() {
+                self.takebacks
+            } else {
+                setup.takebacks
+            },
+            palette: if setup.palette == Palette::default() {
+                self.palette
+            } else {
+                setup.palette
+            },
+            view: if setup.view == View::default() {
+                self.view
+            } else {
+                setup.view
+            },
+            scale: if setup.scale == Scale::default() {
+                self.scale
+            } else {
+                setup.scale
+            },
+            keys: if setup.keys == Keys::default() {
+                self.keys
+            } else {
+                setup.keys
+            },
+            // There is no command line option for them.
+            bindings: self.bindings,
+            volume: if setup.volume == Volume::default() {
+                self.volume
+            } else {
+                setup.volume
+            },
+            mute: setup.mute || self.mute,
+            ..setup
+        }
+    }
+
+    // Puts every setting into setup, for settings changed while it is in use.
+    pub fn set(self, setup: GameSetup) -> GameSetup {
+        GameSetup {
+            confirm_moves: self.confirm_moves,
+            coach: self.coach,
+            takebacks: self.takebacks,
+            palette: self.palette,
+            view: self.view,
+            scale: self.scale,
+            keys: self.keys,
+            bindings: self.bindings,
+            volume: self.volume,
+            mute: self.mute,
+            ..setup
+        }
+    }
+}
+
+pub fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+// Text as a TOML string, quotes included.
+fn toml_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+// A TOML string, boolean or integer as the settings parse it: a string
+// without its quotes, a boolean as on or off. None for anything else.
+fn toml_value(value: &str) -> Option<String> {
+    let Some(quoted) = value.strip_prefix('"') else {
+        let value = value.split('#').next().unwrap_or_default().trim();
+        return match value {
+            "true" => Some(String::from("on")),
+            "false" => Some(String::from("off")),
+            _ => value.parse::<i64>().is_ok().then(|| value.to_string()),
+        };
+    };
+    let mut text = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let rest = chars.as_str().trim_start();
+                return (rest.is_empty() || rest.starts_with('#')).then_some(text);
+            }
+            // Only \" and \\ are written.
+            '\\' => text.push(chars.next()?),
+            c => text.push(c),
+        }
+    }
+    None
+}
+
+impl Display for Settings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let string = |value: &dyn Display| toml_string(&value.to_string());
+        writeln!(f, "confirm = {}", self.confirm_moves)?;
+        writeln!(f, "coach = {}", self.coach)?;
+        writeln!(f, "takebacks = {}", string(&self.takebacks))?;
+        writeln!(f, "palette = {}", string(&self.palette))?;
+        writeln!(f, "view = {}", string(&self.view))?;
+        writeln!(f, "scale = {}", string(&self.scale))?;
+        writeln!(f, "keys = {}", string(&self.keys))?;
+        writeln!(f, "volume = {}", self.volume)?;
+        writeln!(f, "mute = {}", self.mute)?;
+        if let Some(difficulty) = self.difficulty {
+            writeln!(
+                f,
+                "difficulty = {}",
+                string(&difficulty.to_string().to_lowercase())
+            )?;
+        }
+        writeln!(f, "board_size = {}", self.board_size.unwrap_or(3))?;
+
+        // A table goes after every setting outside it.
+        let mut bindings = self.bindings.changed().peekable();
+        if bindings.peek().is_some() {
+            writeln!(f, "\n[bindings]")?;
+        }
+        for (action, key) in bindings {
+            writeln!(f, "{action} = {}", string(&key))?;
+        }
+        Ok(())
+    }
+}
+
+// The setting in a line, and its value as toml_value gives it.
+fn setting_line(line: &str) -> Result<(&str, String), String> {
+    match line.split_once('=') {
+        Some((name, value)) => toml_value(value.trim())
+            .map(|value| (name.trim(), value))
+            .ok_or_else(|| format!("'{line}' should end with a string, a number, true or false.")),
+        // Settings files used to be "<setting> <value>" lines.
+        None => line
+            .split_once(' ')
+            .map(|(name, value)| (name, value.to_string()))
+            .ok_or_else(|| format!("'{line}' is not a setting.")),
+    }
+}
+
+impl FromStr for Settings {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut settings = Settings::default();
+        let mut in_bindings = false;
+
+        for line in s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        {
+            if let Some(table) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                if table.trim() != "bindings" {
+                    return Err(format!("'{line}' is not a table, only [bindings] is."));
+                }
+                in_bindings = true;
+                continue;
+            }
+            let (name, value) = setting_line(line)?;
+            if in_bindings {
+                let mut keys = value.chars();
+                let (Some(key), None) = (keys.next(), keys.next()) else {
+                    return Err(format!("'{line}' should bind a single key."));
+                };
+                settings.bindings.bind(name.parse()?, key)?;
+                continue;
+            }
+
+            let switch = |value: &str| match value {
+                "on" => Ok(true),
+                "off" => Ok(false),
+                _ => Err(format!("'{line}' should be true or false.")),
+            };
+            match name {
+                "confirm" => settings.confirm_moves = switch(&value)?,
+                "coach" => settings.coach = switch(&value)?,
+                "takebacks" => settings.takebacks = value.parse()?,
+                "palette" => settings.palette = value.parse()?,
+                "view" => settings.view = value.parse()?,
+                "scale" => settings.scale = value.parse()?,
+                "keys" => settings.keys = value.parse()?,
+                "volume" => settings.volume = value.parse()?,
+                "mute" => settings.mute = switch(&value)?,
+                "difficulty" => settings.difficulty = Some(value.parse()?),
+                "board_size" => {
+                    settings.board_size = match value.as_str() {
+                        "3" => None,
+                        "4" | "5" => value.parse().ok(),
+                        _ => return Err(format!("'{line}' should be 3, 4 or 5.")),
+                    }
+                }
+                _ => return Err(format!("'{line}' is not a setting.")),
+            }
+        }
+
+        Ok(settings)
+    }
+}
+
+// The settings file, None when there is none.
+pub fn saved_settings() -> Result<Option<Settings>, String> {
+    let path = settings_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+    fs::read_to_string(&path)
+        .map_err(|error| format!("Could not read {}: {error}", path.display()))?
+        .parse()
+        .map(Some)
+        .map_err(|error| format!("{}: {error}", path.display()))
+}
+
+// The settings, the defaults without a settings file.
+pub fn settings() -> Result<Settings, String> {
+    saved_settings().map(Option::unwrap_or_default)
+}
+
+pub fn save_settings(settings: Settings) -> Result<(), String> {
+    let path = settings_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|error| format!("Could not create {}: {error}", dir.display()))?;
+    }
+    fs::write(&path, settings.to_string())
+        .map_err(|error| format!("Could not save the settings to {}: {error}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::Action;
+
+    #[test]
+    fn profile_names_are_safe_directory_names() {
+        for name in ["ann", "Player_2", "x-y"] {
+            assert!(check_name(name).is_ok());
+        }
+        for name in ["", "../ann", "a b", ".hidden", &"a".repeat(33)] {
+            assert!(check_name(name).is_err());
+        }
+    }
+
+    #[test]
+    fn settings_round_trip_and_apply_under_the_command_line() {
+        let settings = Settings {
+            confirm_moves: false,
+            coach: true,
+            takebacks: TakebackAnswer::Refuse,
+            palette: Palette::Protanopia,
+            view: View::Large,
+            scale: Scale::Auto,
+            keys: Keys::Vim,
+            bindings: Bindings::default(),
+            volume: "30".parse().unwrap(),
+            mute: true,
+            difficulty: Some(Difficulty::Hard),
+            board_size: Some(4),
+        };
+        let text = settings.to_string();
+        assert_eq!(
+            text,
+            "confirm = false\ncoach = true\ntakebacks = \"refuse\"\npalette = \"protanopia\"\nview = \"large\"\nscale = \"auto\"\nkeys = \"vim\"\nvolume = 30\nmute = true\ndifficulty = \"hard\"\nboard_size = 4\n"
+        );
+        assert_eq!(text.parse(), Ok(settings));
+        assert!("coach = maybe".parse::<Settings>().is_err());
+
+        let setup = settings.apply(GameSetup {
+            confirm_moves: true,
+            ..GameSetup::default()
+        });
+        assert!(setup.confirm_moves && setup.coach);
+        assert_eq!(setup.takebacks, TakebackAnswer::Refuse);
+        assert_eq!(setup.palette, Palette::Protanopia);
+        assert_eq!(setup.scale, Scale::Auto);
+        assert_eq!(setup.keys, Keys::Vim);
+        assert_eq!(setup.volume.to_string(), "30");
+        assert!(setup.mute);
+
+        // Changes made in the menu replace what the command line asked for.
+        assert!(!settings.set(setup).confirm_moves);
+    }
+
+    #[test]
+    fn settings_files_are_toml_with_a_table_for_the_bindings() {
+        let text = "# Written by hand.\nkeys = \"vim\"  # for h, j, k and l\nmute = true\nboard_size = 3\n\n[bindings]\nleft = \"a\"\nhint = \"\\\"\"\n";
+        let settings: Settings = text.parse().unwrap();
+        assert_eq!(settings.keys, Keys::Vim);
+        assert!(settings.mute);
+        assert_eq!(settings.board_size, None);
+        assert_eq!(settings.bindings.key(Action::Left), 'a');
+        assert_eq!(settings.bindings.key(Action::Hint), '"');
+        assert!(settings
+            .to_string()
+            .ends_with("board_size = 3\n\n[bindings]\nleft = \"a\"\nhint = \"\\\"\"\n"));
+        assert_eq!(settings.to_string().parse(), Ok(settings));
+
+        // Files from before the settings were TOML still load.
+        let old: Settings = "confirm on\npalette protanopia\nvolume 30".parse().unwrap();
+        assert!(old.confirm_moves);
+        assert_eq!(old.palette, Palette::Protanopia);
+
+        for text in [
+            "board_size = 6",
+            "difficulty = \"expert\"",
+            "keys = \"vim",
+            "[colors]",
+            "[bindings]\nleft = \"ab\"",
+            "[bindings]\njump = \"a\"",
+            "[bindings]\nright = \"h\"",
+        ] {
+            assert!(text.parse::<Settings>().is_err(), "{text}");
+        }
+    }
+}
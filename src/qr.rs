@@ -0,0 +1,48 @@
+// Terminal QR codes for positions and game records, available with the `qr`
+// feature, so a phone can pick a game up from the screen. Two rows of modules
+// share a character, drawn inverted by default like `qrencode -t UTF8` since
+// most terminals have a dark background.
+
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+
+pub fn render(text: &str, invert: bool) -> Result<String, String> {
+    let code = QrCode::new(text.as_bytes())
+        .map_err(|error| format!("Could not make a QR code: {error}"))?;
+    let (dark, light) = if invert {
+        (Dense1x2::Light, Dense1x2::Dark)
+    } else {
+        (Dense1x2::Dark, Dense1x2::Light)
+    };
+
+    Ok(code
+        .render::<Dense1x2>()
+        .dark_color(dark)
+        .light_color(light)
+        .quiet_zone(true)
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_fit_a_small_code() {
+        let code = render("+-o/---/o-+", true).unwrap();
+        let lines = code.lines().collect::<Vec<_>>();
+
+        // Version 1 is 21 modules wide, plus a quiet zone of 4 on each side.
+        assert_eq!(lines[0].chars().count(), 29);
+        assert_eq!(lines.len(), 15);
+        // The quiet zone is light, which is a full block when inverted.
+        assert!(lines[0].chars().all(|c| c == '\u{2588}'));
+        assert!(render("+-o/---/o-+", false)
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .chars()
+            .all(|c| c == ' '));
+    }
+}
@@ -0,0 +1,174 @@
+// An Elo rating for the player from games against the built-in bots, which
+// are anchored at fixed ratings so the player's number means the same from
+// one machine to the next.
+//
+// The file has a "rating <rating>" and a "games <games>" line.
+
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::ai::Difficulty;
+use crate::profile;
+use crate::tournament::{self, Record, INITIAL_RATING};
+
+// Where the rating is kept unless TTT_RATING says otherwise.
+pub const DEFAULT_RATING: &str = "tic-tac-toe.rating";
+
+pub fn path() -> PathBuf {
+    std::env::var_os("TTT_RATING").map_or_else(|| profile::file(DEFAULT_RATING), PathBuf::from)
+}
+
+fn bot_rating(difficulty: Difficulty) -> f64 {
+    match difficulty {
+        Difficulty::Easy => 1100.0,
+        Difficulty::Medium => 1500.0,
+        Difficulty::Hard => 1900.0,
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Rating {
+    pub rating: f64,
+    pub games: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            rating: INITIAL_RATING,
+            games: 0,
+        }
+    }
+}
+
+impl Rating {
+    // Counts a game from the player's side, returning the change.
+    pub fn update(&mut self, difficulty: Difficulty, result: Record) -> f64 {
+        let score = f64::from(result.wins) + f64::from(result.draws) / 2.0;
+        let change = tournament::rating_change(self.rating, bot_rating(difficulty), score);
+        self.rating += change;
+        self.games += 1;
+        change
+    }
+
+    // Ratings from different games cannot be combined, so the one from more
+    // games is kept, returning whether that is the other one.
+    pub fn merge(&mut self, other: Rating) -> bool {
+        let replaced = other.games > self.games;
+        if replaced {
+            *self = other;
+        }
+        replaced
+    }
+}
+
+impl Display for Rating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "rating {:.1}", self.rating)?;
+        writeln!(f, "games {}", self.games)
+    }
+}
+
+impl FromStr for Rating {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rating = Rating::default();
+
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            let invalid = || format!("'{line}' is not part of a rating.");
+            match line.split_once(' ') {
+                Some(("rating", value)) => {
+                    rating.rating = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|value| value.is_finite())
+                        .ok_or_else(invalid)?;
+                }
+                Some(("games", value)) => rating.games = value.parse().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(rating)
+    }
+}
+
+// No rated games yet is not an error.
+pub fn load(path: &Path) -> Result<Rating, String> {
+    if !path.exists() {
+        return Ok(Rating::default());
+    }
+    fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {error}", path.display()))?
+        .parse()
+        .map_err(|error| format!("{}: {error}", path.display()))
+}
+
+// Where a game's rating is kept and which bot it is against.
+#[derive(Clone)]
+pub struct RatingTracker {
+    path: PathBuf,
+    difficulty: Difficulty,
+}
+
+impl RatingTracker {
+    pub fn new(path: PathBuf, difficulty: Difficulty) -> RatingTracker {
+        RatingTracker { path, difficulty }
+    }
+
+    // Returns the new rating and the change.
+    pub fn track(&self, result: Record) -> Result<(f64, f64), String> {
+        let mut rating = load(&self.path)?;
+        let change = rating.update(self.difficulty, result);
+        fs::write(&self.path, rating.to_string()).map_err(|error| {
+            format!(
+                "Could not save the rating to {}: {error}",
+                self.path.display()
+            )
+        })?;
+        Ok((rating.rating, change))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_move_the_rating_by_the_bots_strength() {
+        let mut rating = Rating::default();
+        let draw = Record {
+            draws: 1,
+            ..Record::default()
+        };
+
+        // Drawing the hard bot is worth more than drawing the easy one.
+        assert!(rating.update(Difficulty::Hard, draw) > 0.0);
+        assert!(rating.update(Difficulty::Easy, draw) < 0.0);
+        assert!(
+            rating.update(
+                Difficulty::Medium,
+                Record {
+                    wins: 1,
+                    ..Record::default()
+                }
+            ) > 0.0
+        );
+        assert_eq!(rating.games, 3);
+    }
+
+    #[test]
+    fn ratings_round_trip() {
+        let rating = Rating {
+            rating: 1612.5,
+            games: 7,
+        };
+
+        assert_eq!(rating.to_string(), "rating 1612.5\ngames 7\n");
+        assert_eq!(rating.to_string().parse(), Ok(rating));
+        assert!("rating high".parse::<Rating>().is_err());
+    }
+}
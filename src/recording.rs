@@ -0,0 +1,195 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const HEADER: &str = "tic-tac-toe-rust recording v1";
+
+// Long pauses are shortened on replay so nobody waits for a player who went for coffee.
+const MAX_PAUSE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Frame {
+    // Time since the recording started.
+    pub at: Duration,
+    // Starts a new screen, e.g. the board shown at the beginning of a turn.
+    pub new_screen: bool,
+    pub text: String,
+}
+
+pub struct Recording {
+    started: Instant,
+    frames: Vec<Frame>,
+}
+
+impl Recording {
+    pub fn new() -> Recording {
+        Recording {
+            started: Instant::now(),
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn capture(&mut self, text: &str, new_screen: bool) {
+        self.frames.push(Frame {
+            at: self.started.elapsed(),
+            new_screen,
+            text: text.to_string(),
+        });
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serialize(&self.frames))
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+// One frame per line, tab separated: the milliseconds since the start,
+// "screen" or "text", and the escaped text.
+fn serialize(frames: &[Frame]) -> String {
+    let mut contents = format!("{HEADER}\n");
+
+    for frame in frames {
+        let kind = if frame.new_screen { "screen" } else { "text" };
+        let _ = writeln!(
+            contents,
+            "{}\t{kind}\t{}",
+            frame.at.as_millis(),
+            escape(&frame.text)
+        );
+    }
+
+    contents
+}
+
+fn parse(contents: &str) -> Result<Vec<Frame>, String> {
+    let mut lines = contents.lines();
+
+    if lines.next() != Some(HEADER) {
+        return Err(String::from(
+            "The file is not a tic-tac-toe-rust recording.",
+        ));
+    }
+
+    lines
+        .enumerate()
+        .map(|(i, line)| {
+            let invalid = || format!("Line {} of the recording is invalid.", i + 2);
+            let mut fields = line.splitn(3, '\t');
+            let (Some(millis), Some(kind), Some(text)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(invalid());
+            };
+            let millis = millis.parse::<u64>().map_err(|_| invalid())?;
+            let new_screen = match kind {
+                "screen" => true,
+                "text" => false,
+                _ => return Err(invalid()),
+            };
+
+            Ok(Frame {
+                at: Duration::from_millis(millis),
+                new_screen,
+                text: unescape(text),
+            })
+        })
+        .collect()
+}
+
+pub fn load(path: &Path) -> Result<Vec<Frame>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {error}", path.display()))?;
+
+    parse(&contents)
+}
+
+// Prints the frames in order. With render the original timing is kept and
+// the screen is cleared whenever a new board comes up.
+pub fn replay(frames: &[Frame], render: bool) {
+    let mut previous = Duration::ZERO;
+
+    for frame in frames {
+        if render {
+            thread::sleep((frame.at.saturating_sub(previous)).min(MAX_PAUSE));
+            previous = frame.at;
+
+            if frame.new_screen {
+                print!("\x1b[2J\x1b[H");
+            }
+        }
+
+        println!("{}", frame.text);
+        let _ = io::stdout().flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_survive_a_round_trip() {
+        let frames = vec![
+            Frame {
+                at: Duration::from_millis(0),
+                new_screen: true,
+                text: String::from("\nThe current board state is:\n\n| - | - | - |"),
+            },
+            Frame {
+                at: Duration::from_millis(1500),
+                new_screen: false,
+                text: String::from("> 1,1 \\ with a backslash"),
+            },
+        ];
+
+        assert_eq!(parse(&serialize(&frames)).unwrap(), frames);
+    }
+
+    #[test]
+    fn invalid_recordings_are_rejected() {
+        assert!(parse("something else\n0\ttext\thello").is_err());
+        assert!(parse(&format!("{HEADER}\nno tab here")).is_err());
+        assert!(parse(&format!("{HEADER}\nsoon\ttext\thello")).is_err());
+        assert!(parse(&format!("{HEADER}\n0\tsound\thello")).is_err());
+    }
+
+    #[test]
+    fn capture_keeps_frames_in_order() {
+        let mut recording = Recording::new();
+        recording.capture("first", true);
+        recording.capture("second", false);
+
+        let frames = parse(&serialize(&recording.frames)).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].at <= frames[1].at);
+        assert!(frames[0].new_screen);
+        assert_eq!(frames[1].text, "second");
+    }
+}
@@ -0,0 +1,63 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Small xorshift generator, plenty for picking moves without pulling in a dependency.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Xorshift gets stuck on a zero state.
+        Rng { state: seed.max(1) }
+    }
+
+    pub fn from_time() -> Rng {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos());
+
+        Rng::new(u64::try_from(nanos % u128::from(u64::MAX)).unwrap_or(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    // Uniform in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        let high = u32::try_from(self.next_u64() >> 32).expect("value fits in u32");
+        f64::from(high) / (f64::from(u32::MAX) + 1.0)
+    }
+
+    pub fn below(&mut self, upper: usize) -> usize {
+        let upper = u64::try_from(upper).expect("usize fits in u64");
+        usize::try_from(self.next_u64() % upper).expect("value is below a usize")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_sequence() {
+        let mut first = Rng::new(42);
+        let mut second = Rng::new(42);
+
+        for _ in 0..10 {
+            assert_eq!(first.next_u64(), second.next_u64());
+        }
+    }
+
+    #[test]
+    fn below_stays_in_range() {
+        let mut rng = Rng::new(7);
+
+        for _ in 0..1000 {
+            assert!(rng.below(9) < 9);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+// Bots written as Rhai scripts, available with the `scripting` feature.
+//
+// A script defines `fn choose_move(board, me)`. board is an array of rows,
+// each an array of "-", "+" or "o" strings, and me is the symbol to play.
+// It returns [row, column]. An optional `fn name()` names the bot.
+//
+//     fn choose_move(board, me) {
+//         for row in 0..board.len() {
+//             for column in 0..board[row].len() {
+//                 if board[row][column] == "-" { return [row, column]; }
+//             }
+//         }
+//     }
+//
+// Scripts run sandboxed: Rhai has no file or network access, and the limits
+// below stop runaway loops and memory use.
+
+use std::path::Path;
+
+use rhai::{Array, Dynamic, Engine, Scope, AST};
+
+use crate::board::{Board, Symbol};
+use crate::bot::Bot;
+
+const MAX_OPERATIONS: u64 = 1_000_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_ARRAY_SIZE: usize = 1_000;
+const MAX_STRING_SIZE: usize = 1_000;
+
+pub struct ScriptBot {
+    name: String,
+    engine: Engine,
+    ast: AST,
+}
+
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_array_size(MAX_ARRAY_SIZE)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .on_print(|_| {})
+        .on_debug(|_, _, _| {});
+    engine
+}
+
+fn board_to_script(board: &Board) -> Array {
+    board
+        .tiles
+        .iter()
+        .map(|row| {
+            let row = row
+                .iter()
+                .map(|tile| Dynamic::from(Into::<&str>::into(*tile).to_string()))
+                .collect::<Array>();
+            Dynamic::from_array(row)
+        })
+        .collect()
+}
+
+fn move_from_script(result: &Dynamic) -> Option<[usize; 2]> {
+    let position = result.clone().try_cast::<Array>()?;
+
+    match position.as_slice() {
+        [row, column] => Some([
+            usize::try_from(row.as_int().ok()?).ok()?,
+            usize::try_from(column.as_int().ok()?).ok()?,
+        ]),
+        _ => None,
+    }
+}
+
+impl ScriptBot {
+    pub fn load(path: &Path) -> Result<ScriptBot, String> {
+        let engine = sandboxed_engine();
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|error| format!("Could not load script {}: {error}", path.display()))?;
+
+        if !ast
+            .iter_functions()
+            .any(|function| function.name == "choose_move")
+        {
+            return Err(format!(
+                "Could not load script {}: it does not define choose_move(board, me).",
+                path.display()
+            ));
+        }
+
+        let default_name = path.file_stem().map_or_else(
+            || String::from("script"),
+            |stem| stem.to_string_lossy().into_owned(),
+        );
+        let name = engine
+            .call_fn::<String>(&mut Scope::new(), &ast, "name", ())
+            .unwrap_or(default_name);
+
+        Ok(ScriptBot { name, engine, ast })
+    }
+
+    fn call(&self, board: &Board, symbol: Symbol) -> Result<Dynamic, String> {
+        let me = Into::<&str>::into(symbol).to_string();
+
+        self.engine
+            .call_fn::<Dynamic>(
+                &mut Scope::new(),
+                &self.ast,
+                "choose_move",
+                (board_to_script(board), me),
+            )
+            .map_err(|error| error.to_string())
+    }
+}
+
+impl Bot for ScriptBot {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn choose_move(&mut self, board: &Board, symbol: Symbol) -> Option<[usize; 2]> {
+        match self.call(board, symbol) {
+            Ok(result) => move_from_script(&result),
+            Err(error) => {
+                eprintln!("The script {} failed: {error}", self.name);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bot_from_source(source: &str) -> ScriptBot {
+        let engine = sandboxed_engine();
+        let ast = engine.compile(source).unwrap();
+
+        ScriptBot {
+            name: String::from("test"),
+            engine,
+            ast,
+        }
+    }
+
+    #[test]
+    fn script_receives_the_board_and_returns_a_move() {
+        let mut bot = bot_from_source(
+            r#"
+            fn choose_move(board, me) {
+                if board[0][0] == "+" && me == "o" { [2, 1] } else { [0, 0] }
+            }
+            "#,
+        );
+        let board: Board = "+--/---/---".parse().unwrap();
+
+        assert_eq!(bot.choose_move(&board, Symbol::Circle), Some([2, 1]));
+        assert_eq!(bot.choose_move(&board, Symbol::Plus), Some([0, 0]));
+    }
+
+    #[test]
+    fn malformed_results_are_not_moves() {
+        for source in [
+            "fn choose_move(board, me) { 4 }",
+            "fn choose_move(board, me) { [1] }",
+            "fn choose_move(board, me) { [-1, 0] }",
+            r#"fn choose_move(board, me) { ["a", "b"] }"#,
+        ] {
+            let mut bot = bot_from_source(source);
+            assert_eq!(bot.choose_move(&Board::new(), Symbol::Plus), None);
+        }
+    }
+
+    #[test]
+    fn runaway_scripts_are_stopped() {
+        let mut bot = bot_from_source("fn choose_move(board, me) { loop {} }");
+
+        assert_eq!(bot.choose_move(&Board::new(), Symbol::Plus), None);
+    }
+
+    #[test]
+    fn loading_a_missing_script_fails() {
+        assert!(ScriptBot::load(Path::new("this/script/does/not/exist.rhai")).is_err());
+    }
+}
@@ -0,0 +1,572 @@
+// Self-play datasets for machine-learning experiments: a bot plays itself
+// and every position is written out with the move chosen and how the game
+// ended for the side to move.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write as _};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::board::{Board, GameStatus, Line, Symbol};
+use crate::bot::{Bot, BotSpec};
+use crate::rng::Rng;
+use crate::tournament::Record;
+
+const CSV_HEADER: &str = "game,ply,position,to_move,row,column,explored,result";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DatasetFormat {
+    Csv,
+    Jsonl,
+}
+
+impl FromStr for DatasetFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(DatasetFormat::Csv),
+            "jsonl" => Ok(DatasetFormat::Jsonl),
+            _ => Err(format!("'{s}' is not a dataset format, use csv or jsonl.")),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StatsFormat {
+    Table,
+    Csv,
+    // The opening squares as a colored grid, or as CSV with a row per square.
+    Heatmap,
+    HeatmapCsv,
+}
+
+impl FromStr for StatsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(StatsFormat::Table),
+            "csv" => Ok(StatsFormat::Csv),
+            "heatmap" => Ok(StatsFormat::Heatmap),
+            "heatmap-csv" => Ok(StatsFormat::HeatmapCsv),
+            _ => Err(format!(
+                "'{s}' is not a statistics format, use table, csv, heatmap or heatmap-csv."
+            )),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SelfPlayOptions {
+    pub bot: BotSpec,
+    pub games: u32,
+    pub format: DatasetFormat,
+    // Chance in percent that a move is replaced by a random legal one.
+    pub noise: u32,
+    pub seed: Option<u64>,
+    pub out: Option<PathBuf>,
+    // Aggregate statistics over all games, written after the dataset.
+    pub stats: Option<StatsFormat>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum GameOutcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl Display for GameOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let outcome = match self {
+            GameOutcome::Win => "win",
+            GameOutcome::Loss => "loss",
+            GameOutcome::Draw => "draw",
+        };
+        write!(f, "{outcome}")
+    }
+}
+
+struct Sample {
+    position: Board,
+    to_move: Symbol,
+    chosen: [usize; 2],
+    explored: bool,
+}
+
+struct SelfPlayGame {
+    samples: Vec<Sample>,
+    // None for a draw.
+    winner: Option<Symbol>,
+    // None for a draw or a forfeit.
+    line: Option<Line>,
+}
+
+impl SelfPlayGame {
+    fn outcome_for(&self, symbol: Symbol) -> GameOutcome {
+        match self.winner {
+            None => GameOutcome::Draw,
+            Some(winner) if winner == symbol => GameOutcome::Win,
+            Some(_) => GameOutcome::Loss,
+        }
+    }
+}
+
+fn play_game(bots: &mut [Box<dyn Bot>; 2], noise: u32, rng: &mut Rng) -> SelfPlayGame {
+    let mut board = Board::new();
+    let mut symbol = Symbol::Plus;
+    let mut samples = Vec::new();
+
+    loop {
+        match board.status() {
+            GameStatus::Won(winner, line) => {
+                return SelfPlayGame {
+                    samples,
+                    winner: Some(winner),
+                    line: Some(line),
+                }
+            }
+            GameStatus::Draw => {
+                return SelfPlayGame {
+                    samples,
+                    winner: None,
+                    line: None,
+                }
+            }
+            GameStatus::InProgress => {}
+        }
+
+        let explored = rng.below(100) < usize::try_from(noise).unwrap_or(usize::MAX);
+        let chosen = if explored {
+            let empty = board.empty_positions();
+            Some(empty[rng.below(empty.len())])
+        } else {
+            let bot = &mut bots[usize::from(symbol == Symbol::Circle)];
+            bot.choose_move(&board, symbol)
+                .filter(|position| board.is_valid_move(*position).is_ok())
+        };
+
+        // A bot that passes or plays an illegal move forfeits.
+        let Some(chosen) = chosen else {
+            return SelfPlayGame {
+                samples,
+                winner: Some(symbol.opponent()),
+                line: None,
+            };
+        };
+
+        samples.push(Sample {
+            position: board.clone(),
+            to_move: symbol,
+            chosen,
+            explored,
+        });
+        board.place(symbol, chosen);
+        symbol = symbol.opponent();
+    }
+}
+
+fn write_game(
+    writer: &mut dyn Write,
+    format: DatasetFormat,
+    number: u32,
+    game: &SelfPlayGame,
+) -> io::Result<()> {
+    for (ply, sample) in game.samples.iter().enumerate() {
+        let position = format!("{:#}", sample.position);
+        let to_move: &str = sample.to_move.into();
+        let [row, column] = sample.chosen;
+        let result = game.outcome_for(sample.to_move);
+
+        match format {
+            DatasetFormat::Csv => writeln!(
+                writer,
+                "{number},{ply},{position},{to_move},{row},{column},{},{result}",
+                sample.explored
+            )?,
+            DatasetFormat::Jsonl => writeln!(
+                writer,
+                r#"{{"game":{number},"ply":{ply},"position":"{position}","to_move":"{to_move}","move":[{row},{column}],"explored":{},"result":"{result}"}}"#,
+                sample.explored
+            )?,
+        }
+    }
+
+    Ok(())
+}
+
+// Totals over every game played, for spotting bias in a bot or a dataset.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Aggregates {
+    games: u32,
+    // Games by their number of moves.
+    lengths: BTreeMap<usize, u32>,
+    // Results for Plus, who always starts, by the square of the first move.
+    first_moves: BTreeMap<[usize; 2], Record>,
+    // How often each line decided a game.
+    lines: BTreeMap<Line, u32>,
+}
+
+// Background colors of the 256 color palette, from rarely to most often opened on.
+const HEAT: [u8; 5] = [18, 25, 30, 130, 160];
+
+fn format_position([row, column]: [usize; 2]) -> String {
+    format!("{row},{column}")
+}
+
+fn format_line(line: &Line) -> String {
+    line.map(format_position).join(" ")
+}
+
+fn percentage(count: u32, total: u32) -> f64 {
+    f64::from(count) * 100.0 / f64::from(total.max(1))
+}
+
+impl Aggregates {
+    fn add(&mut self, game: &SelfPlayGame) {
+        self.games += 1;
+        *self.lengths.entry(game.samples.len()).or_default() += 1;
+
+        if let Some(first) = game.samples.first() {
+            let record = self.first_moves.entry(first.chosen).or_default();
+            match game.outcome_for(Symbol::Plus) {
+                GameOutcome::Win => record.wins += 1,
+                GameOutcome::Draw => record.draws += 1,
+                GameOutcome::Loss => record.losses += 1,
+            }
+        }
+
+        if let Some(line) = game.line {
+            *self.lines.entry(line).or_default() += 1;
+        }
+    }
+
+    pub fn format(&self, format: StatsFormat) -> String {
+        match format {
+            StatsFormat::Table => self.table(),
+            StatsFormat::Csv => self.csv(),
+            StatsFormat::Heatmap => self.heatmap(),
+            StatsFormat::HeatmapCsv => self.heatmap_csv(),
+        }
+    }
+
+    // How many games opened on the square and the first player's record there.
+    fn opening(&self, position: [usize; 2]) -> (u32, Record) {
+        let record = self.first_moves.get(&position).copied().unwrap_or_default();
+        (record.wins + record.draws + record.losses, record)
+    }
+
+    // Squares are shaded from blue to red by how often they were opened on,
+    // showing the share of games and the first player's win rate.
+    fn heatmap(&self) -> String {
+        let size = Board::new().tiles.len();
+        let most = self
+            .first_moves
+            .values()
+            .map(|record| record.wins + record.draws + record.losses)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let mut heatmap =
+            String::from("Opening squares: share of games and the first player's win rate.\n\n");
+
+        for row in 0..size {
+            for column in 0..size {
+                let (games, record) = self.opening([row, column]);
+                let text = if games == 0 {
+                    format!("{:^14}", "-")
+                } else {
+                    format!(
+                        " {:>5.1}% W{:>3.0}% ",
+                        percentage(games, self.games),
+                        percentage(record.wins, games)
+                    )
+                };
+                let heat = HEAT[usize::try_from(games * 4 / most).unwrap_or(0)];
+                let _ = write!(heatmap, "\x1b[48;5;{heat}m\x1b[97m{text}\x1b[0m");
+            }
+            heatmap.push('\n');
+        }
+
+        heatmap
+    }
+
+    // A row per square, including the ones never opened on, with the share
+    // and win rate as fractions.
+    fn heatmap_csv(&self) -> String {
+        let mut csv = String::from("row,column,games,share,wins,draws,losses,win_rate\n");
+        let size = Board::new().tiles.len();
+
+        for row in 0..size {
+            for column in 0..size {
+                let (games, record) = self.opening([row, column]);
+                let _ = writeln!(
+                    csv,
+                    "{row},{column},{games},{:.4},{},{},{},{:.4}",
+                    percentage(games, self.games) / 100.0,
+                    record.wins,
+                    record.draws,
+                    record.losses,
+                    percentage(record.wins, games) / 100.0
+                );
+            }
+        }
+
+        csv
+    }
+
+    fn table(&self) -> String {
+        let mut table = format!(
+            "Games: {}\n\n{:<6}  {:>6}  {:>6}\n",
+            self.games, "Moves", "Games", "Share"
+        );
+        for (length, count) in &self.lengths {
+            let _ = writeln!(
+                table,
+                "{length:<6}  {count:>6}  {:>5.1}%",
+                percentage(*count, self.games)
+            );
+        }
+
+        let _ = write!(
+            table,
+            "\nResults for the first player by opening square:\n{:<6}  {:>6}  {:>6}  {:>6}  {:>6}\n",
+            "Square", "Games", "Win", "Draw", "Loss"
+        );
+        for (position, record) in &self.first_moves {
+            let games = record.wins + record.draws + record.losses;
+            let _ = writeln!(
+                table,
+                "{:<6}  {games:>6}  {:>5.1}%  {:>5.1}%  {:>5.1}%",
+                format_position(*position),
+                percentage(record.wins, games),
+                percentage(record.draws, games),
+                percentage(record.losses, games)
+            );
+        }
+
+        let _ = write!(table, "\n{:<11}  {:>6}  {:>6}\n", "Line", "Wins", "Share");
+        for (line, count) in &self.lines {
+            let _ = writeln!(
+                table,
+                "{:<11}  {count:>6}  {:>5.1}%",
+                format_line(line),
+                percentage(*count, self.games)
+            );
+        }
+
+        table
+    }
+
+    // One row per number: the statistic, what it is counted for and the count.
+    fn csv(&self) -> String {
+        let mut csv = format!("statistic,key,count\ngames,,{}\n", self.games);
+        for (length, count) in &self.lengths {
+            let _ = writeln!(csv, "game_length,{length},{count}");
+        }
+        for (position, record) in &self.first_moves {
+            let position = format_position(*position);
+            let _ = writeln!(csv, "first_move_wins,\"{position}\",{}", record.wins);
+            let _ = writeln!(csv, "first_move_draws,\"{position}\",{}", record.draws);
+            let _ = writeln!(csv, "first_move_losses,\"{position}\",{}", record.losses);
+        }
+        for (line, count) in &self.lines {
+            let _ = writeln!(csv, "winning_line,\"{}\",{count}", format_line(line));
+        }
+
+        csv
+    }
+}
+
+// Plays options.games games of the two bots against each other, Plus always
+// being bots[0], and writes every position to writer.
+pub fn run(
+    bots: &mut [Box<dyn Bot>; 2],
+    options: &SelfPlayOptions,
+    writer: &mut dyn Write,
+) -> io::Result<Aggregates> {
+    let mut rng = options.seed.map_or_else(Rng::from_time, Rng::new);
+    let mut aggregates = Aggregates::default();
+
+    if options.format == DatasetFormat::Csv {
+        writeln!(writer, "{CSV_HEADER}")?;
+    }
+
+    for number in 0..options.games {
+        let game = play_game(bots, options.noise, &mut rng);
+        write_game(writer, options.format, number, &game)?;
+        aggregates.add(&game);
+    }
+
+    writer.flush()?;
+    Ok(aggregates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::bot::BuiltinBot;
+
+    fn options(format: DatasetFormat, noise: u32) -> SelfPlayOptions {
+        SelfPlayOptions {
+            bot: BotSpec::Builtin(Difficulty::Hard),
+            games: 3,
+            format,
+            noise,
+            seed: Some(5),
+            out: None,
+            stats: None,
+        }
+    }
+
+    fn hard_bots() -> [Box<dyn Bot>; 2] {
+        [
+            Box::new(BuiltinBot::new(Difficulty::Hard, Rng::new(1))),
+            Box::new(BuiltinBot::new(Difficulty::Hard, Rng::new(2))),
+        ]
+    }
+
+    fn generate(options: &SelfPlayOptions) -> String {
+        let mut output = Vec::new();
+        run(&mut hard_bots(), options, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn csv_has_a_row_per_position() {
+        let csv = generate(&options(DatasetFormat::Csv, 0));
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[0], CSV_HEADER);
+        // Perfect play always fills the board.
+        assert_eq!(lines.len(), 1 + 3 * 9);
+        assert!(lines[1].starts_with("0,0,---/---/---,+,"));
+        assert!(lines[1].ends_with(",false,draw"));
+        assert!(lines[27].starts_with("2,8,"));
+    }
+
+    #[test]
+    fn jsonl_has_an_object_per_position() {
+        let jsonl = generate(&options(DatasetFormat::Jsonl, 0));
+        let first = jsonl.lines().next().unwrap();
+
+        assert_eq!(jsonl.lines().count(), 27);
+        assert!(first
+            .starts_with(r#"{"game":0,"ply":0,"position":"---/---/---","to_move":"+","move":["#));
+        assert!(first.ends_with(r#""explored":false,"result":"draw"}"#));
+    }
+
+    #[test]
+    fn full_noise_explores_every_move_and_results_match_the_winner() {
+        let mut rng = Rng::new(9);
+        let game = play_game(&mut hard_bots(), 100, &mut rng);
+
+        assert!(game.samples.iter().all(|sample| sample.explored));
+        if let Some(winner) = game.winner {
+            assert_eq!(game.outcome_for(winner), GameOutcome::Win);
+            assert_eq!(game.outcome_for(winner.opponent()), GameOutcome::Loss);
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_dataset() {
+        let options = options(DatasetFormat::Csv, 30);
+
+        assert_eq!(generate(&options), generate(&options));
+    }
+
+    #[test]
+    fn formats_are_parsed() {
+        assert_eq!("CSV".parse(), Ok(DatasetFormat::Csv));
+        assert_eq!("jsonl".parse(), Ok(DatasetFormat::Jsonl));
+        assert_eq!("heatmap-csv".parse(), Ok(StatsFormat::HeatmapCsv));
+        assert!("parquet".parse::<DatasetFormat>().is_err());
+    }
+
+    #[test]
+    fn aggregates_count_lengths_openings_and_lines() {
+        let mut output = Vec::new();
+        let mut options = options(DatasetFormat::Csv, 100);
+        options.games = 50;
+        let aggregates = run(&mut hard_bots(), &options, &mut output).unwrap();
+
+        assert_eq!(aggregates.games, 50);
+        assert_eq!(aggregates.lengths.values().sum::<u32>(), 50);
+        assert!(aggregates
+            .lengths
+            .keys()
+            .all(|length| (5..=9).contains(length)));
+        let first_moves = aggregates
+            .first_moves
+            .values()
+            .map(|record| record.wins + record.draws + record.losses)
+            .sum::<u32>();
+        assert_eq!(first_moves, 50);
+        // Random play finishes most games with a line.
+        assert!(aggregates.lines.values().sum::<u32>() > 25);
+    }
+
+    #[test]
+    fn opening_squares_are_drawn_as_a_heatmap() {
+        let mut aggregates = Aggregates {
+            games: 5,
+            ..Aggregates::default()
+        };
+        aggregates.first_moves.insert(
+            [1, 1],
+            Record {
+                wins: 3,
+                draws: 1,
+                losses: 0,
+            },
+        );
+        aggregates.first_moves.insert(
+            [0, 0],
+            Record {
+                wins: 0,
+                draws: 1,
+                losses: 0,
+            },
+        );
+
+        let heatmap = aggregates.format(StatsFormat::Heatmap);
+        let rows = heatmap.lines().skip(2).collect::<Vec<_>>();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].starts_with("\x1b[48;5;25m\x1b[97m  20.0% W  0% \x1b[0m"));
+        assert!(rows[1].contains("\x1b[48;5;160m\x1b[97m  80.0% W 75% \x1b[0m"));
+        assert!(rows[2].contains("      -       "));
+
+        let csv = aggregates.format(StatsFormat::HeatmapCsv);
+        assert_eq!(csv.lines().count(), 10);
+        assert!(csv.contains("\n1,1,4,0.8000,3,1,0,0.7500\n"));
+        assert!(csv.ends_with("\n2,2,0,0.0000,0,0,0,0.0000\n"));
+    }
+
+    #[test]
+    fn aggregates_are_formatted_as_a_table_and_csv() {
+        let mut aggregates = Aggregates::default();
+        aggregates.add(&SelfPlayGame {
+            samples: Vec::new(),
+            winner: None,
+            line: None,
+        });
+        let mut game = play_game(&mut hard_bots(), 0, &mut Rng::new(1));
+        game.winner = Some(Symbol::Plus);
+        game.line = Some([[0, 0], [1, 1], [2, 2]]);
+        aggregates.add(&game);
+
+        let table = aggregates.format(StatsFormat::Table);
+        assert!(table.starts_with("Games: 2\n"));
+        assert!(table.contains("0,0 1,1 2,2       1   50.0%"));
+
+        let csv = aggregates.format(StatsFormat::Csv);
+        assert!(
+            csv.starts_with("statistic,key,count\ngames,,2\ngame_length,0,1\ngame_length,9,1\n")
+        );
+        assert!(csv.contains("first_move_wins,"));
+        assert!(csv.ends_with("winning_line,\"0,0 1,1 2,2\",1\n"));
+    }
+}
@@ -0,0 +1,261 @@
+// A simul: one player against several bots at once, as in a chess simul.
+// The player moves first on every board, as +, and goes from board to board
+// making one move each; every bot answers as soon as its board is left. The
+// score at the end counts a draw as half a win.
+
+use crate::board::{Board, GameStatus, Symbol};
+use crate::bot::Bot;
+use crate::console::Console;
+use crate::game::parse_player_move;
+use crate::tournament::{GameResult, Record};
+use crate::view::Panel;
+
+// The most boards a simul can have, so they still fit a terminal.
+pub const MAX_BOARDS: usize = 12;
+
+// One game of the simul.
+struct Table {
+    bot: Box<dyn Bot>,
+    board: Board,
+    // The bot's last reply.
+    reply: Option<[usize; 2]>,
+    // From the player's side, None while the game goes on.
+    result: Option<GameResult>,
+}
+
+impl Table {
+    fn new(bot: Box<dyn Bot>) -> Table {
+        Table {
+            bot,
+            board: Board::new(),
+            reply: None,
+            result: None,
+        }
+    }
+
+    fn check_result(&mut self) {
+        self.result = match self.board.status() {
+            GameStatus::Won(Symbol::Plus, _) => Some(GameResult::FirstWins),
+            GameStatus::Won(..) => Some(GameResult::SecondWins),
+            GameStatus::Draw => Some(GameResult::Draw),
+            GameStatus::InProgress => None,
+        };
+    }
+
+    // Plays the player's move and the bot's reply. A bot that passes or plays
+    // an illegal move loses, as in tournaments.
+    fn play(&mut self, position: [usize; 2]) {
+        self.board.place(Symbol::Plus, position);
+        self.check_result();
+        if self.result.is_some() {
+            return;
+        }
+
+        let reply = self
+            .bot
+            .choose_move(&self.board, Symbol::Circle)
+            .filter(|reply| self.board.is_valid_move(*reply).is_ok());
+        let Some(reply) = reply else {
+            self.result = Some(GameResult::FirstWins);
+            return;
+        };
+        self.board.place(Symbol::Circle, reply);
+        self.reply = Some(reply);
+        self.check_result();
+    }
+
+    fn status(&self, number: usize, current: bool) -> String {
+        let state = match self.result {
+            Some(GameResult::FirstWins) => "You won",
+            Some(GameResult::SecondWins) => "You lost",
+            Some(GameResult::Draw) => "Drawn",
+            None if current => "Your move",
+            None => "Waiting",
+        };
+        format!("{number}. {}\n{state}", self.bot.name())
+    }
+}
+
+// The next board from start on that is still being played.
+fn next_open(tables: &[Table], start: usize) -> Option<usize> {
+    (0..tables.len())
+        .map(|offset| (start + offset) % tables.len())
+        .find(|index| tables[*index].result.is_none())
+}
+
+fn show(console: &mut Console, tables: &[Table], current: Option<usize>) {
+    let panels = tables
+        .iter()
+        .enumerate()
+        .map(|(i, table)| Panel {
+            board: table.board.clone(),
+            marked: table.reply.into_iter().collect(),
+            status: table.status(i + 1, current == Some(i)),
+        })
+        .collect::<Vec<_>>();
+    let boards = console.renderer().panels(&panels);
+    console.show(&format!("\n{boards}\n"));
+}
+
+// The player's score over the finished games.
+fn score(tables: &[Table]) -> Record {
+    let mut record = Record::default();
+    for result in tables.iter().filter_map(|table| table.result) {
+        record.add(match result {
+            GameResult::FirstWins => Record {
+                wins: 1,
+                ..Record::default()
+            },
+            GameResult::SecondWins => Record {
+                losses: 1,
+                ..Record::default()
+            },
+            GameResult::Draw => Record {
+                draws: 1,
+                ..Record::default()
+            },
+        });
+    }
+    record
+}
+
+// Plays a board of each bot until every game is over or stdin is closed,
+// and returns the player's score. "resign" gives up the current board.
+pub fn run(console: &mut Console, bots: Vec<Box<dyn Bot>>) -> Record {
+    let mut tables = bots.into_iter().map(Table::new).collect::<Vec<_>>();
+    console.say(&format!(
+        "\nSimul against {} bots: you play + on every board and move first.",
+        tables.len()
+    ));
+
+    let mut start = 0;
+    while let Some(index) = next_open(&tables, start) {
+        show(console, &tables, Some(index));
+        console.prompt(&format!(
+            "Board {}, your move (row,column or resign):",
+            index + 1
+        ));
+        let Some(input) = console.read_line() else {
+            break;
+        };
+
+        let table = &mut tables[index];
+        if input.trim() == "resign" {
+            table.result = Some(GameResult::SecondWins);
+        } else {
+            let Ok(position) = parse_player_move(&input) else {
+                console.warn("Answer with a free tile, e.g. 0,2, or resign.");
+                continue;
+            };
+            if let Err(error) = table.board.is_valid_move(position) {
+                console.warn(&format!("{error} Please try again."));
+                continue;
+            }
+            table.play(position);
+        }
+        start = index + 1;
+    }
+
+    show(console, &tables, None);
+    let record = score(&tables);
+    console.result(&format!(
+        "Simul over: {} won, {} drawn and {} lost, {} of {} points.",
+        record.wins,
+        record.draws,
+        record.losses,
+        record.score(),
+        tables.len()
+    ));
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Cursor};
+
+    // Plays the first free tile in reading order.
+    struct FirstFree;
+
+    impl Bot for FirstFree {
+        fn name(&self) -> String {
+            String::from("First free")
+        }
+
+        fn choose_move(&mut self, board: &Board, _symbol: Symbol) -> Option<[usize; 2]> {
+            board.empty_positions().first().copied()
+        }
+    }
+
+    // Passes on every move.
+    struct Passing;
+
+    impl Bot for Passing {
+        fn name(&self) -> String {
+            String::from("Passing")
+        }
+
+        fn choose_move(&mut self, _board: &Board, _symbol: Symbol) -> Option<[usize; 2]> {
+            None
+        }
+    }
+
+    fn console(input: &str) -> Console {
+        Console::over(
+            Box::new(Cursor::new(input.as_bytes().to_vec())),
+            Box::new(io::sink()),
+        )
+    }
+
+    #[test]
+    fn boards_take_turns_until_every_game_is_over() {
+        // Board 1 wins down the middle column against a bot filling the top
+        // row, board 2 is resigned after one move.
+        let record = run(
+            &mut console("0,1\n2,2\n1,1\nresign\n2,1\n"),
+            vec![Box::new(FirstFree), Box::new(FirstFree)],
+        );
+
+        assert_eq!(
+            record,
+            Record {
+                wins: 1,
+                draws: 0,
+                losses: 1
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_moves_keep_the_board_and_bots_that_pass_lose() {
+        let mut table = Table::new(Box::new(Passing));
+        table.play([1, 1]);
+        assert_eq!(table.result, Some(GameResult::FirstWins));
+
+        // 0,0 is taken when board 1 comes round again, and 9 is not a move.
+        let record = run(
+            &mut console("0,0\n0,0\n0,0\n9\n"),
+            vec![Box::new(FirstFree), Box::new(Passing)],
+        );
+        assert_eq!(record.games(), 1);
+        assert_eq!(record.wins, 1);
+    }
+
+    #[test]
+    fn finished_boards_are_skipped() {
+        let mut tables = [
+            Table::new(Box::new(Passing)),
+            Table::new(Box::new(Passing)),
+            Table::new(Box::new(Passing)),
+        ];
+        tables[1].result = Some(GameResult::Draw);
+
+        assert_eq!(next_open(&tables, 1), Some(2));
+        assert_eq!(next_open(&tables, 3), Some(0));
+        for table in &mut tables {
+            table.result = Some(GameResult::Draw);
+        }
+        assert_eq!(next_open(&tables, 0), None);
+        assert!((score(&tables).score() - 1.5).abs() < f64::EPSILON);
+    }
+}
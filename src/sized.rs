@@ -0,0 +1,149 @@
+// Games on a 4x4 or 5x5 board, where a line has to go all the way across.
+// The regular game, its bots and its display are built for 3x3, so these are
+// plain games against a second player or a bot that takes wins and blocks.
+
+use crate::achievements::{self, AchievementTracker};
+use crate::ai::Difficulty;
+use crate::board::{AnyBoard, Symbol};
+use crate::bot::BotSpec;
+use crate::console::Console;
+use crate::game::{self, DrawReason, Outcome, Player, WinReason};
+use crate::rng::Rng;
+
+// Easy plays anywhere, the others take a win, then a block, then anywhere.
+fn choose_move(
+    board: &AnyBoard,
+    symbol: Symbol,
+    difficulty: Difficulty,
+    rng: &mut Rng,
+) -> Option<[usize; 2]> {
+    let empty = board.empty_positions();
+    let completes = |symbol| {
+        empty
+            .iter()
+            .copied()
+            .find(|position| board.clone().place(symbol, *position))
+    };
+    let found = match difficulty {
+        Difficulty::Easy => None,
+        Difficulty::Medium | Difficulty::Hard => {
+            completes(symbol).or_else(|| completes(symbol.opponent()))
+        }
+    };
+    found.or_else(|| (!empty.is_empty()).then(|| empty[rng.below(empty.len())]))
+}
+
+// Reads moves until one is legal. None when the input is closed.
+fn read_move(console: &mut Console, board: &AnyBoard, player: Player) -> Option<[usize; 2]> {
+    loop {
+        console.prompt(&format!("{player}, please do your move."));
+        let input = console.read_line()?;
+        let position = match game::parse_player_move(&input) {
+            Ok(position) => position,
+            Err(game::PlayerInputParseError::InvalidFormat(message)) => {
+                console.warn(&format!("{message} {player} please try again!"));
+                continue;
+            }
+        };
+        match board.is_valid_move(position) {
+            Ok(_) => return Some(position),
+            Err(error) => console.warn(&format!("{error} {player} please try again!")),
+        }
+    }
+}
+
+// Plays one game on a board of the size, Player 2 being the bot when there
+// is one. Returns None if the input was closed before the game ended.
+pub fn play(console: &mut Console, size: usize, bot: Option<Difficulty>) -> Option<Outcome> {
+    let mut board = AnyBoard::new(size).ok()?;
+    let mut rng = Rng::from_time();
+    let mut player = Player::One;
+    console.say(&format!(
+        "\nA {size}x{size} game: a line takes {size} marks in a row, column or diagonal."
+    ));
+
+    loop {
+        console.show(&format!("\n{board}\n"));
+        let position = match bot {
+            Some(difficulty) if player == Player::Two => {
+                choose_move(&board, player.into(), difficulty, &mut rng)?
+            }
+            _ => read_move(console, &board, player)?,
+        };
+        let symbol: Symbol = player.into();
+        if board.place(symbol, position) {
+            console.result(&format!("\n{board}\n\nThe winner is: {player}"));
+            return Some(Outcome::Winner(player, WinReason::Line));
+        }
+        if board.is_full() {
+            console.result(&format!("\n{board}\n\nThe board is full, it's a draw!"));
+            return Some(Outcome::Draw(DrawReason::FullBoard));
+        }
+        player = player.other();
+    }
+}
+
+// Counts a game Player 1 played against the bot towards the achievements.
+pub fn track_achievements(
+    console: &mut Console,
+    difficulty: Difficulty,
+    size: usize,
+    outcome: Outcome,
+) {
+    let tracker = AchievementTracker::new(achievements::path(), BotSpec::Builtin(difficulty));
+    match tracker.track_sized(size, outcome) {
+        Ok(unlocked) => {
+            for achievement in unlocked {
+                console.say(&format!("\nAchievement unlocked: {achievement}!"));
+            }
+        }
+        Err(message) => console.warn(&message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Cursor};
+
+    #[test]
+    fn bots_take_wins_then_blocks() {
+        let mut rng = Rng::new(7);
+        let mut board = AnyBoard::new(4).unwrap();
+        for column in 0..3 {
+            board.place(Symbol::Plus, [0, column]);
+            board.place(Symbol::Circle, [1, column]);
+        }
+        // Both can complete their row, and o takes its own.
+        assert_eq!(
+            choose_move(&board, Symbol::Circle, Difficulty::Medium, &mut rng),
+            Some([1, 3])
+        );
+
+        let mut board = AnyBoard::new(5).unwrap();
+        for row in 0..4 {
+            board.place(Symbol::Plus, [row, row]);
+        }
+        assert_eq!(
+            choose_move(&board, Symbol::Circle, Difficulty::Medium, &mut rng),
+            Some([4, 4])
+        );
+    }
+
+    #[test]
+    fn lines_go_all_the_way_across() {
+        // Player 1 fills the top row of a 4x4 board, Player 2 the second.
+        let input = "0,0\n1,0\n0,1\n1,1\n0,2\n1,2\n9,9\n0,3\n";
+        let mut console = Console::over(
+            Box::new(Cursor::new(input.as_bytes().to_vec())),
+            Box::new(io::sink()),
+        );
+        assert_eq!(
+            play(&mut console, 4, None),
+            Some(Outcome::Winner(Player::One, WinReason::Line))
+        );
+
+        let mut closed = Console::over(Box::new(io::empty()), Box::new(io::sink()));
+        assert_eq!(play(&mut closed, 5, None), None);
+    }
+}
@@ -0,0 +1,229 @@
+// Short sounds for moves, mistakes and the end of a game, played with the
+// `audio` feature. The sounds are made here as WAV files and handed to the
+// system's player: aplay or paplay on Linux, afplay on macOS and PowerShell on
+// Windows. Without a player or an audio device the game simply stays silent.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+#[cfg(feature = "audio")]
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(feature = "audio")]
+use std::sync::Arc;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Effect {
+    Move,
+    Invalid,
+    Win,
+    Draw,
+}
+
+// Loudness from 0 to 100.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Volume(u8);
+
+impl Default for Volume {
+    fn default() -> Self {
+        Volume(60)
+    }
+}
+
+impl Display for Volume {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for Volume {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<u8>()
+            .ok()
+            .filter(|volume| *volume <= 100)
+            .map(Volume)
+            .ok_or_else(|| format!("'{s}' is not a volume, use 0 to 100."))
+    }
+}
+
+#[cfg(feature = "audio")]
+const SAMPLE_RATE: u32 = 22_050;
+
+// Notes as frequency in Hz and length in milliseconds.
+#[cfg(feature = "audio")]
+fn notes(effect: Effect) -> &'static [(f64, u32)] {
+    match effect {
+        Effect::Move => &[(880.0, 60)],
+        Effect::Invalid => &[(220.0, 90), (185.0, 140)],
+        Effect::Win => &[(523.25, 110), (659.25, 110), (783.99, 220)],
+        Effect::Draw => &[(440.0, 140), (349.23, 220)],
+    }
+}
+
+// The effect as a 16 bit mono WAV file.
+#[cfg(feature = "audio")]
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn wav(effect: Effect, Volume(volume): Volume) -> Vec<u8> {
+    let amplitude = f64::from(volume) / 100.0 * f64::from(i16::MAX) / 2.0;
+    // Notes fade in and out over a few milliseconds so they do not click.
+    let fade = (SAMPLE_RATE / 200) as usize;
+
+    let mut samples = Vec::new();
+    for &(frequency, milliseconds) in notes(effect) {
+        let length = (SAMPLE_RATE * milliseconds / 1000) as usize;
+        samples.extend((0..length).map(|i| {
+            let envelope = (i.min(length - 1 - i).min(fade) as f64) / fade as f64;
+            let phase = std::f64::consts::TAU * frequency * i as f64 / f64::from(SAMPLE_RATE);
+            (amplitude * envelope * phase.sin()) as i16
+        }));
+    }
+
+    let data_size = u32::try_from(samples.len() * 2).unwrap_or(u32::MAX);
+    let mut bytes = Vec::with_capacity(44 + samples.len() * 2);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, one channel.
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_size.to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    bytes
+}
+
+// The players to try for a file, in order.
+#[cfg(feature = "audio")]
+fn players(path: &std::path::Path) -> Vec<std::process::Command> {
+    use std::process::Command;
+
+    let command = |program: &str, args: &[&str]| {
+        let mut command = Command::new(program);
+        command.args(args);
+        command
+    };
+    let path = path.to_string_lossy();
+    if cfg!(windows) {
+        let script = format!("(New-Object Media.SoundPlayer '{path}').PlaySync()");
+        vec![command(
+            "powershell",
+            &["-NoProfile", "-NonInteractive", "-Command", &script],
+        )]
+    } else if cfg!(target_os = "macos") {
+        vec![command("afplay", &[&path])]
+    } else {
+        vec![
+            command("aplay", &["-q", &path]),
+            command("paplay", &[&path]),
+        ]
+    }
+}
+
+// Plays effects at one volume, giving up for good once no player works.
+pub struct Speaker {
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    volume: Volume,
+    #[cfg(feature = "audio")]
+    working: Arc<AtomicBool>,
+}
+
+impl Speaker {
+    // None when muted or turned all the way down.
+    pub fn new(volume: Volume, mute: bool) -> Option<Speaker> {
+        (!mute && volume.0 > 0).then_some(Speaker {
+            volume,
+            #[cfg(feature = "audio")]
+            working: Arc::new(AtomicBool::new(true)),
+        })
+    }
+
+    // Starts the effect and returns at once, the player runs on its own thread.
+    #[cfg(feature = "audio")]
+    pub fn play(&self, effect: Effect) {
+        use std::process::Stdio;
+
+        static PLAYED: AtomicUsize = AtomicUsize::new(0);
+
+        if !self.working.load(Ordering::Relaxed) {
+            return;
+        }
+        let path = std::env::temp_dir().join(format!(
+            "tic-tac-toe-{}-{}.wav",
+            std::process::id(),
+            PLAYED.fetch_add(1, Ordering::Relaxed)
+        ));
+        if std::fs::write(&path, wav(effect, self.volume)).is_err() {
+            self.working.store(false, Ordering::Relaxed);
+            return;
+        }
+
+        let working = Arc::clone(&self.working);
+        std::thread::spawn(move || {
+            let played = players(&path).into_iter().any(|mut player| {
+                player
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()
+                    .is_ok_and(|status| status.success())
+            });
+            if !played {
+                working.store(false, Ordering::Relaxed);
+            }
+            let _ = std::fs::remove_file(&path);
+        });
+    }
+
+    // Builds without the audio feature stay silent.
+    #[cfg(not(feature = "audio"))]
+    #[allow(clippy::unused_self)]
+    pub fn play(&self, _effect: Effect) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volumes_go_from_0_to_100() {
+        assert_eq!("0".parse(), Ok(Volume(0)));
+        assert_eq!(
+            "100".parse::<Volume>().map(|volume| volume.to_string()),
+            Ok(String::from("100"))
+        );
+        assert!("101".parse::<Volume>().is_err());
+        assert!("loud".parse::<Volume>().is_err());
+    }
+
+    #[test]
+    fn muted_or_silent_speakers_are_left_out() {
+        assert!(Speaker::new(Volume::default(), true).is_none());
+        assert!(Speaker::new(Volume(0), false).is_none());
+        assert!(Speaker::new(Volume(1), false).is_some());
+    }
+
+    #[cfg(feature = "audio")]
+    #[test]
+    fn effects_are_wav_files_as_long_as_their_notes() {
+        let bytes = wav(Effect::Win, Volume(100));
+        assert_eq!(&bytes[..4], b"RIFF");
+        assert_eq!(&bytes[8..16], b"WAVEfmt ");
+        // 440 ms of 16 bit samples after the 44 byte header.
+        assert_eq!(bytes.len(), 44 + 2 * (2425 + 2425 + 4851));
+
+        let silent = wav(Effect::Move, Volume(0));
+        assert!(silent[44..].iter().all(|byte| *byte == 0));
+    }
+}
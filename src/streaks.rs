@@ -0,0 +1,312 @@
+// Win and unbeaten streaks in games against the computer, per opponent and
+// over all of them, kept across sessions. A new best is announced when the
+// game that set it ends.
+//
+// The file has a line per opponent, tab separated: the bot as given to --p2,
+// or "any" for all games, then the current and best win streak and the
+// current and best unbeaten streak.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write as _};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::profile;
+use crate::tournament::Record;
+
+// Where streaks are kept unless TTT_STREAKS says otherwise.
+pub const DEFAULT_STREAKS: &str = "tic-tac-toe.streaks";
+
+// The key of the streaks over every opponent.
+const ANY_OPPONENT: &str = "any";
+
+// Shorter streaks are too common to announce.
+const ANNOUNCED_FROM: u32 = 2;
+
+pub fn path() -> PathBuf {
+    std::env::var_os("TTT_STREAKS").map_or_else(|| profile::file(DEFAULT_STREAKS), PathBuf::from)
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Streak {
+    pub current: u32,
+    pub best: u32,
+}
+
+impl Streak {
+    // Extends or ends the streak, returning whether it is a new best.
+    fn extend(&mut self, extended: bool) -> bool {
+        self.current = if extended { self.current + 1 } else { 0 };
+        let record = self.current > self.best;
+        self.best = self.best.max(self.current);
+        record
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Streaks {
+    pub wins: Streak,
+    // Games without a loss.
+    pub unbeaten: Streak,
+}
+
+impl Streaks {
+    // Counts a game from the player's side, returning the new bests among
+    // the win and the unbeaten streak.
+    fn update(&mut self, result: Record) -> [Option<u32>; 2] {
+        let wins = self.wins.extend(result.wins > 0);
+        let unbeaten = self.unbeaten.extend(result.losses == 0);
+        [
+            wins.then_some(self.wins.best),
+            unbeaten.then_some(self.unbeaten.best),
+        ]
+    }
+}
+
+fn announce(records: [Option<u32>; 2], against: &str) -> Vec<String> {
+    let [wins, unbeaten] = records.map(|record| record.filter(|games| *games >= ANNOUNCED_FROM));
+    let wins = wins.map(|games| format!("New record: {games} wins in a row against {against}!"));
+    let unbeaten = unbeaten
+        .map(|games| format!("New record: {games} games without a loss against {against}!"));
+    [wins, unbeaten].into_iter().flatten().collect()
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct StreakStats {
+    by_opponent: BTreeMap<String, Streaks>,
+}
+
+impl StreakStats {
+    // Counts a game the player played against opponent, returning the new
+    // records to announce.
+    pub fn update(&mut self, opponent: &str, result: Record) -> Vec<String> {
+        let own = self.by_opponent.entry(opponent.to_string()).or_default();
+        let own_records = own.update(result);
+        let own_bests = [own.wins.best, own.unbeaten.best];
+        let any_records = self
+            .by_opponent
+            .entry(ANY_OPPONENT.to_string())
+            .or_default()
+            .update(result);
+
+        // A streak over all opponents is only news when it is longer than
+        // the one against this opponent.
+        let any_records =
+            std::array::from_fn(|i| any_records[i].filter(|games| *games > own_bests[i]));
+        let mut records = announce(own_records, opponent);
+        records.extend(announce(any_records, "the computer"));
+        records
+    }
+
+    // Keeps the best streaks of either. Current streaks only carry on from
+    // the games played here, so those are only taken for new opponents.
+    pub fn merge(&mut self, other: StreakStats) {
+        for (opponent, theirs) in other.by_opponent {
+            let ours = self.by_opponent.entry(opponent).or_insert(theirs);
+            ours.wins.best = ours.wins.best.max(theirs.wins.best);
+            ours.unbeaten.best = ours.unbeaten.best.max(theirs.unbeaten.best);
+        }
+    }
+
+    // A table of every opponent's streaks, the total first.
+    pub fn report(&self) -> String {
+        let Some(any) = self.by_opponent.get(ANY_OPPONENT) else {
+            return String::from("No games against the computer have been played yet.");
+        };
+
+        let mut report = format!(
+            "{:<20}  {:>12}  {:>9}  {:>16}  {:>13}\n",
+            "Opponent", "Win streak", "Best", "Unbeaten streak", "Best"
+        );
+        let rows = [("All opponents", any)].into_iter().chain(
+            self.by_opponent
+                .iter()
+                .filter(|(opponent, _)| *opponent != ANY_OPPONENT)
+                .map(|(opponent, streaks)| (opponent.as_str(), streaks)),
+        );
+        for (opponent, streaks) in rows {
+            let _ = writeln!(
+                report,
+                "{opponent:<20}  {:>12}  {:>9}  {:>16}  {:>13}",
+                streaks.wins.current,
+                streaks.wins.best,
+                streaks.unbeaten.current,
+                streaks.unbeaten.best
+            );
+        }
+        report.trim_end().to_string()
+    }
+}
+
+impl Display for StreakStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (opponent, streaks) in &self.by_opponent {
+            writeln!(
+                f,
+                "{opponent}\t{}\t{}\t{}\t{}",
+                streaks.wins.current,
+                streaks.wins.best,
+                streaks.unbeaten.current,
+                streaks.unbeaten.best
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for StreakStats {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut stats = StreakStats::default();
+
+        for (i, line) in s
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+        {
+            let invalid = || format!("Line {} of the streaks is invalid.", i + 1);
+            let [opponent, wins, best_wins, unbeaten, best_unbeaten] =
+                line.split('\t').collect::<Vec<_>>()[..]
+            else {
+                return Err(invalid());
+            };
+            let count = |value: &str| value.parse::<u32>().map_err(|_| invalid());
+
+            stats.by_opponent.insert(
+                opponent.to_string(),
+                Streaks {
+                    wins: Streak {
+                        current: count(wins)?,
+                        best: count(best_wins)?,
+                    },
+                    unbeaten: Streak {
+                        current: count(unbeaten)?,
+                        best: count(best_unbeaten)?,
+                    },
+                },
+            );
+        }
+
+        Ok(stats)
+    }
+}
+
+// Nothing played yet is not an error.
+pub fn load(path: &Path) -> Result<StreakStats, String> {
+    if !path.exists() {
+        return Ok(StreakStats::default());
+    }
+    fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {error}", path.display()))?
+        .parse()
+        .map_err(|error| format!("{}: {error}", path.display()))
+}
+
+// Where a game's streaks are kept and who it is against.
+#[derive(Clone)]
+pub struct StreakTracker {
+    path: PathBuf,
+    opponent: String,
+}
+
+impl StreakTracker {
+    pub fn new(path: PathBuf, opponent: String) -> StreakTracker {
+        StreakTracker { path, opponent }
+    }
+
+    // Counts the player's result, returning the new records to announce.
+    pub fn track(&self, result: Record) -> Result<Vec<String>, String> {
+        let mut stats = load(&self.path)?;
+        let records = stats.update(&self.opponent, result);
+        fs::write(&self.path, stats.to_string()).map_err(|error| {
+            format!(
+                "Could not save the streaks to {}: {error}",
+                self.path.display()
+            )
+        })?;
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIN: Record = Record {
+        wins: 1,
+        draws: 0,
+        losses: 0,
+    };
+    const DRAW: Record = Record {
+        wins: 0,
+        draws: 1,
+        losses: 0,
+    };
+    const LOSS: Record = Record {
+        wins: 0,
+        draws: 0,
+        losses: 1,
+    };
+
+    #[test]
+    fn new_bests_are_announced() {
+        let mut stats = StreakStats::default();
+
+        assert!(stats.update("easy", WIN).is_empty());
+        assert_eq!(
+            stats.update("easy", WIN),
+            [
+                "New record: 2 wins in a row against easy!",
+                "New record: 2 games without a loss against easy!",
+            ]
+        );
+        // A draw ends the win streak but not the unbeaten one.
+        assert_eq!(
+            stats.update("hard", DRAW),
+            ["New record: 3 games without a loss against the computer!"]
+        );
+        assert!(stats.update("hard", LOSS).is_empty());
+        // Streaks are per opponent, so losing to hard leaves easy's going.
+        assert_eq!(
+            stats.update("easy", WIN),
+            [
+                "New record: 3 wins in a row against easy!",
+                "New record: 3 games without a loss against easy!",
+            ]
+        );
+
+        let easy = stats.by_opponent["easy"];
+        assert_eq!((easy.wins.current, easy.wins.best), (3, 3));
+        let any = stats.by_opponent[ANY_OPPONENT];
+        assert_eq!((any.unbeaten.current, any.unbeaten.best), (1, 3));
+    }
+
+    #[test]
+    fn streaks_round_trip_and_are_reported() {
+        let mut stats = StreakStats::default();
+        assert!(stats.report().starts_with("No games"));
+        stats.update("hard", DRAW);
+        stats.update("medium", WIN);
+
+        let parsed: StreakStats = stats.to_string().parse().unwrap();
+        assert_eq!(parsed, stats);
+
+        let report = stats.report();
+        let lines = report.lines().collect::<Vec<_>>();
+        assert!(lines[1].starts_with("All opponents"));
+        assert!(lines[2].starts_with("hard"));
+        assert_eq!(lines.len(), 4);
+        assert!("hard\t1\t2".parse::<StreakStats>().is_err());
+    }
+
+    #[test]
+    fn merging_keeps_the_best_streaks_and_the_current_ones_here() {
+        let mut ours: StreakStats = "easy\t1\t1\t3\t3".parse().unwrap();
+        let theirs: StreakStats = "easy\t4\t4\t0\t2\nhard\t0\t0\t2\t2".parse().unwrap();
+
+        ours.merge(theirs);
+        assert_eq!(ours.to_string(), "easy\t1\t4\t3\t3\nhard\t0\t0\t2\t2\n");
+    }
+}
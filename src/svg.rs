@@ -0,0 +1,142 @@
+use std::fmt::Write;
+
+use crate::board::{Board, GameStatus, Symbol};
+
+const CELL_SIZE: usize = 100;
+const MARGIN: usize = 10;
+const FRAME_GAP: usize = 40;
+
+fn frame_size(board: &Board) -> usize {
+    board.tiles.len() * CELL_SIZE + 2 * MARGIN
+}
+
+fn center(index: usize) -> usize {
+    MARGIN + index * CELL_SIZE + CELL_SIZE / 2
+}
+
+// Draws a single board with its top left corner at x_offset.
+fn draw_board(svg: &mut String, board: &Board, x_offset: usize) {
+    let size = board.tiles.len();
+    let far_edge = MARGIN + size * CELL_SIZE;
+    let quarter = CELL_SIZE / 4;
+
+    let _ = writeln!(svg, r#"  <g transform="translate({x_offset},0)">"#);
+    let _ = writeln!(
+        svg,
+        r##"    <rect width="{0}" height="{0}" fill="#ffffff"/>"##,
+        frame_size(board)
+    );
+
+    for i in 1..size {
+        let offset = MARGIN + i * CELL_SIZE;
+        let _ = writeln!(
+            svg,
+            r##"    <line x1="{offset}" y1="{MARGIN}" x2="{offset}" y2="{far_edge}" stroke="#333333" stroke-width="4"/>"##
+        );
+        let _ = writeln!(
+            svg,
+            r##"    <line x1="{MARGIN}" y1="{offset}" x2="{far_edge}" y2="{offset}" stroke="#333333" stroke-width="4"/>"##
+        );
+    }
+
+    for (i, row) in board.tiles.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            let (x, y) = (center(j), center(i));
+
+            match tile {
+                Symbol::Empty => {}
+                Symbol::Plus => {
+                    let _ = writeln!(
+                        svg,
+                        r##"    <path d="M{} {y} H{} M{x} {} V{}" stroke="#1f6feb" stroke-width="10"/>"##,
+                        x - quarter,
+                        x + quarter,
+                        y - quarter,
+                        y + quarter
+                    );
+                }
+                Symbol::Circle => {
+                    let _ = writeln!(
+                        svg,
+                        r##"    <circle cx="{x}" cy="{y}" r="{quarter}" fill="none" stroke="#d1242f" stroke-width="10"/>"##
+                    );
+                }
+            }
+        }
+    }
+
+    if let GameStatus::Won(_, line) = board.status() {
+        let [first, last] = [line[0], line[line.len() - 1]];
+        let _ = writeln!(
+            svg,
+            r##"    <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="#2da44e" stroke-width="8" stroke-linecap="round" opacity="0.8"/>"##,
+            center(first[1]),
+            center(first[0]),
+            center(last[1]),
+            center(last[0])
+        );
+    }
+
+    let _ = writeln!(svg, "  </g>");
+}
+
+// Renders one board, or several boards next to each other as a film strip
+// of a whole game. A completed line is drawn through the winning symbols.
+pub fn render(frames: &[Board]) -> String {
+    let height = frames.first().map_or(0, frame_size);
+    let width = frames
+        .iter()
+        .map(|board| frame_size(board) + FRAME_GAP)
+        .sum::<usize>()
+        .saturating_sub(FRAME_GAP);
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    svg.push('\n');
+
+    let mut x_offset = 0;
+    for board in frames {
+        draw_board(&mut svg, board, x_offset);
+        x_offset += frame_size(board) + FRAME_GAP;
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_board_has_one_frame_with_its_symbols() {
+        let board: Board = "+-o/---/---".parse().unwrap();
+        let svg = render(&[board]);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains(r#"width="320" height="320""#));
+        assert_eq!(svg.matches("<g ").count(), 1);
+        assert_eq!(svg.matches("<path").count(), 1);
+        assert_eq!(svg.matches("<circle").count(), 1);
+    }
+
+    #[test]
+    fn several_boards_are_laid_out_as_a_film_strip() {
+        let frames = ["---/---/---", "---/-+-/---", "o--/-+-/---"]
+            .map(|position| position.parse::<Board>().unwrap());
+        let svg = render(&frames);
+
+        assert!(svg.contains(r#"width="1040" height="320""#));
+        assert!(svg.contains("translate(720,0)"));
+        assert_eq!(svg.matches("<g ").count(), 3);
+    }
+
+    #[test]
+    fn winning_line_is_highlighted() {
+        let board: Board = "+++/oo-/---".parse().unwrap();
+
+        assert!(render(&[board]).contains(r#"x1="60" y1="60" x2="260" y2="60""#));
+    }
+}
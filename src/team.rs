@@ -0,0 +1,263 @@
+// Consultation games: two players share a side and agree on its moves,
+// against another team or a bot. With the confirm rule one teammate proposes
+// a move and the other plays it by answering yes or proposes another, until
+// one of them agrees; who proposes first changes every move. With the
+// alternate rule the teammates take turns making the side's moves alone.
+// Every player sits at the same terminal.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::board::{Board, GameStatus, Symbol};
+use crate::bot::Bot;
+use crate::console::Console;
+use crate::game::parse_player_move;
+use crate::tournament::GameResult;
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Rule {
+    #[default]
+    Confirm,
+    Alternate,
+}
+
+pub const RULES: [Rule; 2] = [Rule::Confirm, Rule::Alternate];
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Rule::Confirm => "confirm",
+            Rule::Alternate => "alternate",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RULES
+            .into_iter()
+            .find(|rule| rule.to_string() == s)
+            .ok_or_else(|| format!("'{s}' is not a team rule, use confirm or alternate."))
+    }
+}
+
+pub enum Side {
+    Team([String; 2]),
+    Computer(Box<dyn Bot>),
+}
+
+impl Side {
+    fn name(&self) -> String {
+        match self {
+            Side::Team([first, second]) => format!("{first} and {second}"),
+            Side::Computer(bot) => bot.name(),
+        }
+    }
+}
+
+// Asks until the answer is a free tile, None when stdin is closed.
+fn read_move(console: &mut Console, board: &Board, prompt: &str) -> Option<[usize; 2]> {
+    loop {
+        console.prompt(prompt);
+        let input = console.read_line()?;
+        let Ok(position) = parse_player_move(&input) else {
+            console.warn("Answer with a free tile, e.g. 0,2.");
+            continue;
+        };
+        match board.is_valid_move(position) {
+            Ok(_) => return Some(position),
+            Err(error) => console.warn(&format!("{error} Please try again.")),
+        }
+    }
+}
+
+// The team's move once both agree on it, first proposing.
+fn consult(
+    console: &mut Console,
+    board: &Board,
+    names: &[String; 2],
+    first: usize,
+) -> Option<[usize; 2]> {
+    let mut proposer = first;
+    let mut position = read_move(
+        console,
+        board,
+        &format!("{}, propose a move:", names[proposer]),
+    )?;
+
+    loop {
+        let partner = 1 - proposer;
+        console.prompt(&format!(
+            "{}, play {},{}? Answer yes or propose another move:",
+            names[partner], position[0], position[1]
+        ));
+        let input = console.read_line()?;
+        if matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+            return Some(position);
+        }
+        let Ok(proposal) = parse_player_move(&input) else {
+            console.warn("Answer yes or with a free tile, e.g. 0,2.");
+            continue;
+        };
+        match board.is_valid_move(proposal) {
+            Ok(_) => {
+                position = proposal;
+                proposer = partner;
+            }
+            Err(error) => console.warn(&format!("{error} Please try again.")),
+        }
+    }
+}
+
+// Plays one game, the first side as +, and returns its result, None when
+// stdin is closed. A bot that passes or plays an illegal move loses.
+pub fn run(console: &mut Console, mut sides: [Side; 2], rule: Rule) -> Option<GameResult> {
+    let mut board = Board::new();
+    let mut last = None;
+    let mut turn = 0;
+    // Moves each side has made, to take turns within the team.
+    let mut moves = [0; 2];
+    console.say(&format!(
+        "\n{} (+) against {} (o), teams play by the {rule} rule.",
+        sides[0].name(),
+        sides[1].name()
+    ));
+
+    loop {
+        let boards = console
+            .renderer()
+            .board(&board, &last.into_iter().collect::<Vec<_>>());
+        console.show(&format!("\n{boards}\n"));
+        match board.status() {
+            GameStatus::Won(symbol, _) => {
+                let (result, winner) = if symbol == Symbol::Plus {
+                    (GameResult::FirstWins, &sides[0])
+                } else {
+                    (GameResult::SecondWins, &sides[1])
+                };
+                let verb = match winner {
+                    Side::Team(_) => "win",
+                    Side::Computer(_) => "wins",
+                };
+                console.result(&format!("{} {verb}!", winner.name()));
+                return Some(result);
+            }
+            GameStatus::Draw => {
+                console.result("The game is a draw.");
+                return Some(GameResult::Draw);
+            }
+            GameStatus::InProgress => {}
+        }
+
+        let symbol = if turn == 0 {
+            Symbol::Plus
+        } else {
+            Symbol::Circle
+        };
+        let member = moves[turn] % 2;
+        let position = match (&mut sides[turn], rule) {
+            (Side::Computer(bot), _) => {
+                let choice = bot
+                    .choose_move(&board, symbol)
+                    .filter(|position| board.is_valid_move(*position).is_ok());
+                let Some(position) = choice else {
+                    console.result(&format!("{} forfeits the game.", sides[turn].name()));
+                    return Some(if turn == 0 {
+                        GameResult::SecondWins
+                    } else {
+                        GameResult::FirstWins
+                    });
+                };
+                position
+            }
+            (Side::Team(names), Rule::Confirm) => consult(console, &board, names, member)?,
+            (Side::Team(names), Rule::Alternate) => read_move(
+                console,
+                &board,
+                &format!("{}, your move for the team:", names[member]),
+            )?,
+        };
+
+        board.place(symbol, position);
+        last = Some(position);
+        moves[turn] += 1;
+        turn = 1 - turn;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Cursor};
+
+    // Plays the first free tile in reading order.
+    struct FirstFree;
+
+    impl Bot for FirstFree {
+        fn name(&self) -> String {
+            String::from("First free")
+        }
+
+        fn choose_move(&mut self, board: &Board, _symbol: Symbol) -> Option<[usize; 2]> {
+            board.empty_positions().first().copied()
+        }
+    }
+
+    fn console(input: &str) -> Console {
+        Console::over(
+            Box::new(Cursor::new(input.as_bytes().to_vec())),
+            Box::new(io::sink()),
+        )
+    }
+
+    fn team(first: &str, second: &str) -> Side {
+        Side::Team([String::from(first), String::from(second)])
+    }
+
+    #[test]
+    fn rules_round_trip() {
+        for rule in RULES {
+            assert_eq!(rule.to_string().parse(), Ok(rule));
+        }
+        assert!("vote".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn moves_are_played_once_a_teammate_agrees() {
+        // Ann proposes 2,2, Bob counters with 1,1 and Ann agrees; the bot
+        // takes 0,0. Bob proposes first next, and so on down column 1.
+        let input = "2,2\n1,1\ny\n0,1\nyes\n2,1\nyes\n";
+        let result = run(
+            &mut console(input),
+            [team("Ann", "Bob"), Side::Computer(Box::new(FirstFree))],
+            Rule::Confirm,
+        );
+
+        assert_eq!(result, Some(GameResult::FirstWins));
+    }
+
+    #[test]
+    fn teammates_alternate_and_teams_can_meet() {
+        // Ann, Cat, Bob, Dan and Ann again, and + has the top row.
+        let input = "0,0\n1,0\n0,1\n1,1\n0,2\n";
+        let result = run(
+            &mut console(input),
+            [team("Ann", "Bob"), team("Cat", "Dan")],
+            Rule::Alternate,
+        );
+
+        assert_eq!(result, Some(GameResult::FirstWins));
+        assert_eq!(
+            run(
+                &mut console("0,0\n"),
+                [team("Ann", "Bob"), team("Cat", "Dan")],
+                Rule::Alternate
+            ),
+            None
+        );
+    }
+}
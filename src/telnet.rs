@@ -0,0 +1,1440 @@
+// Serves the menu and games over raw TCP, so telnet clients, MUD clients and
+// retro terminals can play. Every connection gets its own thread and menu;
+// telnet option negotiation is ignored and stripped from the input. Game
+// events can go to webhooks and an MQTT broker, see src/events.rs.
+//
+// The first line sent names the version, the protocol and the board variants.
+// Scripted clients can answer it with "hello <protocol> [<variant>]" and are
+// disconnected right away when the server does not speak it, instead of
+// getting lost in the menus later.
+//
+// With --tokens, only players with a token from the file get past the first
+// prompt, and a dropped game can only be rejoined by the same player.
+//
+// Whoever runs the server can type admin commands into it: list the
+// sessions, kick a player, say something to everyone, or drain the server so
+// it stops once the games in progress end.
+//
+// Limits keep one client from taking over a public server: how many connect
+// at once, from one address or as one token's player, how fast each may send
+// lines, and how long one may sit idle.
+//
+// Each connection is given a rejoin code. When it drops, its game waits a
+// while for the player to connect again and type "rejoin <code>".
+//
+// With a certificate the server speaks TLS instead of plain text, see
+// src/tls.rs. The join command and the menu play on a server from a terminal.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, SendError, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::autosave::{self, SavedGame};
+use crate::console::{Console, Rejoin, Stream};
+use crate::events::Events;
+use crate::menu;
+use crate::metrics::{self, Metrics};
+use crate::mqtt::{Broker, Publisher};
+use crate::tls::{self, Acceptor, Identity, Tls, Verify};
+use crate::webhook::Endpoint;
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:2323";
+
+// Wrong tokens a connection may send before it is closed.
+const TOKEN_ATTEMPTS: usize = 3;
+
+// Lines a connection may send at once before the rate limit applies, and
+// how many more it may send past the limit before it is disconnected.
+const BURST_LINES: u32 = 10;
+const FLOOD_LINES: u32 = 50;
+
+// A client that stops reading is disconnected after this long.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+// How long a game waits for a player whose connection dropped.
+const REJOIN_GRACE: Duration = Duration::from_mins(2);
+
+// Rejoin codes leave out letters and digits that are easy to mix up.
+const CODE_CHARACTERS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 6;
+
+// Version of the protocol: menus, answers and moves as lines of text.
+const PROTOCOL: u32 = 1;
+// Board variants the games are played on.
+const VARIANTS: &[&str] = &["3x3"];
+
+const WELCOME: &str = "Welcome to tic-tac-toe! Answer with a number or a move and press enter.";
+
+pub struct TelnetOptions {
+    pub address: String,
+    pub webhooks: Vec<Endpoint>,
+    pub mqtt: Option<Broker>,
+    // A file of "<name> <token>" lines; anyone can play without one.
+    pub tokens: Option<PathBuf>,
+    // A player to add a token to the file for, instead of serving.
+    pub issue: Option<String>,
+    // A directory to save games in progress to, so they survive a restart.
+    pub saves: Option<PathBuf>,
+    // Where to serve /metrics for Prometheus.
+    pub metrics: Option<String>,
+    pub limits: Limits,
+    // The certificate to serve over TLS with.
+    pub tls: Option<Identity>,
+}
+
+impl TelnetOptions {
+    // Serves on address with the default limits and nothing else.
+    pub fn new(address: &str) -> TelnetOptions {
+        TelnetOptions {
+            address: String::from(address),
+            webhooks: Vec::new(),
+            mqtt: None,
+            tokens: None,
+            issue: None,
+            saves: None,
+            metrics: None,
+            limits: Limits::default(),
+            tls: None,
+        }
+    }
+}
+
+// A client's connection, over TLS when the server uses it.
+enum Link {
+    Plain(TcpStream),
+    Tls(Tls),
+}
+
+impl Link {
+    fn socket(&self) -> &TcpStream {
+        match self {
+            Link::Plain(stream) => stream,
+            Link::Tls(tls) => tls.socket(),
+        }
+    }
+
+    fn try_clone(&self) -> io::Result<Link> {
+        match self {
+            Link::Plain(stream) => stream.try_clone().map(Link::Plain),
+            Link::Tls(tls) => tls.try_clone().map(Link::Tls),
+        }
+    }
+}
+
+impl Read for Link {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Link::Plain(stream) => stream.read(buffer),
+            Link::Tls(tls) => tls.read(buffer),
+        }
+    }
+}
+
+impl Write for Link {
+    fn write(&mut self, text: &[u8]) -> io::Result<usize> {
+        match self {
+            Link::Plain(stream) => stream.write(text),
+            Link::Tls(tls) => tls.write(text),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Link::Plain(stream) => stream.flush(),
+            Link::Tls(tls) => tls.flush(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    // Connections beyond this are turned away until someone leaves.
+    pub connections: usize,
+    // Sessions from one address, or played by one token's player.
+    pub per_client: usize,
+    // Lines a second each connection may send, after a burst.
+    pub lines_per_second: u32,
+    // A player who types nothing for this long is disconnected, so idle
+    // connections do not hold on to their slot.
+    pub idle: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            connections: 32,
+            per_client: 4,
+            lines_per_second: 4,
+            idle: Duration::from_mins(5),
+        }
+    }
+}
+
+// Spaces out a connection's lines: each line books the next slot at the
+// steady rate, and up to BURST_LINES slots may be booked ahead.
+struct Throttle {
+    interval: Duration,
+    // When the slots booked so far run out.
+    booked_until: Instant,
+    // Lines turned away since the last one let through.
+    dropped: u32,
+}
+
+impl Throttle {
+    fn new(lines_per_second: u32) -> Throttle {
+        Throttle {
+            interval: Duration::from_secs(1) / lines_per_second.max(1),
+            booked_until: Instant::now(),
+            dropped: 0,
+        }
+    }
+
+    // Whether a line arriving at the time is let through.
+    fn admit(&mut self, now: Instant) -> bool {
+        let start = self.booked_until.max(now);
+        // Booked further ahead than the burst's last slot, given the time
+        // that passed since it was booked.
+        if start.duration_since(now) > self.interval * (BURST_LINES - 1) {
+            self.dropped += 1;
+            return false;
+        }
+        self.booked_until = start + self.interval;
+        self.dropped = 0;
+        true
+    }
+}
+
+// Telnet command bytes.
+const IAC: u8 = 255;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const WILL: u8 = 251;
+const DONT: u8 = 254;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TelnetState {
+    Data,
+    Command,
+    // WILL, WONT, DO or DONT, which are followed by one option byte.
+    Option,
+    Subnegotiation,
+    SubnegotiationCommand,
+}
+
+// Passes the text through and drops telnet commands, even when they are
+// split across reads.
+struct TelnetReader<R> {
+    inner: R,
+    state: TelnetState,
+}
+
+impl<R: Read> TelnetReader<R> {
+    fn new(inner: R) -> TelnetReader<R> {
+        TelnetReader {
+            inner,
+            state: TelnetState::Data,
+        }
+    }
+
+    // Whether the byte is text, moving on to the next state either way.
+    fn keep(&mut self, byte: u8) -> bool {
+        let keep = match self.state {
+            // Raw TCP clients send no commands, so NUL bytes are the only noise.
+            TelnetState::Data => byte != IAC && byte != 0,
+            // An escaped 255 byte.
+            TelnetState::Command => byte == IAC,
+            _ => false,
+        };
+
+        self.state = match (self.state, byte) {
+            (TelnetState::Data, IAC) => TelnetState::Command,
+            (TelnetState::Command, WILL..=DONT) => TelnetState::Option,
+            (TelnetState::Subnegotiation, IAC) => TelnetState::SubnegotiationCommand,
+            // Only IAC SE ends a subnegotiation.
+            (TelnetState::SubnegotiationCommand, SE) => TelnetState::Data,
+            (TelnetState::Command, SB)
+            | (TelnetState::Subnegotiation | TelnetState::SubnegotiationCommand, _) => {
+                TelnetState::Subnegotiation
+            }
+            _ => TelnetState::Data,
+        };
+        keep
+    }
+}
+
+impl<R: Read> Read for TelnetReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let bytes_read = self.inner.read(buf)?;
+            if bytes_read == 0 {
+                return Ok(0);
+            }
+
+            let mut kept = 0;
+            for i in 0..bytes_read {
+                if self.keep(buf[i]) {
+                    buf[kept] = buf[i];
+                    kept += 1;
+                }
+            }
+
+            // Returning 0 would mean the connection closed, so read past
+            // input that was nothing but commands.
+            if kept > 0 {
+                return Ok(kept);
+            }
+        }
+    }
+}
+
+fn banner() -> String {
+    format!(
+        "tic-tac-toe-rust {} protocol {PROTOCOL} variants {}",
+        env!("CARGO_PKG_VERSION"),
+        VARIANTS.join(",")
+    )
+}
+
+// The answer to a client's "hello <protocol> [<variant>]", or why the client
+// cannot play here.
+fn greet(hello: &str) -> Result<String, String> {
+    let mut words = hello.split_whitespace();
+    let protocol = words.next().and_then(|word| word.parse::<u32>().ok());
+    let variant = words.next().unwrap_or(VARIANTS[0]);
+    let speaks = format!(
+        "this server speaks protocol {PROTOCOL} with {} boards",
+        VARIANTS.join(" and ")
+    );
+
+    match protocol {
+        Some(PROTOCOL) if VARIANTS.contains(&variant) => Ok(format!("hello {PROTOCOL} {variant}")),
+        Some(PROTOCOL) => Err(format!("Variant {variant} is not played here, {speaks}.")),
+        Some(protocol) => Err(format!("Protocol {protocol} is not spoken here, {speaks}.")),
+        None => Err(format!(
+            "'{}' is not a protocol version, {speaks}.",
+            hello.trim()
+        )),
+    }
+}
+
+// Player names by token, from a file of "<name> <token>" lines. Blank lines
+// and lines starting with # are skipped.
+fn parse_tokens(text: &str) -> Result<HashMap<String, String>, String> {
+    let mut tokens = HashMap::new();
+    for line in text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    {
+        let mut words = line.split_whitespace();
+        let (Some(name), Some(token), None) = (words.next(), words.next(), words.next()) else {
+            return Err(format!("'{line}' is not a player name and a token."));
+        };
+        if tokens.insert(token.to_string(), name.to_string()).is_some() {
+            return Err(format!("The token for {name} is given twice."));
+        }
+    }
+    Ok(tokens)
+}
+
+pub fn load_tokens(path: &Path) -> Result<HashMap<String, String>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {error}", path.display()))?;
+    parse_tokens(&text).map_err(|error| format!("{}: {error}", path.display()))
+}
+
+// 128 bits from the keys std seeds its hash maps with, which come from the
+// operating system.
+fn new_token() -> String {
+    let half = || RandomState::new().build_hasher().finish();
+    format!("{:016x}{:016x}", half(), half())
+}
+
+// Adds a token for the player to the file, creating it if needed.
+pub fn issue_token(path: &Path, name: &str) -> Result<String, String> {
+    if name.is_empty() || name.contains(char::is_whitespace) || name.starts_with('#') {
+        return Err(format!("'{name}' cannot be a player name, use one word."));
+    }
+    let tokens = if path.exists() {
+        load_tokens(path)?
+    } else {
+        HashMap::new()
+    };
+    if tokens.values().any(|existing| existing == name) {
+        return Err(format!("{name} already has a token in {}.", path.display()));
+    }
+
+    let token = new_token();
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{name} {token}"))
+        .map_err(|error| format!("Could not write to {}: {error}", path.display()))?;
+    Ok(token)
+}
+
+// Asks for a token until a known one is given, None when the connection
+// closes or after too many wrong ones.
+fn log_in(console: &mut Console, tokens: &HashMap<String, String>) -> Option<String> {
+    for _ in 0..TOKEN_ATTEMPTS {
+        console.prompt("Token:");
+        if let Some(name) = tokens.get(console.read_line()?.trim()) {
+            console.say(&format!("Welcome, {name}."));
+            return Some(name.clone());
+        }
+        console.warn("That token is not known.");
+    }
+    None
+}
+
+// A session waiting for its player to come back.
+struct Waiter {
+    // The player logged in with a token, who alone may rejoin.
+    player: Option<String>,
+    returns: Sender<Stream>,
+}
+
+// A byte of operating system randomness for each character, as for tokens,
+// since a code alone gives a dropped game back. There are 32 characters,
+// so every one is as likely.
+fn rejoin_code() -> String {
+    RandomState::new()
+        .build_hasher()
+        .finish()
+        .to_le_bytes()
+        .iter()
+        .take(CODE_LENGTH)
+        .map(|byte| char::from(CODE_CHARACTERS[usize::from(*byte) % CODE_CHARACTERS.len()]))
+        .collect()
+}
+
+// Codes are file names under the saves directory, so nothing else may
+// get near the file system.
+fn is_rejoin_code(code: &str) -> bool {
+    code.len() == CODE_LENGTH && code.bytes().all(|byte| CODE_CHARACTERS.contains(&byte))
+}
+
+// A session being served, as the admin commands see it.
+struct Session {
+    peer: String,
+    // The player logged in with a token.
+    player: Option<String>,
+    // The connection it is played over, to kick or message the player.
+    socket: Option<Link>,
+    kicked: bool,
+}
+
+impl Session {
+    fn new(peer: &str, socket: Option<&Link>) -> Session {
+        Session {
+            peer: peer.to_string(),
+            player: None,
+            socket: socket.and_then(|socket| socket.try_clone().ok()),
+            kicked: false,
+        }
+    }
+
+    // Writes a line to the player, between whatever the game is showing.
+    fn tell(&self, text: &str) {
+        if let Some(mut socket) = self
+            .socket
+            .as_ref()
+            .and_then(|socket| socket.try_clone().ok())
+        {
+            let _ = write!(socket, "\r\n{text}\r\n");
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum AdminCommand {
+    List,
+    Kick(String),
+    Say(String),
+    Drain,
+    Help,
+}
+
+const ADMIN_HELP: &str = "Commands: list, kick <code>, say <text>, drain, help.";
+
+impl FromStr for AdminCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (command, argument) = s.trim().split_once(' ').unwrap_or((s.trim(), ""));
+        let argument = argument.trim();
+        match (command, argument.is_empty()) {
+            ("list", true) => Ok(AdminCommand::List),
+            ("kick", false) => Ok(AdminCommand::Kick(argument.to_ascii_uppercase())),
+            ("say", false) => Ok(AdminCommand::Say(argument.to_string())),
+            ("drain", true) => Ok(AdminCommand::Drain),
+            ("help", true) => Ok(AdminCommand::Help),
+            _ => Err(format!("'{}' is not a command. {ADMIN_HELP}", s.trim())),
+        }
+    }
+}
+
+// What every connection's thread shares.
+struct Server {
+    // Player names by token, when only they may play.
+    tokens: Option<HashMap<String, String>>,
+    events: Option<Events>,
+    // Where games in progress are saved, so they outlive the server.
+    saves: Option<PathBuf>,
+    // How long a session waits for its player to come back.
+    grace: Duration,
+    limits: Limits,
+    connections: AtomicUsize,
+    // The sessions being served, by rejoin code.
+    sessions: Mutex<HashMap<String, Session>>,
+    // Set by the drain command: no one new gets in, and the server stops
+    // once everyone has left.
+    draining: AtomicBool,
+    // Where the server listens, to wake it up when it has drained.
+    address: Option<SocketAddr>,
+    // Set once it has drained, so the admin commands stop reading stdin.
+    stopped: AtomicBool,
+    // Set when connections are served over TLS.
+    tls: Option<Acceptor>,
+    // Sessions waiting for their player to come back, by rejoin code.
+    waiting: Mutex<HashMap<String, Waiter>>,
+}
+
+impl Server {
+    fn new(tokens: Option<HashMap<String, String>>, events: Option<Events>) -> Server {
+        Server {
+            tokens,
+            events,
+            saves: None,
+            grace: REJOIN_GRACE,
+            limits: Limits::default(),
+            connections: AtomicUsize::new(0),
+            sessions: Mutex::default(),
+            draining: AtomicBool::new(false),
+            address: None,
+            stopped: AtomicBool::new(false),
+            tls: None,
+            waiting: Mutex::default(),
+        }
+    }
+
+    // The connection for a client that just connected.
+    fn link(&self, stream: TcpStream) -> io::Result<Link> {
+        match &self.tls {
+            Some(acceptor) => acceptor.accept(stream).map(Link::Tls),
+            None => Ok(Link::Plain(stream)),
+        }
+    }
+
+    // Tells a connection that is turned away why. Over TLS that waits for the
+    // handshake, on a thread of its own so the client holds up no one else.
+    fn refuse(&self, stream: TcpStream, message: &str) {
+        match self.link(stream) {
+            Ok(Link::Tls(mut tls)) => {
+                let message = message.to_string();
+                thread::spawn(move || {
+                    let _ = tls.socket().set_read_timeout(Some(WRITE_TIMEOUT));
+                    let _ = tls.socket().set_write_timeout(Some(WRITE_TIMEOUT));
+                    if tls.handshake().is_ok() {
+                        let mut console = Console::over(Box::new(io::empty()), Box::new(tls));
+                        console.warn(&message);
+                    }
+                });
+            }
+            Ok(link) => {
+                let mut console = Console::over(Box::new(io::empty()), Box::new(link));
+                console.warn(message);
+            }
+            Err(_) => {}
+        }
+    }
+
+    // A rejoin code for a new session, unlike any other being served.
+    fn open_session(&self, session: Session) -> String {
+        let mut sessions = self.sessions.lock().unwrap();
+        loop {
+            let code = rejoin_code();
+            if !sessions.contains_key(&code) {
+                sessions.insert(code.clone(), session);
+                return code;
+            }
+        }
+    }
+
+    // Takes the code for a session resumed from its save, false when the
+    // code is already being served.
+    fn claim_session(&self, code: &str, session: Session) -> bool {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.contains_key(code) {
+            return false;
+        }
+        sessions.insert(code.to_string(), session);
+        true
+    }
+
+    // Forgets the session once its thread is done, and stops a drained server.
+    fn close_session(&self, code: &str) {
+        self.sessions.lock().unwrap().remove(code);
+        if self.connections.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.wake_if_drained();
+        }
+    }
+
+    // The sessions being served that match, to hold each client to its limit.
+    fn count_sessions(&self, matches: impl Fn(&Session) -> bool) -> usize {
+        self.sessions
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|session| matches(session))
+            .count()
+    }
+
+    fn is_kicked(&self, code: &str) -> bool {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(code)
+            .is_some_and(|session| session.kicked)
+    }
+
+    // The accept loop only looks at the drain flag when someone connects,
+    // so the server connects to itself once no one is left.
+    fn wake_if_drained(&self) {
+        if self.draining.load(Ordering::SeqCst) && self.connections.load(Ordering::SeqCst) == 0 {
+            if let Some(address) = self.address {
+                let _ = TcpStream::connect(address);
+            }
+        }
+    }
+
+    // Runs one admin command, with its answer printed for the admin.
+    fn admin(&self, command: AdminCommand) {
+        match command {
+            AdminCommand::List => {
+                let sessions = self.sessions.lock().unwrap();
+                let waiting = self.waiting.lock().unwrap();
+                if sessions.is_empty() {
+                    println!("No one is connected.");
+                }
+                let mut codes = sessions.keys().collect::<Vec<_>>();
+                codes.sort();
+                for code in codes {
+                    let session = &sessions[code];
+                    println!(
+                        "{code}  {}  {}{}",
+                        session.peer,
+                        session.player.as_deref().unwrap_or("-"),
+                        if waiting.contains_key(code) {
+                            "  (waiting to rejoin)"
+                        } else {
+                            ""
+                        }
+                    );
+                }
+            }
+            AdminCommand::Kick(code) => {
+                let mut sessions = self.sessions.lock().unwrap();
+                let Some(session) = sessions.get_mut(&code) else {
+                    println!("No session has the code {code}.");
+                    return;
+                };
+                session.kicked = true;
+                session.tell("You were disconnected by the server.");
+                if let Some(socket) = &session.socket {
+                    let _ = socket.socket().shutdown(Shutdown::Both);
+                }
+                // A session waiting to rejoin stops waiting.
+                self.waiting.lock().unwrap().remove(&code);
+                println!("Kicked {code}.");
+            }
+            AdminCommand::Say(text) => {
+                let sessions = self.sessions.lock().unwrap();
+                for session in sessions.values() {
+                    session.tell(&format!("[server] {text}"));
+                }
+                println!("Said it to {} sessions.", sessions.len());
+            }
+            AdminCommand::Drain => {
+                self.draining.store(true, Ordering::SeqCst);
+                let sessions = self.sessions.lock().unwrap();
+                for session in sessions.values() {
+                    session.tell("[server] The server stops once the games in progress end.");
+                }
+                println!(
+                    "Draining: no one new gets in, and the server stops when the last of {} sessions ends.",
+                    sessions.len()
+                );
+                drop(sessions);
+                self.wake_if_drained();
+            }
+            AdminCommand::Help => println!("{ADMIN_HELP}"),
+        }
+    }
+
+    // Where a session's games are saved, in a directory of the player's
+    // own when they logged in with a token.
+    fn save_path(&self, code: &str, player: Option<&String>) -> Option<PathBuf> {
+        let saves = self.saves.as_ref()?;
+        let directory = player.map_or_else(|| saves.clone(), |player| saves.join(player));
+        Some(directory.join(format!("{code}.autosave")))
+    }
+
+    // Finishes a game saved before the server restarted, on a thread of its
+    // own since the connection's own session is ending.
+    // The seat's session must have been claimed.
+    fn resume(self: &Arc<Server>, mut stream: Stream, seat: Seat) {
+        let code = seat.code.clone();
+        self.connections.fetch_add(1, Ordering::SeqCst);
+        let game = self
+            .save_path(&code, seat.player.get())
+            .ok_or_else(|| String::from("Games are not saved here."))
+            .and_then(|path| autosave::load(&path));
+        let game = match game {
+            Ok(game) => game,
+            Err(message) => {
+                stream.say(&message);
+                self.close_session(&code);
+                return;
+            }
+        };
+
+        let server = Arc::clone(self);
+        thread::spawn(move || {
+            println!("Resuming the saved game with rejoin code {code}.");
+            let mut console = Console::remote(stream);
+            let player = Arc::clone(&seat.player);
+            console.set_rejoin(Box::new(seat));
+            console.say(&format!("Welcome back, resuming the game saved as {code}."));
+            server.play(console, &code, player.get(), Some(game));
+            server.close_session(&code);
+        });
+    }
+
+    // The menu for a connected player, saving their games when the server
+    // keeps them.
+    fn play(
+        &self,
+        mut console: Console,
+        code: &str,
+        player: Option<&String>,
+        saved: Option<SavedGame>,
+    ) {
+        console.say(&format!(
+            "If the connection drops, connect again within {} minutes and type: rejoin {code}",
+            self.grace.as_secs() / 60
+        ));
+        let autosave = self.save_path(code, player);
+        if let Some(directory) = autosave.as_ref().and_then(|path| path.parent()) {
+            if let Err(error) = fs::create_dir_all(directory) {
+                eprintln!("Could not create {}: {error}", directory.display());
+            }
+        }
+        menu::run_remote(console, self.events.clone(), autosave, saved);
+    }
+}
+
+// One connection's place on the server, which a new connection can take
+// over with the rejoin code.
+struct Seat {
+    code: String,
+    server: Arc<Server>,
+    // Set once the player logs in with a token.
+    player: Arc<OnceLock<String>>,
+    // The connection, for the admin commands of a session it is handed to.
+    peer: String,
+    socket: Option<Link>,
+    // Whether this connection was refused or went on to carry another
+    // session, which ends its own without waiting.
+    gone: bool,
+    throttle: Throttle,
+}
+
+impl Seat {
+    fn new(code: &str, server: &Arc<Server>, peer: &str, socket: Option<Link>) -> Seat {
+        Seat {
+            code: code.to_string(),
+            server: Arc::clone(server),
+            player: Arc::default(),
+            peer: peer.to_string(),
+            socket,
+            gone: false,
+            throttle: Throttle::new(server.limits.lines_per_second),
+        }
+    }
+
+    // The session's record for the admin commands, played over this connection.
+    fn session(&self) -> Session {
+        Session {
+            player: self.player.get().cloned(),
+            ..Session::new(&self.peer, self.socket.as_ref())
+        }
+    }
+
+    // Hands the connection to the session waiting for the code, or to a new
+    // one for a game saved under it. Returns false when neither is there.
+    fn rejoin(&mut self, code: &str, stream: &mut Stream) -> bool {
+        let player = self.player.get();
+        let mut waiting = self.server.waiting.lock().unwrap();
+        let session = match waiting.get(code) {
+            Some(waiter) if waiter.player.as_ref() == player => waiting.remove(code),
+            _ => None,
+        };
+        drop(waiting);
+
+        if let Some(session) = session {
+            return match session
+                .returns
+                .send(std::mem::replace(stream, Stream::closed()))
+            {
+                Ok(()) => {
+                    if let Some(session) = self.server.sessions.lock().unwrap().get_mut(code) {
+                        let rejoined = self.session();
+                        session.peer = rejoined.peer;
+                        session.socket = rejoined.socket;
+                    }
+                    self.gone = true;
+                    true
+                }
+                // The game gave up waiting just now.
+                Err(SendError(own)) => {
+                    *stream = own;
+                    false
+                }
+            };
+        }
+
+        let saved = self
+            .server
+            .save_path(code, player)
+            .is_some_and(|path| path.exists());
+        if !saved || !self.server.claim_session(code, self.session()) {
+            return false;
+        }
+        let seat = Seat {
+            code: code.to_string(),
+            player: Arc::clone(&self.player),
+            socket: self
+                .socket
+                .as_ref()
+                .and_then(|socket| socket.try_clone().ok()),
+            ..Seat::new(code, &self.server, &self.peer, None)
+        };
+        let stream = std::mem::replace(stream, Stream::closed());
+        self.gone = true;
+        self.server.resume(stream, seat);
+        true
+    }
+}
+
+impl Rejoin for Seat {
+    fn intercept(&mut self, line: &str, stream: &mut Stream) -> bool {
+        if !self.throttle.admit(Instant::now()) {
+            if self.throttle.dropped == 1 {
+                stream.say(&format!(
+                    "Slow down: lines beyond {} a second are ignored.",
+                    self.server.limits.lines_per_second
+                ));
+            } else if self.throttle.dropped > FLOOD_LINES {
+                stream.say("Closing the connection, too many lines were sent too fast.");
+                println!("{} was disconnected for flooding.", self.peer);
+                *stream = Stream::closed();
+                self.gone = true;
+            }
+            return true;
+        }
+        if let Some(hello) = line.trim().strip_prefix("hello ") {
+            match greet(hello) {
+                Ok(answer) => stream.say(&answer),
+                Err(error) => {
+                    stream.say(&error);
+                    *stream = Stream::closed();
+                    self.gone = true;
+                }
+            }
+            return true;
+        }
+        let Some(code) = line.trim().strip_prefix("rejoin ") else {
+            return false;
+        };
+        let code = code.trim().to_ascii_uppercase();
+        if !is_rejoin_code(&code) || !self.rejoin(&code, stream) {
+            stream.say("No game is waiting for that code.");
+        }
+        true
+    }
+
+    fn wait(&mut self) -> Option<Stream> {
+        if self.gone || self.server.is_kicked(&self.code) {
+            return None;
+        }
+        let (sender, returns) = mpsc::channel();
+        let waiter = Waiter {
+            player: self.player.get().cloned(),
+            returns: sender,
+        };
+        let waiting = &self.server.waiting;
+        waiting.lock().unwrap().insert(self.code.clone(), waiter);
+        let stream = returns.recv_timeout(self.server.grace).ok();
+        waiting.lock().unwrap().remove(&self.code);
+        // A connection may have been handed over while the code was removed.
+        let stream = stream.or_else(|| returns.try_recv().ok());
+        if stream.is_some() {
+            println!("The player with rejoin code {} is back.", self.code);
+        }
+        stream
+    }
+}
+
+fn serve(server: &Arc<Server>, link: Link, peer: &str, code: &str) -> io::Result<()> {
+    link.socket().set_read_timeout(Some(server.limits.idle))?;
+    link.socket().set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let reader = TelnetReader::new(link.try_clone()?);
+    let seat = Seat::new(code, server, peer, link.try_clone().ok());
+    let mut console = Console::over(Box::new(BufReader::new(reader)), Box::new(link));
+    let player = Arc::clone(&seat.player);
+    console.say(&banner());
+    console.say(WELCOME);
+    console.set_rejoin(Box::new(seat));
+
+    if let Some(tokens) = &server.tokens {
+        let Some(name) = log_in(&mut console, tokens) else {
+            return Ok(());
+        };
+        let playing = server.count_sessions(|session| session.player.as_ref() == Some(&name));
+        if playing >= server.limits.per_client {
+            console.warn(&format!(
+                "{name} already has {playing} sessions on the server, please close one first."
+            ));
+            return Ok(());
+        }
+        println!("The player with rejoin code {code} is {name}.");
+        if let Some(session) = server.sessions.lock().unwrap().get_mut(code) {
+            session.player = Some(name.clone());
+        }
+        let _ = player.set(name);
+    }
+    server.play(console, code, player.get(), None);
+    Ok(())
+}
+
+// Accepts connections until the process is stopped, or issues a token.
+pub fn run(options: TelnetOptions) -> Result<(), String> {
+    listen(options).map(drop)
+}
+
+// Serves like run, for the menu: once the server has drained, the admin
+// commands give stdin back after the player presses enter.
+pub fn host(options: TelnetOptions) -> Result<(), String> {
+    if let Some(admin) = listen(options)? {
+        println!("Press enter to go back.");
+        let _ = admin.join();
+    }
+    Ok(())
+}
+
+// Accepts connections until the server has drained, and returns the thread
+// reading the admin commands. Returns None when a token was issued instead.
+fn listen(options: TelnetOptions) -> Result<Option<thread::JoinHandle<()>>, String> {
+    let tokens = match (&options.tokens, &options.issue) {
+        (Some(path), Some(name)) => {
+            let token = issue_token(path, name)?;
+            println!("{name} can log in with the token {token}.");
+            return Ok(None);
+        }
+        (Some(path), None) => Some(load_tokens(path)?),
+        _ => None,
+    };
+    let tls = options.tls.as_ref().map(Acceptor::new).transpose()?;
+    let address = options.address.as_str();
+    let listener = TcpListener::bind(address)
+        .map_err(|error| format!("Could not listen on {address}: {error}"))?;
+    if tls.is_some() {
+        println!("Listening on {address} over TLS, connect with join --tls.");
+    } else {
+        println!("Listening on {address}, connect with e.g. telnet or nc.");
+    }
+    if tls.is_none()
+        && listener
+            .local_addr()
+            .is_ok_and(|local| !local.ip().is_loopback())
+    {
+        println!(
+            "Connections are not encrypted. Over the internet, serve over TLS with --tls-cert \
+             and --tls-key, or listen on 127.0.0.1 behind a tunnel."
+        );
+    }
+    if !options.webhooks.is_empty() {
+        println!(
+            "Posting game events to {} webhooks.",
+            options.webhooks.len()
+        );
+    }
+    if let (Some(tokens), Some(path)) = (&tokens, &options.tokens) {
+        println!(
+            "Only players with one of the {} tokens in {} can play.",
+            tokens.len(),
+            path.display()
+        );
+    }
+    if let Some(saves) = &options.saves {
+        fs::create_dir_all(saves)
+            .map_err(|error| format!("Could not create {}: {error}", saves.display()))?;
+        println!("Saving games in progress to {}.", saves.display());
+    }
+    let mqtt = options.mqtt.as_ref().map(Publisher::connect).transpose()?;
+    let counters = options
+        .metrics
+        .as_ref()
+        .map(|_| Arc::new(Metrics::default()));
+    let server = Arc::new(Server {
+        saves: options.saves,
+        tls,
+        limits: options.limits,
+        address: listener.local_addr().ok(),
+        ..Server::new(
+            tokens,
+            Events::new(options.webhooks, mqtt, counters.clone()),
+        )
+    });
+    if let (Some(address), Some(counters)) = (&options.metrics, counters) {
+        let server = Arc::clone(&server);
+        metrics::serve(address, move || {
+            counters.render(server.connections.load(Ordering::SeqCst))
+        })?;
+        println!("Serving metrics at http://{address}/metrics.");
+    }
+
+    let admin = Arc::clone(&server);
+    let admin = thread::spawn(move || {
+        for line in io::stdin().lines().map_while(Result::ok) {
+            // Stdin is left to whoever ran the server, e.g. the menu.
+            if admin.stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            match line.parse() {
+                Ok(command) => admin.admin(command),
+                Err(message) => println!("{message}"),
+            }
+        }
+    });
+    println!("{ADMIN_HELP}");
+
+    accept(&server, &listener);
+    server.stopped.store(true, Ordering::SeqCst);
+    Ok(Some(admin))
+}
+
+// Plays on the server at address from this terminal: what it sends is shown
+// as it arrives, and the lines read from console are sent to it. With verify
+// the connection is over TLS, to a server checked as it says.
+pub fn join(console: &mut Console, address: &str, verify: Option<&Verify>) -> Result<(), String> {
+    let failed = |error: io::Error| format!("Could not connect to {address}: {error}");
+    let mut writer = match verify {
+        Some(verify) => Link::Tls(tls::connect(verify, address)?),
+        None => Link::Plain(TcpStream::connect(address).map_err(failed)?),
+    };
+    let mut reader = writer.try_clone().map_err(failed)?;
+
+    let shown = thread::spawn(move || {
+        let mut buffer = [0; 1024];
+        let mut stdout = io::stdout();
+        // Prompts do not end in a newline, so every read is shown at once.
+        while let Ok(read @ 1..) = reader.read(&mut buffer) {
+            let _ = stdout.write_all(&buffer[..read]);
+            let _ = stdout.flush();
+        }
+        println!("\nThe connection was closed, press enter to go back.");
+    });
+    while !shown.is_finished() {
+        let Some(line) = console.read_line() else {
+            break;
+        };
+        if writer.write_all(line.as_bytes()).is_err() {
+            break;
+        }
+    }
+    let _ = writer.socket().shutdown(Shutdown::Both);
+    let _ = shown.join();
+    Ok(())
+}
+
+// Serves each connection on a thread of its own, until the server has drained.
+fn accept(server: &Arc<Server>, listener: &TcpListener) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        if server.draining.load(Ordering::SeqCst) {
+            if server.connections.load(Ordering::SeqCst) == 0 {
+                println!("Everyone has left, stopping.");
+                break;
+            }
+            server.refuse(
+                stream,
+                "The server is shutting down, please try again later.",
+            );
+            continue;
+        }
+        let peer = stream
+            .peer_addr()
+            .map_or_else(|_| String::from("unknown"), |peer| peer.to_string());
+        let ip = stream.peer_addr().ok().map(|peer| peer.ip());
+        let from_ip = server.count_sessions(|session| {
+            let socket = session.socket.as_ref();
+            socket
+                .and_then(|socket| socket.socket().peer_addr().ok())
+                .map(|peer| peer.ip())
+                == ip
+        });
+        if from_ip >= server.limits.per_client {
+            server.refuse(
+                stream,
+                &format!(
+                    "There are already {from_ip} connections from your address, please close one first."
+                ),
+            );
+            continue;
+        }
+
+        if server.connections.fetch_add(1, Ordering::SeqCst) >= server.limits.connections {
+            server.connections.fetch_sub(1, Ordering::SeqCst);
+            server.refuse(stream, "The server is full, please try again later.");
+            continue;
+        }
+
+        let Ok(link) = server.link(stream) else {
+            server.connections.fetch_sub(1, Ordering::SeqCst);
+            continue;
+        };
+        let server = Arc::clone(server);
+        let code = server.open_session(Session::new(&peer, Some(&link)));
+        thread::spawn(move || {
+            println!("{peer} connected, rejoin code {code}.");
+            if let Err(error) = serve(&server, link, &peer, &code) {
+                eprintln!("{peer}: {error}");
+            }
+            server.close_session(&code);
+            println!("{peer} left.");
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Hands out one byte per read, so every command is split across reads.
+    struct Trickle(Vec<u8>);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.0.remove(0);
+            Ok(1)
+        }
+    }
+
+    fn strip(chunks: &[&[u8]]) -> Vec<u8> {
+        let mut reader = TelnetReader::new(Trickle(chunks.concat()));
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn negotiation_is_stripped() {
+        // IAC DO ECHO, IAC WILL NAWS, then a NAWS subnegotiation.
+        let input: &[&[u8]] = &[
+            &[IAC, 253, 1],
+            b"1,",
+            &[IAC, WILL, 31, IAC, SB, 31, 0, 80, 0, 24, IAC, SE],
+            b"1\r\n\0",
+        ];
+        assert_eq!(strip(input), b"1,1\r\n");
+    }
+
+    #[test]
+    fn escaped_bytes_are_kept() {
+        assert_eq!(strip(&[b"a", &[IAC, IAC], b"b"]), [b'a', IAC, b'b']);
+    }
+
+    fn seat(code: &str, server: &Arc<Server>, player: Option<&str>) -> Seat {
+        let seat = Seat::new(code, server, "127.0.0.1:2323", None);
+        if let Some(player) = player {
+            seat.player.set(player.to_string()).unwrap();
+        }
+        seat
+    }
+
+    #[test]
+    fn rejoining_hands_the_connection_to_the_waiting_game() {
+        let server = Arc::new(Server::new(None, None));
+        let mut lost = seat("ABC234", &server, Some("alice"));
+        let game = thread::spawn(move || lost.wait().is_some());
+        while server.waiting.lock().unwrap().is_empty() {
+            thread::yield_now();
+        }
+
+        let mut stream = Stream::closed();
+        // Only the player who logged in can take the game back.
+        let mut stranger = seat("DEF567", &server, None);
+        assert!(stranger.intercept("rejoin ABC234\r\n", &mut stream));
+        assert!(!stranger.gone);
+
+        let mut new = seat("XYZ789", &server, Some("alice"));
+        assert!(!new.intercept("1,1\r\n", &mut stream));
+        assert!(new.intercept("rejoin nope\r\n", &mut stream));
+        assert!(!new.gone);
+        assert!(new.intercept("rejoin abc234\r\n", &mut stream));
+        assert!(new.gone);
+        assert!(game.join().unwrap());
+        // The new connection's own session ends instead of waiting.
+        assert!(new.wait().is_none());
+    }
+
+    // A writer the test can still read after handing it to the server.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn lines_are_throttled_after_a_burst() {
+        let mut throttle = Throttle::new(4);
+        let start = throttle.booked_until;
+        for _ in 0..BURST_LINES {
+            assert!(throttle.admit(start));
+        }
+        assert!(!throttle.admit(start));
+        assert!(!throttle.admit(start));
+        assert_eq!(throttle.dropped, 2);
+        // A quarter of a second frees one slot.
+        let later = start + Duration::from_millis(250);
+        assert!(throttle.admit(later));
+        assert!(!throttle.admit(later));
+        assert!(throttle.admit(later + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn flooding_connections_are_cut_off() {
+        let server = Arc::new(Server::new(None, None));
+        let mut flooder = seat("ABC234", &server, None);
+        let shared = Shared::default();
+        let mut stream = Stream::new(Box::new(io::empty()), Box::new(shared.clone()));
+        for _ in 0..BURST_LINES {
+            assert!(!flooder.intercept("1,1\r\n", &mut stream));
+        }
+        for _ in 0..FLOOD_LINES {
+            assert!(flooder.intercept("1,1\r\n", &mut stream));
+        }
+        let output = String::from_utf8(shared.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            output,
+            "Slow down: lines beyond 4 a second are ignored.\r\n"
+        );
+        assert!(!flooder.gone);
+
+        assert!(flooder.intercept("1,1\r\n", &mut stream));
+        assert!(flooder.gone);
+        assert!(flooder.wait().is_none());
+    }
+
+    #[test]
+    fn saved_games_are_resumed_after_a_restart() {
+        let saves = std::env::temp_dir().join(format!("tic-tac-toe-saves-{}", std::process::id()));
+        let server = Arc::new(Server {
+            saves: Some(saves.clone()),
+            grace: Duration::ZERO,
+            ..Server::new(None, None)
+        });
+        let path = server.save_path("SAVED2", None).unwrap();
+        fs::create_dir_all(&saves).unwrap();
+        fs::write(&path, "opponent human\nrecord ---/---/--- +1,1\n").unwrap();
+        assert_eq!(
+            server.save_path("SAVED2", Some(&String::from("alice"))),
+            Some(saves.join("alice").join("SAVED2.autosave"))
+        );
+
+        let output = Shared::default();
+        let mut stream = Stream::new(Box::new(io::empty()), Box::new(output.clone()));
+        let mut new = seat("NEW234", &server, None);
+        assert!(new.intercept("rejoin saved2\r\n", &mut stream));
+        assert!(new.gone);
+        // The game is being served, so it cannot be taken twice.
+        assert!(!seat("TWO234", &server, None).rejoin("SAVED2", &mut Stream::closed()));
+
+        while server.sessions.lock().unwrap().contains_key("SAVED2") {
+            thread::yield_now();
+        }
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("resuming the game saved as SAVED2"));
+        assert!(output.contains("| - |[+]| - |"));
+        fs::remove_dir_all(&saves).unwrap();
+    }
+
+    #[test]
+    fn rejoin_codes_cannot_leave_the_saves_directory() {
+        let saves = std::env::temp_dir().join(format!("tic-tac-toe-escape-{}", std::process::id()));
+        let server = Arc::new(Server {
+            saves: Some(saves.join("saves")),
+            ..Server::new(None, None)
+        });
+        // Where ../X would lead from alice's directory.
+        fs::create_dir_all(saves.join("saves").join("alice")).unwrap();
+        fs::write(saves.join("saves").join("X.autosave"), "opponent human\n").unwrap();
+
+        let output = Shared::default();
+        let mut stream = Stream::new(Box::new(io::empty()), Box::new(output.clone()));
+        let mut new = seat("NEW234", &server, Some("alice"));
+        for line in [
+            "rejoin ../x\r\n",
+            "rejoin ../ALICE/ABC234\r\n",
+            "rejoin ABC23O\r\n",
+        ] {
+            assert!(new.intercept(line, &mut stream));
+            assert!(!new.gone, "{line}");
+        }
+        assert!(server.sessions.lock().unwrap().is_empty());
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            output.matches("No game is waiting for that code.").count(),
+            3
+        );
+        assert!(is_rejoin_code("ABC234") && !is_rejoin_code("ABC2345"));
+        fs::remove_dir_all(&saves).unwrap();
+    }
+
+    #[test]
+    fn admin_commands_are_parsed() {
+        assert_eq!("list".parse(), Ok(AdminCommand::List));
+        assert_eq!(
+            " kick abc234 ".parse(),
+            Ok(AdminCommand::Kick(String::from("ABC234")))
+        );
+        assert_eq!(
+            "say back in  5 minutes".parse(),
+            Ok(AdminCommand::Say(String::from("back in  5 minutes")))
+        );
+        assert_eq!("drain".parse(), Ok(AdminCommand::Drain));
+        for line in ["", "kick", "say", "list all", "stop"] {
+            assert!(line.parse::<AdminCommand>().is_err(), "{line}");
+        }
+    }
+
+    #[test]
+    fn kicked_players_cannot_rejoin() {
+        let server = Arc::new(Server::new(None, None));
+        let code = server.open_session(Session::new("127.0.0.1:2323", None));
+        server.connections.fetch_add(1, Ordering::SeqCst);
+
+        server.admin(AdminCommand::Kick(code.clone()));
+        assert!(seat(&code, &server, None).wait().is_none());
+        server.close_session(&code);
+        assert!(server.sessions.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn clients_say_hello_with_the_protocol() {
+        assert_eq!(greet("1"), Ok(String::from("hello 1 3x3")));
+        assert_eq!(greet(" 1 3x3 "), Ok(String::from("hello 1 3x3")));
+        assert_eq!(
+            greet("2"),
+            Err(String::from(
+                "Protocol 2 is not spoken here, this server speaks protocol 1 with 3x3 boards."
+            ))
+        );
+        assert!(greet("1 4x4").unwrap_err().starts_with("Variant 4x4"));
+        assert!(greet("one").unwrap_err().starts_with("'one' is not"));
+
+        let mut seat = seat("ABC234", &Arc::new(Server::new(None, None)), None);
+        let mut stream = Stream::closed();
+        assert!(seat.intercept("hello 1\r\n", &mut stream));
+        assert!(!seat.gone);
+        assert!(seat.intercept("hello 9\r\n", &mut stream));
+        assert!(seat.wait().is_none());
+    }
+
+    #[test]
+    fn tokens_name_the_players() {
+        let tokens = parse_tokens("# players\nalice 0123abcd\n\n  bob  4567ef01 \n").unwrap();
+        assert_eq!(tokens.get("0123abcd").map(String::as_str), Some("alice"));
+        assert_eq!(tokens.get("4567ef01").map(String::as_str), Some("bob"));
+        for text in ["alice", "alice 01 02", "alice 01\nbob 01"] {
+            assert!(parse_tokens(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn issued_tokens_are_appended() {
+        let path = std::env::temp_dir().join(format!("tic-tac-toe-tokens-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let alice = issue_token(&path, "alice").unwrap();
+        let bob = issue_token(&path, "bob").unwrap();
+        assert_eq!(alice.len(), 32);
+        assert_ne!(alice, bob);
+        assert!(issue_token(&path, "alice").is_err());
+        assert!(issue_token(&path, "two words").is_err());
+
+        let tokens = load_tokens(&path).unwrap();
+        assert_eq!(tokens.get(&bob).map(String::as_str), Some("bob"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejoin_codes_avoid_look_alike_characters() {
+        let code = rejoin_code();
+        assert_eq!(code.len(), CODE_LENGTH);
+        assert!(!code.contains(['0', 'O', '1', 'I']));
+        assert!(is_rejoin_code(&code));
+        assert_ne!(code, rejoin_code());
+    }
+    #[test]
+    fn joining_sends_the_lines_typed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut received = String::new();
+            BufReader::new(&stream)
+                .read_to_string(&mut received)
+                .unwrap();
+            received
+        });
+
+        let mut console = Console::over(
+            Box::new(io::Cursor::new(b"2\n1,1\n".to_vec())),
+            Box::new(io::sink()),
+        );
+        join(&mut console, &address, None).unwrap();
+        assert_eq!(server.join().unwrap(), "2\n1,1\n");
+
+        let closed = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = closed.local_addr().unwrap().to_string();
+        drop(closed);
+        assert!(join(&mut console, &address, None).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+// The size of the terminal, so boards can be drawn to fit it, and what it
+// can show. Windows consoles take escape codes and UTF-8 only once asked to,
+// and the legacy console of older Windows versions cannot, so games there
+// fall back to plain text and ASCII.
+
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+// Used when there is no terminal, e.g. for remote players.
+pub const DEFAULT_SIZE: (usize, usize) = (80, 24);
+
+#[cfg(unix)]
+mod ioctl {
+    use std::ffi::{c_int, c_ulong, c_ushort};
+
+    #[repr(C)]
+    #[derive(Default)]
+    pub struct WinSize {
+        pub rows: c_ushort,
+        pub columns: c_ushort,
+        pub x_pixels: c_ushort,
+        pub y_pixels: c_ushort,
+    }
+
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly"
+    ))]
+    pub const TIOCGWINSZ: c_ulong = 0x4008_7468;
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly"
+    )))]
+    pub const TIOCGWINSZ: c_ulong = 0x5413;
+
+    extern "C" {
+        pub fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+    }
+}
+
+#[cfg(unix)]
+fn from_terminal() -> Option<(usize, usize)> {
+    let mut size = ioctl::WinSize::default();
+    // SAFETY: TIOCGWINSZ only writes a WinSize to the pointer it is given.
+    let result = unsafe { ioctl::ioctl(1, ioctl::TIOCGWINSZ, &raw mut size) };
+    (result == 0 && size.columns > 0 && size.rows > 0)
+        .then(|| (usize::from(size.columns), usize::from(size.rows)))
+}
+
+#[cfg(not(unix))]
+fn from_terminal() -> Option<(usize, usize)> {
+    None
+}
+
+// COLUMNS and LINES, which shells set for scripts.
+fn from_env() -> Option<(usize, usize)> {
+    let read = |name| {
+        env::var(name)
+            .ok()?
+            .parse::<usize>()
+            .ok()
+            .filter(|n| *n > 0)
+    };
+    Some((read("COLUMNS")?, read("LINES")?))
+}
+
+// Columns and rows of the terminal on standard output.
+pub fn size() -> (usize, usize) {
+    from_terminal().or_else(from_env).unwrap_or(DEFAULT_SIZE)
+}
+
+#[cfg(unix)]
+mod signal {
+    use std::ffi::{c_int, c_void};
+
+    pub const SIGWINCH: c_int = 28;
+
+    extern "C" {
+        // None is SIG_DFL, the default handler.
+        pub fn signal(signum: c_int, handler: Option<extern "C" fn(c_int)>) -> usize;
+        pub fn write(fd: c_int, buf: *const c_void, count: usize) -> isize;
+    }
+}
+
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+const RESIZED_MESSAGE: &str = "\nThe terminal was resized, press enter to draw the screen again.\n";
+
+#[cfg(unix)]
+extern "C" fn resized(_: std::ffi::c_int) {
+    // Once per resize, however many signals dragging the window sends.
+    if !RESIZED.swap(true, Ordering::SeqCst) {
+        // SAFETY: write is async-signal-safe and the message is never changed.
+        unsafe { signal::write(1, RESIZED_MESSAGE.as_ptr().cast(), RESIZED_MESSAGE.len()) };
+    }
+}
+
+// Resize handling for as long as a game is played on the terminal, since
+// the message would end up in anything else standard output is. Dropping it
+// puts the default handler back.
+pub struct Resizes;
+
+impl Drop for Resizes {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: the default handler needs nothing from the program.
+        unsafe {
+            signal::signal(signal::SIGWINCH, None);
+        }
+    }
+}
+
+// Tells the player when the terminal is resized, since input is read a line
+// at a time and the screen can only be drawn again after enter, until the
+// result is dropped. Boards are drawn to the size at that moment. Only Unix
+// signals resizes.
+pub fn watch_resizes() -> Resizes {
+    take_resize();
+    #[cfg(unix)]
+    // SAFETY: resized only calls async-signal-safe functions.
+    unsafe {
+        signal::signal(signal::SIGWINCH, Some(resized));
+    }
+    Resizes
+}
+
+// Whether the terminal was resized since the screen was last drawn.
+pub fn take_resize() -> bool {
+    RESIZED.swap(false, Ordering::SeqCst)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Capabilities {
+    // Escape codes for colors and clearing the screen.
+    pub escapes: bool,
+    // Characters beyond ASCII, e.g. box drawing, blocks and Braille.
+    pub unicode: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities {
+            escapes: true,
+            unicode: true,
+        }
+    }
+}
+
+#[cfg(windows)]
+mod console {
+    use std::ffi::{c_int, c_uint, c_void};
+
+    // STD_OUTPUT_HANDLE is (DWORD)-11.
+    pub const STD_OUTPUT_HANDLE: c_uint = 0xFFFF_FFF5;
+    pub const ENABLE_VIRTUAL_TERMINAL_PROCESSING: c_uint = 0x0004;
+    pub const CP_UTF8: c_uint = 65001;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn GetStdHandle(std_handle: c_uint) -> *mut c_void;
+        pub fn GetConsoleMode(console: *mut c_void, mode: *mut c_uint) -> c_int;
+        pub fn SetConsoleMode(console: *mut c_void, mode: c_uint) -> c_int;
+        pub fn SetConsoleOutputCP(code_page: c_uint) -> c_int;
+    }
+}
+
+// Turns on escape codes and UTF-8 output. Windows 10 and later take both;
+// the legacy console takes neither, and its raster fonts have no box
+// drawing or Braille. Windows Terminal sets WT_SESSION.
+#[cfg(windows)]
+fn detect() -> Capabilities {
+    let mut mode = 0;
+    // SAFETY: these calls only read and set the mode of this process's console.
+    unsafe {
+        let output = console::GetStdHandle(console::STD_OUTPUT_HANDLE);
+        if console::GetConsoleMode(output, &raw mut mode) == 0 {
+            // Not a console, e.g. a pipe, so the text goes out as it is.
+            return Capabilities::default();
+        }
+        let escapes = mode & console::ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+            || console::SetConsoleMode(output, mode | console::ENABLE_VIRTUAL_TERMINAL_PROCESSING)
+                != 0;
+        let utf8 = console::SetConsoleOutputCP(console::CP_UTF8) != 0;
+        Capabilities {
+            escapes,
+            unicode: utf8 && (escapes || env::var_os("WT_SESSION").is_some()),
+        }
+    }
+}
+
+// Terminals elsewhere take escape codes unless TERM says otherwise, and
+// UTF-8 unless the locale says otherwise, e.g. LANG=C.
+#[cfg(not(windows))]
+fn detect() -> Capabilities {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()));
+    Capabilities {
+        escapes: env::var("TERM").map_or(true, |term| term != "dumb"),
+        unicode: locale.is_none_or(|locale| {
+            let locale = locale.to_uppercase();
+            locale.contains("UTF-8") || locale.contains("UTF8")
+        }),
+    }
+}
+
+// What the terminal can show, set up the first time it is asked.
+pub fn capabilities() -> Capabilities {
+    static CAPABILITIES: OnceLock<Capabilities> = OnceLock::new();
+    *CAPABILITIES.get_or_init(detect)
+}
+
+// Clears a terminal that takes no escape codes: with cls on Windows, or by
+// scrolling the old text away elsewhere.
+pub fn clear_without_escapes() {
+    #[cfg(windows)]
+    if std::process::Command::new("cmd")
+        .args(["/C", "cls"])
+        .status()
+        .is_ok_and(|status| status.success())
+    {
+        return;
+    }
+    print!("{}", "\n".repeat(size().1));
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resizes_are_only_watched_during_a_game() {
+        let resizes = watch_resizes();
+        // SAFETY: resized only calls async-signal-safe functions.
+        let installed = unsafe { signal::signal(signal::SIGWINCH, Some(resized)) };
+        assert_ne!(installed, 0);
+        drop(resizes);
+        // SAFETY: the default handler needs nothing from the program.
+        let previous = unsafe { signal::signal(signal::SIGWINCH, None) };
+        // SIG_DFL is 0.
+        assert_eq!(previous, 0);
+    }
+}
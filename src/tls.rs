@@ -0,0 +1,300 @@
+// TLS for the telnet server and the join command, with the `tls` feature, so
+// games played over the internet are not sent in plain text. The server
+// needs a certificate and its key as PEM files; the join command checks the
+// server's certificate against the usual web roots, or against CA
+// certificates of its own for a self-signed server.
+//
+// A connection is read on one thread and written on another, e.g. while an
+// admin command tells the player something. Both share the TLS session, and
+// reading waits for the socket without holding it.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+
+#[cfg(not(feature = "tls"))]
+use std::convert::Infallible;
+#[cfg(feature = "tls")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "tls")]
+use rustls::pki_types::pem::PemObject;
+#[cfg(feature = "tls")]
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+
+// The server's certificate chain and private key, as PEM files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub chain: PathBuf,
+    pub key: PathBuf,
+}
+
+// How a client checks the server it joins.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Verify {
+    // CA certificates to trust instead of the usual web roots.
+    pub ca: Option<PathBuf>,
+    // The name the certificate must be for, when not the address's host.
+    pub name: Option<String>,
+}
+
+// Hands out the server's certificate to each connection.
+pub struct Acceptor {
+    #[cfg(feature = "tls")]
+    config: Arc<rustls::ServerConfig>,
+    #[cfg(not(feature = "tls"))]
+    never: Infallible,
+}
+
+// A connection over TLS, which can be cloned to read and write it apart.
+pub struct Tls {
+    #[cfg(feature = "tls")]
+    session: Arc<Mutex<rustls::Connection>>,
+    #[cfg(feature = "tls")]
+    socket: TcpStream,
+    #[cfg(not(feature = "tls"))]
+    never: Infallible,
+}
+
+#[cfg(feature = "tls")]
+fn read_chain(path: &std::path::Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    let failed = |error| {
+        format!(
+            "Could not read certificates from {}: {error}",
+            path.display()
+        )
+    };
+    let chain = CertificateDer::pem_file_iter(path)
+        .map_err(failed)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(failed)?;
+    if chain.is_empty() {
+        return Err(format!("{} holds no certificates.", path.display()));
+    }
+    Ok(chain)
+}
+
+impl Acceptor {
+    #[cfg(feature = "tls")]
+    pub fn new(identity: &Identity) -> Result<Acceptor, String> {
+        let chain = read_chain(&identity.chain)?;
+        let key = PrivateKeyDer::from_pem_file(&identity.key).map_err(|error| {
+            format!(
+                "Could not read a private key from {}: {error}",
+                identity.key.display()
+            )
+        })?;
+        let config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(chain, key)
+            .map_err(|error| format!("Could not serve over TLS: {error}"))?;
+        Ok(Acceptor {
+            config: Arc::new(config),
+        })
+    }
+
+    #[cfg(not(feature = "tls"))]
+    pub fn new(_identity: &Identity) -> Result<Acceptor, String> {
+        Err(String::from(
+            "Could not serve over TLS: this build does not include the tls feature.",
+        ))
+    }
+
+    // Serves a client that just connected. The handshake happens as the
+    // connection is read, and what is written meanwhile waits for it.
+    #[cfg(feature = "tls")]
+    pub fn accept(&self, socket: TcpStream) -> io::Result<Tls> {
+        let session =
+            rustls::ServerConnection::new(Arc::clone(&self.config)).map_err(io::Error::other)?;
+        Ok(Tls::new(session.into(), socket))
+    }
+
+    #[cfg(not(feature = "tls"))]
+    pub fn accept(&self, _socket: TcpStream) -> io::Result<Tls> {
+        match self.never {}
+    }
+}
+
+// Connects to the server at address, which is checked as verify says.
+#[cfg(feature = "tls")]
+pub fn connect(verify: &Verify, address: &str) -> Result<Tls, String> {
+    let mut roots = rustls::RootCertStore::empty();
+    match &verify.ca {
+        Some(path) => {
+            for certificate in read_chain(path)? {
+                roots.add(certificate).map_err(|error| {
+                    format!("{} is not a CA certificate: {error}", path.display())
+                })?;
+            }
+        }
+        None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    }
+    let config = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    let name = verify
+        .name
+        .clone()
+        .unwrap_or_else(|| host(address).to_string());
+    let server = ServerName::try_from(name.clone())
+        .map_err(|_| format!("'{name}' is not a name a certificate can be for."))?;
+    let failed = |error: io::Error| format!("Could not connect to {address}: {error}");
+    let session = rustls::ClientConnection::new(Arc::new(config), server)
+        .map_err(|error| failed(io::Error::other(error)))?;
+    let socket = TcpStream::connect(address).map_err(failed)?;
+    let mut tls = Tls::new(session.into(), socket);
+    tls.handshake()
+        .map_err(|error| format!("Could not connect to {address} over TLS: {error}"))?;
+    Ok(tls)
+}
+
+#[cfg(not(feature = "tls"))]
+pub fn connect(_verify: &Verify, address: &str) -> Result<Tls, String> {
+    Err(format!(
+        "Could not connect to {address} over TLS: this build does not include the tls feature."
+    ))
+}
+
+// The host of a host:port address, without the brackets around IPv6 ones.
+#[cfg_attr(not(feature = "tls"), allow(dead_code))]
+fn host(address: &str) -> &str {
+    let host = address.rsplit_once(':').map_or(address, |(host, _)| host);
+    host.strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host)
+}
+
+#[cfg(feature = "tls")]
+impl Tls {
+    fn new(session: rustls::Connection, socket: TcpStream) -> Tls {
+        Tls {
+            session: Arc::new(Mutex::new(session)),
+            socket,
+        }
+    }
+
+    // Finishes the handshake now, and sends what was written before it.
+    pub fn handshake(&mut self) -> io::Result<()> {
+        let mut session = self.session.lock().unwrap();
+        while session.is_handshaking() {
+            session.complete_io(&mut self.socket)?;
+        }
+        while session.wants_write() {
+            session.write_tls(&mut self.socket)?;
+        }
+        Ok(())
+    }
+
+    pub fn socket(&self) -> &TcpStream {
+        &self.socket
+    }
+
+    pub fn try_clone(&self) -> io::Result<Tls> {
+        Ok(Tls {
+            session: Arc::clone(&self.session),
+            socket: self.socket.try_clone()?,
+        })
+    }
+}
+
+#[cfg(not(feature = "tls"))]
+impl Tls {
+    pub fn handshake(&mut self) -> io::Result<()> {
+        match self.never {}
+    }
+
+    pub fn socket(&self) -> &TcpStream {
+        match self.never {}
+    }
+
+    pub fn try_clone(&self) -> io::Result<Tls> {
+        match self.never {}
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Read for Tls {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.session.lock().unwrap().reader().read(buffer) {
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => {}
+                result => return result,
+            }
+
+            let mut records = [0; 4096];
+            let read = self.socket.read(&mut records)?;
+            if read == 0 {
+                return Ok(0);
+            }
+            let mut session = self.session.lock().unwrap();
+            let mut records = &records[..read];
+            while !records.is_empty() {
+                session.read_tls(&mut records)?;
+                session.process_new_packets().map_err(io::Error::other)?;
+            }
+            // E.g. a key update to answer.
+            while session.wants_write() {
+                session.write_tls(&mut self.socket)?;
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "tls"))]
+impl Read for Tls {
+    fn read(&mut self, _buffer: &mut [u8]) -> io::Result<usize> {
+        match self.never {}
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Write for Tls {
+    fn write(&mut self, text: &[u8]) -> io::Result<usize> {
+        let mut session = self.session.lock().unwrap();
+        let written = session.writer().write(text)?;
+        while session.wants_write() {
+            session.write_tls(&mut self.socket)?;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "tls"))]
+impl Write for Tls {
+    fn write(&mut self, _text: &[u8]) -> io::Result<usize> {
+        match self.never {}
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.never {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn certificates_are_for_the_address_host() {
+        assert_eq!(host("example.org:2323"), "example.org");
+        assert_eq!(host("[::1]:2323"), "::1");
+        assert_eq!(host("localhost"), "localhost");
+    }
+
+    #[cfg(not(feature = "tls"))]
+    #[test]
+    fn builds_without_the_feature_say_so() {
+        let identity = Identity {
+            chain: PathBuf::from("cert.pem"),
+            key: PathBuf::from("key.pem"),
+        };
+        assert!(Acceptor::new(&identity)
+            .err()
+            .is_some_and(|message| message.contains("tls feature")));
+    }
+}
@@ -0,0 +1,321 @@
+// Round robin between bots: every pairing plays a number of games with
+// alternating first player, and the results are shown as a crosstable with
+// Elo ratings.
+
+use std::fmt::Write as _;
+
+use crate::board::{Board, GameStatus, Symbol};
+use crate::bot::Bot;
+
+pub const INITIAL_RATING: f64 = 1500.0;
+const RATING_K_FACTOR: f64 = 32.0;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GameResult {
+    FirstWins,
+    SecondWins,
+    Draw,
+}
+
+// Plays one game without any output, first as Plus. A bot that passes or
+// plays an illegal move loses on the spot.
+pub fn play_game(first: &mut dyn Bot, second: &mut dyn Bot) -> GameResult {
+    let mut board = Board::new();
+    let mut symbol = Symbol::Plus;
+
+    loop {
+        match board.status() {
+            GameStatus::Won(Symbol::Plus, _) => return GameResult::FirstWins,
+            GameStatus::Won(..) => return GameResult::SecondWins,
+            GameStatus::Draw => return GameResult::Draw,
+            GameStatus::InProgress => {}
+        }
+
+        let choice = if symbol == Symbol::Plus {
+            first.choose_move(&board, symbol)
+        } else {
+            second.choose_move(&board, symbol)
+        };
+        let legal = choice.filter(|position| board.is_valid_move(*position).is_ok());
+
+        let Some(position) = legal else {
+            return if symbol == Symbol::Plus {
+                GameResult::SecondWins
+            } else {
+                GameResult::FirstWins
+            };
+        };
+
+        board.place(symbol, position);
+        symbol = symbol.opponent();
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Record {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Record {
+    pub fn add(&mut self, other: Record) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+
+    pub fn games(self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    // Points scored, counting a draw as half a win.
+    pub fn score(self) -> f64 {
+        f64::from(self.wins) + f64::from(self.draws) / 2.0
+    }
+}
+
+// How much a rating moves after a game scoring score (1 for a win, 0.5 for
+// a draw) against an opponent with the other rating.
+pub fn rating_change(rating: f64, opponent: f64, score: f64) -> f64 {
+    let expected = 1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0));
+    RATING_K_FACTOR * (score - expected)
+}
+
+pub struct Standings {
+    pub names: Vec<String>,
+    // records[i][j] is how bot i did against bot j.
+    pub records: Vec<Vec<Record>>,
+    pub ratings: Vec<f64>,
+}
+
+impl Standings {
+    fn new(names: Vec<String>) -> Standings {
+        let count = names.len();
+
+        Standings {
+            names,
+            records: vec![vec![Record::default(); count]; count],
+            ratings: vec![INITIAL_RATING; count],
+        }
+    }
+
+    fn add_game(&mut self, first: usize, second: usize, result: GameResult) {
+        let first_score = match result {
+            GameResult::FirstWins => {
+                self.records[first][second].wins += 1;
+                self.records[second][first].losses += 1;
+                1.0
+            }
+            GameResult::SecondWins => {
+                self.records[first][second].losses += 1;
+                self.records[second][first].wins += 1;
+                0.0
+            }
+            GameResult::Draw => {
+                self.records[first][second].draws += 1;
+                self.records[second][first].draws += 1;
+                0.5
+            }
+        };
+
+        let change = rating_change(self.ratings[first], self.ratings[second], first_score);
+        self.ratings[first] += change;
+        self.ratings[second] -= change;
+    }
+
+    pub fn total(&self, bot: usize) -> Record {
+        let mut total = Record::default();
+        for record in &self.records[bot] {
+            total.add(*record);
+        }
+        total
+    }
+}
+
+// Plays games_per_pairing games between every two bots, alternating who starts.
+pub fn run(bots: &mut [Box<dyn Bot>], games_per_pairing: u32) -> Standings {
+    let mut standings = Standings::new(bots.iter().map(|bot| bot.name()).collect());
+
+    for second in 1..bots.len() {
+        for first in 0..second {
+            let (head, tail) = bots.split_at_mut(second);
+            let (one, two) = (&mut head[first], &mut tail[0]);
+
+            for game in 0..games_per_pairing {
+                if game % 2 == 0 {
+                    standings.add_game(first, second, play_game(one.as_mut(), two.as_mut()));
+                } else {
+                    standings.add_game(second, first, play_game(two.as_mut(), one.as_mut()));
+                }
+            }
+        }
+    }
+
+    standings
+}
+
+// Renders the crosstable, best score first. Each cell is wins-draws-losses
+// of the row's bot against the column's.
+pub fn crosstable(standings: &Standings) -> String {
+    let count = standings.names.len();
+    let mut order = (0..count).collect::<Vec<_>>();
+    order.sort_by(|a, b| {
+        standings
+            .total(*b)
+            .score()
+            .total_cmp(&standings.total(*a).score())
+            .then(standings.ratings[*b].total_cmp(&standings.ratings[*a]))
+    });
+
+    let name_width = standings
+        .names
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(3);
+    let cell = |record: Record| format!("{}-{}-{}", record.wins, record.draws, record.losses);
+    let cell_width = standings
+        .records
+        .iter()
+        .flatten()
+        .map(|record| cell(*record).len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    let total_width = (0..count)
+        .map(|bot| cell(standings.total(bot)).len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+
+    let mut table = format!("{:>2}  {:<name_width$}", "#", "Bot");
+    for place in 1..=count {
+        let _ = write!(table, "  {place:>cell_width$}");
+    }
+    let _ = writeln!(table, "  {:>total_width$}  {:>5}  Rating", "W-D-L", "Score");
+
+    for (place, bot) in order.iter().enumerate() {
+        let _ = write!(
+            table,
+            "{:>2}  {:<name_width$}",
+            place + 1,
+            standings.names[*bot]
+        );
+        for opponent in &order {
+            let text = if opponent == bot {
+                String::from("x")
+            } else {
+                cell(standings.records[*bot][*opponent])
+            };
+            let _ = write!(table, "  {text:>cell_width$}");
+        }
+        let total = standings.total(*bot);
+        let _ = writeln!(
+            table,
+            "  {:>total_width$}  {:>5.1}  {:>6.0}",
+            cell(total),
+            total.score(),
+            standings.ratings[*bot]
+        );
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::allocations;
+    use crate::bot::BuiltinBot;
+    use crate::rng::Rng;
+
+    // Always asks for the top left tile, so it forfeits once that is taken.
+    struct Stubborn;
+
+    impl Bot for Stubborn {
+        fn name(&self) -> String {
+            String::from("Stubborn")
+        }
+
+        fn choose_move(&mut self, _board: &Board, _symbol: Symbol) -> Option<[usize; 2]> {
+            Some([0, 0])
+        }
+    }
+
+    fn hard() -> Box<dyn Bot> {
+        Box::new(BuiltinBot::new(Difficulty::Hard, Rng::new(7)))
+    }
+
+    #[test]
+    fn illegal_moves_forfeit_the_game() {
+        assert_eq!(
+            play_game(hard().as_mut(), &mut Stubborn),
+            GameResult::FirstWins
+        );
+        assert_eq!(
+            play_game(&mut Stubborn, hard().as_mut()),
+            GameResult::SecondWins
+        );
+    }
+
+    #[test]
+    fn perfect_play_is_a_draw() {
+        assert_eq!(
+            play_game(hard().as_mut(), hard().as_mut()),
+            GameResult::Draw
+        );
+    }
+
+    #[test]
+    fn every_pairing_plays_every_game() {
+        let mut bots: Vec<Box<dyn Bot>> = vec![hard(), Box::new(Stubborn), hard()];
+        let standings = run(&mut bots, 4);
+
+        assert_eq!(
+            standings.records[0][1],
+            Record {
+                wins: 4,
+                draws: 0,
+                losses: 0
+            }
+        );
+        assert_eq!(standings.records[1][2].losses, 4);
+        assert_eq!(standings.records[0][2].draws, 4);
+        assert_eq!(standings.total(1).losses, 8);
+        assert!(standings.ratings[0] > INITIAL_RATING);
+        assert!(standings.ratings[1] < INITIAL_RATING);
+        assert!((standings.ratings.iter().sum::<f64>() - 3.0 * INITIAL_RATING).abs() < 1e-6);
+    }
+
+    #[test]
+    fn crosstable_lists_the_best_bot_first() {
+        let mut bots: Vec<Box<dyn Bot>> = vec![Box::new(Stubborn), hard()];
+        let table = crosstable(&run(&mut bots, 2));
+        let lines = table.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("W-D-L"));
+        assert!(lines[1].contains("Hard computer"));
+        assert!(lines[1].contains("2-0-0"));
+        assert!(lines[2].contains("Stubborn"));
+        assert!(lines[2].contains("0-0-2"));
+    }
+
+    #[test]
+    fn simulated_games_do_not_allocate() {
+        let mut first = BuiltinBot::new(Difficulty::Medium, Rng::new(1));
+        let mut second = BuiltinBot::new(Difficulty::Hard, Rng::new(2));
+
+        let ((), allocations) = allocations::count(|| {
+            for _ in 0..5 {
+                play_game(&mut first, &mut second);
+                // Forfeits go through rejecting an illegal move.
+                play_game(&mut Stubborn, &mut second);
+            }
+        });
+        assert_eq!(allocations, 0);
+    }
+}
@@ -0,0 +1,365 @@
+// Tabular Q-learning through self-play. Both sides share one table, looked up
+// from the point of view of the side to move, and a move's value is updated
+// towards the negated value of the opponent's best reply.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::ai::Difficulty;
+use crate::board::{Board, Symbol};
+use crate::bot::{Bot, BuiltinBot};
+use crate::rng::Rng;
+use crate::tournament::{self, GameResult, Record};
+
+const HEADER: &str = "tic-tac-toe-rust policy v1";
+
+const LEARNING_RATE: f64 = 0.3;
+const DISCOUNT: f64 = 0.9;
+// Games played against each reference bot for a progress report.
+const EVALUATION_GAMES: u32 = 100;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TrainOptions {
+    pub episodes: u32,
+    // Chance in percent of exploring a random move instead of the best one.
+    pub epsilon: u32,
+    pub report_every: u32,
+    pub seed: Option<u64>,
+    pub out: PathBuf,
+}
+
+// The board as a base 3 number seen from the side to move: 1 for its own
+// marks and 2 for the opponent's.
+fn state_key(board: &Board, to_move: Symbol) -> u32 {
+    board.tiles.iter().flatten().fold(0, |key, tile| {
+        key * 3
+            + match *tile {
+                Symbol::Empty => 0,
+                tile if tile == to_move => 1,
+                _ => 2,
+            }
+    })
+}
+
+fn action_index(position: [usize; 2], size: usize) -> usize {
+    position[0] * size + position[1]
+}
+
+#[derive(Default)]
+pub struct Policy {
+    values: HashMap<(u32, usize), f64>,
+}
+
+impl Policy {
+    fn value(&self, state: u32, action: usize) -> f64 {
+        self.values.get(&(state, action)).copied().unwrap_or(0.0)
+    }
+
+    // The highest valued empty position for symbol, the first one on ties.
+    fn best(&self, board: &Board, symbol: Symbol) -> Option<([usize; 2], f64)> {
+        let state = state_key(board, symbol);
+        let size = board.tiles.len();
+        let mut best: Option<([usize; 2], f64)> = None;
+
+        for position in board.empty_positions() {
+            let value = self.value(state, action_index(position, size));
+            if best.is_none_or(|(_, best_value)| value > best_value) {
+                best = Some((position, value));
+            }
+        }
+
+        best
+    }
+
+    // One line per known move, tab separated: the position with the side to
+    // move as +, the tile index and its value.
+    fn serialize(&self) -> String {
+        let mut entries = self.values.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(key, _)| **key);
+
+        let mut contents = format!("{HEADER}\n");
+        for ((state, action), value) in entries {
+            let mut board = Board::new();
+            let mut key = *state;
+            for tile in board.tiles.iter_mut().flatten().rev() {
+                *tile = match key % 3 {
+                    0 => Symbol::Empty,
+                    1 => Symbol::Plus,
+                    _ => Symbol::Circle,
+                };
+                key /= 3;
+            }
+            let _ = writeln!(contents, "{board:#}\t{action}\t{value}");
+        }
+
+        contents
+    }
+
+    fn parse(contents: &str) -> Result<Policy, String> {
+        let mut lines = contents.lines();
+
+        if lines.next() != Some(HEADER) {
+            return Err(String::from("The file is not a tic-tac-toe-rust policy."));
+        }
+
+        let mut values = HashMap::new();
+        for (i, line) in lines.enumerate() {
+            let invalid = || format!("Line {} of the policy is invalid.", i + 2);
+            let mut fields = line.split('\t');
+            let (Some(position), Some(action), Some(value), None) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                return Err(invalid());
+            };
+
+            let board = position.parse::<Board>().map_err(|_| invalid())?;
+            let action = action.parse::<usize>().map_err(|_| invalid())?;
+            let value = value.parse::<f64>().map_err(|_| invalid())?;
+            values.insert((state_key(&board, Symbol::Plus), action), value);
+        }
+
+        Ok(Policy { values })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.serialize())
+    }
+
+    pub fn load(path: &Path) -> Result<Policy, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| format!("Could not read {}: {error}", path.display()))?;
+
+        Policy::parse(&contents)
+    }
+}
+
+// Plays the learned policy greedily.
+pub struct PolicyBot {
+    name: String,
+    policy: Policy,
+}
+
+impl PolicyBot {
+    pub fn load(path: &Path) -> Result<PolicyBot, String> {
+        let stem = path.file_stem().map_or_else(
+            || String::from("policy"),
+            |stem| stem.to_string_lossy().into_owned(),
+        );
+
+        Ok(PolicyBot {
+            name: format!("Trained policy ({stem})"),
+            policy: Policy::load(path)?,
+        })
+    }
+}
+
+impl Bot for PolicyBot {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn choose_move(&mut self, board: &Board, symbol: Symbol) -> Option<[usize; 2]> {
+        self.policy
+            .best(board, symbol)
+            .map(|(position, _)| position)
+    }
+}
+
+// Plays one self-play game, updating the policy after every move.
+fn train_episode(policy: &mut Policy, epsilon: u32, rng: &mut Rng) {
+    let mut board = Board::new();
+    let mut symbol = Symbol::Plus;
+    let size = board.tiles.len();
+
+    loop {
+        let empty = board.empty_positions();
+        let explore = rng.below(100) < usize::try_from(epsilon).unwrap_or(usize::MAX);
+        let Some(position) = (if explore {
+            Some(empty[rng.below(empty.len())])
+        } else {
+            policy.best(&board, symbol).map(|(position, _)| position)
+        }) else {
+            return;
+        };
+
+        let state = state_key(&board, symbol);
+        let won = board.place(symbol, position);
+        let finished = won || board.is_full();
+        let target = if won {
+            1.0
+        } else if finished {
+            0.0
+        } else {
+            -DISCOUNT
+                * policy
+                    .best(&board, symbol.opponent())
+                    .map_or(0.0, |(_, value)| value)
+        };
+
+        let action = action_index(position, size);
+        let value = policy.value(state, action);
+        policy
+            .values
+            .insert((state, action), value + LEARNING_RATE * (target - value));
+
+        if finished {
+            return;
+        }
+        symbol = symbol.opponent();
+    }
+}
+
+// How the policy does against opponent over a number of games, alternating
+// who starts.
+fn measure(bot: &mut PolicyBot, opponent: &mut dyn Bot, games: u32) -> Record {
+    let mut record = Record::default();
+
+    for game in 0..games {
+        let result = if game % 2 == 0 {
+            tournament::play_game(bot, opponent)
+        } else {
+            match tournament::play_game(opponent, bot) {
+                GameResult::FirstWins => GameResult::SecondWins,
+                GameResult::SecondWins => GameResult::FirstWins,
+                GameResult::Draw => GameResult::Draw,
+            }
+        };
+
+        match result {
+            GameResult::FirstWins => record.wins += 1,
+            GameResult::SecondWins => record.losses += 1,
+            GameResult::Draw => record.draws += 1,
+        }
+    }
+
+    record
+}
+
+pub fn run(options: &TrainOptions) -> Result<(), String> {
+    let mut rng = options.seed.map_or_else(Rng::from_time, Rng::new);
+    let mut bot = PolicyBot {
+        name: String::from("Training policy"),
+        policy: Policy::default(),
+    };
+    let mut random = BuiltinBot::new(Difficulty::Easy, Rng::new(rng.next_u64()));
+    let mut minimax = BuiltinBot::new(Difficulty::Hard, Rng::new(rng.next_u64()));
+
+    for episode in 1..=options.episodes {
+        train_episode(&mut bot.policy, options.epsilon, &mut rng);
+
+        if episode % options.report_every == 0 || episode == options.episodes {
+            let against_random = measure(&mut bot, &mut random, EVALUATION_GAMES);
+            let against_minimax = measure(&mut bot, &mut minimax, EVALUATION_GAMES);
+            println!(
+                "Episode {episode}: against random {}-{}-{}, against minimax {}-{}-{} (W-D-L)",
+                against_random.wins,
+                against_random.draws,
+                against_random.losses,
+                against_minimax.wins,
+                against_minimax.draws,
+                against_minimax.losses
+            );
+        }
+    }
+
+    bot.policy
+        .save(&options.out)
+        .map_err(|error| format!("Could not save {}: {error}", options.out.display()))?;
+    println!(
+        "Saved {} learned values to {}.",
+        bot.policy.values.len(),
+        options.out.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trained(episodes: u32) -> PolicyBot {
+        let mut rng = Rng::new(3);
+        let mut bot = PolicyBot {
+            name: String::from("test"),
+            policy: Policy::default(),
+        };
+        for _ in 0..episodes {
+            train_episode(&mut bot.policy, 20, &mut rng);
+        }
+        bot
+    }
+
+    #[test]
+    fn states_are_seen_from_the_side_to_move() {
+        let board: Board = "+--/---/--o".parse().unwrap();
+        let swapped: Board = "o--/---/--+".parse().unwrap();
+
+        assert_eq!(
+            state_key(&board, Symbol::Plus),
+            state_key(&swapped, Symbol::Circle)
+        );
+        assert_ne!(
+            state_key(&board, Symbol::Plus),
+            state_key(&board, Symbol::Circle)
+        );
+    }
+
+    #[test]
+    fn winning_moves_are_valued_as_wins() {
+        let bot = trained(2_000);
+        let contents = bot.policy.serialize();
+        let mut winning_moves = 0;
+
+        for line in contents.lines().skip(1) {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let mut board = fields[0].parse::<Board>().unwrap();
+            let action = fields[1].parse::<usize>().unwrap();
+            let value = fields[2].parse::<f64>().unwrap();
+
+            if board.place(Symbol::Plus, [action / 3, action % 3]) {
+                winning_moves += 1;
+                assert!(value > 0.0);
+            }
+        }
+
+        assert!(winning_moves > 0);
+    }
+
+    #[test]
+    fn training_beats_random_play_and_holds_minimax() {
+        let mut bot = trained(20_000);
+        let mut random = BuiltinBot::new(Difficulty::Easy, Rng::new(5));
+        let mut minimax = BuiltinBot::new(Difficulty::Hard, Rng::new(5));
+
+        let against_random = measure(&mut bot, &mut random, 100);
+        let against_minimax = measure(&mut bot, &mut minimax, 10);
+
+        assert!(against_random.wins > against_random.losses * 4);
+        assert_eq!(against_minimax.wins, 0);
+    }
+
+    #[test]
+    fn policies_survive_a_round_trip() {
+        let bot = trained(200);
+        let parsed = Policy::parse(&bot.policy.serialize()).unwrap();
+
+        assert_eq!(parsed.values, bot.policy.values);
+    }
+
+    #[test]
+    fn invalid_policies_are_rejected() {
+        for contents in [
+            "",
+            "not a policy",
+            "tic-tac-toe-rust policy v1\n---/---/---\t4",
+            "tic-tac-toe-rust policy v1\n---/---/---\tfour\t0.5",
+            "tic-tac-toe-rust policy v1\n---/---/--x\t4\t0.5",
+        ] {
+            assert!(Policy::parse(contents).is_err());
+        }
+    }
+}
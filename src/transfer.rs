@@ -0,0 +1,322 @@
+// Profiles are moved between machines as one portable text file: a header
+// with the export's id, then a "[<part>]" line before each of the openings,
+// achievements, streaks, rating and settings, in the format of their own
+// files.
+//
+// Importing merges the statistics into the ones already here: opening
+// results are added up, achievements and best streaks are kept from either
+// side and the rating from more games wins. Settings already here are kept.
+// With --replace the imported ones simply take over instead.
+//
+// The ids of imported exports are kept, and the same export is not merged
+// twice so no game is counted twice.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::achievements::{self, Achievements};
+use crate::openings::{self, OpeningStats};
+use crate::profile::{self, Settings};
+use crate::rating::{self, Rating};
+use crate::streaks::{self, StreakStats};
+
+const HEADER: &str = "tic-tac-toe-rust profile";
+
+// The ids of the exports imported into this profile, one per line.
+pub const IMPORTS: &str = "tic-tac-toe.imports";
+
+const PARTS: [&str; 5] = ["openings", "achievements", "streaks", "rating", "settings"];
+
+#[derive(Debug, Default, PartialEq)]
+struct ProfileData {
+    id: String,
+    openings: OpeningStats,
+    achievements: Achievements,
+    streaks: StreakStats,
+    rating: Rating,
+    settings: Option<Settings>,
+}
+
+impl ProfileData {
+    // What is kept for the selected profile, or without one.
+    fn load() -> Result<ProfileData, String> {
+        Ok(ProfileData {
+            id: String::new(),
+            openings: openings::load(&openings::path())?,
+            achievements: achievements::load(&achievements::path())?,
+            streaks: streaks::load(&streaks::path())?,
+            rating: rating::load(&rating::path())?,
+            settings: profile::saved_settings()?,
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let save = |path: &Path, text: String| {
+            fs::write(path, text)
+                .map_err(|error| format!("Could not save {}: {error}", path.display()))
+        };
+        save(&openings::path(), self.openings.to_string())?;
+        save(&achievements::path(), self.achievements.to_string())?;
+        save(&streaks::path(), self.streaks.to_string())?;
+        save(&rating::path(), self.rating.to_string())?;
+        match self.settings {
+            Some(settings) => profile::save_settings(settings),
+            None => Ok(()),
+        }
+    }
+
+    // Merges an export into this, returning what happened to show the player.
+    fn merge(&mut self, other: ProfileData) -> Vec<String> {
+        let mut report = vec![String::from(
+            "Added up the opening results and kept the best streaks.",
+        )];
+
+        self.openings.merge(other.openings);
+        self.streaks.merge(other.streaks);
+
+        let unlocked = self.achievements.merge(other.achievements);
+        report.push(match unlocked {
+            0 => String::from("No new achievements."),
+            1 => String::from("Unlocked 1 new achievement."),
+            _ => format!("Unlocked {unlocked} new achievements."),
+        });
+
+        let (ours, theirs) = (self.rating, other.rating);
+        if self.rating.merge(theirs) {
+            report.push(format!(
+                "Took the imported rating of {:.0} from {} games.",
+                theirs.rating, theirs.games
+            ));
+        } else if theirs.games > 0 {
+            report.push(format!(
+                "Kept your rating of {:.0} from {} games, the imported one is from fewer.",
+                ours.rating, ours.games
+            ));
+        }
+
+        match (self.settings, other.settings) {
+            (None, Some(settings)) => {
+                self.settings = Some(settings);
+                report.push(String::from("Took the imported settings."));
+            }
+            (Some(ours), Some(theirs)) if ours != theirs => {
+                report.push(String::from(
+                    "Kept your settings, the imported ones are different.",
+                ));
+            }
+            _ => {}
+        }
+
+        report
+    }
+}
+
+// The header line, then each part under its name.
+impl Display for ProfileData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{HEADER} {}", self.id)?;
+        write!(f, "[openings]\n{}", self.openings)?;
+        write!(f, "[achievements]\n{}", self.achievements)?;
+        write!(f, "[streaks]\n{}", self.streaks)?;
+        write!(f, "[rating]\n{}", self.rating)?;
+        if let Some(settings) = self.settings {
+            write!(f, "[settings]\n{settings}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ProfileData {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+        let id = lines
+            .next()
+            .and_then(|line| line.strip_prefix(HEADER))
+            .and_then(|id| id.strip_prefix(' '))
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| String::from("It is not an exported profile."))?;
+
+        let mut parts: BTreeMap<&str, String> = BTreeMap::new();
+        let mut part = None;
+        for line in lines {
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                let name = PARTS
+                    .into_iter()
+                    .find(|part| *part == name)
+                    .ok_or_else(|| format!("'{name}' is not part of a profile."))?;
+                part = Some(name);
+                parts.entry(name).or_default();
+            } else if let Some(part) = part {
+                let text = parts.entry(part).or_default();
+                text.push_str(line);
+                text.push('\n');
+            } else if !line.trim().is_empty() {
+                return Err(format!("'{line}' is not in any part of the profile."));
+            }
+        }
+
+        let part = |name: &str| parts.get(name).map_or("", String::as_str);
+        Ok(ProfileData {
+            id: id.to_string(),
+            openings: part("openings").parse()?,
+            achievements: part("achievements").parse()?,
+            streaks: part("streaks").parse()?,
+            rating: part("rating").parse()?,
+            settings: parts.get("settings").map(|text| text.parse()).transpose()?,
+        })
+    }
+}
+
+// Writes the selected profile's statistics, achievements and settings to path.
+pub fn export(path: &Path) -> Result<(), String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let data = ProfileData {
+        id: format!("{}.{:09}", now.as_secs(), now.subsec_nanos()),
+        ..ProfileData::load()?
+    };
+    fs::write(path, data.to_string())
+        .map_err(|error| format!("Could not write {}: {error}", path.display()))
+}
+
+// Imports an export into the selected profile, merging it with what is
+// already there unless replace is given, and returns what happened.
+pub fn import(path: &Path, replace: bool) -> Result<Vec<String>, String> {
+    let imported: ProfileData = fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {error}", path.display()))?
+        .parse()
+        .map_err(|error| format!("{}: {error}", path.display()))?;
+
+    let imports_path = profile::file(IMPORTS);
+    let mut imports = fs::read_to_string(&imports_path).unwrap_or_default();
+    if !replace && imports.lines().any(|id| id == imported.id) {
+        return Err(format!(
+            "{} has already been imported, merging it again would count its games twice. \
+             Use --replace to take it over as it is.",
+            path.display()
+        ));
+    }
+
+    let mut report = Vec::new();
+    let mut data = ProfileData::load()?;
+    if replace {
+        let settings = data.settings;
+        data = imported;
+        data.settings = data.settings.or(settings);
+        report.push(String::from(
+            "Replaced your statistics with the imported ones.",
+        ));
+    } else {
+        let id = imported.id.clone();
+        report.extend(data.merge(imported));
+        data.id = id;
+    }
+    data.save()?;
+
+    imports.push_str(&data.id);
+    imports.push('\n');
+    fs::write(&imports_path, imports)
+        .map_err(|error| format!("Could not save {}: {error}", imports_path.display()))?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::TakebackAnswer;
+    use crate::tournament::Record;
+
+    const WIN: Record = Record {
+        wins: 1,
+        draws: 0,
+        losses: 0,
+    };
+
+    fn data(id: &str, rating: f64, games: u32, settings: Option<Settings>) -> ProfileData {
+        let mut openings = OpeningStats::default();
+        openings.add("hard", "+1,1 o0,0", WIN);
+        ProfileData {
+            id: id.to_string(),
+            openings,
+            rating: Rating { rating, games },
+            settings,
+            ..ProfileData::default()
+        }
+    }
+
+    #[test]
+    fn exports_round_trip() {
+        let settings = Settings {
+            coach: true,
+            ..Settings::default()
+        };
+        let exported = data("17.5", 1550.0, 3, Some(settings));
+
+        let text = exported.to_string();
+        assert!(text.starts_with("tic-tac-toe-rust profile 17.5\n[openings]\nhard\t"));
+        assert_eq!(text.parse(), Ok(exported));
+
+        let without_settings = data("18", 1500.0, 0, None);
+        assert_eq!(without_settings.to_string().parse(), Ok(without_settings));
+    }
+
+    #[test]
+    fn broken_exports_are_rejected() {
+        for text in [
+            "",
+            "tic-tac-toe-rust profile",
+            "tic-tac-toe.rating\nrating 1500",
+            "tic-tac-toe-rust profile 1\n[games]\n",
+            "tic-tac-toe-rust profile 1\nrating 1500\n",
+            "tic-tac-toe-rust profile 1\n[rating]\nrating high\n",
+        ] {
+            assert!(text.parse::<ProfileData>().is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn merging_keeps_the_better_of_each_and_our_settings() {
+        let ours_settings = Settings {
+            takebacks: TakebackAnswer::Refuse,
+            ..Settings::default()
+        };
+        let mut ours = data("", 1600.0, 10, Some(ours_settings));
+        let theirs = data("2", 1700.0, 4, Some(Settings::default()));
+
+        let report = ours.merge(theirs);
+        assert_eq!(ours.rating.games, 10);
+        assert_eq!(ours.settings, Some(ours_settings));
+        assert!(report[2].starts_with("Kept your rating of 1600 from 10 games"));
+        assert_eq!(
+            report[3],
+            "Kept your settings, the imported ones are different."
+        );
+
+        let mut expected = OpeningStats::default();
+        expected.add("hard", "+1,1 o0,0", Record { wins: 2, ..WIN });
+        assert_eq!(ours.openings, expected);
+
+        // A fresh profile takes everything over.
+        let mut fresh = ProfileData::default();
+        let report = fresh.merge(data("3", 1450.0, 2, Some(ours_settings)));
+        assert_eq!(
+            fresh.rating,
+            Rating {
+                rating: 1450.0,
+                games: 2
+            }
+        );
+        assert_eq!(fresh.settings, Some(ours_settings));
+        assert_eq!(report[3], "Took the imported settings.");
+    }
+}
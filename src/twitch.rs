@@ -0,0 +1,180 @@
+// Twitch chat plays the bot: viewers vote with !move b2 and the move with the
+// most votes is played when voting closes. Twitch chat is IRC, so the IRC
+// bot's parsing and the crowd vote core do the work, while the board and the
+// votes as they come in are drawn on this terminal for the stream. Without a
+// token in TTT_TWITCH_TOKEN the chat is only read; with one, results are also
+// posted to it.
+
+use std::env;
+use std::fmt::Write as _;
+use std::io;
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
+
+use crate::board::{GameStatus, Symbol};
+use crate::console::Console;
+use crate::irc::{self, ChatCommand, IrcOptions, Message};
+use crate::rng::Rng;
+use crate::vote::{self, CrowdGame, Round};
+
+const TOKEN_VARIABLE: &str = "TTT_TWITCH_TOKEN";
+
+// Twitch lets any justinfan nick read chat without logging in.
+const ANONYMOUS_NICK: &str = "justinfan31415";
+
+// The countdown is drawn again this often while voting is open.
+const REDRAW: Duration = Duration::from_secs(1);
+
+// The board, the last moves marked, and the votes so far.
+fn screen(
+    console: &Console,
+    game: &CrowdGame,
+    marked: &[[usize; 2]],
+    news: &str,
+    now: Instant,
+) -> String {
+    let board = console.renderer().board(game.board(), marked);
+    let votes = match game.closes() {
+        Some(closes) => format!(
+            "Votes, closing in {}s:\n{}",
+            closes.saturating_duration_since(now).as_secs(),
+            vote::format_tally(&game.tally(), irc::square_name)
+        ),
+        None => String::from("Vote for the crowd's move with !move b2 in chat.\n"),
+    };
+    format!("\n{news}\n\n{board}\n\n{votes}")
+}
+
+// What a round came to, for the terminal and the chat.
+fn describe(round: &Round, bot: &str) -> String {
+    let mut text = format!("The crowd played {}", irc::square_name(round.played));
+    if let Some(reply) = round.reply {
+        let _ = write!(text, ", {bot} answered {}", irc::square_name(reply));
+    }
+    text.push_str(match round.status {
+        _ if round.forfeit => ". The bot has no move, the crowd wins!",
+        GameStatus::Won(Symbol::Plus, _) => ". The crowd wins!",
+        GameStatus::Won(..) => ". The bot wins!",
+        GameStatus::Draw => ". It's a draw!",
+        GameStatus::InProgress => ".",
+    });
+    if round.forfeit || round.status != GameStatus::InProgress {
+        text.push_str(" A new game starts.");
+    }
+    text
+}
+
+// Joins the channel and plays until the connection closes.
+pub fn run(console: &mut Console, options: &IrcOptions) -> Result<(), String> {
+    let failed =
+        |error: io::Error| format!("Twitch connection to {} failed: {error}", options.server);
+    let mut stream = std::net::TcpStream::connect(&options.server).map_err(failed)?;
+    let lines = irc::read_lines(&stream).map_err(failed)?;
+    let token = env::var(TOKEN_VARIABLE)
+        .ok()
+        .filter(|token| !token.is_empty());
+    let nick = if token.is_some() {
+        options.nick.to_lowercase()
+    } else {
+        String::from(ANONYMOUS_NICK)
+    };
+    let channel = options.channel.to_lowercase();
+    let bot = options.bot.load()?;
+    let name = bot.name();
+    let mut game = CrowdGame::new(
+        bot,
+        options.crowd.unwrap_or(vote::DEFAULT_WINDOW),
+        options.tiebreak,
+        Rng::from_time(),
+    );
+
+    if let Some(token) = &token {
+        let token = token.strip_prefix("oauth:").unwrap_or(token);
+        irc::send(&mut stream, &format!("PASS oauth:{token}")).map_err(failed)?;
+    }
+    irc::send(&mut stream, &format!("NICK {nick}")).map_err(failed)?;
+    irc::send(&mut stream, &format!("JOIN {channel}")).map_err(failed)?;
+
+    let mut marked = Vec::new();
+    let mut news = format!("Joining {channel}, chat plays + against {name}.");
+    loop {
+        let now = Instant::now();
+        console.clear();
+        console.show(&screen(console, &game, &marked, &news, now));
+
+        let line = irc::next_line(&lines, game.closes().map(|closes| closes.min(now + REDRAW)));
+        if let Some(round) = game.close(Instant::now()) {
+            news = describe(&round, &name);
+            marked = round.reply.into_iter().chain([round.played]).collect();
+            if round.forfeit || round.status != GameStatus::InProgress {
+                marked.clear();
+            }
+            if token.is_some() {
+                irc::send(&mut stream, &format!("PRIVMSG {channel} :{news}")).map_err(failed)?;
+            }
+        }
+        let line = match line {
+            Ok(line) => line.map_err(failed)?,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+        let Some(message) = Message::parse(&line) else {
+            continue;
+        };
+
+        match (message.command, message.params.as_slice()) {
+            ("PING", params) => {
+                irc::send(&mut stream, &format!("PONG :{}", params.join(" "))).map_err(failed)?;
+            }
+            // Twitch turns away a bad token with a notice.
+            ("NOTICE", [_, text]) if text.contains("authentication failed") => {
+                return Err(format!(
+                    "Twitch refused the token in {TOKEN_VARIABLE}: {text}"
+                ));
+            }
+            ("JOIN", _) if message.sender == Some(nick.as_str()) => {
+                news = format!("Joined {channel}, chat plays + against {name}.");
+            }
+            ("PRIVMSG", [target, text]) if target.eq_ignore_ascii_case(&channel) => {
+                let position = match ChatCommand::parse(text) {
+                    Some(ChatCommand::Move(square) | ChatCommand::Vote(square)) => {
+                        irc::parse_square(&square)
+                    }
+                    _ => None,
+                };
+                if let Some(position) = position {
+                    // Votes for taken squares are left out.
+                    let _ = game.vote(message.sender.unwrap_or_default(), position, Instant::now());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::bot::BuiltinBot;
+
+    #[test]
+    fn rounds_are_described_for_chat() {
+        let start = Instant::now();
+        let mut game = CrowdGame::new(
+            Box::new(BuiltinBot::new(Difficulty::Hard, Rng::new(1))),
+            Duration::ZERO,
+            vote::Tiebreak::First,
+            Rng::new(1),
+        );
+        let console = Console::over(Box::new(io::empty()), Box::new(io::sink()));
+        assert!(screen(&console, &game, &[], "", start).ends_with("!move b2 in chat.\n"));
+
+        game.vote("alice", [1, 1], start).unwrap();
+        assert!(screen(&console, &game, &[], "", start).ends_with("closing in 0s:\nb2 # 1\n"));
+        let round = game.close(start).unwrap();
+        let text = describe(&round, "Hard computer");
+        assert!(text.starts_with("The crowd played b2, Hard computer answered "));
+        assert!(text.ends_with('.'));
+    }
+}
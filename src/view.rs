@@ -0,0 +1,533 @@
+// How games draw the board: the usual grid, large print that grows with the
+// terminal, or a line of Braille for small panes. All come in the colors of
+// the palette. The grid can be drawn two or three times as big, or as big as
+// fits the terminal. Several boards can share the terminal, side by side.
+
+use std::fmt::{Display, Write as _};
+use std::str::FromStr;
+
+use crate::board::{Board, Mark};
+use crate::braille;
+use crate::largeprint;
+use crate::palette::{self, Palette};
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum View {
+    #[default]
+    Grid,
+    Large,
+    Braille,
+}
+
+pub const VIEWS: [View; 3] = [View::Grid, View::Large, View::Braille];
+
+impl Display for View {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            View::Grid => "grid",
+            View::Large => "large",
+            View::Braille => "braille",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for View {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        VIEWS
+            .into_iter()
+            .find(|view| view.to_string() == s)
+            .ok_or_else(|| format!("'{s}' is not a view, use grid, large or braille."))
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Scale {
+    #[default]
+    One,
+    Two,
+    Three,
+    // The largest that fits the terminal.
+    Auto,
+}
+
+pub const SCALES: [Scale; 4] = [Scale::One, Scale::Two, Scale::Three, Scale::Auto];
+
+// Lines kept free for the text shown with the board.
+const TEXT_LINES: usize = 8;
+
+impl Scale {
+    // How many times as big tiles of a board of n a side are drawn.
+    fn factor(self, n: usize, (columns, rows): (usize, usize)) -> usize {
+        match self {
+            Scale::One => 1,
+            Scale::Two => 2,
+            Scale::Three => 3,
+            Scale::Auto => (1..=3)
+                .rev()
+                .find(|factor| {
+                    n * (4 * factor - 1) + n - 1 <= columns
+                        && n * (2 * factor - 1) + n - 1 + TEXT_LINES <= rows
+                })
+                .unwrap_or(1),
+        }
+    }
+}
+
+impl Display for Scale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Scale::One => "1",
+            Scale::Two => "2",
+            Scale::Three => "3",
+            Scale::Auto => "auto",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Scale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SCALES
+            .into_iter()
+            .find(|scale| scale.to_string() == s.trim_end_matches('x'))
+            .ok_or_else(|| format!("'{s}' is not a scale, use 1, 2, 3 or auto."))
+    }
+}
+
+// Draws boards for a terminal of the given size in columns and rows.
+#[derive(Debug, Clone, Copy)]
+pub struct Renderer {
+    pub view: View,
+    pub palette: Palette,
+    // Row and column numbers around the grid. Large boards always label
+    // their empty tiles and Braille comes with a description instead.
+    pub labels: bool,
+    // Of the grid.
+    pub scale: Scale,
+    // Without Unicode, large boards are drawn in ASCII and Braille becomes
+    // the grid.
+    pub unicode: bool,
+    pub size: (usize, usize),
+}
+
+// Row numbers before the lines of a grid and column numbers above it, lined
+// up with the cells of the game's formatter, e.g. "| + |".
+fn label_grid(grid: &str, n: usize) -> String {
+    let header = (0..n).fold(String::new(), |mut header, j| {
+        let _ = write!(header, "{j:>4}");
+        header
+    });
+    let rows = grid
+        .lines()
+        .enumerate()
+        .map(|(i, line)| format!("{i} {line}"));
+    [format!(" {header}")]
+        .into_iter()
+        .chain(rows)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// The grid with tiles 4 * factor - 1 characters wide and 2 * factor - 1
+// lines high, the mark in the middle of each.
+fn scaled_grid(board: &Board, marked: &[[usize; 2]], factor: usize, palette: Palette) -> String {
+    let (width, height) = (4 * factor - 1, 2 * factor - 1);
+    let colors = palette.colors();
+    let paint = |text: &str, color: Option<&str>| match color {
+        Some(color) => palette::paint(text, color),
+        None => String::from(text),
+    };
+    let divider = vec!["-".repeat(width); board.tiles.len()].join("+");
+    let blank = vec![" ".repeat(width); board.tiles.len()].join("|");
+
+    let mut lines = Vec::new();
+    for (i, row) in board.tiles.iter().enumerate() {
+        if i > 0 {
+            lines.push(divider.clone());
+        }
+        let middle = row
+            .iter()
+            .enumerate()
+            .map(|(j, symbol)| {
+                let side = colors
+                    .zip(symbol.side())
+                    .and_then(|(colors, side)| colors.sides.get(side).copied());
+                let (open, close) = if marked.contains(&[i, j]) {
+                    let color = colors.map(|colors| colors.marked);
+                    (paint("[", color), paint("]", color))
+                } else {
+                    (String::from(" "), String::from(" "))
+                };
+                let margin = " ".repeat((width - 3) / 2);
+                format!(
+                    "{margin}{open}{}{close}{margin}",
+                    paint(<&str>::from(*symbol), side)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("|");
+        for y in 0..height {
+            lines.push(if y == height / 2 {
+                middle.clone()
+            } else {
+                blank.clone()
+            });
+        }
+    }
+    lines.join("\n")
+}
+
+// A board drawn with others, with lines below it on how its game stands.
+pub struct Panel {
+    pub board: Board,
+    pub marked: Vec<[usize; 2]>,
+    pub status: String,
+}
+
+// Columns between boards drawn side by side.
+const GAP: usize = 4;
+
+// Columns a line takes up in the terminal, leaving out its color codes.
+fn visible_width(line: &str) -> usize {
+    let mut width = 0;
+    let mut escape = false;
+    for c in line.chars() {
+        match c {
+            '\x1b' => escape = true,
+            'm' if escape => escape = false,
+            _ if escape => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+impl Renderer {
+    // The board with the given tiles marked.
+    pub fn board(&self, board: &Board, marked: &[[usize; 2]]) -> String {
+        match self.view {
+            View::Braille if !self.unicode => Renderer {
+                view: View::Grid,
+                ..*self
+            }
+            .board(board, marked),
+            // Row and column numbers line up with the usual size only.
+            View::Grid if self.scale.factor(board.tiles.len(), self.size) > 1 => scaled_grid(
+                board,
+                marked,
+                self.scale.factor(board.tiles.len(), self.size),
+                self.palette,
+            ),
+            View::Grid => {
+                let grid = self.palette.formatter().format_marked(board, marked);
+                if self.labels {
+                    label_grid(&grid, board.tiles.len())
+                } else {
+                    grid
+                }
+            }
+            View::Large if !self.fits_large(board) => {
+                let (columns, rows) = largeprint::minimum_size(board.tiles.len());
+                format!(
+                    "The terminal is {}x{}, large print needs at least {columns}x{rows}.\n\n{}",
+                    self.size.0,
+                    self.size.1,
+                    Renderer {
+                        view: View::Grid,
+                        ..*self
+                    }
+                    .board(board, marked)
+                )
+            }
+            View::Large => largeprint::render(
+                board,
+                marked,
+                largeprint::fit(board.tiles.len(), self.size),
+                self.palette.colors(),
+                self.unicode,
+            ),
+            View::Braille => format!(
+                "{}\n{}",
+                braille::render(board, marked, self.palette.colors()),
+                braille::describe(board, marked)
+            ),
+        }
+    }
+
+    // The boards in rows, as many side by side as fit the terminal's width.
+    // Each is drawn for its share of the terminal, so large print and auto
+    // scale shrink as more boards are added.
+    pub fn panels(&self, panels: &[Panel]) -> String {
+        let (columns, rows) = self.size;
+        let layout = |per_row: usize| {
+            let renderer = Renderer {
+                size: (
+                    ((columns + GAP) / per_row).saturating_sub(GAP),
+                    rows / panels.len().div_ceil(per_row),
+                ),
+                ..*self
+            };
+            let drawn = panels
+                .iter()
+                .map(|panel| {
+                    let board = renderer.board(&panel.board, &panel.marked);
+                    board
+                        .lines()
+                        .chain(panel.status.lines())
+                        .map(String::from)
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let width = drawn
+                .iter()
+                .flatten()
+                .map(|line| visible_width(line))
+                .max()
+                .unwrap_or(0);
+            (drawn, width)
+        };
+        let (per_row, (drawn, width)) = (1..=panels.len())
+            .rev()
+            .map(|per_row| (per_row, layout(per_row)))
+            .find(|(per_row, (_, width))| per_row * (width + GAP) - GAP <= columns)
+            .unwrap_or_else(|| (1, layout(1)));
+
+        drawn
+            .chunks(per_row)
+            .map(|row| {
+                let height = row.iter().map(Vec::len).max().unwrap_or(0);
+                (0..height)
+                    .map(|y| {
+                        row.iter()
+                            .fold(String::new(), |mut text, lines| {
+                                let line = lines.get(y).map_or("", String::as_str);
+                                let padding = width + GAP - visible_width(line);
+                                let _ = write!(text, "{line}{}", " ".repeat(padding));
+                                text
+                            })
+                            .trim_end()
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn fits_large(&self, board: &Board) -> bool {
+        let (columns, rows) = largeprint::minimum_size(board.tiles.len());
+        self.size.0 >= columns && self.size.1 >= rows
+    }
+
+    // The board during a game. Braille leaves room for every move so far.
+    pub fn position(&self, board: &Board, marked: &[[usize; 2]], moves: &[[usize; 2]]) -> String {
+        match self.view {
+            View::Braille if self.unicode => format!(
+                "{}\n{}",
+                braille::render_with_history(board, marked, moves, self.palette.colors()),
+                braille::describe(board, marked)
+            ),
+            _ => self.board(board, marked),
+        }
+    }
+
+    // What a move changed. Large and Braille boards are drawn once, the new
+    // one with the changes marked.
+    pub fn diff(&self, previous: &Board, current: &Board) -> String {
+        match self.view {
+            View::Grid => self.palette.formatter().format_diff(previous, current),
+            View::Large | View::Braille => {
+                let changed = current
+                    .tiles
+                    .iter()
+                    .flatten()
+                    .zip(previous.tiles.iter().flatten())
+                    .enumerate()
+                    .filter(|(_, (current, previous))| current != previous)
+                    .map(|(i, _)| [i / current.tiles.len(), i % current.tiles.len()])
+                    .collect::<Vec<_>>();
+                self.board(current, &changed)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn views_round_trip() {
+        for view in VIEWS {
+            assert_eq!(view.to_string().parse(), Ok(view));
+        }
+        assert!("tiny".parse::<View>().is_err());
+    }
+
+    #[test]
+    fn grids_can_be_labelled_with_rows_and_columns() {
+        let renderer = Renderer {
+            view: View::Grid,
+            palette: Palette::Plain,
+            labels: true,
+            scale: Scale::One,
+            unicode: true,
+            size: (80, 24),
+        };
+        let board: Board = "+--/-o-/---".parse().unwrap();
+
+        assert_eq!(
+            renderer.board(&board, &[[1, 1]]),
+            "    0   1   2\n0 | + | - | - |\n1 | - |[o]| - |\n2 | - | - | - |"
+        );
+    }
+
+    #[test]
+    fn small_terminals_get_the_grid_with_a_warning() {
+        let renderer = Renderer {
+            view: View::Large,
+            palette: Palette::Plain,
+            labels: false,
+            scale: Scale::One,
+            unicode: true,
+            size: (30, 20),
+        };
+        let board: Board = "+--/---/---".parse().unwrap();
+
+        assert_eq!(
+            renderer.board(&board, &[]),
+            "The terminal is 30x20, large print needs at least 32x17.\n\n| + | - | - |\n| - | - | - |\n| - | - | - |"
+        );
+    }
+
+    #[test]
+    fn grids_scale_up_to_what_fits() {
+        let renderer = Renderer {
+            view: View::Grid,
+            palette: Palette::Plain,
+            labels: false,
+            scale: Scale::Two,
+            unicode: true,
+            size: (80, 24),
+        };
+        let board: Board = "+--/-o-/---".parse().unwrap();
+
+        assert_eq!(
+            renderer
+                .board(&board, &[[1, 1]])
+                .lines()
+                .take(5)
+                .collect::<Vec<_>>(),
+            [
+                "       |       |       ",
+                "   +   |   -   |   -   ",
+                "       |       |       ",
+                "-------+-------+-------",
+                "       |       |       ",
+            ]
+        );
+        assert!(renderer.board(&board, &[[1, 1]]).contains("  [o]  "));
+
+        assert_eq!(Scale::Auto.factor(3, (80, 24)), 2);
+        assert_eq!(Scale::Auto.factor(3, (120, 40)), 3);
+        assert_eq!(Scale::Auto.factor(3, (20, 10)), 1);
+        assert_eq!("2x".parse(), Ok(Scale::Two));
+        assert!("4".parse::<Scale>().is_err());
+    }
+
+    #[test]
+    fn boards_are_drawn_side_by_side_as_far_as_they_fit() {
+        let mut renderer = Renderer {
+            view: View::Grid,
+            palette: Palette::Standard,
+            labels: false,
+            scale: Scale::One,
+            unicode: true,
+            size: (80, 24),
+        };
+        let panel = |position: &str, status: &str| Panel {
+            board: position.parse().unwrap(),
+            marked: Vec::new(),
+            status: String::from(status),
+        };
+        let panels = [
+            panel("+--/---/---", "Game 1"),
+            panel("---/-o-/---", "Game 2"),
+            panel("---/---/--+", "Game 3"),
+        ];
+
+        let text = renderer.panels(&panels);
+        let first = text.lines().next().unwrap();
+        assert_eq!(visible_width(first), 3 * 13 + 2 * GAP);
+        assert!(text.lines().last().unwrap().starts_with("Game 1"));
+        assert!(text.contains("Game 3"));
+
+        renderer.size = (30, 24);
+        let text = renderer.panels(&panels);
+        assert_eq!(text.split("\n\n").count(), 2);
+        assert!(text.lines().all(|line| visible_width(line) <= 30));
+    }
+
+    #[test]
+    fn braille_needs_unicode() {
+        let renderer = Renderer {
+            view: View::Braille,
+            palette: Palette::Plain,
+            labels: false,
+            scale: Scale::One,
+            unicode: false,
+            size: (80, 24),
+        };
+        let board: Board = "+--/---/---".parse().unwrap();
+
+        assert_eq!(
+            renderer.position(&board, &[], &[[0, 0]]),
+            "| + | - | - |\n| - | - | - |\n| - | - | - |"
+        );
+    }
+
+    #[test]
+    fn braille_positions_come_with_their_moves() {
+        let renderer = Renderer {
+            view: View::Braille,
+            palette: Palette::Plain,
+            labels: false,
+            scale: Scale::One,
+            unicode: true,
+            size: (80, 24),
+        };
+        let board: Board = "+--/-o-/---".parse().unwrap();
+
+        assert_eq!(
+            renderer.position(&board, &[[1, 1]], &[[0, 0], [1, 1]]),
+            "⠭⠤⠤ ⠤⣕⠤ ⠤⠤⠤  0,0 1,1\n+ 0,0; o 1,1; marked 1,1"
+        );
+    }
+
+    #[test]
+    fn large_diffs_mark_the_changes_on_the_new_board() {
+        let renderer = Renderer {
+            view: View::Large,
+            palette: Palette::Plain,
+            labels: false,
+            scale: Scale::One,
+            unicode: true,
+            size: (80, 24),
+        };
+        let previous: Board = "+--/---/---".parse().unwrap();
+        let current: Board = "+--/-o-/---".parse().unwrap();
+
+        assert_eq!(
+            renderer.diff(&previous, &current),
+            renderer.board(&current, &[[1, 1]])
+        );
+    }
+}
@@ -0,0 +1,324 @@
+// Crowd votes: one side's moves are picked by whoever votes during a window,
+// e.g. a chat channel playing a bot. Each voter has one vote per move and can
+// change it; the window opens with the first vote and the move with the most
+// votes is played when it closes. Ties are broken by the tiebreak rule.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write as _};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::ai;
+use crate::board::{Board, GameStatus, Symbol};
+use crate::bot::Bot;
+use crate::rng::Rng;
+
+// How long voting stays open unless set otherwise.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Tiebreak {
+    // The tied move that got its votes first.
+    #[default]
+    First,
+    Random,
+    // The tied move the computer rates best.
+    Best,
+}
+
+pub const TIEBREAKS: [Tiebreak; 3] = [Tiebreak::First, Tiebreak::Random, Tiebreak::Best];
+
+impl Display for Tiebreak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Tiebreak::First => "first",
+            Tiebreak::Random => "random",
+            Tiebreak::Best => "best",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Tiebreak {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TIEBREAKS
+            .into_iter()
+            .find(|tiebreak| tiebreak.to_string() == s)
+            .ok_or_else(|| format!("'{s}' is not a tiebreak, use first, random or best."))
+    }
+}
+
+// The votes for one move, in the order they were cast.
+#[derive(Debug, Default)]
+pub struct Ballot {
+    votes: Vec<(String, [usize; 2])>,
+}
+
+impl Ballot {
+    // Counts the voter's vote, replacing an earlier one.
+    pub fn cast(&mut self, voter: &str, position: [usize; 2]) {
+        self.votes.retain(|(name, _)| name != voter);
+        self.votes.push((voter.to_string(), position));
+    }
+
+    // Every move voted for with its votes, the most first. Of moves with as
+    // many votes, the one that reached its count first comes first.
+    pub fn tally(&self) -> Vec<([usize; 2], usize)> {
+        let mut counts = BTreeMap::new();
+        for (i, (_, position)) in self.votes.iter().enumerate() {
+            let (votes, reached) = counts.entry(*position).or_insert((0, 0));
+            *votes += 1;
+            *reached = i;
+        }
+        let mut tally = counts.into_iter().collect::<Vec<_>>();
+        tally.sort_by_key(|(_, (votes, reached))| (std::cmp::Reverse(*votes), *reached));
+        tally
+            .into_iter()
+            .map(|(position, (votes, _))| (position, votes))
+            .collect()
+    }
+
+    // The move to play, None without votes.
+    pub fn winner(
+        &self,
+        tiebreak: Tiebreak,
+        board: &Board,
+        symbol: Symbol,
+        rng: &mut Rng,
+    ) -> Option<[usize; 2]> {
+        let tally = self.tally();
+        let most = tally.first()?.1;
+        let tied = tally
+            .iter()
+            .take_while(|(_, votes)| *votes == most)
+            .map(|(position, _)| *position)
+            .collect::<Vec<_>>();
+
+        match tiebreak {
+            Tiebreak::First => tied.first().copied(),
+            Tiebreak::Random => Some(tied[rng.below(tied.len())]),
+            Tiebreak::Best => ai::move_scores(board, symbol)
+                .into_iter()
+                .filter(|(position, _)| tied.contains(position))
+                // The first of equally good moves.
+                .rev()
+                .max_by_key(|(_, score)| *score)
+                .map(|(position, _)| position),
+        }
+    }
+}
+
+// The tally as lines with a bar per move, e.g. "b2 ### 3", naming squares
+// with name.
+pub fn format_tally(tally: &[([usize; 2], usize)], name: impl Fn([usize; 2]) -> String) -> String {
+    tally
+        .iter()
+        .fold(String::new(), |mut text, (position, votes)| {
+            let _ = writeln!(text, "{} {} {votes}", name(*position), "#".repeat(*votes));
+            text
+        })
+}
+
+// What happened when a window closed.
+pub struct Round {
+    pub tally: Vec<([usize; 2], usize)>,
+    // The crowd's move and the bot's reply.
+    pub played: [usize; 2],
+    pub reply: Option<[usize; 2]>,
+    // The board after both, and how the game stands. A new game starts
+    // after one that is over.
+    pub board: Board,
+    pub status: GameStatus,
+    // Whether the bot passed or played an illegal move, losing the game.
+    pub forfeit: bool,
+}
+
+// The crowd plays + against a bot, game after game.
+pub struct CrowdGame {
+    board: Board,
+    ballot: Ballot,
+    bot: Box<dyn Bot>,
+    tiebreak: Tiebreak,
+    window: Duration,
+    // None until the first vote.
+    closes: Option<Instant>,
+    rng: Rng,
+}
+
+impl CrowdGame {
+    pub fn new(bot: Box<dyn Bot>, window: Duration, tiebreak: Tiebreak, rng: Rng) -> CrowdGame {
+        CrowdGame {
+            board: Board::new(),
+            ballot: Ballot::default(),
+            bot,
+            tiebreak,
+            window,
+            closes: None,
+            rng,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn tally(&self) -> Vec<([usize; 2], usize)> {
+        self.ballot.tally()
+    }
+
+    // When the window closes, None before the first vote.
+    pub fn closes(&self) -> Option<Instant> {
+        self.closes
+    }
+
+    // Counts a vote and returns whether it opened the window.
+    pub fn vote(
+        &mut self,
+        voter: &str,
+        position: [usize; 2],
+        now: Instant,
+    ) -> Result<bool, String> {
+        self.board
+            .is_valid_move(position)
+            .map_err(|error| error.to_string())?;
+        self.ballot.cast(voter, position);
+        let opened = self.closes.is_none();
+        if opened {
+            self.closes = Some(now + self.window);
+        }
+        Ok(opened)
+    }
+
+    // Plays the crowd's move and the bot's reply once the window is over.
+    pub fn close(&mut self, now: Instant) -> Option<Round> {
+        if self.closes.is_none_or(|closes| now < closes) {
+            return None;
+        }
+        let played = self
+            .ballot
+            .winner(self.tiebreak, &self.board, Symbol::Plus, &mut self.rng)?;
+        let tally = self.ballot.tally();
+        self.ballot = Ballot::default();
+        self.closes = None;
+
+        self.board.place(Symbol::Plus, played);
+        let mut reply = None;
+        let mut forfeit = false;
+        if self.board.status() == GameStatus::InProgress {
+            reply = self
+                .bot
+                .choose_move(&self.board, Symbol::Circle)
+                .filter(|reply| self.board.is_valid_move(*reply).is_ok());
+            match reply {
+                Some(reply) => {
+                    self.board.place(Symbol::Circle, reply);
+                }
+                None => forfeit = true,
+            }
+        }
+
+        let board = self.board.clone();
+        let status = board.status();
+        if forfeit || status != GameStatus::InProgress {
+            self.board = Board::new();
+        }
+        Some(Round {
+            tally,
+            played,
+            reply,
+            board,
+            status,
+            forfeit,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Plays the first free tile in reading order.
+    struct FirstFree;
+
+    impl Bot for FirstFree {
+        fn name(&self) -> String {
+            String::from("First free")
+        }
+
+        fn choose_move(&mut self, board: &Board, _symbol: Symbol) -> Option<[usize; 2]> {
+            board.empty_positions().first().copied()
+        }
+    }
+
+    #[test]
+    fn tiebreaks_round_trip() {
+        for tiebreak in TIEBREAKS {
+            assert_eq!(tiebreak.to_string().parse(), Ok(tiebreak));
+        }
+        assert!("loudest".parse::<Tiebreak>().is_err());
+    }
+
+    #[test]
+    fn voters_can_change_their_vote() {
+        let mut ballot = Ballot::default();
+        ballot.cast("ann", [0, 0]);
+        ballot.cast("bob", [1, 1]);
+        ballot.cast("cat", [0, 0]);
+        ballot.cast("ann", [1, 1]);
+
+        assert_eq!(ballot.tally(), [([1, 1], 2), ([0, 0], 1)]);
+        assert_eq!(
+            format_tally(&ballot.tally(), |[row, column]| format!("{row},{column}")),
+            "1,1 ## 2\n0,0 # 1\n"
+        );
+    }
+
+    #[test]
+    fn ties_are_broken_by_the_rule() {
+        // 2,2 gets its votes first, 0,1 blocks the win o threatens on row 0.
+        let board: Board = "oo-/-+-/---".parse().unwrap();
+        let mut ballot = Ballot::default();
+        ballot.cast("ann", [2, 2]);
+        ballot.cast("bob", [0, 2]);
+        let mut rng = Rng::new(3);
+
+        assert_eq!(
+            ballot.winner(Tiebreak::First, &board, Symbol::Plus, &mut rng),
+            Some([2, 2])
+        );
+        assert_eq!(
+            ballot.winner(Tiebreak::Best, &board, Symbol::Plus, &mut rng),
+            Some([0, 2])
+        );
+        let random = ballot.winner(Tiebreak::Random, &board, Symbol::Plus, &mut rng);
+        assert!(random == Some([2, 2]) || random == Some([0, 2]));
+        assert_eq!(
+            Ballot::default().winner(Tiebreak::First, &board, Symbol::Plus, &mut rng),
+            None
+        );
+    }
+
+    #[test]
+    fn windows_open_with_the_first_vote_and_play_the_winner() {
+        let start = Instant::now();
+        let window = Duration::from_secs(10);
+        let mut game = CrowdGame::new(Box::new(FirstFree), window, Tiebreak::First, Rng::new(1));
+
+        assert!(game.close(start + window).is_none());
+        assert_eq!(game.vote("ann", [1, 1], start), Ok(true));
+        assert_eq!(game.vote("bob", [1, 1], start), Ok(false));
+        assert!(game.vote("bob", [3, 3], start).is_err());
+        assert!(game.close(start).is_none());
+
+        let round = game.close(start + window).unwrap();
+        assert_eq!(round.played, [1, 1]);
+        assert_eq!(round.reply, Some([0, 0]));
+        assert_eq!(round.tally, [([1, 1], 2)]);
+        assert_eq!(round.status, GameStatus::InProgress);
+        assert!(game.closes().is_none());
+        assert!(game.vote("cat", [0, 0], start).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+// Bots compiled to WebAssembly, available with the `wasm-bots` feature.
+//
+// The module runs in an interpreter with no imports, so it can only compute.
+// It must export:
+//
+//   memory                                   its linear memory
+//   ttt_board_buffer() -> i32                where the host writes the board
+//   ttt_choose_move(size: i32, symbol: i32) -> i32
+//
+// and may export `ttt_bot_name() -> i32` pointing at a NUL-terminated name.
+// The board and move use the same encoding as native plugins (see
+// plugin.rs). Each move gets a fixed amount of fuel, and a bot that runs
+// out, traps or asks for too much memory simply produces no move.
+
+use std::fs;
+use std::path::Path;
+
+use wasmi::{Config, Engine, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::board::{Board, Symbol};
+use crate::bot::Bot;
+use crate::plugin::{decode_move, encode_symbol, encode_tiles};
+
+const FUEL_PER_MOVE: u64 = 10_000_000;
+const MAX_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+const MAX_NAME_LENGTH: usize = 64;
+
+pub struct WasmBot {
+    name: String,
+    store: Store<StoreLimits>,
+    instance: Instance,
+}
+
+impl WasmBot {
+    pub fn load(path: &Path) -> Result<WasmBot, String> {
+        let failed =
+            |reason: String| format!("Could not load WASM bot {}: {reason}", path.display());
+        let bytes = fs::read(path).map_err(|error| failed(error.to_string()))?;
+        let default_name = path.file_stem().map_or_else(
+            || String::from("wasm"),
+            |stem| stem.to_string_lossy().into_owned(),
+        );
+
+        WasmBot::from_bytes(&bytes, default_name).map_err(failed)
+    }
+
+    // Accepts binary modules as well as the WAT text format.
+    fn from_bytes(bytes: &[u8], default_name: String) -> Result<WasmBot, String> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, bytes).map_err(|error| error.to_string())?;
+
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MAX_MEMORY_BYTES)
+            .build();
+        let mut store = Store::new(&engine, limits);
+        store.limiter(|limits| limits);
+        store
+            .set_fuel(FUEL_PER_MOVE)
+            .map_err(|error| error.to_string())?;
+
+        let instance = Linker::new(&engine)
+            .instantiate_and_start(&mut store, &module)
+            .map_err(|error| error.to_string())?;
+
+        let mut bot = WasmBot {
+            name: default_name,
+            store,
+            instance,
+        };
+
+        if bot.instance.get_memory(&bot.store, "memory").is_none() {
+            return Err(String::from("it does not export its memory"));
+        }
+
+        for export in ["ttt_board_buffer", "ttt_choose_move"] {
+            if bot.instance.get_func(&bot.store, export).is_none() {
+                return Err(format!("it does not export {export}"));
+            }
+        }
+
+        if let Some(name) = bot.read_name() {
+            bot.name = name;
+        }
+
+        Ok(bot)
+    }
+
+    fn read_name(&mut self) -> Option<String> {
+        let name_pointer = self
+            .instance
+            .get_typed_func::<(), i32>(&self.store, "ttt_bot_name")
+            .ok()?
+            .call(&mut self.store, ())
+            .ok()?;
+        let memory = self.instance.get_memory(&self.store, "memory")?;
+
+        let start = usize::try_from(name_pointer).ok()?;
+        let bytes = memory.data(&self.store).get(start..)?;
+        let length = bytes
+            .iter()
+            .take(MAX_NAME_LENGTH)
+            .position(|byte| *byte == 0)?;
+
+        Some(String::from_utf8_lossy(&bytes[..length]).into_owned())
+    }
+
+    fn call(&mut self, board: &Board, symbol: Symbol) -> Result<i32, String> {
+        self.store
+            .set_fuel(FUEL_PER_MOVE)
+            .map_err(|error| error.to_string())?;
+
+        let buffer = self
+            .instance
+            .get_typed_func::<(), i32>(&self.store, "ttt_board_buffer")
+            .and_then(|buffer| buffer.call(&mut self.store, ()))
+            .map_err(|error| error.to_string())?;
+        let memory = self
+            .instance
+            .get_memory(&self.store, "memory")
+            .ok_or_else(|| String::from("it does not export its memory"))?;
+        memory
+            .write(
+                &mut self.store,
+                usize::try_from(buffer).map_err(|error| error.to_string())?,
+                &encode_tiles(board),
+            )
+            .map_err(|error| error.to_string())?;
+
+        let size = i32::try_from(board.tiles.len()).map_err(|error| error.to_string())?;
+        self.instance
+            .get_typed_func::<(i32, i32), i32>(&self.store, "ttt_choose_move")
+            .and_then(|choose_move| {
+                choose_move.call(&mut self.store, (size, i32::from(encode_symbol(symbol))))
+            })
+            .map_err(|error| error.to_string())
+    }
+}
+
+impl Bot for WasmBot {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn choose_move(&mut self, board: &Board, symbol: Symbol) -> Option<[usize; 2]> {
+        match self.call(board, symbol) {
+            Ok(index) => decode_move(index, board.tiles.len()),
+            Err(error) => {
+                eprintln!("The WASM bot {} failed: {error}", self.name);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Plays the first empty tile and calls itself "First empty".
+    const FIRST_EMPTY: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (data (i32.const 0) "First empty\00")
+          (func (export "ttt_bot_name") (result i32) i32.const 0)
+          (func (export "ttt_board_buffer") (result i32) i32.const 64)
+          (func (export "ttt_choose_move") (param $size i32) (param $symbol i32) (result i32)
+            (local $i i32)
+            (block $done
+              (loop $next
+                (br_if $done (i32.ge_u (local.get $i) (i32.mul (local.get $size) (local.get $size))))
+                (if (i32.eqz (i32.load8_u offset=64 (local.get $i)))
+                  (then (return (local.get $i))))
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br $next)))
+            i32.const -1))
+    "#;
+
+    fn bot(source: &str) -> Result<WasmBot, String> {
+        WasmBot::from_bytes(source.as_bytes(), String::from("test"))
+    }
+
+    #[test]
+    fn wasm_bot_reads_the_board_and_returns_a_move() {
+        let mut bot = bot(FIRST_EMPTY).unwrap();
+        let board: Board = "++o/-o-/---".parse().unwrap();
+
+        assert_eq!(bot.name(), "First empty");
+        assert_eq!(bot.choose_move(&board, Symbol::Plus), Some([1, 0]));
+        assert_eq!(
+            bot.choose_move(&"+++/ooo/+++".parse().unwrap(), Symbol::Plus),
+            None
+        );
+    }
+
+    #[test]
+    fn endless_bots_run_out_of_fuel() {
+        let mut bot = bot(r#"
+            (module
+              (memory (export "memory") 1)
+              (func (export "ttt_board_buffer") (result i32) i32.const 0)
+              (func (export "ttt_choose_move") (param i32 i32) (result i32)
+                (loop $forever (br $forever))
+                i32.const 0))
+            "#)
+        .unwrap();
+
+        assert_eq!(bot.name(), "test");
+        assert_eq!(bot.choose_move(&Board::new(), Symbol::Plus), None);
+    }
+
+    #[test]
+    fn modules_without_the_abi_are_rejected() {
+        assert!(bot("(module)").is_err());
+        assert!(bot(r#"(module (memory (export "memory") 1))"#).is_err());
+        assert!(bot("not a module").is_err());
+    }
+}
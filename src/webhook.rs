@@ -0,0 +1,149 @@
+// Webhooks for server mode: every game event (see src/events.rs) is POSTed
+// as JSON to each configured URL, for chat ops and external recorders. std
+// has no TLS, so only http:// URLs can be posted to.
+//
+// Posts go out in order on a thread of their own, so a slow endpoint never
+// holds up a game. One that fails is reported on stderr and not retried.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::str::FromStr;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+// How long an endpoint gets to accept a post and answer.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// An http:// URL to post to.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Endpoint {
+    // host:port to connect to, the port 80 unless given.
+    address: String,
+    // The host as written, for the Host header.
+    host: String,
+    path: String,
+}
+
+impl FromStr for Endpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("https://") {
+            return Err(format!(
+                "'{s}' is an https URL, but webhooks can only be posted over http."
+            ));
+        }
+        let rest = s.strip_prefix("http://").ok_or_else(|| {
+            format!("'{s}' is not a webhook URL, use e.g. http://host:8080/path.")
+        })?;
+        let (host, path) = rest.find('/').map_or((rest, "/"), |i| rest.split_at(i));
+        if host.is_empty() || host.contains('@') {
+            return Err(format!("'{s}' needs a host, without a user name."));
+        }
+        let address = if host
+            .rsplit_once(':')
+            .is_some_and(|(_, port)| !port.contains(']'))
+        {
+            host.to_string()
+        } else {
+            format!("{host}:80")
+        };
+
+        Ok(Endpoint {
+            address,
+            host: host.to_string(),
+            path: path.to_string(),
+        })
+    }
+}
+
+impl Endpoint {
+    fn request(&self, body: &str) -> String {
+        format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: tic-tac-toe-rust\r\n\
+             Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            self.path,
+            self.host,
+            body.len()
+        )
+    }
+
+    // Posts the body and checks the answer's status is 2xx.
+    fn post(&self, body: &str) -> io::Result<()> {
+        let mut stream = TcpStream::connect(&self.address)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        stream.write_all(self.request(body).as_bytes())?;
+
+        let mut status = String::new();
+        BufReader::new(stream).read_line(&mut status)?;
+        match status.split_whitespace().nth(1) {
+            Some(code) if code.starts_with('2') => Ok(()),
+            _ => Err(io::Error::other(format!(
+                "answered '{}'",
+                status.trim_end()
+            ))),
+        }
+    }
+}
+
+// Posts every body sent on the channel to each endpoint, in order, on a
+// thread of its own.
+pub fn start(endpoints: Vec<Endpoint>) -> Sender<String> {
+    let (posts, bodies) = mpsc::channel::<String>();
+    thread::spawn(move || {
+        for body in bodies {
+            for endpoint in &endpoints {
+                if let Err(error) = endpoint.post(&body) {
+                    eprintln!(
+                        "Webhook http://{}{} failed: {error}",
+                        endpoint.host, endpoint.path
+                    );
+                }
+            }
+        }
+    });
+    posts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_http_urls_are_endpoints() {
+        let endpoint: Endpoint = "http://example.org/hooks/ttt".parse().unwrap();
+        assert_eq!(endpoint.address, "example.org:80");
+        assert_eq!(endpoint.path, "/hooks/ttt");
+
+        let endpoint: Endpoint = "http://[::1]:8080".parse().unwrap();
+        assert_eq!(endpoint.address, "[::1]:8080");
+        assert_eq!(endpoint.path, "/");
+        assert_eq!(
+            "http://[::1]"
+                .parse::<Endpoint>()
+                .map(|endpoint| endpoint.address),
+            Ok(String::from("[::1]:80"))
+        );
+
+        for url in [
+            "https://example.org/",
+            "example.org",
+            "http://",
+            "http://a@b/",
+        ] {
+            assert!(url.parse::<Endpoint>().is_err(), "{url}");
+        }
+    }
+
+    #[test]
+    fn posts_carry_the_json_with_its_length() {
+        let endpoint: Endpoint = "http://localhost:8080/events".parse().unwrap();
+        let request = endpoint.request("{}");
+
+        assert!(request.starts_with("POST /events HTTP/1.1\r\nHost: localhost:8080\r\n"));
+        assert!(request.contains("Content-Length: 2\r\n"));
+        assert!(request.ends_with("\r\n\r\n{}"));
+    }
+}
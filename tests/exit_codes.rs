@@ -0,0 +1,39 @@
+// The exit codes scripts rely on, from the built program with piped input.
+// Each run gets its own home directory, so saved games and statistics stay
+// out of the real one.
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn exit_code(name: &str, args: &[&str], input: &[u8]) -> i32 {
+    let home = std::env::temp_dir().join(format!("ttt-exit-codes-{name}-{}", std::process::id()));
+    fs::create_dir_all(&home).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tic-tac-toe-rust"))
+        .args(args)
+        .current_dir(&home)
+        .env("HOME", &home)
+        .env("XDG_DATA_HOME", home.join("data"))
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let status = child.wait().unwrap();
+    fs::remove_dir_all(&home).unwrap();
+    status.code().unwrap()
+}
+
+#[test]
+fn finished_games_exit_with_the_winner() {
+    let input = b"0,0\n1,0\n0,1\n1,1\n0,2\nn\n";
+    assert_eq!(exit_code("winner", &["--p2", "human"], input), 1);
+}
+
+#[test]
+fn input_that_is_not_utf8_ends_the_game() {
+    let input = b"1,1\n\xff\xfe\n0,0\n";
+    assert_eq!(exit_code("utf8", &["--p2", "human"], input), 11);
+}